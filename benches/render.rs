@@ -0,0 +1,106 @@
+//! Rendering benchmarks
+//!
+//! Times the stages a frame goes through, on the deterministic signals from
+//! the test harness:
+//!
+//! ```text
+//! cargo bench --bench render
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use scope_rs::audio::SampleBuffer;
+use scope_rs::harness::{RenderHarness, TestSignal};
+use scope_rs::render::{OscilloscopeSettings, Rasterizer, ScreenTransform};
+
+/// Rasterizing a full buffer of each signal
+fn rasterize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rasterize");
+    let settings = OscilloscopeSettings {
+        sample_count: SampleBuffer::DEFAULT_CAPACITY,
+        ..OscilloscopeSettings::default()
+    };
+    for &signal in TestSignal::all() {
+        let samples = signal.samples(settings.sample_count);
+        let mut rasterizer = Rasterizer::new(512, 512);
+        group.throughput(Throughput::Elements(samples.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(signal.name()),
+            &samples,
+            |b, samples| b.iter(|| rasterizer.render(samples, &settings).len()),
+        );
+    }
+    group.finish();
+}
+
+/// Mapping samples to screen points, per sample and with SIMD
+fn transform(c: &mut Criterion) {
+    let settings = OscilloscopeSettings {
+        invert_y: true,
+        zoom: 0.8,
+        ..OscilloscopeSettings::default()
+    };
+    let rect = Rect::from_min_size(Pos2::ZERO, Vec2::new(1000.0, 1000.0));
+    let samples = TestSignal::Lissajous.samples(50_000);
+    let mut points = Vec::with_capacity(samples.len());
+
+    let mut group = c.benchmark_group("transform");
+    group.throughput(Throughput::Elements(samples.len() as u64));
+    group.bench_function("scalar", |b| {
+        b.iter(|| {
+            points.clear();
+            points.extend(samples.iter().map(|&sample| {
+                let (ux, uy) = settings.sample_to_unit(sample);
+                Pos2::new(
+                    rect.left() + ux * rect.width(),
+                    rect.bottom() - uy * rect.height(),
+                )
+            }));
+            points.len()
+        })
+    });
+    group.bench_function("simd", |b| {
+        b.iter(|| {
+            points.clear();
+            ScreenTransform::new(&settings, rect).extend(&samples, &mut points);
+            points.len()
+        })
+    });
+    group.finish();
+}
+
+/// Pushing one frame's samples and reading the buffer back
+fn buffer(c: &mut Criterion) {
+    let samples = TestSignal::Sine.samples(RenderHarness::frame_len());
+    let buffer = SampleBuffer::new(SampleBuffer::DEFAULT_CAPACITY);
+    let mut consumer = buffer.take_consumer().unwrap();
+    let mut out = Vec::new();
+
+    let mut group = c.benchmark_group("buffer");
+    group.throughput(Throughput::Elements(samples.len() as u64));
+    group.bench_function("push_and_copy", |b| {
+        b.iter(|| {
+            buffer.push_slice(&samples);
+            consumer.update();
+            consumer.copy_samples_into(&mut out);
+            out.len()
+        })
+    });
+    group.finish();
+}
+
+/// Whole frames: generate, buffer, read back and rasterize
+fn frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame");
+    for &signal in TestSignal::all() {
+        let mut harness = RenderHarness::new(signal, 512, 512);
+        group.bench_function(BenchmarkId::from_parameter(signal.name()), |b| {
+            b.iter(|| harness.run(1).len())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, rasterize, transform, buffer, frame);
+criterion_main!(benches);
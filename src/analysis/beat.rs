@@ -0,0 +1,281 @@
+//! Beat and tempo detection
+//!
+//! Kicks are found in the low end: the mono sum goes through a low pass, its
+//! energy is taken every few milliseconds, and a kick is that energy jumping
+//! well above its average over the last second.
+//!
+//! The tempo comes from the autocorrelation of the energy's rises over the
+//! last few seconds, searched between `MIN_BPM` and `MAX_BPM`. A steady
+//! kick correlates just as well at two beats as at one, so the fastest
+//! tempo whose peak comes close to the best wins. With a tempo, a beat
+//! clock runs at that rate and each kick pulls its phase towards itself, so
+//! beats keep coming through a break and syncopated kicks don't add extra
+//! ones. Without one every kick is a beat.
+
+use std::collections::VecDeque;
+
+use super::envelope::Biquad;
+use crate::audio::XYSample;
+
+/// Low pass ahead of the energy, in Hz
+const KICK_HZ: f32 = 150.0;
+
+/// Length of one energy reading, in seconds
+const BLOCK_SECONDS: f32 = 0.005;
+
+/// Time constant of the average a kick stands out from, in seconds
+const AVERAGE_SECONDS: f32 = 1.0;
+
+/// How far above the average the energy has to jump for a kick
+const KICK_RATIO: f32 = 2.0;
+
+/// Low end energy, in dBFS, under which nothing counts as a kick
+const KICK_FLOOR_DB: f32 = -50.0;
+
+/// Shortest time between kicks, in seconds
+const MIN_KICK_GAP: f32 = 0.15;
+
+/// Tempo range searched
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 180.0;
+
+/// Stretch of history the tempo is measured over, in seconds
+const TEMPO_HISTORY: f32 = 6.0;
+
+/// How often the tempo is measured again, in seconds
+const TEMPO_INTERVAL: f32 = 0.5;
+
+/// Autocorrelation peak, relative to the zero lag, that counts as a tempo
+const MIN_CONFIDENCE: f32 = 0.1;
+
+/// Share of the best peak a faster tempo's peak needs to win
+const FASTER_PEAK: f32 = 0.8;
+
+/// Share of a kick's timing error the beat clock takes on
+const PHASE_PULL: f32 = 0.5;
+
+/// What the detector has found so far
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Beats {
+    /// Tempo, while the kicks are regular enough to have one
+    pub bpm: Option<f32>,
+    /// Beats counted since the detector started
+    pub count: u64,
+    /// Kicks detected since the detector started
+    pub kicks: u64,
+    /// How far through the current beat, 0 to 1
+    pub phase: f32,
+}
+
+/// Finds kicks, the tempo and the beat in a sample stream
+#[derive(Clone, Debug)]
+pub struct BeatDetector {
+    filter: Biquad,
+    /// Samples per energy reading
+    block: usize,
+    /// Readings per second
+    rate: f32,
+    sum: f32,
+    count: usize,
+    average: f32,
+    /// Whether the last reading was above the kick threshold
+    above: bool,
+    /// Readings since the last kick
+    since_kick: usize,
+    /// Rise of the energy in dB at each reading, oldest first
+    rises: VecDeque<f32>,
+    last_db: f32,
+    /// Readings until the tempo is measured again
+    until_tempo: usize,
+    /// Beat period in readings, while there is a tempo
+    period: Option<f32>,
+    beats: Beats,
+}
+
+impl BeatDetector {
+    pub fn new(sample_rate: u32) -> Self {
+        let block = ((sample_rate as f32 * BLOCK_SECONDS).round() as usize).max(1);
+        Self {
+            filter: Biquad::butterworth(KICK_HZ, sample_rate, false),
+            block,
+            rate: sample_rate as f32 / block as f32,
+            sum: 0.0,
+            count: 0,
+            average: 0.0,
+            above: false,
+            since_kick: usize::MAX,
+            rises: VecDeque::new(),
+            last_db: KICK_FLOOR_DB,
+            until_tempo: 0,
+            period: None,
+            beats: Beats::default(),
+        }
+    }
+
+    pub fn push(&mut self, sample: XYSample) {
+        let low = self.filter.process((sample.x + sample.y) * 0.5);
+        self.sum += low * low;
+        self.count += 1;
+        if self.count == self.block {
+            let energy = self.sum / self.count as f32;
+            self.sum = 0.0;
+            self.count = 0;
+            self.reading(energy);
+        }
+    }
+
+    pub fn beats(&self) -> Beats {
+        self.beats
+    }
+
+    fn reading(&mut self, energy: f32) {
+        let db = 10.0 * energy.max(1e-12).log10();
+        let threshold = self.average * KICK_RATIO;
+        let above = energy > threshold && db > KICK_FLOOR_DB;
+        self.average +=
+            (energy - self.average) * (1.0 - (-1.0 / (AVERAGE_SECONDS * self.rate)).exp());
+
+        let gap = (MIN_KICK_GAP * self.rate) as usize;
+        self.since_kick = self.since_kick.saturating_add(1);
+        let kick = above && !self.above && self.since_kick >= gap;
+        self.above = above;
+
+        self.rises
+            .push_back((db.max(KICK_FLOOR_DB) - self.last_db).max(0.0));
+        self.last_db = db.max(KICK_FLOOR_DB);
+        if self.rises.len() > (TEMPO_HISTORY * self.rate) as usize {
+            self.rises.pop_front();
+        }
+        if self.until_tempo == 0 {
+            self.period = self.measure_period();
+            self.beats.bpm = self.period.map(|period| 60.0 * self.rate / period);
+            self.until_tempo = (TEMPO_INTERVAL * self.rate) as usize;
+        }
+        self.until_tempo -= 1;
+
+        if kick {
+            self.since_kick = 0;
+            self.beats.kicks += 1;
+        }
+        match self.period {
+            Some(period) => {
+                if kick {
+                    // Early kicks pull the next beat in, late ones push the
+                    // one after out; neither adds or drops a beat
+                    let error = if self.beats.phase < 0.5 {
+                        self.beats.phase
+                    } else {
+                        self.beats.phase - 1.0
+                    };
+                    self.beats.phase -= error * PHASE_PULL;
+                }
+                self.beats.phase += 1.0 / period;
+                if self.beats.phase >= 1.0 {
+                    self.beats.phase -= 1.0;
+                    self.beats.count += 1;
+                }
+            }
+            None if kick => {
+                self.beats.count += 1;
+                self.beats.phase = 0.0;
+            }
+            None => {}
+        }
+    }
+
+    /// Beat period in readings, from the autocorrelation of the rises
+    fn measure_period(&self) -> Option<f32> {
+        let min_lag = (60.0 * self.rate / MAX_BPM).floor() as usize;
+        let max_lag = (60.0 * self.rate / MIN_BPM).ceil() as usize;
+        if self.rises.len() < max_lag * 2 {
+            return None;
+        }
+        let mean = self.rises.iter().sum::<f32>() / self.rises.len() as f32;
+        let rises: Vec<f32> = self.rises.iter().map(|r| r - mean).collect();
+        let correlation = |lag: usize| -> f32 {
+            rises
+                .iter()
+                .zip(&rises[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+                / (rises.len() - lag) as f32
+        };
+        let zero = correlation(0);
+        if zero <= 0.0 {
+            return None;
+        }
+        let scores: Vec<f32> = (min_lag - 1..=max_lag + 1).map(correlation).collect();
+        let peaks: Vec<usize> = (1..scores.len() - 1)
+            .filter(|&i| scores[i] >= scores[i - 1] && scores[i] >= scores[i + 1])
+            .collect();
+        let top = peaks.iter().map(|&i| scores[i]).fold(0.0, f32::max);
+        if top < zero * MIN_CONFIDENCE {
+            return None;
+        }
+        let best = *peaks.iter().find(|&&i| scores[i] >= top * FASTER_PEAK)?;
+        // Parabola through the peak and its neighbours
+        let (before, peak, after) = (scores[best - 1], scores[best], scores[best + 1]);
+        let curve = before - 2.0 * peak + after;
+        let offset = if curve < 0.0 {
+            (0.5 * (before - after) / curve).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+        Some((min_lag - 1 + best) as f32 + offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const RATE: u32 = 48_000;
+
+    /// `seconds` of 55 Hz kicks at `bpm`, each decaying over 100 ms, with a
+    /// quieter 3 kHz hat between them
+    fn run(detector: &mut BeatDetector, bpm: f32, seconds: f32) {
+        let period = (60.0 / bpm * RATE as f32) as usize;
+        for i in 0..(seconds * RATE as f32) as usize {
+            let t = (i % period) as f32 / RATE as f32;
+            let kick = 0.8 * (-t / 0.1).exp() * (TAU * 55.0 * t).sin();
+            let offbeat = ((i + period / 2) % period) as f32 / RATE as f32;
+            let hat = 0.1 * (-offbeat / 0.02).exp() * (TAU * 3000.0 * offbeat).sin();
+            detector.push(XYSample::new(kick + hat, kick + hat));
+        }
+    }
+
+    #[test]
+    fn test_finds_the_tempo_and_counts_beats() {
+        for bpm in [96.0, 128.0, 174.0] {
+            let mut detector = BeatDetector::new(RATE);
+            run(&mut detector, bpm, 10.0);
+            let beats = detector.beats();
+            let expected = (bpm / 6.0) as u64;
+            assert!(beats.kicks.abs_diff(expected) <= 1, "{bpm}: {beats:?}");
+            assert!(beats.count.abs_diff(expected) <= 1, "{bpm}: {beats:?}");
+            let found = beats.bpm.expect("a tempo");
+            assert!((found - bpm).abs() < 1.5, "{bpm}: {found}");
+        }
+    }
+
+    #[test]
+    fn test_beats_carry_on_through_a_break() {
+        let mut detector = BeatDetector::new(RATE);
+        run(&mut detector, 120.0, 8.0);
+        let before = detector.beats();
+        // A second of silence, short enough to keep the tempo
+        for _ in 0..RATE {
+            detector.push(XYSample::default());
+        }
+        let after = detector.beats();
+        assert_eq!(after.kicks, before.kicks);
+        assert!(after.count.abs_diff(before.count + 2) <= 1, "{after:?}");
+
+        let mut quiet = BeatDetector::new(RATE);
+        for _ in 0..RATE * 4 {
+            quiet.push(XYSample::default());
+        }
+        assert_eq!(quiet.beats(), Beats::default());
+    }
+}
@@ -0,0 +1,91 @@
+//! DC meter
+//!
+//! The worker keeps running sums of everything it reads. Recording those
+//! totals every frame and differencing the newest against the one from a
+//! second ago gives the mean of the last second of input, which is the DC
+//! offset a "null" button has to cancel.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::worker::Analysis;
+
+/// Stretch of input the mean covers
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Snapshot of the worker's running totals
+#[derive(Clone, Copy, Debug)]
+struct Totals {
+    at: Instant,
+    samples: u64,
+    sum_x: f64,
+    sum_y: f64,
+}
+
+/// Mean X/Y over the last second, from the worker's running sums
+#[derive(Debug, Default)]
+pub struct DcMeter {
+    history: VecDeque<Totals>,
+}
+
+impl DcMeter {
+    /// Note the worker's totals as of `now`
+    pub fn record(&mut self, now: Instant, analysis: &Analysis) {
+        self.history.push_back(Totals {
+            at: now,
+            samples: analysis.samples,
+            sum_x: analysis.sum_x,
+            sum_y: analysis.sum_y,
+        });
+        // Keep one snapshot at or beyond the window's start to difference from
+        while self
+            .history
+            .get(1)
+            .is_some_and(|next| now.duration_since(next.at) >= WINDOW)
+        {
+            self.history.pop_front();
+        }
+    }
+
+    /// Mean X and Y of the input over the last second, or over as much as
+    /// has been recorded. `None` if no samples arrived in that time.
+    pub fn mean(&self) -> Option<(f32, f32)> {
+        let (first, last) = (self.history.front()?, self.history.back()?);
+        let count = last.samples - first.samples;
+        (count > 0).then(|| {
+            (
+                ((last.sum_x - first.sum_x) / count as f64) as f32,
+                ((last.sum_y - first.sum_y) / count as f64) as f32,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_covers_the_last_second() {
+        let start = Instant::now();
+        let mut meter = DcMeter::default();
+        let mut analysis = Analysis::default();
+        meter.record(start, &analysis);
+        assert_eq!(meter.mean(), None);
+
+        // Ten frames a second: a second at +0.5, then a second at -0.25
+        for frame in 1..=20 {
+            let x = if frame <= 10 { 0.5 } else { -0.25 };
+            analysis.samples += 100;
+            analysis.sum_x += 100.0 * x;
+            analysis.sum_y += 100.0 * 0.1;
+            meter.record(start + Duration::from_millis(frame * 100), &analysis);
+            if frame == 10 {
+                let (x, y) = meter.mean().unwrap();
+                assert!((x - 0.5).abs() < 1e-6 && (y - 0.1).abs() < 1e-6);
+            }
+        }
+        let (x, _) = meter.mean().unwrap();
+        assert!((x + 0.25).abs() < 1e-6, "{x}");
+    }
+}
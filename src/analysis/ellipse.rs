@@ -0,0 +1,144 @@
+//! Ellipse measurement
+//!
+//! Two sines of the same frequency trace an ellipse in XY. Its shape encodes
+//! their relationship: with the DC removed, the normalized correlation of X
+//! and Y is the cosine of the phase difference, and the ratio of their RMS
+//! levels is the gain difference. Correlation alone can't tell +30° from
+//! -30°, so the sign comes from the direction the trace rotates.
+//!
+//! All of that assumes the figure really is such an ellipse. A sampled sine
+//! obeys `s[n+1] + s[n-1] = 2cos(ω)·s[n]`, so fitting one shared `ω` to both
+//! channels and measuring what the recurrence leaves unexplained tells a
+//! clean two-sine ellipse (nothing left) from Lissajous figures, noise or
+//! music. The residual is compared with the signal's second difference
+//! rather than the signal itself, since at audio rates neighbouring samples
+//! nearly repeat and any recurrence would look like a good fit.
+
+use crate::audio::XYSample;
+
+/// Phase and level relationship between the two channels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EllipseMeasurement {
+    /// Phase of Y relative to X in degrees (positive = Y leads)
+    pub phase_deg: f32,
+    /// Level of Y relative to X in dB
+    pub gain_db: f32,
+    /// Channel RMS levels in dBFS
+    pub level_x_db: f32,
+    pub level_y_db: f32,
+    /// How far the figure is from an ellipse: near 0 for two clean sines of
+    /// one frequency, 0.3 and up for other ratios, noise or music
+    pub fit_error: f32,
+}
+
+impl EllipseMeasurement {
+    /// Measure a block of samples. Returns `None` if either channel is
+    /// (nearly) silent. The block should span several periods.
+    pub fn measure(samples: &[XYSample]) -> Option<Self> {
+        if samples.len() < 3 {
+            return None;
+        }
+        let n = samples.len() as f64;
+        let mean_x = samples.iter().map(|s| s.x as f64).sum::<f64>() / n;
+        let mean_y = samples.iter().map(|s| s.y as f64).sum::<f64>() / n;
+
+        let (mut sxx, mut syy, mut sxy, mut cross) = (0.0, 0.0, 0.0, 0.0);
+        // Sums for the least-squares fit of s[n+1] + s[n-1] = c·s[n]: with
+        // a = s[n] and b = s[n+1] + s[n-1], c = Σab / Σaa and the residual
+        // is Σbb - c·Σab
+        let (mut saa, mut sab, mut sbb) = (0.0, 0.0, 0.0);
+        let centered = |s: &XYSample| (s.x as f64 - mean_x, s.y as f64 - mean_y);
+        let mut prev: Option<(f64, f64)> = None;
+        for (i, s) in samples.iter().enumerate() {
+            let (x, y) = centered(s);
+            sxx += x * x;
+            syy += y * y;
+            sxy += x * y;
+            if let Some((px, py)) = prev {
+                cross += px * y - py * x;
+                if let Some(next) = samples.get(i + 1) {
+                    let (nx, ny) = centered(next);
+                    let (bx, by) = (nx + px, ny + py);
+                    saa += x * x + y * y;
+                    sab += x * bx + y * by;
+                    sbb += bx * bx + by * by;
+                }
+            }
+            prev = Some((x, y));
+        }
+
+        let rms_x = (sxx / n).sqrt();
+        let rms_y = (syy / n).sqrt();
+        if rms_x < 1e-6 || rms_y < 1e-6 {
+            return None;
+        }
+
+        let correlation = (sxy / (sxx * syy).sqrt()).clamp(-1.0, 1.0);
+        // Y leading X makes the trace turn clockwise (negative cross product)
+        let sign = if cross > 0.0 { -1.0 } else { 1.0 };
+
+        let residual = (sbb - sab * sab / saa).max(0.0);
+        // Energy of the second difference b - 2a
+        let curvature = sbb - 4.0 * sab + 4.0 * saa;
+
+        Some(Self {
+            phase_deg: (sign * correlation.acos().to_degrees()) as f32,
+            gain_db: (20.0 * (rms_y / rms_x).log10()) as f32,
+            level_x_db: (20.0 * rms_x.log10()) as f32,
+            level_y_db: (20.0 * rms_y.log10()) as f32,
+            fit_error: (residual / curvature.max(f64::MIN_POSITIVE)).sqrt() as f32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sines(phase_deg: f32, gain: f32) -> Vec<XYSample> {
+        lissajous(1.0, phase_deg, gain)
+    }
+
+    /// Y at `ratio` times the frequency of X
+    fn lissajous(ratio: f32, phase_deg: f32, gain: f32) -> Vec<XYSample> {
+        let phase = phase_deg.to_radians();
+        (0..4800)
+            .map(|i| {
+                let t = i as f32 / 48000.0 * 1000.0 * std::f32::consts::TAU;
+                XYSample::new(0.5 * t.sin() + 0.1, 0.5 * gain * (t * ratio + phase).sin())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_phase_sign_and_gain() {
+        for phase in [-120.0, -30.0, 0.0, 45.0, 90.0] {
+            let m = EllipseMeasurement::measure(&sines(phase, 0.5)).unwrap();
+            assert!(
+                (m.phase_deg - phase).abs() < 0.5,
+                "{} vs {}",
+                m.phase_deg,
+                phase
+            );
+            assert!((m.gain_db - -6.02).abs() < 0.05);
+            assert!(m.fit_error < 0.01, "fit error {}", m.fit_error);
+        }
+    }
+
+    #[test]
+    fn test_fit_error_rejects_other_figures() {
+        let m = EllipseMeasurement::measure(&lissajous(1.5, 0.0, 1.0)).unwrap();
+        assert!(m.fit_error > 0.3, "3:2 fit error {}", m.fit_error);
+
+        let mut state = 1u32;
+        let mut noise = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 - 0.5
+        };
+        let samples: Vec<XYSample> = (0..4800).map(|_| XYSample::new(noise(), noise())).collect();
+        let m = EllipseMeasurement::measure(&samples).unwrap();
+        assert!(m.fit_error > 0.3, "noise fit error {}", m.fit_error);
+    }
+}
@@ -0,0 +1,175 @@
+//! Band levels for audio-reactive modulation
+//!
+//! The mono sum of the input is split into lows, mids and highs with
+//! second-order Butterworth filters crossing over at 200 Hz and 2 kHz, so a
+//! kick drum and a hi-hat can drive different things. Each band's RMS over
+//! a stretch of samples is what an envelope follower then smooths.
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::XYSample;
+
+/// Low/mid crossover in Hz
+const LOW_MID_HZ: f32 = 200.0;
+
+/// Mid/high crossover in Hz
+const MID_HIGH_HZ: f32 = 2000.0;
+
+/// Part of the spectrum an envelope follows
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Band {
+    #[default]
+    Full,
+    Low,
+    Mid,
+    High,
+}
+
+impl Band {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Full => "Full range",
+            Self::Low => "Lows (< 200 Hz)",
+            Self::Mid => "Mids",
+            Self::High => "Highs (> 2 kHz)",
+        }
+    }
+
+    pub fn all() -> &'static [Band] {
+        &[Self::Full, Self::Low, Self::Mid, Self::High]
+    }
+
+    /// Position in `Band::all()` and in `BandLevels`' readings
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Second-order IIR section, transposed direct form II
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    state: [f32; 2],
+}
+
+impl Biquad {
+    /// Butterworth low or high pass at `freq`, from the RBJ cookbook
+    pub(super) fn butterworth(freq: f32, sample_rate: u32, high_pass: bool) -> Self {
+        let w = std::f32::consts::TAU * freq / sample_rate as f32;
+        let (sin, cos) = w.sin_cos();
+        let alpha = sin / std::f32::consts::SQRT_2;
+        let a0 = 1.0 + alpha;
+        let (b0, b1) = if high_pass {
+            ((1.0 + cos) / 2.0, -(1.0 + cos))
+        } else {
+            ((1.0 - cos) / 2.0, 1.0 - cos)
+        };
+        Self {
+            b: [b0 / a0, b1 / a0, b0 / a0],
+            a: [-2.0 * cos / a0, (1.0 - alpha) / a0],
+            state: [0.0; 2],
+        }
+    }
+
+    /// Filter with numerator `b` and denominator `1, a[0], a[1]`
+    pub(super) fn new(b: [f32; 3], a: [f32; 2]) -> Self {
+        Self {
+            b,
+            a,
+            state: [0.0; 2],
+        }
+    }
+
+    pub(super) fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Splits a stream into bands and measures each one's RMS
+#[derive(Clone, Debug)]
+pub struct BandLevels {
+    low: Biquad,
+    /// High pass then low pass
+    mid: [Biquad; 2],
+    high: Biquad,
+    /// Sums of squares since the last reading, in `Band::all()` order
+    sums: [f64; 4],
+    count: usize,
+}
+
+impl BandLevels {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            low: Biquad::butterworth(LOW_MID_HZ, sample_rate, false),
+            mid: [
+                Biquad::butterworth(LOW_MID_HZ, sample_rate, true),
+                Biquad::butterworth(MID_HIGH_HZ, sample_rate, false),
+            ],
+            high: Biquad::butterworth(MID_HIGH_HZ, sample_rate, true),
+            sums: [0.0; 4],
+            count: 0,
+        }
+    }
+
+    pub fn push(&mut self, sample: XYSample) {
+        let mono = (sample.x + sample.y) * 0.5;
+        let mid = self
+            .mid
+            .iter_mut()
+            .fold(mono, |v, filter| filter.process(v));
+        let bands = [mono, self.low.process(mono), mid, self.high.process(mono)];
+        for (sum, v) in self.sums.iter_mut().zip(bands) {
+            *sum += (v * v) as f64;
+        }
+        self.count += 1;
+    }
+
+    /// RMS of each band since the last reading, in `Band::all()` order
+    pub fn take(&mut self) -> [f32; 4] {
+        let count = self.count.max(1) as f64;
+        let rms = self.sums.map(|sum| (sum / count).sqrt() as f32);
+        self.sums = [0.0; 4];
+        self.count = 0;
+        rms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48_000;
+
+    /// Band RMS of a second of a sine at `freq`, amplitude 1 on both
+    /// channels, skipping the filters' settling
+    fn levels(freq: f32) -> [f32; 4] {
+        let mut levels = BandLevels::new(RATE);
+        let sine = |i: usize| (std::f32::consts::TAU * freq * i as f32 / RATE as f32).sin();
+        for i in 0..RATE as usize / 10 {
+            levels.push(XYSample::new(sine(i), sine(i)));
+        }
+        levels.take();
+        for i in RATE as usize / 10..RATE as usize {
+            levels.push(XYSample::new(sine(i), sine(i)));
+        }
+        levels.take()
+    }
+
+    #[test]
+    fn test_bands_split_the_spectrum() {
+        let full = std::f32::consts::FRAC_1_SQRT_2;
+        let [all, low, mid, high] = levels(50.0);
+        assert!((all - full).abs() < 0.01);
+        assert!(low > 0.65 && mid < 0.2 && high < 0.01, "{low} {mid} {high}");
+
+        let [_, low, mid, high] = levels(630.0);
+        assert!(mid > 0.6 && low < 0.3 && high < 0.3, "{low} {mid} {high}");
+
+        let [_, low, mid, high] = levels(8000.0);
+        assert!(high > 0.65 && mid < 0.2 && low < 0.01, "{low} {mid} {high}");
+    }
+}
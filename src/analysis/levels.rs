@@ -0,0 +1,95 @@
+//! Level and correlation meter
+//!
+//! The figures a stereo meter shows: RMS and peak level per channel, and the
+//! correlation between the channels. Correlation is +1 for identical
+//! channels, around 0 for unrelated ones and -1 when one is the other
+//! inverted, which on the scope is a diagonal line, a filled blob and the
+//! opposite diagonal.
+
+use crate::audio::XYSample;
+
+/// Level reported for a silent channel
+pub const SILENCE_DB: f32 = -120.0;
+
+/// Channel levels and correlation over a block of samples
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StereoLevels {
+    /// RMS levels in dBFS
+    pub rms_x_db: f32,
+    pub rms_y_db: f32,
+    /// Peak levels in dBFS
+    pub peak_x_db: f32,
+    pub peak_y_db: f32,
+    /// -1 to +1; 0 if either channel is silent
+    pub correlation: f32,
+}
+
+impl StereoLevels {
+    /// Measure a block of samples. Returns `None` for an empty block.
+    pub fn measure(samples: &[XYSample]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let (mut sxx, mut syy, mut sxy) = (0.0f64, 0.0f64, 0.0f64);
+        let (mut peak_x, mut peak_y) = (0.0f32, 0.0f32);
+        for s in samples {
+            let (x, y) = (s.x as f64, s.y as f64);
+            sxx += x * x;
+            syy += y * y;
+            sxy += x * y;
+            peak_x = peak_x.max(s.x.abs());
+            peak_y = peak_y.max(s.y.abs());
+        }
+
+        let n = samples.len() as f64;
+        let norm = (sxx * syy).sqrt();
+        let correlation = if norm > 1e-12 { sxy / norm } else { 0.0 };
+        Some(Self {
+            rms_x_db: to_db((sxx / n).sqrt() as f32),
+            rms_y_db: to_db((syy / n).sqrt() as f32),
+            peak_x_db: to_db(peak_x),
+            peak_y_db: to_db(peak_y),
+            correlation: correlation.clamp(-1.0, 1.0) as f32,
+        })
+    }
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 1e-6 {
+        20.0 * level.log10()
+    } else {
+        SILENCE_DB
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(gain_y: f32) -> Vec<XYSample> {
+        (0..4800)
+            .map(|i| {
+                let v = (i as f32 / 48.0 * std::f32::consts::TAU).sin() * 0.5;
+                XYSample::new(v, v * gain_y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_levels_and_correlation() {
+        let m = StereoLevels::measure(&sine(1.0)).unwrap();
+        // A 0.5 peak sine is -6 dB peak and -9 dB RMS
+        assert!((m.peak_x_db + 6.02).abs() < 0.05, "{m:?}");
+        assert!((m.rms_x_db + 9.03).abs() < 0.05, "{m:?}");
+        assert!((m.correlation - 1.0).abs() < 1e-4);
+
+        let m = StereoLevels::measure(&sine(-0.5)).unwrap();
+        assert!((m.rms_x_db - m.rms_y_db - 6.02).abs() < 0.05, "{m:?}");
+        assert!((m.correlation + 1.0).abs() < 1e-4);
+
+        let m = StereoLevels::measure(&sine(0.0)).unwrap();
+        assert_eq!(m.rms_y_db, SILENCE_DB);
+        assert_eq!(m.correlation, 0.0);
+        assert_eq!(StereoLevels::measure(&[]), None);
+    }
+}
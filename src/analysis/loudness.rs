@@ -0,0 +1,153 @@
+//! Momentary loudness
+//!
+//! Loudness as EBU R128 (ITU-R BS.1770) measures it: each channel goes
+//! through the K-weighting filter, a high shelf for the head's effect on
+//! what reaches the ear followed by a high pass that leaves out the lowest
+//! bass, and the mean square of both channels over the last 400 ms is
+//! given in LUFS. The window moves on in 100 ms steps, as in the standard.
+
+use std::collections::VecDeque;
+
+use super::envelope::Biquad;
+use super::levels::SILENCE_DB;
+use crate::audio::XYSample;
+
+/// Steps the window moves in, in seconds
+const BLOCK_SECONDS: f32 = 0.1;
+
+/// Steps per momentary window (400 ms)
+const BLOCKS: usize = 4;
+
+/// Stage 1 of the K-weighting: high shelf of about +4 dB above 1.5 kHz
+fn pre_filter(sample_rate: u32) -> Biquad {
+    const F0: f64 = 1681.974450955533;
+    const GAIN_DB: f64 = 3.999843853973347;
+    const Q: f64 = 0.7071752369554196;
+
+    let k = (std::f64::consts::PI * F0 / sample_rate as f64).tan();
+    let vh = 10f64.powf(GAIN_DB / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / Q + k * k;
+    Biquad::new(
+        [
+            ((vh + vb * k / Q + k * k) / a0) as f32,
+            (2.0 * (k * k - vh) / a0) as f32,
+            ((vh - vb * k / Q + k * k) / a0) as f32,
+        ],
+        [
+            (2.0 * (k * k - 1.0) / a0) as f32,
+            ((1.0 - k / Q + k * k) / a0) as f32,
+        ],
+    )
+}
+
+/// Stage 2 of the K-weighting: the RLB high pass at about 38 Hz
+fn rlb_filter(sample_rate: u32) -> Biquad {
+    const F0: f64 = 38.13547087602444;
+    const Q: f64 = 0.5003270373238773;
+
+    let k = (std::f64::consts::PI * F0 / sample_rate as f64).tan();
+    let a0 = 1.0 + k / Q + k * k;
+    Biquad::new(
+        [1.0, -2.0, 1.0],
+        [
+            (2.0 * (k * k - 1.0) / a0) as f32,
+            ((1.0 - k / Q + k * k) / a0) as f32,
+        ],
+    )
+}
+
+/// Follows the momentary loudness of a stereo stream
+#[derive(Clone, Debug)]
+pub struct LoudnessMeter {
+    /// Pre-filter and high pass for X, then for Y
+    filters: [[Biquad; 2]; 2],
+    block_len: usize,
+    /// Sum of both channels' weighted squares in the current block
+    sum: f64,
+    count: usize,
+    /// Mean squares of the last few complete blocks
+    blocks: VecDeque<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let channel = [pre_filter(sample_rate), rlb_filter(sample_rate)];
+        Self {
+            filters: [channel, channel],
+            block_len: ((sample_rate as f32 * BLOCK_SECONDS) as usize).max(1),
+            sum: 0.0,
+            count: 0,
+            blocks: VecDeque::with_capacity(BLOCKS),
+        }
+    }
+
+    pub fn push(&mut self, sample: XYSample) {
+        for (filters, v) in self.filters.iter_mut().zip([sample.x, sample.y]) {
+            let weighted = filters.iter_mut().fold(v, |v, filter| filter.process(v));
+            self.sum += (weighted * weighted) as f64;
+        }
+        self.count += 1;
+        if self.count == self.block_len {
+            if self.blocks.len() == BLOCKS {
+                self.blocks.pop_front();
+            }
+            self.blocks.push_back(self.sum / self.count as f64);
+            self.sum = 0.0;
+            self.count = 0;
+        }
+    }
+
+    /// Loudness of the last 400 ms in LUFS, once that much has been pushed
+    pub fn momentary(&self) -> Option<f32> {
+        if self.blocks.len() < BLOCKS {
+            return None;
+        }
+        let power = self.blocks.iter().sum::<f64>() / BLOCKS as f64;
+        Some(if power > 1e-12 {
+            (-0.691 + 10.0 * power.log10()) as f32
+        } else {
+            SILENCE_DB
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure(sample_rate: u32, x: f32, y: f32) -> Option<f32> {
+        let mut meter = LoudnessMeter::new(sample_rate);
+        for i in 0..sample_rate / 2 {
+            let v = (i as f32 / sample_rate as f32 * 997.0 * std::f32::consts::TAU).sin();
+            meter.push(XYSample::new(v * x, v * y));
+        }
+        meter.momentary()
+    }
+
+    #[test]
+    fn test_reference_levels() {
+        for rate in [44100, 48000, 96000] {
+            // A full scale 997 Hz sine in one channel reads -3.01 LUFS
+            let lufs = measure(rate, 1.0, 0.0).unwrap();
+            assert!((lufs + 3.01).abs() < 0.05, "{rate}: {lufs}");
+
+            // The same at -20 dBFS in both channels adds 3 dB back
+            let lufs = measure(rate, 0.1, 0.1).unwrap();
+            assert!((lufs + 20.0).abs() < 0.05, "{rate}: {lufs}");
+        }
+    }
+
+    #[test]
+    fn test_waits_for_a_full_window() {
+        let mut meter = LoudnessMeter::new(48000);
+        for _ in 0..4800 * 3 {
+            meter.push(XYSample::new(0.0, 0.0));
+        }
+        assert_eq!(meter.momentary(), None);
+        for _ in 0..4800 {
+            meter.push(XYSample::new(0.0, 0.0));
+        }
+        assert_eq!(meter.momentary(), Some(SILENCE_DB));
+    }
+}
@@ -0,0 +1,53 @@
+//! Analysis module - measurements taken from XY sample streams
+//!
+//! This module provides:
+//! - Beat detection (kicks, tempo and a beat clock)
+//! - DC meter (mean input over the last second)
+//! - Ellipse measurement (phase and gain between two sines)
+//! - Band levels for envelope followers
+//! - Stereo levels and correlation
+//! - Momentary loudness (EBU R128)
+//! - Pitch detection (YIN)
+//! - Lissajous frequency ratio and lock detection
+//! - Third-octave real-time analyzer built on the spectrum
+//! - Silence detection, for holding or blanking the display between songs
+//! - Spectrum analyzer with selectable window, overlap and averaging
+//! - A worker thread that runs the continuous measurements off the UI thread
+
+mod beat;
+mod dc;
+mod ellipse;
+mod envelope;
+mod levels;
+mod loudness;
+mod pitch;
+mod ratio;
+mod rta;
+mod silence;
+mod spectrum;
+mod worker;
+
+#[allow(unused_imports)]
+pub use beat::{BeatDetector, Beats};
+pub use dc::DcMeter;
+pub use ellipse::EllipseMeasurement;
+#[allow(unused_imports)]
+pub use envelope::{Band, BandLevels};
+#[allow(unused_imports)]
+pub use levels::StereoLevels;
+#[allow(unused_imports)]
+pub use loudness::LoudnessMeter;
+#[allow(unused_imports)]
+pub use pitch::Pitch;
+#[allow(unused_imports)]
+pub use ratio::FrequencyRatio;
+#[allow(unused_imports)]
+pub use rta::{band_edges, Ballistics, Rta, BAND_CENTERS};
+pub use silence::{SilenceAction, SilenceDetector};
+#[allow(unused_imports)]
+pub use spectrum::{
+    Spectrum, SpectrumAnalyzer, SpectrumSettings, SpectrumTrace, WindowFunction, MAX_OVERLAP,
+    SIZES as SPECTRUM_SIZES,
+};
+#[allow(unused_imports)]
+pub use worker::{Analysis, AnalysisWorker};
@@ -0,0 +1,139 @@
+//! Pitch detection
+//!
+//! YIN (de Cheveigné and Kawahara, 2002) on the mono sum of the input. The
+//! squared difference between the signal and a copy of itself delayed by
+//! `tau` dips towards zero at every multiple of the period. Normalising
+//! each difference by the mean of the ones before it flattens out the dip
+//! at `tau = 0` and lets one threshold pick the first true dip, which is the
+//! period rather than a multiple of it. Parabolic interpolation around that
+//! dip gives the period to a fraction of a sample.
+
+use crate::audio::XYSample;
+
+/// Lowest pitch looked for, in Hz
+pub const MIN_HZ: f32 = 50.0;
+
+/// Highest pitch looked for, in Hz
+pub const MAX_HZ: f32 = 2000.0;
+
+/// Samples compared at each delay
+const INTEGRATION: usize = 2048;
+
+/// Normalised difference a dip has to reach to count as a period
+const THRESHOLD: f32 = 0.15;
+
+/// Mean square below which the input counts as silence
+const MIN_POWER: f32 = 1e-8;
+
+/// A detected pitch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pitch {
+    /// Fundamental frequency in Hz
+    pub hz: f32,
+    /// 0 to 1; how closely one period repeats the next
+    pub clarity: f32,
+}
+
+impl Pitch {
+    /// Measure the newest samples of a block at `sample_rate`. Returns
+    /// `None` for silence, for a block too short to hold the lowest pitch,
+    /// and for anything without a clear period.
+    pub fn measure(samples: &[XYSample], sample_rate: u32) -> Option<Self> {
+        let rate = sample_rate as f32;
+        let min_tau = ((rate / MAX_HZ).floor() as usize).max(2);
+        let max_tau = (rate / MIN_HZ).ceil() as usize;
+        let len = INTEGRATION + max_tau + 1;
+        let start = samples.len().checked_sub(len)?;
+        let mono: Vec<f32> = samples[start..].iter().map(|s| (s.x + s.y) * 0.5).collect();
+        let power = mono[..INTEGRATION].iter().map(|v| v * v).sum::<f32>() / INTEGRATION as f32;
+        if power < MIN_POWER {
+            return None;
+        }
+
+        // Cumulative mean normalised difference, from tau = 1 on
+        let mut cmnd = vec![1.0f32; max_tau + 2];
+        let mut running = 0.0f32;
+        for tau in 1..=max_tau + 1 {
+            let d: f32 = mono[..INTEGRATION]
+                .iter()
+                .zip(&mono[tau..tau + INTEGRATION])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            running += d;
+            cmnd[tau] = if running > 0.0 {
+                d * tau as f32 / running
+            } else {
+                1.0
+            };
+        }
+
+        // First dip under the threshold, followed down to its bottom
+        let mut tau = (min_tau..=max_tau).find(|&tau| cmnd[tau] < THRESHOLD)?;
+        while tau < max_tau && cmnd[tau + 1] < cmnd[tau] {
+            tau += 1;
+        }
+
+        let (a, b, c) = (cmnd[tau - 1], cmnd[tau], cmnd[tau + 1]);
+        let curve = a - 2.0 * b + c;
+        let offset = if curve > 0.0 {
+            (0.5 * (a - c) / curve).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+        Some(Self {
+            hz: rate / (tau as f32 + offset),
+            clarity: (1.0 - b).clamp(0.0, 1.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(hz: f32, harmonics: &[f32]) -> Vec<XYSample> {
+        (0..4096)
+            .map(|i| {
+                let t = i as f32 / 48000.0;
+                let v: f32 = harmonics
+                    .iter()
+                    .enumerate()
+                    .map(|(h, gain)| gain * (t * hz * (h + 1) as f32 * std::f32::consts::TAU).sin())
+                    .sum();
+                XYSample::new(v * 0.5, v * 0.5)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_finds_the_fundamental() {
+        for hz in [55.0, 220.0, 261.63, 1760.0] {
+            let pitch = Pitch::measure(&tone(hz, &[1.0]), 48000).unwrap();
+            assert!((pitch.hz - hz).abs() < hz * 0.002, "{hz}: {}", pitch.hz);
+            assert!(pitch.clarity > 0.9);
+        }
+
+        // Strong overtones don't pull it up an octave
+        let pitch = Pitch::measure(&tone(110.0, &[0.4, 1.0, 0.8]), 48000).unwrap();
+        assert!((pitch.hz - 110.0).abs() < 0.5, "{}", pitch.hz);
+    }
+
+    #[test]
+    fn test_no_pitch_in_silence_or_noise() {
+        let silence = vec![XYSample::new(0.0, 0.0); 4096];
+        assert_eq!(Pitch::measure(&silence, 48000), None);
+
+        let mut seed = 1u32;
+        let noise: Vec<XYSample> = (0..4096)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                let v = (seed >> 8) as f32 / (1 << 24) as f32 - 0.5;
+                XYSample::new(v, v)
+            })
+            .collect();
+        assert_eq!(Pitch::measure(&noise, 48000), None);
+
+        // Too short for the lowest pitch
+        assert_eq!(Pitch::measure(&tone(440.0, &[1.0])[..1024], 48000), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! Lissajous frequency ratio
+//!
+//! A Lissajous figure stands still when the channel frequencies are in a
+//! ratio of small whole numbers, and its shape (the number of lobes along
+//! each axis) gives that ratio away. Here each channel's frequency is
+//! estimated on its own from the spacing of its rising zero crossings,
+//! interpolated between samples, and the ratio is matched against fractions
+//! with small terms.
+//!
+//! Close to a ratio is not the same as on it: with `fx:fy = p:q`, the
+//! figure rolls through its shapes at `q·fx - p·fy` cycles per second. That
+//! drift is what tells a locked figure from one that is slowly turning.
+
+use crate::audio::XYSample;
+
+/// Largest term of a ratio worth naming; beyond this figures are too busy
+/// to read
+pub const MAX_TERM: u32 = 9;
+
+/// Relative distance from `p/q` still counted as that ratio
+const RATIO_TOLERANCE: f64 = 0.005;
+
+/// Figures rolling slower than this are shown as locked
+const LOCK_DRIFT_HZ: f64 = 0.1;
+
+/// Largest spread of the crossing intervals, relative to the mean period,
+/// for a channel to count as a steady tone
+const MAX_PERIOD_JITTER: f64 = 0.05;
+
+/// Frequency ratio between the channels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrequencyRatio {
+    /// Ratio terms, `x:y = fx:fy`, in lowest terms
+    pub x: u32,
+    pub y: u32,
+    /// Channel frequencies in cycles per sample
+    pub freq_x: f64,
+    pub freq_y: f64,
+}
+
+impl FrequencyRatio {
+    /// Measure a block of samples. Returns `None` unless both channels
+    /// are steady tones whose frequencies are close to a small ratio.
+    pub fn measure(samples: &[XYSample]) -> Option<Self> {
+        let freq_x = frequency(samples, |s| s.x)?;
+        let freq_y = frequency(samples, |s| s.y)?;
+        let ratio = freq_x / freq_y;
+        (1..=MAX_TERM).find_map(|y| {
+            let x = (ratio * y as f64).round();
+            let near = (x / y as f64 - ratio).abs() <= RATIO_TOLERANCE * ratio;
+            (near && (1.0..=MAX_TERM as f64).contains(&x)).then_some(Self {
+                x: x as u32,
+                y,
+                freq_x,
+                freq_y,
+            })
+        })
+    }
+
+    /// How fast the figure rolls, in cycles per second at `sample_rate`
+    pub fn drift_hz(&self, sample_rate: u32) -> f64 {
+        (self.y as f64 * self.freq_x - self.x as f64 * self.freq_y) * sample_rate as f64
+    }
+
+    /// Whether the figure stands still at `sample_rate`
+    pub fn is_locked(&self, sample_rate: u32) -> bool {
+        self.drift_hz(sample_rate).abs() < LOCK_DRIFT_HZ
+    }
+}
+
+/// Frequency of one channel in cycles per sample, from its rising zero
+/// crossings. `None` for silence, fewer than two whole periods, or a
+/// channel whose crossings aren't evenly spaced.
+fn frequency(samples: &[XYSample], channel: impl Fn(&XYSample) -> f32) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|s| channel(s) as f64).sum::<f64>() / n;
+    let rms = (samples
+        .iter()
+        .map(|s| (channel(s) as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n)
+        .sqrt();
+    if rms < 1e-6 {
+        return None;
+    }
+
+    // Hysteresis: after a crossing the signal has to dip below -hysteresis
+    // before the next one counts, so noise riding on a slow slope can't
+    // add crossings
+    let hysteresis = 0.1 * rms;
+    let mut armed = false;
+    let mut crossings = Vec::new();
+    let mut prev = channel(&samples[0]) as f64 - mean;
+    for (i, s) in samples.iter().enumerate().skip(1) {
+        let v = channel(s) as f64 - mean;
+        if v < -hysteresis {
+            armed = true;
+        }
+        if armed && prev < 0.0 && v >= 0.0 {
+            crossings.push((i - 1) as f64 + prev / (prev - v));
+            armed = false;
+        }
+        prev = v;
+    }
+    if crossings.len() < 3 {
+        return None;
+    }
+
+    let period = (crossings[crossings.len() - 1] - crossings[0]) / (crossings.len() - 1) as f64;
+    let steady = crossings
+        .windows(2)
+        .all(|w| ((w[1] - w[0]) - period).abs() <= MAX_PERIOD_JITTER * period);
+    steady.then(|| 1.0 / period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48_000;
+
+    fn tones(freq_x: f32, freq_y: f32) -> Vec<XYSample> {
+        (0..4096)
+            .map(|i| {
+                let t = i as f32 / RATE as f32 * std::f32::consts::TAU;
+                XYSample::new(0.5 * (t * freq_x).sin(), 0.5 * (t * freq_y + 0.3).cos())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ratio_and_lock() {
+        let ratio = FrequencyRatio::measure(&tones(600.0, 400.0)).unwrap();
+        assert_eq!((ratio.x, ratio.y), (3, 2));
+        assert!((ratio.freq_x * RATE as f64 - 600.0).abs() < 0.01);
+        assert!(ratio.is_locked(RATE), "drift {}", ratio.drift_hz(RATE));
+
+        // Reduced to lowest terms
+        let ratio = FrequencyRatio::measure(&tones(500.0, 1000.0)).unwrap();
+        assert_eq!((ratio.x, ratio.y), (1, 2));
+
+        // Detuned: still 3:2, but rolling at 2·600 - 3·401 = -3 Hz
+        let ratio = FrequencyRatio::measure(&tones(600.0, 401.0)).unwrap();
+        assert_eq!((ratio.x, ratio.y), (3, 2));
+        assert!((ratio.drift_hz(RATE) + 3.0).abs() < 0.05);
+        assert!(!ratio.is_locked(RATE));
+    }
+
+    #[test]
+    fn test_rejects_unsteady_or_unrelated_channels() {
+        // No small ratio near 1000:617
+        assert_eq!(FrequencyRatio::measure(&tones(1000.0, 617.0)), None);
+
+        let mut state = 1u32;
+        let mut noise = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 - 0.5
+        };
+        let samples: Vec<XYSample> = (0..4096).map(|_| XYSample::new(noise(), noise())).collect();
+        assert_eq!(FrequencyRatio::measure(&samples), None);
+
+        let silent = vec![XYSample::new(0.0, 0.0); 4096];
+        assert_eq!(FrequencyRatio::measure(&silent), None);
+        assert_eq!(FrequencyRatio::measure(&[]), None);
+    }
+}
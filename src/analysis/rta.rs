@@ -0,0 +1,225 @@
+//! Third-octave real-time analyzer
+//!
+//! Folds the spectrum analyzer's bins into the 31 standard third-octave
+//! bands from 20 Hz to 20 kHz (IEC 61260 base-ten centers) and smooths each
+//! band's level with sound-level-meter ballistics. A band's power is the
+//! sum of the bins inside it, divided by the window's noise bandwidth so a
+//! tone reads the same as in the spectrum. Bands narrower than a bin take
+//! the bin nearest their center; a longer FFT resolves the bottom octaves
+//! properly.
+//!
+//! The analysis worker folds in every frame the analyzer produces, with
+//! ballistics over the time one hop spans, so the response doesn't depend on
+//! how often the UI looks or how many frames come between its looks.
+
+use serde::{Deserialize, Serialize};
+
+use super::spectrum::{SpectrumAnalyzer, WindowFunction};
+
+/// Nominal band centers in Hz, as labelled on the front of every RTA
+pub const BAND_CENTERS: [f32; 31] = [
+    20.0, 25.0, 31.5, 40.0, 50.0, 63.0, 80.0, 100.0, 125.0, 160.0, 200.0, 250.0, 315.0, 400.0,
+    500.0, 630.0, 800.0, 1000.0, 1250.0, 1600.0, 2000.0, 2500.0, 3150.0, 4000.0, 5000.0, 6300.0,
+    8000.0, 10000.0, 12500.0, 16000.0, 20000.0,
+];
+
+/// Index of the 1 kHz band, the reference the exact centers count from
+const REFERENCE_BAND: usize = 17;
+
+/// Power of an empty band (-200 dB)
+const FLOOR: f32 = 1e-20;
+
+/// Exact center of band `i`; the nominal values are rounded from these
+pub fn band_center(i: usize) -> f32 {
+    1000.0 * 10f32.powf((i as f32 - REFERENCE_BAND as f32) / 10.0)
+}
+
+/// Lower and upper edges of band `i`
+pub fn band_edges(i: usize) -> (f32, f32) {
+    let center = band_center(i);
+    let half = 10f32.powf(0.05);
+    (center / half, center * half)
+}
+
+/// Time weighting of the band levels
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Ballistics {
+    /// 125 ms, follows speech and music
+    #[default]
+    Fast,
+    /// 1 s, for steady readings of noise
+    Slow,
+}
+
+impl Ballistics {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fast => "Fast",
+            Self::Slow => "Slow",
+        }
+    }
+
+    pub fn all() -> &'static [Ballistics] {
+        &[Self::Fast, Self::Slow]
+    }
+
+    /// Exponential time constant in seconds
+    pub fn time_constant(&self) -> f32 {
+        match self {
+            Self::Fast => 0.125,
+            Self::Slow => 1.0,
+        }
+    }
+}
+
+/// Smoothed third-octave levels of both channels
+#[derive(Clone, Debug, Default)]
+pub struct Rta {
+    pub ballistics: Ballistics,
+    /// Band power, X then Y; empty until the first frame
+    power: [Vec<f32>; 2],
+}
+
+impl Rta {
+    pub fn new(ballistics: Ballistics) -> Self {
+        Self {
+            ballistics,
+            ..Self::default()
+        }
+    }
+
+    /// Fold in the newest frame of `analyzer`; call once for every frame
+    /// it analyses
+    pub fn push(&mut self, analyzer: &SpectrumAnalyzer, sample_rate: u32) {
+        let settings = analyzer.settings();
+        let elapsed = settings.hop() as f32 / sample_rate as f32;
+        let alpha = 1.0 - (-elapsed / self.ballistics.time_constant()).exp();
+        for (channel, power) in self.power.iter_mut().enumerate() {
+            let bands = bands(
+                analyzer.latest_power(channel),
+                settings.size,
+                settings.window,
+                sample_rate,
+            );
+            if power.is_empty() {
+                *power = bands;
+            } else {
+                for (power, band) in power.iter_mut().zip(bands) {
+                    *power += (band - *power) * alpha;
+                }
+            }
+        }
+    }
+
+    /// Level of each band in dB, for X (`channel` 0) or Y (1); empty until
+    /// the first frame
+    pub fn levels(&self, channel: usize) -> Vec<f32> {
+        self.power[channel]
+            .iter()
+            .map(|p| 10.0 * p.max(FLOOR).log10())
+            .collect()
+    }
+}
+
+/// Power in each band from one frame's power per bin
+fn bands(power: &[f32], size: usize, window: WindowFunction, sample_rate: u32) -> Vec<f32> {
+    let bin_hz = sample_rate as f32 / size as f32;
+    let bandwidth = window.noise_bandwidth();
+    (0..BAND_CENTERS.len())
+        .map(|i| {
+            let (low, high) = band_edges(i);
+            let first = (low / bin_hz).ceil() as usize;
+            let last = ((high / bin_hz).ceil() as usize).min(power.len());
+            if first < last {
+                power[first..last].iter().sum::<f32>() / bandwidth
+            } else {
+                let nearest = (band_center(i) / bin_hz).round() as usize;
+                power.get(nearest).copied().unwrap_or(FLOOR)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::spectrum::{SpectrumAnalyzer, SpectrumSettings};
+    use crate::audio::XYSample;
+    use std::f32::consts::TAU;
+
+    const RATE: u32 = 48_000;
+
+    /// Run `count` samples of a sine through an analyzer, folding each of
+    /// its frames into `rtas`
+    fn analyse(
+        analyzer: &mut SpectrumAnalyzer,
+        rtas: &mut [&mut Rta],
+        freq: f32,
+        amplitude: f32,
+        count: usize,
+    ) {
+        for i in 0..count {
+            let x = amplitude * (TAU * freq * i as f32 / RATE as f32).sin();
+            if analyzer.push(XYSample::new(x, 0.0)) {
+                for rta in rtas.iter_mut() {
+                    rta.push(analyzer, RATE);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bands_follow_the_standard() {
+        assert!((band_center(0) - 20.0).abs() < 0.1);
+        assert!((band_center(REFERENCE_BAND) - 1000.0).abs() < 0.01);
+        for (i, nominal) in BAND_CENTERS.iter().enumerate() {
+            assert!((band_center(i) / nominal - 1.0).abs() < 0.03, "{nominal}");
+        }
+        // Bands tile: each one's top is the next one's bottom
+        assert!((band_edges(10).1 - band_edges(11).0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_tone_reads_its_level_in_its_band() {
+        let mut analyzer = SpectrumAnalyzer::new(SpectrumSettings {
+            size: 8192,
+            averages: 1,
+            ..SpectrumSettings::default()
+        });
+        // 1 kHz at amplitude 0.5, off any bin center: -6 dB in band 17
+        let mut rta = Rta::default();
+        analyse(&mut analyzer, &mut [&mut rta], 1000.0, 0.5, 16384);
+        let levels = rta.levels(0);
+        assert!((levels[REFERENCE_BAND] + 6.02).abs() < 0.2, "{levels:?}");
+        assert!(levels[REFERENCE_BAND + 3] < -40.0);
+    }
+
+    #[test]
+    fn test_ballistics_smooth_level_changes() {
+        let settings = SpectrumSettings {
+            size: 4096,
+            overlap: 0.0,
+            averages: 1,
+            ..SpectrumSettings::default()
+        };
+        let mut analyzer = SpectrumAnalyzer::new(settings);
+        let mut rta = Rta::default();
+        analyse(&mut analyzer, &mut [&mut rta], 1000.0, 0.5, 4096);
+        let loud = rta.levels(0)[REFERENCE_BAND];
+
+        // Eleven frames (0.94 s) of silence: fast all but forgets the tone,
+        // slow keeps 39% of its power, 4.1 dB down
+        let mut slow = rta.clone();
+        slow.ballistics = Ballistics::Slow;
+        analyse(
+            &mut analyzer,
+            &mut [&mut rta, &mut slow],
+            1000.0,
+            0.0,
+            RATE as usize,
+        );
+        assert!(rta.levels(0)[REFERENCE_BAND] < loud - 30.0);
+        let drop = loud - slow.levels(0)[REFERENCE_BAND];
+        assert!((drop - 4.1).abs() < 0.2, "{drop}");
+    }
+}
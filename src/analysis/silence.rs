@@ -0,0 +1,120 @@
+//! Silence detection
+//!
+//! Between songs a live input still carries hiss and hum, which the scope
+//! faithfully draws as a restless dot in the middle of the screen. The
+//! detector watches the input's peak level and reports silence once it has
+//! stayed under a threshold for a while, so the display can do something
+//! better with the gap.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// What the display does while the input is silent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SilenceAction {
+    /// Keep drawing the input as it is
+    #[default]
+    Off,
+    /// Freeze the last figure
+    Hold,
+    /// Let the figure fade out and leave the screen dark
+    Blank,
+    /// Show the demo scene until the input comes back
+    Demo,
+}
+
+impl SilenceAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Hold => "Hold last figure",
+            Self::Blank => "Fade to black",
+            Self::Demo => "Show demo",
+        }
+    }
+
+    pub fn all() -> &'static [SilenceAction] {
+        &[Self::Off, Self::Hold, Self::Blank, Self::Demo]
+    }
+}
+
+/// Tracks how long the input has been quiet
+#[derive(Clone, Debug)]
+pub struct SilenceDetector {
+    pub action: SilenceAction,
+    /// Peak level in dBFS below which the input counts as quiet
+    pub threshold_db: f32,
+    /// How long the input has to stay quiet, in seconds
+    pub delay: f32,
+    quiet_since: Option<Instant>,
+    silent: bool,
+}
+
+impl Default for SilenceDetector {
+    fn default() -> Self {
+        Self {
+            action: SilenceAction::Off,
+            threshold_db: -50.0,
+            delay: 3.0,
+            quiet_since: None,
+            silent: false,
+        }
+    }
+}
+
+impl SilenceDetector {
+    /// Feed the latest peak level in dBFS (`None` when no samples are
+    /// arriving, which counts as quiet). Returns whether the input is now
+    /// silent; never while the action is `Off`.
+    pub fn update(&mut self, now: Instant, peak_db: Option<f32>) -> bool {
+        let quiet = peak_db.is_none_or(|db| db < self.threshold_db);
+        if self.action == SilenceAction::Off || !quiet {
+            self.reset();
+            return false;
+        }
+        let since = *self.quiet_since.get_or_insert(now);
+        self.silent = now.duration_since(since) >= Duration::from_secs_f32(self.delay.max(0.0));
+        self.silent
+    }
+
+    /// Forget the quiet stretch, e.g. when the source stops
+    pub fn reset(&mut self) {
+        self.quiet_since = None;
+        self.silent = false;
+    }
+
+    /// The action to take right now, if the input is silent
+    pub fn active(&self) -> Option<SilenceAction> {
+        self.silent.then_some(self.action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_silent_after_the_delay() {
+        let start = Instant::now();
+        let at = |secs: f32| start + Duration::from_secs_f32(secs);
+        let mut detector = SilenceDetector {
+            action: SilenceAction::Hold,
+            ..SilenceDetector::default()
+        };
+
+        assert!(!detector.update(at(0.0), Some(-20.0)));
+        assert!(!detector.update(at(1.0), Some(-70.0)));
+        assert!(!detector.update(at(3.5), Some(-70.0)));
+        assert!(detector.update(at(4.0), None));
+        assert_eq!(detector.active(), Some(SilenceAction::Hold));
+
+        // Sound brings the display back at once, and the wait starts over
+        assert!(!detector.update(at(4.1), Some(-10.0)));
+        assert_eq!(detector.active(), None);
+        assert!(!detector.update(at(5.0), Some(-70.0)));
+
+        detector.action = SilenceAction::Off;
+        assert!(!detector.update(at(20.0), Some(-70.0)));
+    }
+}
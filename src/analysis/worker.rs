@@ -0,0 +1,339 @@
+//! Analysis worker
+//!
+//! Measurements run over thousands of samples and would eat into the UI
+//! frame if done there. The worker thread reads its own copy of the sample
+//! stream from a buffer tap, measures the newest window every time another
+//! hop's worth of samples has arrived, and publishes the result through a
+//! triple buffer. The UI reads the latest complete `Analysis` without
+//! locking; neither side ever waits for the other.
+//!
+//! Each window gets levels and correlation, and, when the figure fits an
+//! ellipse well enough, the phase and amplitude ratio between the channels.
+//! Steady tones in a small whole-number ratio get that ratio. Once the UI
+//! has told it the sample rate, the worker also measures the level of each
+//! band for the envelope followers, follows the beat and the momentary
+//! loudness, and detects the pitch of the mono sum.
+//!
+//! While the UI asks for one, the worker also runs the spectrum analyzer
+//! over every sample it reads. The spectrum goes out through a triple
+//! buffer of its own, since it is published at the analyzer's frame rate
+//! rather than once per hop. The third-octave analyzer, when asked for,
+//! folds in every one of those frames and goes out with the spectrum.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ringbuf::traits::{Consumer, Observer};
+use triple_buffer::TripleBuffer;
+
+use super::beat::{BeatDetector, Beats};
+use super::ellipse::EllipseMeasurement;
+use super::envelope::BandLevels;
+use super::levels::StereoLevels;
+use super::loudness::LoudnessMeter;
+use super::pitch::Pitch;
+use super::ratio::FrequencyRatio;
+use super::rta::{Ballistics, Rta};
+use super::spectrum::{Spectrum, SpectrumAnalyzer, SpectrumSettings};
+use crate::audio::{SampleBuffer, XYSample};
+
+/// Samples each measurement looks at
+const WINDOW: usize = 4096;
+
+/// New samples between measurements
+const HOP: usize = 1024;
+
+/// Sleep while waiting for samples
+const IDLE_WAIT: Duration = Duration::from_millis(5);
+
+/// Measurements are withdrawn once no samples have come for this long
+const STALE_AFTER: Duration = Duration::from_millis(500);
+
+/// Ellipse fits worse than this aren't two sines of one frequency, and
+/// their phase and amplitude ratio mean nothing
+const MAX_FIT_ERROR: f32 = 0.1;
+
+/// One published set of measurements
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Analysis {
+    /// Samples the worker has read so far
+    pub samples: u64,
+    /// Running sums of those samples' X and Y, for means over any stretch
+    pub sum_x: f64,
+    pub sum_y: f64,
+    pub levels: Option<StereoLevels>,
+    /// Phase and amplitude ratio, while the figure is a clean ellipse
+    pub ellipse: Option<EllipseMeasurement>,
+    /// Frequency ratio, while both channels are steady tones
+    pub ratio: Option<FrequencyRatio>,
+    /// RMS of each `Band` over the last hop; zero until the sample rate is
+    /// known
+    pub bands: [f32; 4],
+    /// Kicks, beats and tempo so far; the counts start over with the
+    /// sample rate
+    pub beats: Beats,
+    /// Pitch of the mono sum, while it has a clear one
+    pub pitch: Option<Pitch>,
+    /// Momentary loudness in LUFS, once 400 ms have been read at a known
+    /// sample rate
+    pub loudness: Option<f32>,
+}
+
+/// Settings sent to the thread
+enum Command {
+    Spectrum(Option<SpectrumSettings>),
+    Rta(Option<Ballistics>),
+    SampleRate(Option<u32>),
+}
+
+/// Handle to the analysis thread; stops it when dropped
+pub struct AnalysisWorker {
+    output: triple_buffer::Output<Analysis>,
+    spectrum: triple_buffer::Output<Spectrum>,
+    commands: mpsc::Sender<Command>,
+    /// Settings last sent to the thread
+    spectrum_sent: Option<SpectrumSettings>,
+    rta_sent: Option<Ballistics>,
+    sample_rate_sent: Option<u32>,
+    running: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl AnalysisWorker {
+    /// Tap `buffer` and start analysing everything pushed into it
+    pub fn start(buffer: &SampleBuffer) -> Self {
+        let tap = buffer.open_tap(WINDOW * 4);
+        let (input, output) = TripleBuffer::new(&Analysis::default()).split();
+        let (spectrum_input, spectrum) = TripleBuffer::new(&Spectrum::default()).split();
+        let (commands, received) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_running = Arc::clone(&running);
+        let handle = Some(thread::spawn(move || {
+            run(tap, input, spectrum_input, received, &thread_running)
+        }));
+
+        Self {
+            output,
+            spectrum,
+            commands,
+            spectrum_sent: None,
+            rta_sent: None,
+            sample_rate_sent: None,
+            running,
+            handle,
+        }
+    }
+
+    /// The most recent complete analysis
+    pub fn latest(&mut self) -> &Analysis {
+        self.output.read()
+    }
+
+    /// Run the spectrum analyzer with `settings`, or stop it with `None`.
+    /// New settings start the traces over.
+    pub fn set_spectrum(&mut self, settings: Option<SpectrumSettings>) {
+        if settings != self.spectrum_sent {
+            self.spectrum_sent = settings;
+            let _ = self.commands.send(Command::Spectrum(settings));
+        }
+    }
+
+    /// Start the spectrum's averages and peaks over
+    pub fn reset_spectrum(&mut self) {
+        let _ = self.commands.send(Command::Spectrum(self.spectrum_sent));
+    }
+
+    /// Run the third-octave analyzer with `ballistics` over every spectrum
+    /// frame, or stop it with `None`. It needs the spectrum and the sample
+    /// rate too.
+    pub fn set_rta(&mut self, ballistics: Option<Ballistics>) {
+        if ballistics != self.rta_sent {
+            self.rta_sent = ballistics;
+            let _ = self.commands.send(Command::Rta(ballistics));
+        }
+    }
+
+    /// Tell the worker the rate of the stream, which the band filters, the
+    /// beat detector, the loudness meter and the pitch detector are tuned for
+    pub fn set_sample_rate(&mut self, sample_rate: Option<u32>) {
+        if sample_rate != self.sample_rate_sent {
+            self.sample_rate_sent = sample_rate;
+            let _ = self.commands.send(Command::SampleRate(sample_rate));
+        }
+    }
+
+    /// The most recent spectrum; empty while the analyzer is stopped
+    pub fn spectrum(&mut self) -> &Spectrum {
+        self.spectrum.read()
+    }
+}
+
+impl Drop for AnalysisWorker {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn run(
+    mut tap: ringbuf::HeapCons<XYSample>,
+    mut input: triple_buffer::Input<Analysis>,
+    mut spectrum_input: triple_buffer::Input<Spectrum>,
+    commands: mpsc::Receiver<Command>,
+    running: &AtomicBool,
+) {
+    let mut window = VecDeque::with_capacity(WINDOW);
+    let mut analysis = Analysis::default();
+    let mut since_last = 0;
+    let mut last_arrival = Instant::now();
+    let mut analyzer: Option<SpectrumAnalyzer> = None;
+    let mut band_levels: Option<BandLevels> = None;
+    let mut beat: Option<BeatDetector> = None;
+    let mut loudness: Option<LoudnessMeter> = None;
+    let mut sample_rate: Option<u32> = None;
+    let mut rta_ballistics: Option<Ballistics> = None;
+    let mut rta: Option<Rta> = None;
+
+    while running.load(Ordering::Relaxed) {
+        for command in commands.try_iter() {
+            match command {
+                Command::Spectrum(settings) => {
+                    analyzer = settings.map(SpectrumAnalyzer::new);
+                    rta = rta_ballistics.map(Rta::new);
+                    spectrum_input.write(Spectrum::default());
+                }
+                Command::Rta(ballistics) => {
+                    rta_ballistics = ballistics;
+                    match (rta.as_mut(), ballistics) {
+                        (Some(rta), Some(ballistics)) => rta.ballistics = ballistics,
+                        _ => rta = ballistics.map(Rta::new),
+                    }
+                }
+                Command::SampleRate(rate) => {
+                    band_levels = rate.map(BandLevels::new);
+                    beat = rate.map(BeatDetector::new);
+                    loudness = rate.map(LoudnessMeter::new);
+                    rta = rta_ballistics.map(Rta::new);
+                    sample_rate = rate;
+                    analysis.bands = [0.0; 4];
+                    analysis.beats = Beats::default();
+                    analysis.pitch = None;
+                    analysis.loudness = None;
+                }
+            }
+        }
+        if !tap.is_empty() {
+            last_arrival = Instant::now();
+        }
+        let mut new_frame = false;
+        for sample in tap.pop_iter() {
+            if window.len() == WINDOW {
+                window.pop_front();
+            }
+            window.push_back(sample);
+            since_last += 1;
+            analysis.samples += 1;
+            analysis.sum_x += sample.x as f64;
+            analysis.sum_y += sample.y as f64;
+            if let Some(analyzer) = analyzer.as_mut() {
+                if analyzer.push(sample) {
+                    new_frame = true;
+                    if let (Some(rta), Some(rate)) = (rta.as_mut(), sample_rate) {
+                        rta.push(analyzer, rate);
+                    }
+                }
+            }
+            if let Some(band_levels) = band_levels.as_mut() {
+                band_levels.push(sample);
+            }
+            if let Some(beat) = beat.as_mut() {
+                beat.push(sample);
+            }
+            if let Some(loudness) = loudness.as_mut() {
+                loudness.push(sample);
+            }
+        }
+        if let Some(analyzer) = analyzer.as_ref().filter(|_| new_frame) {
+            let mut spectrum = analyzer.spectrum();
+            if let Some(rta) = rta.as_ref() {
+                spectrum.bands = [rta.levels(0), rta.levels(1)];
+            }
+            spectrum_input.write(spectrum);
+        }
+        if since_last < HOP {
+            // A stopped source leaves no figure, so no readings either
+            if analysis.levels.is_some() && last_arrival.elapsed() > STALE_AFTER {
+                window.clear();
+                analysis.levels = None;
+                analysis.ellipse = None;
+                analysis.ratio = None;
+                analysis.bands = [0.0; 4];
+                analysis.beats.bpm = None;
+                analysis.pitch = None;
+                analysis.loudness = None;
+                if let Some(rate) = sample_rate {
+                    loudness = Some(LoudnessMeter::new(rate));
+                }
+                input.write(analysis);
+            }
+            thread::sleep(IDLE_WAIT);
+            continue;
+        }
+        since_last = 0;
+
+        let samples = window.make_contiguous();
+        analysis.levels = StereoLevels::measure(samples);
+        analysis.ellipse =
+            EllipseMeasurement::measure(samples).filter(|m| m.fit_error < MAX_FIT_ERROR);
+        analysis.ratio = FrequencyRatio::measure(samples);
+        if let Some(band_levels) = band_levels.as_mut() {
+            analysis.bands = band_levels.take();
+        }
+        if let Some(beat) = beat.as_ref() {
+            analysis.beats = beat.beats();
+        }
+        analysis.pitch = sample_rate.and_then(|rate| Pitch::measure(samples, rate));
+        analysis.loudness = loudness.as_ref().and_then(LoudnessMeter::momentary);
+        input.write(analysis);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_publishes_measurements_of_the_stream() {
+        let buffer = SampleBuffer::new(1024);
+        let mut worker = AnalysisWorker::start(&buffer);
+        assert!(worker.latest().levels.is_none());
+
+        // Identical channels: fully correlated
+        let block: Vec<XYSample> = (0..HOP)
+            .map(|i| {
+                let v = (i as f32 * 0.05).sin() * 0.5;
+                XYSample::new(v, v)
+            })
+            .collect();
+        buffer.push_slice(&block);
+
+        let started = Instant::now();
+        while worker.latest().levels.is_none() {
+            assert!(started.elapsed() < Duration::from_secs(5), "no analysis");
+            thread::sleep(IDLE_WAIT);
+        }
+        let analysis = *worker.latest();
+        assert_eq!(analysis.samples, HOP as u64);
+        let levels = analysis.levels.unwrap();
+        assert!((levels.correlation - 1.0).abs() < 1e-4);
+        let ellipse = analysis.ellipse.expect("a sine on both channels");
+        assert!(ellipse.phase_deg.abs() < 0.5 && ellipse.gain_db.abs() < 0.05);
+    }
+}
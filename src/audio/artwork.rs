@@ -0,0 +1,170 @@
+//! Cover art colors
+//!
+//! Files often carry their album's cover as an embedded PNG or JPEG. The
+//! cover is decoded, JPEGs at reduced size, and a grid of its pixels is
+//! binned by color. The fullest bins, with nearby ones merged into them,
+//! are the cover's dominant colors.
+
+use std::io::Cursor;
+
+/// Most colors returned
+const MAX_COLORS: usize = 5;
+
+/// Most pixels looked at
+const MAX_SAMPLES: usize = 16_384;
+
+/// Side JPEGs are scaled down towards while decoding
+const JPEG_SIZE: u16 = 128;
+
+/// RGB distance under which two bins are one color
+const MERGE_DISTANCE: f32 = 64.0;
+
+/// Dominant colors of an encoded PNG or JPEG, most prominent first; `None`
+/// if it can't be decoded
+pub fn cover_colors(data: &[u8]) -> Option<Vec<[u8; 3]>> {
+    let pixels = if data.starts_with(b"\x89PNG") {
+        decode_png(data)
+    } else if data.starts_with(&[0xFF, 0xD8]) {
+        decode_jpeg(data)
+    } else {
+        None
+    }?;
+    (!pixels.is_empty()).then(|| dominant_colors(&pixels))
+}
+
+fn decode_png(data: &[u8]) -> Option<Vec<[u8; 3]>> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf).ok()?;
+    let bytes = &buf[..frame.buffer_size()];
+    let pixels = match frame.color_type {
+        png::ColorType::Rgb => bytes.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect(),
+        png::ColorType::Rgba => bytes.chunks_exact(4).map(|p| [p[0], p[1], p[2]]).collect(),
+        png::ColorType::Grayscale => bytes.iter().map(|&v| [v; 3]).collect(),
+        png::ColorType::GrayscaleAlpha => bytes.chunks_exact(2).map(|p| [p[0]; 3]).collect(),
+        png::ColorType::Indexed => return None,
+    };
+    Some(pixels)
+}
+
+fn decode_jpeg(data: &[u8]) -> Option<Vec<[u8; 3]>> {
+    let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(data));
+    decoder.read_info().ok()?;
+    decoder.scale(JPEG_SIZE, JPEG_SIZE).ok()?;
+    let bytes = decoder.decode().ok()?;
+    let pixels = match decoder.info()?.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => {
+            bytes.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect()
+        }
+        jpeg_decoder::PixelFormat::L8 => bytes.iter().map(|&v| [v; 3]).collect(),
+        // Big-endian, so the first byte is the high one
+        jpeg_decoder::PixelFormat::L16 => bytes.chunks_exact(2).map(|p| [p[0]; 3]).collect(),
+        jpeg_decoder::PixelFormat::CMYK32 => return None,
+    };
+    Some(pixels)
+}
+
+/// One color's share of the picture
+#[derive(Clone, Copy, Default)]
+struct Bin {
+    count: u32,
+    sum: [f32; 3],
+}
+
+impl Bin {
+    fn add(&mut self, other: &Bin) {
+        self.count += other.count;
+        for (sum, add) in self.sum.iter_mut().zip(other.sum) {
+            *sum += add;
+        }
+    }
+
+    fn mean(&self) -> [f32; 3] {
+        self.sum.map(|sum| sum / self.count.max(1) as f32)
+    }
+}
+
+fn dominant_colors(pixels: &[[u8; 3]]) -> Vec<[u8; 3]> {
+    // Four bits a channel
+    let mut bins = vec![Bin::default(); 4096];
+    let step = pixels.len().div_ceil(MAX_SAMPLES).max(1);
+    for pixel in pixels.iter().step_by(step) {
+        let [r, g, b] = pixel.map(|c| c as usize >> 4);
+        let bin = &mut bins[(r << 8) | (g << 4) | b];
+        bin.count += 1;
+        for (sum, c) in bin.sum.iter_mut().zip(pixel) {
+            *sum += *c as f32;
+        }
+    }
+    bins.retain(|bin| bin.count > 0);
+    bins.sort_by_key(|bin| std::cmp::Reverse(bin.count));
+
+    let mut colors: Vec<Bin> = Vec::new();
+    for bin in &bins {
+        let mean = bin.mean();
+        let near = colors.iter().position(|color| {
+            let distance: f32 = color
+                .mean()
+                .iter()
+                .zip(mean)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            distance.sqrt() < MERGE_DISTANCE
+        });
+        match near {
+            Some(i) => colors[i].add(bin),
+            None if colors.len() < MAX_COLORS => colors.push(*bin),
+            None => {}
+        }
+    }
+    colors.sort_by_key(|color| std::cmp::Reverse(color.count));
+    colors
+        .iter()
+        .map(|color| color.mean().map(|c| c.round() as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a `size` square PNG where `color` decides each pixel
+    fn png(size: u32, color: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, size, size);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels: Vec<u8> = (0..size * size)
+            .flat_map(|i| color(i % size, i / size))
+            .collect();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&pixels).unwrap();
+        drop(writer);
+        data
+    }
+
+    #[test]
+    fn test_finds_the_main_colors_in_order() {
+        // Three quarters deep blue with a little noise, a quarter orange
+        let data = png(64, |x, y| {
+            if x < 16 {
+                [240, 130, 20]
+            } else {
+                [10, 20 + ((x + y) % 8) as u8, 120]
+            }
+        });
+        let colors = cover_colors(&data).unwrap();
+        assert_eq!(colors.len(), 2, "{colors:?}");
+        let [r, g, b] = colors[0];
+        assert!(r < 20 && (20..30).contains(&g) && b == 120, "{colors:?}");
+        assert_eq!(colors[1], [240, 130, 20]);
+    }
+
+    #[test]
+    fn test_rejects_what_isnt_an_image() {
+        assert_eq!(cover_colors(b"ID3\x04"), None);
+        assert_eq!(cover_colors(&[0xFF, 0xD8, 0xFF, 0xE0]), None);
+    }
+}
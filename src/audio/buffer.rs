@@ -25,13 +25,29 @@
 //! The buffer also maintains a "snapshot" for the UI - a separate copy that
 //! the UI can read without affecting the ring buffer. This is updated
 //! periodically by draining available samples from the ring.
+//!
+//! The UI takes the consumer once and owns it, so reading never locks. The
+//! producer stays in a shared slot because several sources (capture, file,
+//! generator, demo) may be running at once; each audio block takes the slot
+//! once with `try_lock` and pushes the whole block, rather than locking per
+//! sample.
+//!
+//! A second reader, such as the analysis worker, gets its own copy of the
+//! stream through a tap (`open_tap`): a separate ring the producer fills
+//! alongside the main one, so neither reader can hold the other up.
+//!
+//! One source can stand in for the others (`stand_in_ref`): while it does
+//! (`set_standing_in`), only its samples reach the display and only the
+//! others' reach the tap. The demo uses this to cover a silent input
+//! without the silence mixing into the picture, while the analysis keeps
+//! listening for the input to come back.
 
 use ringbuf::{
-    traits::{Consumer, Producer, Split},
+    traits::{Consumer, Observer, Producer, Split},
     HeapRb,
 };
 use std::sync::{
-    atomic::{AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     Arc, Mutex,
 };
 
@@ -52,7 +68,11 @@ impl XYSample {
 /// Producer half of the sample buffer (owned by audio thread)
 pub struct SampleProducer {
     producer: ringbuf::HeapProd<XYSample>,
+    /// Copy of the stream for a second reader, see `SampleBuffer::open_tap`
+    tap: Option<ringbuf::HeapProd<XYSample>>,
     samples_written: Arc<AtomicU64>,
+    samples_pushed: Arc<AtomicU64>,
+    samples_dropped: Arc<AtomicU64>,
 }
 
 impl SampleProducer {
@@ -62,20 +82,42 @@ impl SampleProducer {
     /// If the buffer is full, the sample is dropped (acceptable for visualization).
     #[inline]
     pub fn push(&mut self, sample: XYSample) {
-        // try_push returns Err if full - we just ignore it
-        let _ = self.producer.try_push(sample);
+        // try_push returns Err if full - we just count it
+        if self.producer.try_push(sample).is_err() {
+            self.samples_dropped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.samples_pushed.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(tap) = &mut self.tap {
+            let _ = tap.try_push(sample);
+        }
         self.samples_written.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Push multiple samples into the buffer
     #[inline]
     pub fn push_slice(&mut self, samples: &[XYSample]) {
-        for &sample in samples {
-            let _ = self.producer.try_push(sample);
-        }
+        self.push_slice_display(samples);
+        self.push_slice_tap(samples);
+    }
+
+    /// Push into the display ring only
+    fn push_slice_display(&mut self, samples: &[XYSample]) {
+        let pushed = self.producer.push_slice(samples);
+        self.samples_pushed
+            .fetch_add(pushed as u64, Ordering::Relaxed);
+        self.samples_dropped
+            .fetch_add((samples.len() - pushed) as u64, Ordering::Relaxed);
         self.samples_written
             .fetch_add(samples.len() as u64, Ordering::Relaxed);
     }
+
+    /// Copy to the tap only; the display and its counters never see these
+    fn push_slice_tap(&mut self, samples: &[XYSample]) {
+        if let Some(tap) = &mut self.tap {
+            tap.push_slice(samples);
+        }
+    }
 }
 
 /// Consumer half of the sample buffer (owned by UI thread)
@@ -88,6 +130,10 @@ pub struct SampleConsumer {
     capacity: usize,
     /// Current write position in snapshot (circular)
     write_pos: usize,
+    /// Samples moved into the snapshot since the buffer was created
+    drained: u64,
+    /// Ring buffer fill (0.0 - 1.0) seen by the last `update()`
+    fill: f32,
 }
 
 impl SampleConsumer {
@@ -95,10 +141,13 @@ impl SampleConsumer {
     ///
     /// Call this once per frame before reading samples.
     pub fn update(&mut self) {
+        self.fill = self.consumer.occupied_len() as f32 / self.consumer.capacity().get() as f32;
+
         // Drain all available samples into our snapshot buffer
         while let Some(sample) = self.consumer.try_pop() {
             self.snapshot[self.write_pos] = sample;
             self.write_pos = (self.write_pos + 1) % self.capacity;
+            self.drained += 1;
         }
     }
 
@@ -107,20 +156,65 @@ impl SampleConsumer {
     /// Call `update()` first to get the latest samples.
     pub fn get_samples(&self) -> Vec<XYSample> {
         let mut result = Vec::with_capacity(self.capacity);
+        self.copy_samples_into(&mut result);
+        result
+    }
 
-        // Read from write_pos (oldest) and wrap around
-        for i in 0..self.capacity {
-            let idx = (self.write_pos + i) % self.capacity;
-            result.push(self.snapshot[idx]);
-        }
+    /// The snapshot as two slices, oldest first, without copying
+    pub fn as_slices(&self) -> (&[XYSample], &[XYSample]) {
+        // Everything from write_pos on is older than everything before it
+        let (newer, older) = self.snapshot.split_at(self.write_pos);
+        (older, newer)
+    }
 
-        result
+    /// Replace the contents of `out` with the samples in chronological
+    /// order, reusing its allocation
+    pub fn copy_samples_into(&self, out: &mut Vec<XYSample>) {
+        let (older, newer) = self.as_slices();
+        out.clear();
+        out.extend_from_slice(older);
+        out.extend_from_slice(newer);
     }
 
     /// Get total samples written (for statistics)
     pub fn samples_written(&self) -> u64 {
         self.samples_written.load(Ordering::Relaxed)
     }
+
+    /// Stream position just after the newest sample in the snapshot: the
+    /// number of samples read from the ring so far
+    pub fn drained(&self) -> u64 {
+        self.drained
+    }
+
+    /// Replace the contents of `out` with the `len` samples that end at
+    /// stream position `end` (see `drained`), oldest first. Returns false,
+    /// leaving `out` alone, if any of them isn't in the snapshot.
+    pub fn copy_range_into(&self, end: u64, len: usize, out: &mut Vec<XYSample>) -> bool {
+        let Some(age) = self.drained.checked_sub(end) else {
+            return false;
+        };
+        if age as usize + len > self.capacity || len as u64 > end {
+            return false;
+        }
+        // Snapshot index of the first sample, counting back from write_pos
+        let back = age as usize + len;
+        let start = (self.write_pos + self.capacity - back) % self.capacity;
+        out.clear();
+        let first = len.min(self.capacity - start);
+        out.extend_from_slice(&self.snapshot[start..start + first]);
+        out.extend_from_slice(&self.snapshot[..len - first]);
+        true
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Ring buffer fill level (0.0 - 1.0) just before the last `update()`
+    pub fn fill_level(&self) -> f32 {
+        self.fill
+    }
 }
 
 /// Thread-safe sample buffer using lock-free ring buffer
@@ -134,37 +228,121 @@ pub struct SampleBuffer {
     consumer: Arc<Mutex<Option<SampleConsumer>>>,
     /// Shared sample counter
     samples_written: Arc<AtomicU64>,
-    /// Buffer capacity
-    capacity: usize,
+    /// Samples that made it into the ring, which the consumer's `drained`
+    /// counts out again
+    samples_pushed: Arc<AtomicU64>,
+    /// Samples lost because the ring was full or the producer was busy
+    samples_dropped: Arc<AtomicU64>,
+    /// Buffer capacity, shared so every clone sees a resize
+    capacity: Arc<AtomicUsize>,
+    /// Set while the stand-in source has the display to itself
+    standing_in: Arc<AtomicBool>,
+    /// Whether this handle belongs to the stand-in source
+    stand_in: bool,
 }
 
 impl SampleBuffer {
+    pub const DEFAULT_CAPACITY: usize = 2048;
+
+    /// Capacities offered in the settings
+    pub const CAPACITIES: &[usize] = &[1024, 2048, 4096, 8192, 16384, 32768, 65536];
+
+    /// Room to reserve for the blocks audio callbacks collect before
+    /// `push_slice`; anything bigger grows the block once
+    pub const BLOCK_CAPACITY: usize = 4096;
+
     /// Create a new sample buffer with the given capacity
     pub fn new(capacity: usize) -> Self {
+        let samples_written = Arc::new(AtomicU64::new(0));
+        let samples_pushed = Arc::new(AtomicU64::new(0));
+        let samples_dropped = Arc::new(AtomicU64::new(0));
+        let (producer, consumer) = Self::halves(
+            capacity,
+            &samples_written,
+            &samples_pushed,
+            &samples_dropped,
+        );
+
+        Self {
+            producer: Arc::new(Mutex::new(Some(producer))),
+            consumer: Arc::new(Mutex::new(Some(consumer))),
+            samples_written,
+            samples_pushed,
+            samples_dropped,
+            capacity: Arc::new(AtomicUsize::new(capacity)),
+            standing_in: Arc::new(AtomicBool::new(false)),
+            stand_in: false,
+        }
+    }
+
+    fn halves(
+        capacity: usize,
+        samples_written: &Arc<AtomicU64>,
+        samples_pushed: &Arc<AtomicU64>,
+        samples_dropped: &Arc<AtomicU64>,
+    ) -> (SampleProducer, SampleConsumer) {
         let rb = HeapRb::<XYSample>::new(capacity * 2); // Extra space for ring buffer
         let (prod, cons) = rb.split();
 
-        let samples_written = Arc::new(AtomicU64::new(0));
-
         let producer = SampleProducer {
             producer: prod,
-            samples_written: Arc::clone(&samples_written),
+            tap: None,
+            samples_written: Arc::clone(samples_written),
+            samples_pushed: Arc::clone(samples_pushed),
+            samples_dropped: Arc::clone(samples_dropped),
         };
 
         let consumer = SampleConsumer {
             consumer: cons,
-            samples_written: Arc::clone(&samples_written),
+            samples_written: Arc::clone(samples_written),
             snapshot: vec![XYSample::default(); capacity],
             capacity,
             write_pos: 0,
+            drained: 0,
+            fill: 0.0,
         };
 
-        Self {
-            producer: Arc::new(Mutex::new(Some(producer))),
-            consumer: Arc::new(Mutex::new(Some(consumer))),
-            samples_written,
-            capacity,
+        (producer, consumer)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Relaxed)
+    }
+
+    /// Replace the ring with one of a new capacity, swapping `consumer`
+    /// (taken from this buffer) for the new one with the newest samples
+    /// carried over
+    ///
+    /// The new producer goes into the shared slot, so every clone, and the
+    /// audio threads pushing through them, switches over at its next block.
+    pub fn resize(&self, capacity: usize, consumer: &mut SampleConsumer) {
+        if capacity == consumer.capacity {
+            return;
         }
+        let (mut producer, mut fresh) = Self::halves(
+            capacity,
+            &self.samples_written,
+            &self.samples_pushed,
+            &self.samples_dropped,
+        );
+
+        // Swap the producer first, so nothing is pushed into the old ring
+        // after its last samples have been carried over. The tap moves
+        // across untouched.
+        let mut producer_slot = self.producer.lock().unwrap();
+        producer.tap = producer_slot.as_mut().and_then(|old| old.tap.take());
+        *producer_slot = Some(producer);
+        drop(producer_slot);
+
+        consumer.update();
+        let mut recent = Vec::new();
+        consumer.copy_samples_into(&mut recent);
+        let keep = recent.len().min(capacity);
+        fresh.snapshot[..keep].copy_from_slice(&recent[recent.len() - keep..]);
+        fresh.write_pos = keep % capacity;
+        fresh.drained = consumer.drained;
+        *consumer = fresh;
+        self.capacity.store(capacity, Ordering::Relaxed);
     }
 
     /// Take the producer handle (audio thread should call this once)
@@ -177,16 +355,64 @@ impl SampleBuffer {
         self.consumer.lock().unwrap().take()
     }
 
+    /// Attach a second reader: a ring of `capacity` samples that gets a
+    /// copy of everything pushed from now on, replacing any earlier tap
+    ///
+    /// A tap reader that falls behind misses samples; the display never
+    /// waits for it. Only pushes through this buffer (not through a taken
+    /// producer) reach the tap.
+    pub fn open_tap(&self, capacity: usize) -> ringbuf::HeapCons<XYSample> {
+        let (prod, cons) = HeapRb::<XYSample>::new(capacity).split();
+        if let Some(producer) = self.producer.lock().unwrap().as_mut() {
+            producer.tap = Some(prod);
+        }
+        cons
+    }
+
+    /// A handle for a source that can take over the display from the
+    /// others, see `set_standing_in`. Clones of it stay stand-in handles.
+    pub fn stand_in_ref(&self) -> Self {
+        Self {
+            stand_in: true,
+            ..self.clone_ref()
+        }
+    }
+
+    /// Give the display to the stand-in source, or hand it back. While
+    /// standing in, pushes through other handles only reach the tap, and
+    /// the stand-in's only reach the display.
+    pub fn set_standing_in(&self, standing_in: bool) {
+        self.standing_in.store(standing_in, Ordering::Relaxed);
+    }
+
+    pub fn is_standing_in(&self) -> bool {
+        self.standing_in.load(Ordering::Relaxed)
+    }
+
     /// Push a sample (compatibility API - uses internal producer if available)
     ///
     /// Note: For best performance, use `take_producer()` and push directly.
     pub fn push(&self, sample: XYSample) -> bool {
+        self.push_slice(std::slice::from_ref(&sample))
+    }
+
+    /// Push a block of samples with a single lock of the producer slot
+    ///
+    /// If another source is pushing at this moment the whole block is
+    /// dropped (and counted) rather than waiting.
+    pub fn push_slice(&self, samples: &[XYSample]) -> bool {
         if let Ok(mut guard) = self.producer.try_lock() {
             if let Some(ref mut prod) = *guard {
-                prod.push(sample);
+                match (self.is_standing_in(), self.stand_in) {
+                    (false, _) => prod.push_slice(samples),
+                    (true, true) => prod.push_slice_display(samples),
+                    (true, false) => prod.push_slice_tap(samples),
+                }
                 return true;
             }
         }
+        self.samples_dropped
+            .fetch_add(samples.len() as u64, Ordering::Relaxed);
         false
     }
 
@@ -200,7 +426,7 @@ impl SampleBuffer {
                 return cons.get_samples();
             }
         }
-        vec![XYSample::default(); self.capacity]
+        vec![XYSample::default(); self.capacity()]
     }
 
     /// Get total samples written
@@ -208,13 +434,29 @@ impl SampleBuffer {
         self.samples_written.load(Ordering::Relaxed)
     }
 
+    /// Stream position just after the newest sample in the ring, in the
+    /// terms of `SampleConsumer::drained`: unlike `samples_written`, this
+    /// leaves out samples dropped on a full ring
+    pub fn samples_pushed(&self) -> u64 {
+        self.samples_pushed.load(Ordering::Relaxed)
+    }
+
+    /// Get total samples dropped
+    pub fn samples_dropped(&self) -> u64 {
+        self.samples_dropped.load(Ordering::Relaxed)
+    }
+
     /// Clone reference to share between threads
     pub fn clone_ref(&self) -> Self {
         Self {
             producer: Arc::clone(&self.producer),
             consumer: Arc::clone(&self.consumer),
             samples_written: Arc::clone(&self.samples_written),
-            capacity: self.capacity,
+            samples_pushed: Arc::clone(&self.samples_pushed),
+            samples_dropped: Arc::clone(&self.samples_dropped),
+            capacity: Arc::clone(&self.capacity),
+            standing_in: Arc::clone(&self.standing_in),
+            stand_in: self.stand_in,
         }
     }
 }
@@ -266,6 +508,128 @@ mod tests {
         assert!(values.contains(&2.0) || values.contains(&3.0) || values.contains(&4.0));
     }
 
+    #[test]
+    fn test_copy_samples_into_reuses_allocation() {
+        let buffer = SampleBuffer::new(3);
+        let mut consumer = buffer.take_consumer().unwrap();
+        buffer.push_slice(&[0.0, 1.0, 2.0, 3.0, 4.0].map(|x| XYSample::new(x, 0.0)));
+
+        let mut out = Vec::new();
+        consumer.update();
+        consumer.copy_samples_into(&mut out);
+        let xs: Vec<f32> = out.iter().map(|s| s.x).collect();
+        assert_eq!(xs, vec![2.0, 3.0, 4.0]);
+
+        let ptr = out.as_ptr();
+        buffer.push(XYSample::new(5.0, 0.0));
+        consumer.update();
+        consumer.copy_samples_into(&mut out);
+        assert_eq!(out.as_ptr(), ptr);
+        assert_eq!(out[2].x, 5.0);
+    }
+
+    #[test]
+    fn test_full_ring_counts_drops() {
+        let buffer = SampleBuffer::new(2);
+        let mut producer = buffer.take_producer().unwrap();
+        let mut consumer = buffer.take_consumer().unwrap();
+
+        // The ring holds twice the snapshot capacity
+        producer.push_slice(&[XYSample::default(); 6]);
+        assert_eq!(buffer.samples_written(), 6);
+        assert_eq!(buffer.samples_dropped(), 2);
+        assert_eq!(buffer.samples_pushed(), 4);
+
+        consumer.update();
+        assert_eq!(consumer.fill_level(), 1.0);
+        assert_eq!(consumer.drained(), buffer.samples_pushed());
+
+        // The slot is empty now, so a block through the buffer is dropped whole
+        assert!(!buffer.push_slice(&[XYSample::default(); 3]));
+        assert_eq!(buffer.samples_dropped(), 5);
+    }
+
+    #[test]
+    fn test_resize_keeps_newest_samples() {
+        let buffer = SampleBuffer::new(4);
+        let other = buffer.clone_ref();
+        let mut consumer = buffer.take_consumer().unwrap();
+        for i in 0..4 {
+            buffer.push(XYSample::new(i as f32, 0.0));
+        }
+
+        buffer.resize(2, &mut consumer);
+        assert_eq!(other.capacity(), 2);
+        let xs: Vec<f32> = consumer.get_samples().iter().map(|s| s.x).collect();
+        assert_eq!(xs, vec![2.0, 3.0]);
+
+        buffer.resize(5, &mut consumer);
+        // Pushes through any clone land in the new ring
+        other.push(XYSample::new(4.0, 0.0));
+        consumer.update();
+        let xs: Vec<f32> = consumer.get_samples().iter().map(|s| s.x).collect();
+        assert_eq!(xs, vec![0.0, 0.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_copy_range_into() {
+        let buffer = SampleBuffer::new(4);
+        let mut consumer = buffer.take_consumer().unwrap();
+        buffer.push_slice(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0].map(|x| XYSample::new(x, 0.0)));
+        consumer.update();
+        assert_eq!(consumer.drained(), 6);
+
+        let mut out = Vec::new();
+        assert!(consumer.copy_range_into(5, 3, &mut out));
+        let xs: Vec<f32> = out.iter().map(|s| s.x).collect();
+        assert_eq!(xs, vec![3.0, 4.0, 5.0]);
+
+        // Too old, not arrived yet, or before the start of the stream
+        assert!(!consumer.copy_range_into(4, 3, &mut out));
+        assert!(!consumer.copy_range_into(7, 1, &mut out));
+        assert!(!consumer.copy_range_into(2, 3, &mut out));
+    }
+
+    #[test]
+    fn test_tap_gets_a_copy_across_resizes() {
+        let buffer = SampleBuffer::new(4);
+        let mut consumer = buffer.take_consumer().unwrap();
+        let mut tap = buffer.open_tap(16);
+        buffer.push_slice(&[1.0, 2.0].map(|x| XYSample::new(x, 0.0)));
+        buffer.resize(8, &mut consumer);
+        buffer.push(XYSample::new(3.0, 0.0));
+
+        let xs: Vec<f32> = tap.pop_iter().map(|s| s.x).collect();
+        assert_eq!(xs, vec![1.0, 2.0, 3.0]);
+        consumer.update();
+        assert_eq!(consumer.get_samples().last().unwrap().x, 3.0);
+    }
+
+    #[test]
+    fn test_stand_in_takes_over_the_display() {
+        let buffer = SampleBuffer::new(4);
+        let mut consumer = buffer.take_consumer().unwrap();
+        let mut tap = buffer.open_tap(16);
+        let stand_in = buffer.stand_in_ref().clone_ref();
+        let xs = |tap: &mut ringbuf::HeapCons<XYSample>| -> Vec<f32> {
+            tap.pop_iter().map(|s| s.x).collect()
+        };
+
+        // Not standing in: both reach everything
+        buffer.push(XYSample::new(1.0, 0.0));
+        stand_in.push(XYSample::new(2.0, 0.0));
+        assert_eq!(xs(&mut tap), vec![1.0, 2.0]);
+
+        buffer.set_standing_in(true);
+        buffer.push_slice(&[XYSample::new(3.0, 0.0)]);
+        stand_in.push_slice(&[XYSample::new(4.0, 0.0)]);
+        assert_eq!(xs(&mut tap), vec![3.0]);
+        assert_eq!(buffer.samples_written(), 3);
+        consumer.update();
+        let shown: Vec<f32> = consumer.get_samples().iter().map(|s| s.x).collect();
+        assert_eq!(shown[1..], [1.0, 2.0, 4.0]);
+    }
+
     #[test]
     fn test_compatibility_api() {
         let buffer = SampleBuffer::new(4);
@@ -0,0 +1,99 @@
+//! Every channel of a multichannel source
+//!
+//! The XY display only ever sees a source's first two channels. Sources
+//! with more also keep the newest frames of all of them here, interleaved,
+//! for the grid of scopes. As with the display's producer slot, the audio
+//! side only ever `try_lock`s: a block that finds the UI reading is
+//! dropped rather than waited for.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Frames kept, enough for the largest display window
+const CAPACITY: usize = 65_536;
+
+#[derive(Default)]
+struct ChannelRing {
+    channels: usize,
+    /// Interleaved samples, oldest first
+    samples: VecDeque<f32>,
+}
+
+/// The newest frames of every channel, shared between a source's audio
+/// thread and the UI
+#[derive(Clone, Default)]
+pub struct ChannelBuffer {
+    ring: Arc<Mutex<ChannelRing>>,
+}
+
+impl ChannelBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start over with frames of `channels` channels; 0 while the source
+    /// has nothing for the grid
+    pub fn set_channels(&self, channels: usize) {
+        let mut ring = self.ring.lock().unwrap();
+        ring.channels = channels;
+        ring.samples.clear();
+    }
+
+    pub fn channels(&self) -> usize {
+        self.ring.lock().unwrap().channels
+    }
+
+    /// Append interleaved frames. Returns false if the block was dropped
+    /// because the UI was reading.
+    pub fn push_interleaved(&self, samples: &[f32]) -> bool {
+        let Ok(mut ring) = self.ring.try_lock() else {
+            return false;
+        };
+        if ring.channels == 0 {
+            return true;
+        }
+        ring.samples.extend(samples);
+        let excess = ring.samples.len().saturating_sub(CAPACITY * ring.channels);
+        ring.samples.drain(..excess);
+        true
+    }
+
+    /// Replace the contents of `out` with up to the newest `frames` frames,
+    /// interleaved, and return the channel count
+    pub fn copy_latest_into(&self, frames: usize, out: &mut Vec<f32>) -> usize {
+        let ring = self.ring.lock().unwrap();
+        let len = (frames * ring.channels).min(ring.samples.len());
+        out.clear();
+        out.extend(ring.samples.range(ring.samples.len() - len..));
+        ring.channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_the_newest_frames() {
+        let buffer = ChannelBuffer::new();
+        // Nothing is kept until the layout is known
+        assert!(buffer.push_interleaved(&[1.0; 6]));
+        let mut out = Vec::new();
+        assert_eq!(buffer.copy_latest_into(8, &mut out), 0);
+        assert!(out.is_empty());
+
+        buffer.set_channels(3);
+        let frames: Vec<f32> = (0..CAPACITY as u32 + 10)
+            .flat_map(|i| [i as f32, 0.5, -0.5])
+            .collect();
+        buffer.push_interleaved(&frames);
+        assert_eq!(buffer.copy_latest_into(2, &mut out), 3);
+        let newest = (CAPACITY + 9) as f32;
+        assert_eq!(out, vec![newest - 1.0, 0.5, -0.5, newest, 0.5, -0.5]);
+
+        // Asking for more than is kept gets everything
+        buffer.copy_latest_into(CAPACITY * 2, &mut out);
+        assert_eq!(out.len(), CAPACITY * 3);
+        assert_eq!(out[0], 10.0);
+    }
+}
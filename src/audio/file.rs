@@ -1,13 +1,19 @@
 //! Audio file playback
 //!
 //! This module handles loading and playing audio files using symphonia.
+//!
+//! Playback runs on its own thread. The UI never locks anything the thread
+//! uses: it steers the thread through a channel of `PlaybackCommand`s and
+//! reads the state and position back from atomics.
 
+use std::collections::VecDeque;
 use std::fs::File;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, OnceLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use ringbuf::{
@@ -15,16 +21,18 @@ use ringbuf::{
     HeapRb,
 };
 use symphonia::core::audio::{AudioBufferRef, Signal};
-use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::errors::Error as SymphoniaError;
-use symphonia::core::formats::{FormatOptions, SeekMode, SeekTo};
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
 use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey};
 use symphonia::core::probe::Hint;
 use symphonia::core::units::{Time, TimeBase};
 use thiserror::Error;
 
+use super::artwork::cover_colors;
 use super::buffer::{SampleBuffer, XYSample};
+use super::channels::ChannelBuffer;
 
 /// Errors that can occur during audio file operations
 #[derive(Error, Debug)]
@@ -43,6 +51,9 @@ pub enum FileError {
 
     #[error("Decoder error: {0}")]
     DecoderError(String),
+
+    #[error("Loading cancelled")]
+    Cancelled,
 }
 
 /// Playback state
@@ -54,6 +65,40 @@ pub enum PlaybackState {
     Paused,
 }
 
+impl PlaybackState {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Playing,
+            2 => Self::Paused,
+            _ => Self::Stopped,
+        }
+    }
+}
+
+/// `PlaybackState` shared with the playback thread without a lock
+#[derive(Default)]
+struct SharedState(AtomicU8);
+
+impl SharedState {
+    fn load(&self) -> PlaybackState {
+        PlaybackState::from_u8(self.0.load(Ordering::Acquire))
+    }
+
+    fn store(&self, state: PlaybackState) {
+        self.0.store(state as u8, Ordering::Release);
+    }
+}
+
+/// Requests from the UI to the playback thread
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlaybackCommand {
+    Play,
+    Pause,
+    Stop,
+    /// Jump to a frame
+    Seek(u64),
+}
+
 /// Audio file metadata
 #[derive(Debug, Clone)]
 pub struct AudioFileInfo {
@@ -63,6 +108,11 @@ pub struct AudioFileInfo {
     pub sample_rate: u32,
     pub channels: u32,
     pub format: String,
+    /// Track title from the file's tags
+    pub title: Option<String>,
+    /// Dominant colors of the embedded cover art, most prominent first;
+    /// empty without one
+    pub artwork: Vec<[u8; 3]>,
 }
 
 /// Audio file player
@@ -70,8 +120,11 @@ pub struct AudioFilePlayer {
     /// Current file info
     pub info: Option<AudioFileInfo>,
 
-    /// Playback state
-    state: Arc<Mutex<PlaybackState>>,
+    /// Playback state, written by the UI and by the thread when a file ends
+    state: Arc<SharedState>,
+
+    /// Commands to the running playback thread
+    commands: Option<mpsc::Sender<PlaybackCommand>>,
 
     /// Current position in samples
     position: Arc<AtomicU64>,
@@ -82,17 +135,14 @@ pub struct AudioFilePlayer {
     /// Sample rate
     sample_rate: u32,
 
-    /// Whether playback thread is running
-    is_running: Arc<AtomicBool>,
-
     /// Playback thread handle
     thread_handle: Option<thread::JoinHandle<()>>,
 
     /// Sample buffer for visualization
     buffer: SampleBuffer,
 
-    /// Audio output ring buffer producer (for feeding cpal)
-    audio_producer: Arc<Mutex<Option<ringbuf::HeapProd<f32>>>>,
+    /// Every channel, while the file has more than two
+    pub channels: ChannelBuffer,
 
     /// cpal output stream for audio playback
     output_stream: Option<cpal::Stream>,
@@ -100,6 +150,16 @@ pub struct AudioFilePlayer {
     /// Shared volume for audio thread (AtomicU32 with f32 bits)
     volume_atomic: Arc<AtomicU32>,
 
+    /// Frames the output stream has played since it was opened
+    frames_played: Arc<AtomicU64>,
+
+    /// Output latency reported by the device, in frames
+    output_latency: Arc<AtomicU64>,
+
+    /// Shared `av_sync` and `display_offset_ms` (f32 bits) for the playback thread
+    av_sync_atomic: Arc<AtomicBool>,
+    display_offset_atomic: Arc<AtomicU32>,
+
     /// Playback speed multiplier
     pub speed: f32,
 
@@ -109,11 +169,41 @@ pub struct AudioFilePlayer {
     /// Loop playback
     pub loop_playback: bool,
 
+    /// Hold samples back from the display until the output has played them,
+    /// so the picture matches what is heard
+    pub av_sync: bool,
+
+    /// Extra display delay in milliseconds on top of the measured one;
+    /// negative values show samples earlier
+    pub display_offset_ms: f32,
+
     /// Status message
     pub status: String,
 
     /// Waveform overview (downsampled)
     pub waveform: Vec<(f32, f32)>,
+
+    /// Background load in progress
+    loading: Option<LoadingFile>,
+
+    /// Position to seek to once the file being loaded is ready
+    seek_after_load: Option<f32>,
+}
+
+/// A file being probed and scanned on a background thread
+struct LoadingFile {
+    path: PathBuf,
+    cancel: Arc<AtomicBool>,
+    /// Scan progress as f32 bits; negative while the length is unknown
+    progress: Arc<AtomicU32>,
+    receiver: mpsc::Receiver<Result<LoadedFile, FileError>>,
+}
+
+/// Everything `load` works out about a file
+struct LoadedFile {
+    info: AudioFileInfo,
+    total_samples: u64,
+    waveform: Vec<(f32, f32)>,
 }
 
 impl AudioFilePlayer {
@@ -121,262 +211,225 @@ impl AudioFilePlayer {
     pub fn new(buffer: SampleBuffer) -> Self {
         Self {
             info: None,
-            state: Arc::new(Mutex::new(PlaybackState::Stopped)),
+            state: Arc::new(SharedState::default()),
+            commands: None,
             position: Arc::new(AtomicU64::new(0)),
             total_samples: 0,
             sample_rate: 44100,
-            is_running: Arc::new(AtomicBool::new(false)),
             thread_handle: None,
             buffer,
-            audio_producer: Arc::new(Mutex::new(None)),
+            channels: ChannelBuffer::new(),
             output_stream: None,
             volume_atomic: Arc::new(AtomicU32::new(1.0_f32.to_bits())),
+            frames_played: Arc::new(AtomicU64::new(0)),
+            output_latency: Arc::new(AtomicU64::new(0)),
+            av_sync_atomic: Arc::new(AtomicBool::new(true)),
+            display_offset_atomic: Arc::new(AtomicU32::new(0.0_f32.to_bits())),
             speed: 1.0,
             volume: 1.0,
             loop_playback: false,
+            av_sync: true,
+            display_offset_ms: 0.0,
             status: "No file loaded".to_string(),
             waveform: Vec::new(),
+            loading: None,
+            seek_after_load: None,
         }
     }
 
-    /// Load an audio file
-    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), FileError> {
+    /// Start loading an audio file in the background
+    ///
+    /// Probing and the waveform scan run on their own thread; call
+    /// `poll_loading` every frame to pick up the result. The current file
+    /// stays loaded until the new one is ready, and loading another file
+    /// cancels this one.
+    pub fn load(&mut self, path: impl AsRef<Path>) {
         // Stop any current playback
         self.stop();
+        self.cancel_loading();
+        self.channels.set_channels(0);
 
-        let path = path.as_ref();
-        let file = File::open(path)?;
-
-        // Create media source stream
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-        // Create hint from file extension
-        let mut hint = Hint::new();
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            hint.with_extension(ext);
+        let path = path.as_ref().to_path_buf();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new(AtomicU32::new(0.0_f32.to_bits()));
+        let (sender, receiver) = mpsc::channel();
+        {
+            let path = path.clone();
+            let cancel = Arc::clone(&cancel);
+            let progress = Arc::clone(&progress);
+            thread::spawn(move || {
+                let _ = sender.send(read_file(&path, &cancel, &progress));
+            });
         }
 
-        // Probe the file
-        let probed = symphonia::default::get_probe()
-            .format(
-                &hint,
-                mss,
-                &FormatOptions::default(),
-                &MetadataOptions::default(),
-            )
-            .map_err(|e| FileError::ProbeError(e.to_string()))?;
-
-        let format = probed.format;
-
-        // Get the default track
-        let track = format
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-            .ok_or(FileError::NoTracks)?;
-
-        let codec_params = &track.codec_params;
-        let sample_rate = codec_params.sample_rate.unwrap_or(44100);
-        let channels = codec_params.channels.map(|c| c.count() as u32).unwrap_or(2);
-
-        // Calculate duration
-        let duration = if let Some(n_frames) = codec_params.n_frames {
-            let time_base = codec_params
-                .time_base
-                .unwrap_or(TimeBase::new(1, sample_rate));
-            let time = time_base.calc_time(n_frames);
-            Duration::from_secs_f64(time.seconds as f64 + time.frac)
-        } else {
-            Duration::ZERO
-        };
-
-        let total_samples = codec_params.n_frames.unwrap_or(0);
-
-        // Get format name from codec
-        let format_name = format!("{:?}", codec_params.codec).replace("CODEC_TYPE_", "");
-
-        let filename = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("Unknown")
-            .to_string();
-
-        self.info = Some(AudioFileInfo {
-            path: path.to_path_buf(),
-            filename: filename.clone(),
-            duration,
-            sample_rate,
-            channels,
-            format: format_name,
+        self.status = format!("Loading {}", display_name(&path));
+        log::info!("Loading audio file: {:?}", path);
+        self.loading = Some(LoadingFile {
+            path,
+            cancel,
+            progress,
+            receiver,
         });
-
-        self.total_samples = total_samples;
-        self.sample_rate = sample_rate;
-        self.position.store(0, Ordering::Relaxed);
-
-        // Generate waveform overview
-        self.generate_waveform(path)?;
-
-        self.status = format!("Loaded: {}", filename);
-        log::info!("Loaded audio file: {:?}", path);
-
-        Ok(())
     }
 
-    /// Generate waveform overview by reading the file
-    fn generate_waveform(&mut self, path: &Path) -> Result<(), FileError> {
-        let file = File::open(path)?;
-        let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-        let mut hint = Hint::new();
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            hint.with_extension(ext);
-        }
-
-        let probed = symphonia::default::get_probe()
-            .format(
-                &hint,
-                mss,
-                &FormatOptions::default(),
-                &MetadataOptions::default(),
-            )
-            .map_err(|e| FileError::ProbeError(e.to_string()))?;
-
-        let mut format = probed.format;
-
-        let track = format
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-            .ok_or(FileError::NoTracks)?;
-
-        let track_id = track.id;
-
-        let mut decoder = symphonia::default::get_codecs()
-            .make(&track.codec_params, &DecoderOptions::default())
-            .map_err(|e| FileError::DecoderError(e.to_string()))?;
-
-        // Collect samples for waveform (downsample to ~1000 points)
-        let target_points = 1000;
-        let mut all_samples: Vec<(f32, f32)> = Vec::new();
-
-        loop {
-            let packet = match format.next_packet() {
-                Ok(p) => p,
-                Err(SymphoniaError::IoError(e))
-                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
-                {
-                    break;
-                }
-                Err(_) => break,
-            };
-
-            if packet.track_id() != track_id {
-                continue;
+    /// Install a finished load, if there is one. Returns its outcome once.
+    pub fn poll_loading(&mut self) -> Option<Result<(), FileError>> {
+        let loading = self.loading.as_ref()?;
+        let result = match loading.receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(FileError::DecoderError("loader stopped".to_string()))
             }
-
-            match decoder.decode(&packet) {
-                Ok(decoded) => {
-                    let samples = extract_samples(&decoded);
-                    all_samples.extend(samples);
+        };
+        let path = self.loading.take().map(|l| l.path).unwrap_or_default();
+
+        match result {
+            Ok(loaded) => {
+                let filename = loaded.info.filename.clone();
+                self.sample_rate = loaded.info.sample_rate;
+                self.info = Some(loaded.info);
+                self.total_samples = loaded.total_samples;
+                self.waveform = loaded.waveform;
+                self.position.store(0, Ordering::Relaxed);
+                if let Some(fraction) = self.seek_after_load.take() {
+                    self.seek(fraction);
                 }
-                Err(_) => continue,
+                self.status = format!("Loaded: {}", filename);
+                log::info!("Loaded audio file: {:?}", path);
+                Some(Ok(()))
+            }
+            Err(e) => {
+                self.seek_after_load = None;
+                Some(Err(e))
             }
         }
+    }
 
-        // Downsample for overview
-        if all_samples.is_empty() {
-            self.waveform = Vec::new();
-        } else {
-            let step = (all_samples.len() / target_points).max(1);
-            self.waveform = all_samples
-                .chunks(step)
-                .map(|chunk| {
-                    let (sum_x, sum_y) = chunk
-                        .iter()
-                        .fold((0.0, 0.0), |acc, s| (acc.0 + s.0, acc.1 + s.1));
-                    (sum_x / chunk.len() as f32, sum_y / chunk.len() as f32)
-                })
-                .collect();
+    /// Abandon a load in progress
+    pub fn cancel_loading(&mut self) {
+        if let Some(loading) = self.loading.take() {
+            loading.cancel.store(true, Ordering::Relaxed);
+            self.seek_after_load = None;
+            self.status = "Loading cancelled".to_string();
+            log::info!("Cancelled loading {:?}", loading.path);
         }
+    }
 
-        Ok(())
+    /// The file being loaded and how far the scan has got (0.0 - 1.0, or
+    /// None if the length is unknown)
+    pub fn loading(&self) -> Option<(&Path, Option<f32>)> {
+        self.loading.as_ref().map(|loading| {
+            let progress = f32::from_bits(loading.progress.load(Ordering::Relaxed));
+            (
+                loading.path.as_path(),
+                (progress >= 0.0).then_some(progress),
+            )
+        })
     }
 
     /// Start playback
     pub fn play(&mut self) {
-        if self.info.is_none() {
+        if self.info.is_none() || self.loading.is_some() {
             return;
         }
 
         // If paused, just resume
-        {
-            let mut state = self.state.lock().unwrap();
-            if *state == PlaybackState::Paused {
-                *state = PlaybackState::Playing;
-                self.status = "Playing".to_string();
-                return;
-            }
+        if self.state() == PlaybackState::Paused {
+            self.send(PlaybackCommand::Play);
+            self.state.store(PlaybackState::Playing);
+            self.status = "Playing".to_string();
+            return;
+        }
+
+        // Closing the channel ends any earlier thread, including one that
+        // stopped by itself at the end of the file
+        self.commands = None;
+        if let Some(handle) = self.thread_handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
         }
 
-        // Set up cpal audio output
-        self.start_audio_output();
+        // Set up cpal audio output. Its callback wakes the playback thread
+        // as it makes room.
+        let waker = Arc::new(OnceLock::new());
+        let audio_producer = self.start_audio_output(Arc::clone(&waker));
 
-        // Sync volume to atomic
+        // Sync volume and display delay to atomics
         self.volume_atomic
             .store(self.volume.to_bits(), Ordering::Relaxed);
+        self.sync_display_delay();
 
         // Start new playback thread
-        self.is_running.store(true, Ordering::Relaxed);
+        let (sender, commands) = mpsc::channel();
+        self.commands = Some(sender);
 
         let path = self.info.as_ref().unwrap().path.clone();
         let buffer = self.buffer.clone_ref();
-        let audio_producer = Arc::clone(&self.audio_producer);
+        let channels = self.channels.clone();
         let state = Arc::clone(&self.state);
         let position = Arc::clone(&self.position);
-        let is_running = Arc::clone(&self.is_running);
         let volume_atomic = Arc::clone(&self.volume_atomic);
+        // Without an output stream nothing is heard, so nothing is held back
+        // and the thread keeps time by itself
+        let audio_producer = self.output_stream.is_some().then_some(audio_producer);
+        let delay = self.output_stream.is_some().then(|| DisplayDelay {
+            frames_played: Arc::clone(&self.frames_played),
+            output_latency: Arc::clone(&self.output_latency),
+            enabled: Arc::clone(&self.av_sync_atomic),
+            offset_ms: Arc::clone(&self.display_offset_atomic),
+            sample_rate: self.sample_rate,
+        });
         let sample_rate = self.sample_rate;
         let speed = self.speed;
         let loop_playback = self.loop_playback;
 
-        *self.state.lock().unwrap() = PlaybackState::Playing;
+        self.state.store(PlaybackState::Playing);
         self.status = "Playing".to_string();
 
-        self.thread_handle = Some(thread::spawn(move || {
+        let handle = thread::spawn(move || {
             if let Err(e) = playback_thread(
                 &path,
                 buffer,
+                channels,
                 audio_producer,
+                commands,
                 state,
                 position,
-                is_running,
                 volume_atomic,
+                delay,
                 sample_rate,
                 speed,
                 loop_playback,
             ) {
                 log::error!("Playback error: {}", e);
             }
-        }));
+        });
+        let _ = waker.set(handle.thread().clone());
+        self.thread_handle = Some(handle);
     }
 
-    /// Set up cpal audio output stream
-    fn start_audio_output(&mut self) {
+    /// Set up cpal audio output stream, returning the producer end of its
+    /// ring buffer for the playback thread. The callback unparks the thread
+    /// in `waker` each time it takes samples.
+    ///
+    /// If no output can be opened the producer is still returned, but
+    /// `output_stream` stays None and nothing reads from it.
+    fn start_audio_output(
+        &mut self,
+        waker: Arc<OnceLock<thread::Thread>>,
+    ) -> ringbuf::HeapProd<f32> {
         // Create audio ring buffer (stereo interleaved: L R L R ...)
         let rb = HeapRb::<f32>::new(48000 * 2); // ~1 second of stereo audio
         let (prod, mut cons) = rb.split();
 
-        // Store producer for the playback thread
-        *self.audio_producer.lock().unwrap() = Some(prod);
-
         // Open cpal output
         let host = cpal::default_host();
         let device = match host.default_output_device() {
             Some(d) => d,
             None => {
                 log::warn!("No output device for file playback audio");
-                return;
+                return prod;
             }
         };
 
@@ -384,16 +437,32 @@ impl AudioFilePlayer {
             Ok(c) => c,
             Err(e) => {
                 log::warn!("Failed to get output config: {}", e);
-                return;
+                return prod;
             }
         };
 
         let channels = config.channels() as usize;
+        let output_rate = config.sample_rate().0;
+
+        self.frames_played.store(0, Ordering::Relaxed);
+        let frames_played = Arc::clone(&self.frames_played);
+        let output_latency = Arc::clone(&self.output_latency);
 
         let stream = device.build_output_stream(
             &config.into(),
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+                // How long until this block reaches the speakers
+                let timestamp = info.timestamp();
+                if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+                    let frames = latency.as_secs_f64() * output_rate as f64;
+                    output_latency.store(frames as u64, Ordering::Relaxed);
+                }
+
+                let mut played = 0;
                 for frame in data.chunks_mut(channels) {
+                    if cons.occupied_len() >= 2 {
+                        played += 1;
+                    }
                     let left = cons.try_pop().unwrap_or(0.0);
                     let right = cons.try_pop().unwrap_or(0.0);
                     if channels >= 2 {
@@ -406,6 +475,10 @@ impl AudioFilePlayer {
                         frame[0] = (left + right) / 2.0;
                     }
                 }
+                frames_played.fetch_add(played, Ordering::Relaxed);
+                if let Some(thread) = waker.get() {
+                    thread.unpark();
+                }
             },
             |err| log::error!("Audio output error: {}", err),
             None,
@@ -415,7 +488,7 @@ impl AudioFilePlayer {
             Ok(s) => {
                 if let Err(e) = s.play() {
                     log::warn!("Failed to start output stream: {}", e);
-                    return;
+                    return prod;
                 }
                 self.output_stream = Some(s);
             }
@@ -423,21 +496,23 @@ impl AudioFilePlayer {
                 log::warn!("Failed to build output stream: {}", e);
             }
         }
+        prod
     }
 
     /// Pause playback
     pub fn pause(&mut self) {
-        let mut state = self.state.lock().unwrap();
-        if *state == PlaybackState::Playing {
-            *state = PlaybackState::Paused;
+        if self.state() == PlaybackState::Playing {
+            self.send(PlaybackCommand::Pause);
+            self.state.store(PlaybackState::Paused);
             self.status = "Paused".to_string();
         }
     }
 
     /// Stop playback
     pub fn stop(&mut self) {
-        self.is_running.store(false, Ordering::Relaxed);
-        *self.state.lock().unwrap() = PlaybackState::Stopped;
+        self.send(PlaybackCommand::Stop);
+        self.commands = None;
+        self.state.store(PlaybackState::Stopped);
 
         if let Some(handle) = self.thread_handle.take() {
             let _ = handle.join();
@@ -445,7 +520,6 @@ impl AudioFilePlayer {
 
         // Clean up audio output
         self.output_stream = None;
-        *self.audio_producer.lock().unwrap() = None;
 
         self.position.store(0, Ordering::Relaxed);
         self.status = if self.info.is_some() {
@@ -461,10 +535,16 @@ impl AudioFilePlayer {
             .store(self.volume.to_bits(), Ordering::Relaxed);
     }
 
+    /// Sync the A/V sync settings to the playback thread
+    pub fn sync_display_delay(&self) {
+        self.av_sync_atomic.store(self.av_sync, Ordering::Relaxed);
+        self.display_offset_atomic
+            .store(self.display_offset_ms.to_bits(), Ordering::Relaxed);
+    }
+
     /// Toggle play/pause
     pub fn toggle(&mut self) {
-        let state = *self.state.lock().unwrap();
-        match state {
+        match self.state() {
             PlaybackState::Stopped => self.play(),
             PlaybackState::Playing => self.pause(),
             PlaybackState::Paused => self.play(),
@@ -473,7 +553,19 @@ impl AudioFilePlayer {
 
     /// Get current playback state
     pub fn state(&self) -> PlaybackState {
-        *self.state.lock().unwrap()
+        self.state.load()
+    }
+
+    /// Pass a command to the playback thread, if one is running, and wake
+    /// it in case it's waiting for the output
+    fn send(&self, command: PlaybackCommand) {
+        if let Some(commands) = &self.commands {
+            // A thread that already finished has nothing left to do
+            let _ = commands.send(command);
+        }
+        if let Some(handle) = &self.thread_handle {
+            handle.thread().unpark();
+        }
     }
 
     /// Get current position as fraction (0.0 - 1.0)
@@ -491,10 +583,17 @@ impl AudioFilePlayer {
     }
 
     /// Seek to position (0.0 - 1.0)
+    ///
+    /// While a file is loading, the seek applies once it is ready.
     pub fn seek(&mut self, fraction: f32) {
         let fraction = fraction.clamp(0.0, 1.0);
+        if self.loading.is_some() {
+            self.seek_after_load = Some(fraction);
+            return;
+        }
         let target_sample = (self.total_samples as f32 * fraction) as u64;
         self.position.store(target_sample, Ordering::Relaxed);
+        self.send(PlaybackCommand::Seek(target_sample));
     }
 
     /// Check if a file is loaded
@@ -503,6 +602,199 @@ impl AudioFilePlayer {
     }
 }
 
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Probe a file and scan it for the waveform overview. Runs on the loader
+/// thread; `cancel` is checked between packets and `progress` updated as it
+/// goes.
+fn read_file(
+    path: &Path,
+    cancel: &AtomicBool,
+    progress: &AtomicU32,
+) -> Result<LoadedFile, FileError> {
+    let file = File::open(path)?;
+
+    // Create media source stream
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    // Create hint from file extension
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    // Probe the file
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| FileError::ProbeError(e.to_string()))?;
+
+    // Tags sit ahead of the container (ID3v2) or in it
+    let mut format = probed.format;
+    let tags = probed.metadata.get();
+    let leading = tags.as_ref().and_then(|metadata| metadata.current());
+    let mut artwork = leading.and_then(artwork_colors);
+    let mut title = leading.and_then(track_title);
+    let contained = format.metadata();
+    if artwork.is_none() {
+        artwork = contained.current().and_then(artwork_colors);
+    }
+    if title.is_none() {
+        title = contained.current().and_then(track_title);
+    }
+
+    // Get the default track
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(FileError::NoTracks)?;
+
+    let codec_params = &track.codec_params;
+    let track_id = track.id;
+    let sample_rate = codec_params.sample_rate.unwrap_or(44100);
+    let channels = codec_params.channels.map(|c| c.count() as u32).unwrap_or(2);
+
+    // Calculate duration
+    let duration = if let Some(n_frames) = codec_params.n_frames {
+        let time_base = codec_params
+            .time_base
+            .unwrap_or(TimeBase::new(1, sample_rate));
+        let time = time_base.calc_time(n_frames);
+        Duration::from_secs_f64(time.seconds as f64 + time.frac)
+    } else {
+        Duration::ZERO
+    };
+
+    let total_samples = codec_params.n_frames.unwrap_or(0);
+
+    // Get format name from codec
+    let format_name = format!("{:?}", codec_params.codec).replace("CODEC_TYPE_", "");
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(codec_params, &DecoderOptions::default())
+        .map_err(|e| FileError::DecoderError(e.to_string()))?;
+
+    // Waveform overview, downsampled to ~1000 points. With a known length
+    // the points are averaged on the fly, so long files aren't held in
+    // memory; otherwise everything is collected and averaged at the end.
+    let target_points = 1000;
+    let step = (total_samples as usize / target_points).max(1);
+    let mut waveform: Vec<(f32, f32)> = Vec::new();
+    let mut all_samples: Vec<(f32, f32)> = Vec::new();
+    let mut sum = (0.0, 0.0);
+    let mut in_sum = 0;
+    let mut scanned: u64 = 0;
+    progress.store(
+        if total_samples > 0 { 0.0_f32 } else { -1.0 }.to_bits(),
+        Ordering::Relaxed,
+    );
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(FileError::Cancelled);
+        }
+
+        let packet = match format.next_packet() {
+            Ok(p) => p,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break;
+            }
+            Err(_) => break,
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let samples = extract_samples(&decoded);
+                scanned += samples.len() as u64;
+                if total_samples == 0 {
+                    all_samples.extend(samples);
+                    continue;
+                }
+                for (x, y) in samples {
+                    sum = (sum.0 + x, sum.1 + y);
+                    in_sum += 1;
+                    if in_sum == step {
+                        waveform.push((sum.0 / step as f32, sum.1 / step as f32));
+                        sum = (0.0, 0.0);
+                        in_sum = 0;
+                    }
+                }
+                let fraction = (scanned as f64 / total_samples as f64).min(1.0) as f32;
+                progress.store(fraction.to_bits(), Ordering::Relaxed);
+            }
+            Err(_) => continue,
+        }
+    }
+
+    if total_samples == 0 && !all_samples.is_empty() {
+        let step = (all_samples.len() / target_points).max(1);
+        waveform = all_samples
+            .chunks(step)
+            .map(|chunk| {
+                let (sum_x, sum_y) = chunk
+                    .iter()
+                    .fold((0.0, 0.0), |acc, s| (acc.0 + s.0, acc.1 + s.1));
+                (sum_x / chunk.len() as f32, sum_y / chunk.len() as f32)
+            })
+            .collect();
+    } else if in_sum > 0 {
+        waveform.push((sum.0 / in_sum as f32, sum.1 / in_sum as f32));
+    }
+
+    Ok(LoadedFile {
+        info: AudioFileInfo {
+            path: path.to_path_buf(),
+            filename: display_name(path),
+            duration,
+            sample_rate,
+            channels,
+            format: format_name,
+            title,
+            artwork: artwork.unwrap_or_default(),
+        },
+        total_samples,
+        waveform,
+    })
+}
+
+fn track_title(revision: &MetadataRevision) -> Option<String> {
+    revision
+        .tags()
+        .iter()
+        .find(|tag| tag.std_key == Some(StandardTagKey::TrackTitle))
+        .map(|tag| tag.value.to_string())
+        .filter(|title| !title.trim().is_empty())
+}
+
+/// Colors of the front cover among a file's pictures, or of the first
+/// picture if none is marked as the front cover
+fn artwork_colors(revision: &MetadataRevision) -> Option<Vec<[u8; 3]>> {
+    let visuals = revision.visuals();
+    let cover = visuals
+        .iter()
+        .find(|v| v.usage == Some(StandardVisualKey::FrontCover))
+        .or(visuals.first())?;
+    let colors = cover_colors(&cover.data);
+    if colors.is_none() {
+        log::warn!("Could not decode {} cover art", cover.media_type);
+    }
+    colors
+}
+
 /// Extract XY samples from decoded audio buffer
 fn extract_samples(buffer: &AudioBufferRef<'_>) -> Vec<(f32, f32)> {
     let mut samples = Vec::new();
@@ -552,20 +844,134 @@ fn extract_samples(buffer: &AudioBufferRef<'_>) -> Vec<(f32, f32)> {
     samples
 }
 
-/// Playback thread function
-#[allow(clippy::too_many_arguments)]
-fn playback_thread(
-    path: &Path,
-    buffer: SampleBuffer,
-    audio_producer: Arc<Mutex<Option<ringbuf::HeapProd<f32>>>>,
-    state: Arc<Mutex<PlaybackState>>,
-    position: Arc<AtomicU64>,
-    is_running: Arc<AtomicBool>,
-    volume_atomic: Arc<AtomicU32>,
+/// Where the output stream is, shared with the playback thread
+struct DisplayDelay {
+    frames_played: Arc<AtomicU64>,
+    output_latency: Arc<AtomicU64>,
+    enabled: Arc<AtomicBool>,
+    offset_ms: Arc<AtomicU32>,
     sample_rate: u32,
-    _speed: f32,
-    loop_playback: bool,
-) -> Result<(), FileError> {
+}
+
+impl DisplayDelay {
+    /// Number of decoded frames that have been heard by now, or None if
+    /// samples shouldn't be held back
+    fn audible_frames(&self) -> Option<u64> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let offset_ms = f32::from_bits(self.offset_ms.load(Ordering::Relaxed));
+        let offset = (offset_ms / 1000.0 * self.sample_rate as f32) as i64;
+        let played = self.frames_played.load(Ordering::Relaxed) as i64;
+        let latency = self.output_latency.load(Ordering::Relaxed) as i64;
+        Some((played - latency - offset).max(0) as u64)
+    }
+}
+
+/// Decoded samples waiting for the output to catch up before they go to the
+/// display. Each is numbered by its position in the stream of decoded frames,
+/// which the output's played-frame counter also counts.
+#[derive(Default)]
+struct HeldSamples {
+    pending: VecDeque<XYSample>,
+    /// Frame number of the front of `pending`
+    next_frame: u64,
+    /// The same frames with all `width` channels interleaved, for files
+    /// with more than two; `width` is 0 otherwise
+    wide: VecDeque<f32>,
+    width: usize,
+    channels: ChannelBuffer,
+}
+
+impl HeldSamples {
+    /// Push everything up to frame `audible` to the display (everything,
+    /// if None)
+    fn release(&mut self, buffer: &SampleBuffer, audible: Option<u64>) {
+        let count = match audible {
+            Some(audible) => audible.saturating_sub(self.next_frame) as usize,
+            None => self.pending.len(),
+        }
+        .min(self.pending.len());
+        let (front, back) = self.pending.as_slices();
+        let split = count.min(front.len());
+        buffer.push_slice(&front[..split]);
+        buffer.push_slice(&back[..count - split]);
+        self.pending.drain(..count);
+        if self.width > 0 {
+            let count = (count * self.width).min(self.wide.len());
+            let (front, back) = self.wide.as_slices();
+            let split = count.min(front.len());
+            self.channels.push_interleaved(&front[..split]);
+            self.channels.push_interleaved(&back[..count - split]);
+            self.wide.drain(..count);
+        }
+        self.next_frame += count as u64;
+    }
+
+    /// Drop everything still held, after a seek. Those frames are already
+    /// queued for the output, so they still count towards the numbering.
+    fn skip(&mut self) {
+        self.next_frame += self.pending.len() as u64;
+        self.pending.clear();
+        self.wide.clear();
+    }
+}
+
+/// How often a paused thread with held samples checks whether they're heard
+const HELD_POLL: Duration = Duration::from_millis(10);
+
+/// Keeps the pace of playback when there is no output to do it
+struct WallClock {
+    started: Instant,
+    /// Frame playing at `started`
+    frame: u64,
+    sample_rate: u32,
+}
+
+impl WallClock {
+    fn new(frame: u64, sample_rate: u32) -> Self {
+        Self {
+            started: Instant::now(),
+            frame,
+            sample_rate: sample_rate.max(1),
+        }
+    }
+
+    /// How long until frame `frame` is due
+    fn until(&self, frame: u64) -> Duration {
+        let due =
+            self.frame as f64 / self.sample_rate as f64 + self.started.elapsed().as_secs_f64();
+        let at = frame as f64 / self.sample_rate as f64;
+        Duration::from_secs_f64((at - due).max(0.0))
+    }
+}
+
+/// Next command from the UI, waiting up to `timeout` for one (forever if
+/// None). A closed channel reads as `Stop`.
+fn next_command(
+    commands: &mpsc::Receiver<PlaybackCommand>,
+    timeout: Option<Duration>,
+) -> Option<PlaybackCommand> {
+    let received = match timeout {
+        None => commands.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        Some(timeout) if timeout.is_zero() => commands.try_recv().map_err(|e| match e {
+            TryRecvError::Empty => RecvTimeoutError::Timeout,
+            TryRecvError::Disconnected => RecvTimeoutError::Disconnected,
+        }),
+        Some(timeout) => commands.recv_timeout(timeout),
+    };
+    match received {
+        Ok(command) => Some(command),
+        Err(RecvTimeoutError::Timeout) => None,
+        Err(RecvTimeoutError::Disconnected) => Some(PlaybackCommand::Stop),
+    }
+}
+
+/// A file's demuxer, and a decoder for its track with that id
+type OpenTrack = (Box<dyn FormatReader>, Box<dyn Decoder>, u32);
+
+/// Open `path` and a decoder for its first audio track
+fn open_track(path: &Path) -> Result<OpenTrack, FileError> {
     let file = File::open(path)?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
@@ -583,7 +989,7 @@ fn playback_thread(
         )
         .map_err(|e| FileError::ProbeError(e.to_string()))?;
 
-    let mut format = probed.format;
+    let format = probed.format;
 
     let track = format
         .tracks()
@@ -592,10 +998,95 @@ fn playback_thread(
         .ok_or(FileError::NoTracks)?;
 
     let track_id = track.id;
-
-    let mut decoder = symphonia::default::get_codecs()
+    let decoder = symphonia::default::get_codecs()
         .make(&track.codec_params, &DecoderOptions::default())
         .map_err(|e| FileError::DecoderError(e.to_string()))?;
+    Ok((format, decoder, track_id))
+}
+
+/// A file's samples decoded in order, from the start, as fast as they can
+/// be read. For rendering offline, where playback timing doesn't matter.
+pub struct FileReader {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+    /// Decoded samples not read yet
+    pending: VecDeque<XYSample>,
+    finished: bool,
+}
+
+impl FileReader {
+    pub fn open(path: &Path) -> Result<Self, FileError> {
+        let (format, decoder, track_id) = open_track(path)?;
+        let sample_rate = decoder.codec_params().sample_rate.unwrap_or(44100);
+        Ok(Self {
+            format,
+            decoder,
+            track_id,
+            sample_rate,
+            pending: VecDeque::new(),
+            finished: false,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Move up to `count` samples to `out`, and return how many were moved.
+    /// Fewer than `count` only at the end of the file.
+    pub fn read(&mut self, count: usize, out: &mut VecDeque<XYSample>) -> usize {
+        while self.pending.len() < count && !self.finished {
+            self.decode_packet();
+        }
+        let count = count.min(self.pending.len());
+        out.extend(self.pending.drain(..count));
+        count
+    }
+
+    fn decode_packet(&mut self) {
+        let packet = match self.format.next_packet() {
+            Ok(packet) => packet,
+            Err(_) => {
+                self.finished = true;
+                return;
+            }
+        };
+        if packet.track_id() != self.track_id {
+            return;
+        }
+        // Like playback, a packet that won't decode is skipped
+        if let Ok(decoded) = self.decoder.decode(&packet) {
+            self.pending.extend(
+                extract_samples(&decoded)
+                    .into_iter()
+                    .map(|(x, y)| XYSample::new(x, y)),
+            );
+        }
+    }
+}
+
+/// Playback thread function
+#[allow(clippy::too_many_arguments)]
+fn playback_thread(
+    path: &Path,
+    buffer: SampleBuffer,
+    channels: ChannelBuffer,
+    mut audio_producer: Option<ringbuf::HeapProd<f32>>,
+    commands: mpsc::Receiver<PlaybackCommand>,
+    state: Arc<SharedState>,
+    position: Arc<AtomicU64>,
+    volume_atomic: Arc<AtomicU32>,
+    delay: Option<DisplayDelay>,
+    sample_rate: u32,
+    _speed: f32,
+    loop_playback: bool,
+) -> Result<(), FileError> {
+    let (mut format, mut decoder, track_id) = open_track(path)?;
+    let channel_count = decoder.codec_params().channels.map_or(2, |c| c.count());
+    let width = if channel_count > 2 { channel_count } else { 0 };
+    channels.set_channels(width);
 
     // Seek to current position if needed
     let start_sample = position.load(Ordering::Relaxed);
@@ -609,27 +1100,84 @@ fn playback_thread(
         );
     }
 
-    // Sleep duration for pacing the decoder (slightly faster than real-time,
-    // cpal output callback drives actual timing)
-    let packet_sleep = Duration::from_millis(5);
-
     let mut current_sample = start_sample;
+    let mut held = HeldSamples {
+        channels,
+        width,
+        ..HeldSamples::default()
+    };
+    let mut interleaved: Option<symphonia::core::audio::SampleBuffer<f32>> = None;
+    let mut paused = false;
+    // Decoded output samples the ring had no room for yet, from `sent` on
+    let mut unsent: Vec<f32> = Vec::new();
+    let mut sent = 0;
+    let mut clock = WallClock::new(current_sample, sample_rate);
 
     loop {
-        if !is_running.load(Ordering::Relaxed) {
-            break;
-        }
+        held.release(
+            &buffer,
+            delay.as_ref().and_then(DisplayDelay::audible_frames),
+        );
 
-        // Check if paused
-        {
-            let s = state.lock().unwrap();
-            if *s == PlaybackState::Paused {
-                thread::sleep(Duration::from_millis(10));
+        // Take the UI's commands before each packet. While paused there is
+        // nothing to decode, so sleep until told otherwise, waking only to
+        // pass held samples on as they are heard. Without an output, wait
+        // for the clock to reach the next packet.
+        let timeout = if paused {
+            (!held.pending.is_empty()).then_some(HELD_POLL)
+        } else if audio_producer.is_none() {
+            Some(clock.until(current_sample))
+        } else {
+            Some(Duration::ZERO)
+        };
+        match next_command(&commands, timeout) {
+            Some(PlaybackCommand::Stop) => break,
+            Some(PlaybackCommand::Pause) => {
+                paused = true;
                 continue;
             }
-            if *s == PlaybackState::Stopped {
-                break;
+            Some(PlaybackCommand::Play) => {
+                paused = false;
+                clock = WallClock::new(current_sample, sample_rate);
+                continue;
+            }
+            Some(PlaybackCommand::Seek(target)) => {
+                let _ = format.seek(
+                    SeekMode::Accurate,
+                    SeekTo::Time {
+                        time: Time::from(target as f64 / sample_rate as f64),
+                        track_id: Some(track_id),
+                    },
+                );
+                decoder.reset();
+                held.skip();
+                // What was decoded before the jump isn't played after it
+                unsent.clear();
+                sent = 0;
+                current_sample = target;
+                position.store(target, Ordering::Relaxed);
+                clock = WallClock::new(target, sample_rate);
+                continue;
             }
+            None if paused => continue,
+            None => {}
+        }
+
+        match &mut audio_producer {
+            // Hand the last packet to the output, waiting for room. The
+            // output callback wakes this thread as it plays, and so does the
+            // UI when it sends a command.
+            Some(producer) => {
+                sent += producer.push_slice(&unsent[sent..]);
+                if sent < unsent.len() {
+                    thread::park();
+                    continue;
+                }
+                unsent.clear();
+                sent = 0;
+            }
+            None if !clock.until(current_sample).is_zero() => continue,
+            None => {}
         }
 
         // Read and decode a packet
@@ -648,9 +1196,10 @@ fn playback_thread(
                     );
                     current_sample = 0;
                     position.store(0, Ordering::Relaxed);
+                    clock = WallClock::new(0, sample_rate);
                     continue;
                 } else {
-                    *state.lock().unwrap() = PlaybackState::Stopped;
+                    state.store(PlaybackState::Stopped);
                     break;
                 }
             }
@@ -667,33 +1216,38 @@ fn playback_thread(
                 let num_samples = samples.len();
                 let volume = f32::from_bits(volume_atomic.load(Ordering::Relaxed));
 
-                // Push samples to visualization buffer
-                for &(x, y) in &samples {
-                    buffer.push(XYSample::new(x * volume, y * volume));
-                }
-
-                // Push interleaved stereo samples to audio output
-                if let Ok(mut guard) = audio_producer.try_lock() {
-                    if let Some(ref mut prod) = *guard {
-                        for &(x, y) in &samples {
-                            let _ = prod.try_push(x * volume);
-                            let _ = prod.try_push(y * volume);
+                // Queue samples for the display until they're heard
+                held.pending.extend(
+                    samples
+                        .iter()
+                        .map(|&(x, y)| XYSample::new(x * volume, y * volume)),
+                );
+                if width > 0 {
+                    let spec = *decoded.spec();
+                    let frames = decoded.capacity() as u64;
+                    let interleaved = match &mut interleaved {
+                        Some(buf) if buf.capacity() as u64 >= frames * width as u64 => buf,
+                        slot => {
+                            slot.insert(symphonia::core::audio::SampleBuffer::new(frames, spec))
                         }
-                    }
+                    };
+                    interleaved.copy_interleaved_ref(decoded);
+                    held.wide
+                        .extend(interleaved.samples().iter().map(|v| v * volume));
+                }
+                held.release(
+                    &buffer,
+                    delay.as_ref().and_then(DisplayDelay::audible_frames),
+                );
+
+                // Interleaved stereo for the output, which takes it before
+                // the next packet is decoded; that paces the decoder
+                if audio_producer.is_some() {
+                    unsent.extend(samples.iter().flat_map(|&(x, y)| [x * volume, y * volume]));
                 }
 
                 current_sample += num_samples as u64;
                 position.store(current_sample, Ordering::Relaxed);
-
-                // Pace the decoder - wait if audio buffer is getting full
-                // This prevents decoding too far ahead while cpal drains at real-time
-                let should_wait = audio_producer
-                    .try_lock()
-                    .map(|guard| guard.as_ref().map(|p| p.is_full()).unwrap_or(false))
-                    .unwrap_or(false);
-                if should_wait {
-                    thread::sleep(packet_sleep);
-                }
             }
             Err(_) => continue,
         }
@@ -701,3 +1255,90 @@ fn playback_thread(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_file_scans_and_cancels() {
+        let path = std::env::temp_dir().join(format!("scope-rs-read-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..16_000 {
+            let v = if i < 8000 { 16384 } else { -16384 };
+            writer.write_sample(v as i16).unwrap();
+            writer.write_sample(0_i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let progress = AtomicU32::new(0);
+        let loaded = read_file(&path, &AtomicBool::new(false), &progress).unwrap();
+        assert_eq!(loaded.total_samples, 16_000);
+        assert_eq!(loaded.info.duration, Duration::from_secs(2));
+        assert_eq!(loaded.waveform.len(), 1000);
+        assert!((loaded.waveform[0].0 - 0.5).abs() < 1e-3);
+        assert!((loaded.waveform[999].0 + 0.5).abs() < 1e-3);
+        assert_eq!(f32::from_bits(progress.load(Ordering::Relaxed)), 1.0);
+
+        let cancelled = read_file(&path, &AtomicBool::new(true), &progress);
+        assert!(matches!(cancelled, Err(FileError::Cancelled)));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_held_samples_release_when_heard() {
+        let buffer = SampleBuffer::new(8);
+        let mut held = HeldSamples::default();
+        held.pending
+            .extend((1..=6).map(|i| XYSample::new(i as f32, 0.0)));
+
+        held.release(&buffer, Some(2));
+        assert_eq!(held.pending.len(), 4);
+        // Asking for frames that haven't been decoded yet releases what's there
+        held.release(&buffer, Some(100));
+        assert!(held.pending.is_empty());
+
+        held.pending.push_back(XYSample::new(7.0, 0.0));
+        held.release(&buffer, Some(6));
+        assert_eq!(held.pending.len(), 1);
+        held.release(&buffer, None);
+        assert_eq!(buffer.get_samples()[7].x, 7.0);
+    }
+
+    #[test]
+    fn test_commands_and_state() {
+        let (sender, commands) = mpsc::channel();
+        assert_eq!(next_command(&commands, Some(Duration::ZERO)), None);
+        sender.send(PlaybackCommand::Seek(42)).unwrap();
+        assert_eq!(
+            next_command(&commands, None),
+            Some(PlaybackCommand::Seek(42))
+        );
+        // The player dropping its end means stop
+        drop(sender);
+        assert_eq!(
+            next_command(&commands, Some(HELD_POLL)),
+            Some(PlaybackCommand::Stop)
+        );
+
+        let state = SharedState::default();
+        assert_eq!(state.load(), PlaybackState::Stopped);
+        for s in [PlaybackState::Playing, PlaybackState::Paused] {
+            state.store(s);
+            assert_eq!(state.load(), s);
+        }
+
+        // Samples dropped by a seek still count towards the frame numbering
+        let mut held = HeldSamples::default();
+        held.pending.extend([XYSample::new(1.0, 0.0); 5]);
+        held.skip();
+        assert!(held.pending.is_empty());
+        assert_eq!(held.next_frame, 5);
+    }
+}
@@ -3,10 +3,12 @@
 //! This module handles capturing audio from input devices (microphones, etc.)
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use ringbuf::traits::Producer;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use super::buffer::{SampleBuffer, XYSample};
+use super::channels::ChannelBuffer;
 
 /// Audio input capture engine
 pub struct AudioInput {
@@ -31,6 +33,15 @@ pub struct AudioInput {
     /// Gain value for UI binding
     pub gain: f32,
 
+    /// Optional second consumer of captured samples (e.g. measurements)
+    tap: Arc<Mutex<Option<ringbuf::HeapProd<XYSample>>>>,
+
+    /// Every channel, while the device has more than two
+    pub channels: ChannelBuffer,
+
+    /// Sample rate of the running stream
+    sample_rate: Option<u32>,
+
     /// Status message
     pub status: String,
 }
@@ -55,6 +66,9 @@ impl AudioInput {
             selected_device: 0,
             gain_atomic: Arc::new(AtomicU32::new(1.0_f32.to_bits())),
             gain: 1.0,
+            tap: Arc::new(Mutex::new(None)),
+            channels: ChannelBuffer::new(),
+            sample_rate: None,
             status: if device_count > 0 {
                 format!("Found {} input device(s)", device_count)
             } else {
@@ -68,6 +82,21 @@ impl AudioInput {
         self.is_capturing.load(Ordering::Relaxed)
     }
 
+    /// Sample rate of the running capture stream
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+    }
+
+    /// Attach (or detach with `None`) a tap that receives a copy of every
+    /// captured sample, independent of the display buffer. Returns the tap
+    /// it replaces.
+    pub fn set_tap(
+        &self,
+        producer: Option<ringbuf::HeapProd<XYSample>>,
+    ) -> Option<ringbuf::HeapProd<XYSample>> {
+        std::mem::replace(&mut *self.tap.lock().unwrap(), producer)
+    }
+
     /// Start audio capture
     pub fn start(&mut self) {
         if self.stream.is_some() {
@@ -107,7 +136,14 @@ impl AudioInput {
         log::info!("Audio config: {:?}", config);
 
         let channels = config.channels() as usize;
+        let sample_rate = config.sample_rate().0;
         let buffer = self.buffer.clone_ref();
+        let mut block = Vec::with_capacity(SampleBuffer::BLOCK_CAPACITY);
+        self.channels
+            .set_channels(if channels > 2 { channels } else { 0 });
+        let channel_buffer = self.channels.clone();
+        let mut wide = Vec::new();
+        let tap = Arc::clone(&self.tap);
         let is_capturing = Arc::clone(&self.is_capturing);
         // Sync current UI gain to atomic before starting
         self.gain_atomic
@@ -123,10 +159,22 @@ impl AudioInput {
                     }
 
                     let gain = f32::from_bits(gain_atomic.load(Ordering::Relaxed));
+                    let mut tap = tap.try_lock();
+                    block.clear();
                     for frame in data.chunks(channels) {
                         let x = frame[0] * gain;
                         let y = if channels > 1 { frame[1] * gain } else { x };
-                        buffer.push(XYSample::new(x, y));
+                        let sample = XYSample::new(x, y);
+                        block.push(sample);
+                        if let Ok(Some(ref mut prod)) = tap.as_deref_mut() {
+                            let _ = prod.try_push(sample);
+                        }
+                    }
+                    buffer.push_slice(&block);
+                    if channels > 2 {
+                        wide.clear();
+                        wide.extend(data.iter().map(|v| v * gain));
+                        channel_buffer.push_interleaved(&wide);
                     }
                 },
                 |err| log::error!("Audio error: {}", err),
@@ -135,7 +183,11 @@ impl AudioInput {
             cpal::SampleFormat::I16 => {
                 let is_capturing = Arc::clone(&self.is_capturing);
                 let buffer = self.buffer.clone_ref();
+                let mut block = Vec::with_capacity(SampleBuffer::BLOCK_CAPACITY);
+                let tap = Arc::clone(&self.tap);
                 let gain_atomic = Arc::clone(&self.gain_atomic);
+                let channel_buffer = self.channels.clone();
+                let mut wide = Vec::new();
                 device.build_input_stream(
                     &config.into(),
                     move |data: &[i16], _: &cpal::InputCallbackInfo| {
@@ -144,6 +196,8 @@ impl AudioInput {
                         }
 
                         let gain = f32::from_bits(gain_atomic.load(Ordering::Relaxed));
+                        let mut tap = tap.try_lock();
+                        block.clear();
                         for frame in data.chunks(channels) {
                             let x = (frame[0] as f32 / 32768.0) * gain;
                             let y = if channels > 1 {
@@ -151,7 +205,17 @@ impl AudioInput {
                             } else {
                                 x
                             };
-                            buffer.push(XYSample::new(x, y));
+                            let sample = XYSample::new(x, y);
+                            block.push(sample);
+                            if let Ok(Some(ref mut prod)) = tap.as_deref_mut() {
+                                let _ = prod.try_push(sample);
+                            }
+                        }
+                        buffer.push_slice(&block);
+                        if channels > 2 {
+                            wide.clear();
+                            wide.extend(data.iter().map(|&v| v as f32 / 32768.0 * gain));
+                            channel_buffer.push_interleaved(&wide);
                         }
                     },
                     |err| log::error!("Audio error: {}", err),
@@ -173,6 +237,7 @@ impl AudioInput {
 
                 self.is_capturing.store(true, Ordering::Relaxed);
                 self.stream = Some(s);
+                self.sample_rate = Some(sample_rate);
                 self.status = format!("Capturing: {}", device_name);
                 log::info!("Capture started");
             }
@@ -186,6 +251,8 @@ impl AudioInput {
     pub fn stop(&mut self) {
         self.is_capturing.store(false, Ordering::Relaxed);
         self.stream = None;
+        self.sample_rate = None;
+        self.channels.set_channels(0);
         self.status = "Stopped".to_string();
         log::info!("Capture stopped");
     }
@@ -3,13 +3,23 @@
 //! This module provides:
 //! - Ring buffer for thread-safe sample sharing
 //! - Audio input capture
-//! - Audio file playback
+//! - Every channel of multichannel sources, for a grid of scopes
+//! - Audio file playback, with the colors of its cover art
+//! - Fixed-rate frame extraction for the display
+//! - An edge trigger for where the display window starts
 
+mod artwork;
 mod buffer;
+mod channels;
 mod file;
 mod input;
+mod pacer;
+mod trigger;
 
-pub use buffer::{SampleBuffer, XYSample};
+pub use buffer::{SampleBuffer, SampleConsumer, XYSample};
+pub use channels::ChannelBuffer;
 #[allow(unused_imports)]
-pub use file::{AudioFileInfo, AudioFilePlayer, FileError, PlaybackState};
+pub use file::{AudioFileInfo, AudioFilePlayer, FileError, FileReader, PlaybackState};
 pub use input::AudioInput;
+pub use pacer::FramePacer;
+pub use trigger::{ExternalTrigger, Trigger, TriggerSource};
@@ -0,0 +1,128 @@
+//! Fixed-rate frame extraction
+//!
+//! Samples reach the display in bursts: the file decoder pushes whole packets
+//! and sleeps between them, so "the newest N samples" jumps by a different
+//! amount every frame and figures shimmer. `FramePacer` instead keeps its own
+//! read position in the sample stream and advances it by exactly the audio
+//! clock's worth of samples for the time since the last frame. The position
+//! trails the newest sample by a small margin so bursts never run it dry,
+//! and drifts back towards that margin if the two clocks disagree.
+
+use std::time::Instant;
+
+use super::buffer::{SampleConsumer, XYSample};
+
+/// How far behind the newest sample the read position aims to stay
+const MARGIN_SECONDS: f64 = 0.03;
+
+/// Lag errors beyond this are not worth drifting back from
+const RESYNC_SECONDS: f64 = 0.25;
+
+/// Fraction of the lag error corrected each frame
+const DRIFT_CORRECTION: f64 = 0.02;
+
+/// Steps a read position through the sample stream at the audio clock rate
+#[derive(Default)]
+pub struct FramePacer {
+    /// Stream position of the end of the last window, or None to resync
+    cursor: Option<f64>,
+    last_frame: Option<Instant>,
+}
+
+impl FramePacer {
+    /// Forget the position; the next frame starts at the newest samples
+    pub fn reset(&mut self) {
+        self.cursor = None;
+        self.last_frame = None;
+    }
+
+    /// Advance by the time since the last call and copy the `len` samples
+    /// ending at the new position into `out`
+    pub fn extract(
+        &mut self,
+        consumer: &SampleConsumer,
+        sample_rate: u32,
+        len: usize,
+        out: &mut Vec<XYSample>,
+    ) {
+        let now = Instant::now();
+        let elapsed = self
+            .last_frame
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.last_frame = Some(now);
+        self.advance(consumer, sample_rate, elapsed, len, out);
+    }
+
+    fn advance(
+        &mut self,
+        consumer: &SampleConsumer,
+        sample_rate: u32,
+        elapsed: f64,
+        len: usize,
+        out: &mut Vec<XYSample>,
+    ) {
+        let rate = sample_rate as f64;
+        let newest = consumer.drained() as f64;
+        let target = newest - MARGIN_SECONDS * rate;
+        let len = len.min(consumer.capacity());
+
+        let cursor = match self.cursor {
+            Some(cursor) => {
+                let cursor = cursor + elapsed * rate;
+                let error = target - cursor;
+                if error.abs() > RESYNC_SECONDS * rate {
+                    target
+                } else {
+                    cursor + error * DRIFT_CORRECTION
+                }
+            }
+            None => target,
+        };
+        self.cursor = Some(cursor);
+
+        let end = cursor.clamp(0.0, newest) as u64;
+        if !consumer.copy_range_into(end, len, out) {
+            // Not enough history yet (or any more): show what there is
+            consumer.copy_samples_into(out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::SampleBuffer;
+
+    #[test]
+    fn test_steps_at_the_audio_clock() {
+        let buffer = SampleBuffer::new(8192);
+        let mut consumer = buffer.take_consumer().unwrap();
+        let mut pacer = FramePacer::default();
+        let mut out = Vec::new();
+        let rate = 48_000;
+        let frame = 1.0 / 60.0;
+        let ramp = |from: usize, to: usize| -> Vec<XYSample> {
+            (from..to).map(|i| XYSample::new(i as f32, 0.0)).collect()
+        };
+
+        buffer.push_slice(&ramp(0, 4000));
+        consumer.update();
+        pacer.advance(&consumer, rate, 0.0, 100, &mut out);
+        let first = out.last().unwrap().x;
+
+        // Samples arrive in uneven bursts, but the window moves by 800
+        // (48000 / 60) every frame
+        let mut pushed = 4000;
+        for burst in [1600, 0, 800, 1200, 400] {
+            buffer.push_slice(&ramp(pushed, pushed + burst));
+            pushed += burst;
+            consumer.update();
+            let before = out.last().unwrap().x;
+            pacer.advance(&consumer, rate, frame, 100, &mut out);
+            assert_eq!(out.len(), 100);
+            let step = out.last().unwrap().x - before;
+            assert!((step - 800.0).abs() < 30.0, "step {step}");
+        }
+        assert!(out.last().unwrap().x > first);
+    }
+}
@@ -0,0 +1,320 @@
+//! Edge trigger for the display window
+//!
+//! Without a trigger the display shows whatever the newest samples are, so
+//! a window shorter than the figure's period starts at a different point of
+//! it every frame. The trigger instead starts the window where X rises
+//! through a level, with two guards against noise:
+//!
+//! - Hysteresis: after firing, X has to drop below the level by the
+//!   hysteresis before the trigger re-arms, so noise riding on the edge
+//!   can't fire it twice.
+//! - Holdoff: a trigger is ignored until the holdoff time has passed since
+//!   the last one. Triggers are chained from one frame to the next, so on a
+//!   signal with several crossings per period the holdoff picks the same
+//!   crossing every time.
+//!
+//! The trigger can also come from outside the signal: a MIDI note, an OSC
+//! message or a key, fired through an `ExternalTrigger` handle. Each event
+//! is stamped with the stream position it arrived at, so the window locks
+//! to, say, a sequencer's downbeat to within an audio block. Holdoff applies
+//! to these too.
+//!
+//! Until the next trigger comes along the window stays where the last one
+//! was. When there is none at all the newest samples are shown as before,
+//! like a scope's auto mode.
+
+use std::collections::VecDeque;
+use std::sync::mpsc;
+
+use serde::{Deserialize, Serialize};
+
+use super::buffer::{SampleBuffer, XYSample};
+
+/// What the trigger fires on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TriggerSource {
+    /// X rising through the level
+    #[default]
+    Signal,
+    /// Events fired through an `ExternalTrigger`
+    External,
+}
+
+impl TriggerSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Signal => "Signal (X)",
+            Self::External => "External event",
+        }
+    }
+
+    pub fn all() -> &'static [TriggerSource] {
+        &[Self::Signal, Self::External]
+    }
+}
+
+/// Fires the trigger from outside the signal; clone it into whatever
+/// receives the events, on any thread
+#[derive(Clone)]
+pub struct ExternalTrigger {
+    events: mpsc::Sender<u64>,
+    buffer: SampleBuffer,
+}
+
+impl ExternalTrigger {
+    /// Trigger at the newest sample pushed so far. Samples dropped on a
+    /// full ring never reach the display, so they aren't counted.
+    pub fn fire(&self) {
+        let _ = self.events.send(self.buffer.samples_pushed());
+    }
+}
+
+/// Display trigger, on an edge of X or on external events
+#[derive(Debug)]
+pub struct Trigger {
+    pub enabled: bool,
+    pub source: TriggerSource,
+    /// X level to trigger at, rising
+    pub level: f32,
+    /// How far below the level X has to go to re-arm
+    pub hysteresis: f32,
+    /// Minimum time between triggers, in seconds
+    pub holdoff: f32,
+    /// Stream position of the last trigger
+    last: Option<u64>,
+    /// Whether the last window was triggered
+    triggered: bool,
+    sender: mpsc::Sender<u64>,
+    events: mpsc::Receiver<u64>,
+    /// External events without a whole window after them yet
+    pending: VecDeque<u64>,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        let (sender, events) = mpsc::channel();
+        Self {
+            enabled: false,
+            source: TriggerSource::default(),
+            level: 0.0,
+            hysteresis: 0.05,
+            holdoff: 0.0,
+            last: None,
+            triggered: false,
+            sender,
+            events,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl Trigger {
+    /// A handle that fires this trigger, stamping events with positions
+    /// in `buffer`'s stream
+    pub fn external(&self, buffer: &SampleBuffer) -> ExternalTrigger {
+        ExternalTrigger {
+            events: self.sender.clone(),
+            buffer: buffer.clone_ref(),
+        }
+    }
+
+    /// Forget the last trigger, e.g. when the stream restarts
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.triggered = false;
+        self.pending.clear();
+    }
+
+    /// Whether the last window started at a trigger
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    /// Find where in `samples` (oldest first, the newest at stream position
+    /// `end - 1`) the window of `len` samples should start: the latest
+    /// trigger with a whole window after it, or the last one again if
+    /// nothing newer qualifies. `None` if there is neither.
+    pub fn find(
+        &mut self,
+        samples: &[XYSample],
+        end: u64,
+        len: usize,
+        sample_rate: Option<u32>,
+    ) -> Option<usize> {
+        self.triggered = false;
+        self.pending.extend(self.events.try_iter());
+        if !self.enabled || self.source != TriggerSource::External {
+            self.pending.clear();
+        }
+        let count = samples.len();
+        if !self.enabled || len == 0 || len >= count || end < count as u64 {
+            return None;
+        }
+        let start = end - count as u64;
+        let latest = start + (count - len) as u64;
+        let holdoff = sample_rate.map_or(0, |rate| (self.holdoff.max(0.0) * rate as f32) as u64);
+        // A last trigger the stream has since run past can't be chained from
+        let mut last = self.last.filter(|&last| last <= end);
+        // Triggers up to the last one were dealt with before
+        let mut accept = |position: u64| {
+            if last.is_none_or(|last| position >= last + holdoff.max(1)) {
+                last = Some(position);
+            }
+        };
+
+        match self.source {
+            TriggerSource::Signal => {
+                let rearm = self.level - self.hysteresis.max(0.0);
+                let mut armed = false;
+                for (i, pair) in samples[..=count - len].windows(2).enumerate() {
+                    if pair[0].x < rearm {
+                        armed = true;
+                    }
+                    if armed && pair[0].x < self.level && pair[1].x >= self.level {
+                        armed = false;
+                        accept(start + i as u64 + 1);
+                    }
+                }
+            }
+            TriggerSource::External => {
+                while let Some(&position) = self.pending.front() {
+                    if position > latest {
+                        break;
+                    }
+                    self.pending.pop_front();
+                    // Events from before the snapshot can't be shown
+                    if position >= start {
+                        accept(position);
+                    }
+                }
+            }
+        }
+
+        self.last = last;
+        let found = last
+            .filter(|last| (start..=latest).contains(last))
+            .map(|last| (last - start) as usize);
+        self.triggered = found.is_some();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sawtooth-like ramp from -1 to 1 every `period` samples, plus a
+    /// small wiggle at `bump` into each period
+    fn ramp(count: usize, period: usize, bump: Option<usize>) -> Vec<XYSample> {
+        (0..count)
+            .map(|i| {
+                let phase = i % period;
+                let mut x = phase as f32 / period as f32 * 2.0 - 1.0;
+                if bump == Some(phase) {
+                    x -= 0.04;
+                }
+                XYSample::new(x, 0.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_triggers_at_rising_level() {
+        let samples = ramp(1000, 100, None);
+        let mut trigger = Trigger {
+            enabled: true,
+            ..Trigger::default()
+        };
+        // X crosses 0 halfway through each period; the last with 200
+        // samples after it is at 750
+        assert_eq!(trigger.find(&samples, 1000, 200, None), Some(750));
+        assert!(trigger.is_triggered());
+
+        trigger.enabled = false;
+        assert_eq!(trigger.find(&samples, 1000, 200, None), None);
+        assert!(!trigger.is_triggered());
+    }
+
+    #[test]
+    fn test_hysteresis_rejects_wiggles() {
+        // Just past the level X dips back under it: a second crossing
+        let samples = ramp(1000, 100, Some(51));
+        let mut trigger = Trigger {
+            enabled: true,
+            hysteresis: 0.0,
+            ..Trigger::default()
+        };
+        assert_eq!(trigger.find(&samples, 1000, 200, None), Some(752));
+
+        trigger.reset();
+        trigger.hysteresis = 0.1;
+        assert_eq!(trigger.find(&samples, 1000, 200, None), Some(750));
+    }
+
+    #[test]
+    fn test_holdoff_chains_across_frames() {
+        let samples = ramp(1000, 100, None);
+        let mut trigger = Trigger {
+            enabled: true,
+            // 250 samples at 1 kHz: every third crossing from the first
+            holdoff: 0.25,
+            ..Trigger::default()
+        };
+        assert_eq!(trigger.find(&samples, 1000, 200, Some(1000)), Some(650));
+
+        // Next frame, 100 samples on: 650 + 300 = 950 is the next in the
+        // chain but leaves no room, so the window stays at 650
+        let next: Vec<XYSample> = ramp(1100, 100, None)[100..].to_vec();
+        assert_eq!(trigger.find(&next, 1100, 200, Some(1000)), Some(550));
+        let later: Vec<XYSample> = ramp(1300, 100, None)[300..].to_vec();
+        assert_eq!(trigger.find(&later, 1300, 200, Some(1000)), Some(650));
+    }
+
+    #[test]
+    fn test_external_events_start_the_window() {
+        let buffer = SampleBuffer::new(4096);
+        let mut trigger = Trigger {
+            enabled: true,
+            source: TriggerSource::External,
+            ..Trigger::default()
+        };
+        let external = trigger.external(&buffer);
+        let silence = |count| vec![XYSample::default(); count];
+
+        buffer.push_slice(&silence(1000));
+        external.fire();
+        buffer.push_slice(&silence(500));
+        assert_eq!(trigger.find(&silence(1500), 1500, 200, None), Some(1000));
+
+        // Too recent for a whole window: wait, keeping the last one
+        external.fire();
+        assert_eq!(trigger.find(&silence(1500), 1500, 200, None), Some(1000));
+        assert_eq!(trigger.find(&silence(1500), 1800, 200, None), Some(1200));
+    }
+
+    #[test]
+    fn test_external_events_after_an_overflow() {
+        let buffer = SampleBuffer::new(1024);
+        let mut consumer = buffer.take_consumer().unwrap();
+        let mut trigger = Trigger {
+            enabled: true,
+            source: TriggerSource::External,
+            ..Trigger::default()
+        };
+        let external = trigger.external(&buffer);
+        let silence = |count| vec![XYSample::default(); count];
+
+        // The ring holds 2048; the rest is dropped before anything is read
+        buffer.push_slice(&silence(3000));
+        consumer.update();
+        buffer.push_slice(&silence(300));
+        external.fire();
+        buffer.push_slice(&silence(500));
+        consumer.update();
+
+        // The event is 500 samples back from the newest drained sample
+        let end = consumer.drained();
+        let samples = consumer.get_samples();
+        assert_eq!(trigger.find(&samples, end, 200, None), Some(1024 - 500));
+    }
+}
@@ -0,0 +1,97 @@
+//! Dual-oscillator calibration
+//!
+//! Plays the same sine on both channels (equal level, 0° phase) and measures
+//! what comes back through the input. Any phase or level difference between
+//! the channels in the result is introduced by the chain itself: output
+//! converter, cables, input converter.
+
+use std::collections::VecDeque;
+
+use ringbuf::{
+    traits::{Consumer, Split},
+    HeapRb,
+};
+use serde::{Deserialize, Serialize};
+
+use super::function::{ChannelParams, FunctionParams, FunctionSource, Waveform};
+use crate::analysis::EllipseMeasurement;
+use crate::audio::XYSample;
+
+/// Length of the measurement window
+const WINDOW_SECS: f32 = 0.2;
+
+/// Calibration signal settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalibrationParams {
+    pub frequency: f32,
+    /// Peak level in dBFS
+    pub level_db: f32,
+}
+
+impl Default for CalibrationParams {
+    fn default() -> Self {
+        Self {
+            frequency: 1000.0,
+            level_db: -12.0,
+        }
+    }
+}
+
+impl CalibrationParams {
+    /// Identical sines on both channels
+    pub fn build(&self) -> FunctionSource {
+        let channel = ChannelParams {
+            waveform: Waveform::Sine,
+            frequency: self.frequency,
+            level_db: self.level_db,
+            phase_deg: 0.0,
+            enabled: true,
+        };
+        FunctionParams {
+            left: channel.clone(),
+            right: channel,
+        }
+        .build()
+    }
+}
+
+/// A running calibration measurement
+pub struct Calibration {
+    consumer: ringbuf::HeapCons<XYSample>,
+    window: VecDeque<XYSample>,
+    window_len: usize,
+    /// Latest measurement, if the input carries signal
+    pub result: Option<EllipseMeasurement>,
+}
+
+impl Calibration {
+    /// Returns the measurement plus the producer the audio input should
+    /// feed (see `AudioInput::set_tap`)
+    pub fn new(sample_rate: u32) -> (Self, ringbuf::HeapProd<XYSample>) {
+        let window_len = (WINDOW_SECS * sample_rate as f32) as usize;
+        let (prod, cons) = HeapRb::<XYSample>::new(sample_rate as usize).split();
+        let calibration = Self {
+            consumer: cons,
+            window: VecDeque::with_capacity(window_len),
+            window_len,
+            result: None,
+        };
+        (calibration, prod)
+    }
+
+    /// Drain new input and re-measure. Call once per frame.
+    pub fn update(&mut self) {
+        let mut received = false;
+        while let Some(sample) = self.consumer.try_pop() {
+            if self.window.len() == self.window_len {
+                self.window.pop_front();
+            }
+            self.window.push_back(sample);
+            received = true;
+        }
+        if received && self.window.len() == self.window_len {
+            self.result = EllipseMeasurement::measure(self.window.make_contiguous());
+        }
+    }
+}
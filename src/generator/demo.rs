@@ -0,0 +1,474 @@
+//! Built-in demo scenes
+//!
+//! Vector scenes drawn when nothing else is feeding the scope, so an
+//! unattended display always shows something. Each scene is redrawn once per
+//! refresh into a buffer sized when the sample rate is set; drawing only
+//! writes into that buffer, so the audio thread never allocates.
+//!
+//! The attract sequence takes turns through several scenes. Installations
+//! switch to it once the display has had no signal and nobody at the
+//! controls for a while (`IdleTimer`).
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::SignalSource;
+use crate::audio::XYSample;
+
+/// Samples spent moving between separate strokes
+const JUMP_SAMPLES: usize = 2;
+
+/// Redraws per second
+const DEMO_REFRESH_HZ: u32 = 50;
+
+/// Scenes the attract sequence takes turns through, in order
+const ATTRACT_SEQUENCE: [DemoScene; 3] = [DemoScene::Wireframe, DemoScene::Sweep, DemoScene::Clock];
+
+/// Seconds each scene of the attract sequence is shown
+const ATTRACT_SCENE_SECS: f32 = 20.0;
+
+/// A demo scene
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum DemoScene {
+    /// Analog clock showing local time
+    #[default]
+    Clock,
+    /// A square and a circle bouncing around the screen
+    Bounce,
+    /// A slowly drifting Lissajous figure
+    Drift,
+    /// A cube and an octahedron turning in 3D
+    Wireframe,
+    /// A Lissajous figure sweeping through frequency ratios
+    Sweep,
+    /// Wireframe, sweep and clock in turn
+    Attract,
+}
+
+impl DemoScene {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clock => "Clock",
+            Self::Bounce => "Bounce",
+            Self::Drift => "Drift",
+            Self::Wireframe => "Wireframe",
+            Self::Sweep => "Lissajous sweep",
+            Self::Attract => "Attract sequence",
+        }
+    }
+
+    pub fn all() -> &'static [DemoScene] {
+        &[
+            Self::Clock,
+            Self::Bounce,
+            Self::Drift,
+            Self::Wireframe,
+            Self::Sweep,
+            Self::Attract,
+        ]
+    }
+
+    /// The scene drawn `time` seconds in: the attract sequence's current
+    /// turn, or the scene itself
+    fn at(self, time: f32) -> DemoScene {
+        match self {
+            Self::Attract => {
+                let turn = (time / ATTRACT_SCENE_SECS) as usize;
+                ATTRACT_SEQUENCE[turn % ATTRACT_SEQUENCE.len()]
+            }
+            scene => scene,
+        }
+    }
+}
+
+/// Demo mode settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DemoParams {
+    /// Show a demo scene whenever no source is active
+    pub enabled: bool,
+    pub scene: DemoScene,
+    /// Switch to the attract sequence after `attract_after` minutes without
+    /// signal or user input
+    pub attract: bool,
+    pub attract_after: f32,
+}
+
+impl Default for DemoParams {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scene: DemoScene::Clock,
+            attract: false,
+            attract_after: 5.0,
+        }
+    }
+}
+
+impl DemoParams {
+    /// Wait before the attract sequence starts
+    pub fn attract_delay(&self) -> Duration {
+        Duration::from_secs_f32(self.attract_after.max(0.0) * 60.0)
+    }
+
+    pub fn build(&self) -> DemoSource {
+        Self::build_scene(self.scene)
+    }
+
+    pub fn build_scene(scene: DemoScene) -> DemoSource {
+        // Look the offset up once here: time zone lookups may hit the disk,
+        // which the audio thread must not do
+        let utc_offset = jiff::Zoned::now().offset().seconds();
+        let mut source = DemoSource {
+            scene,
+            utc_offset,
+            points: Vec::new(),
+            frame_len: 0,
+            sample_rate: 48000,
+            pos: 0,
+            time: 0.0,
+            ball: Ball {
+                pos: (0.3, 0.1),
+                vel: (0.37, 0.29),
+            },
+            box_: Ball {
+                pos: (-0.4, -0.2),
+                vel: (-0.23, 0.41),
+            },
+        };
+        source.set_sample_rate(48000);
+        source
+    }
+}
+
+/// Tracks how long the display has gone without signal or anyone at the
+/// controls
+#[derive(Clone, Debug, Default)]
+pub struct IdleTimer {
+    active_at: Option<Instant>,
+}
+
+impl IdleTimer {
+    /// Feed whether there was signal or input this frame. Returns whether
+    /// there has been neither for at least `after`.
+    pub fn update(&mut self, now: Instant, active: bool, after: Duration) -> bool {
+        if active {
+            self.active_at = Some(now);
+            return false;
+        }
+        let since = *self.active_at.get_or_insert(now);
+        now.duration_since(since) >= after
+    }
+}
+
+/// A moving object bouncing off the screen edges
+struct Ball {
+    pos: (f32, f32),
+    /// Units per second
+    vel: (f32, f32),
+}
+
+impl Ball {
+    /// Move by `dt` seconds, keeping an object of `radius` inside [-1, 1]
+    fn advance(&mut self, dt: f32, radius: f32) {
+        let limit = 1.0 - radius;
+        for (p, v) in [
+            (&mut self.pos.0, &mut self.vel.0),
+            (&mut self.pos.1, &mut self.vel.1),
+        ] {
+            *p += *v * dt;
+            if p.abs() > limit {
+                *p = p.clamp(-limit, limit);
+                *v = -*v;
+            }
+        }
+    }
+}
+
+/// Running demo scene
+pub struct DemoSource {
+    scene: DemoScene,
+    utc_offset: i32,
+    points: Vec<XYSample>,
+    /// Samples per frame (one redraw)
+    frame_len: usize,
+    sample_rate: u32,
+    pos: usize,
+    /// Seconds since the scene started
+    time: f32,
+    ball: Ball,
+    box_: Ball,
+}
+
+impl DemoSource {
+    /// Draw the next frame into `points`
+    fn render_frame(&mut self, dt: f32) {
+        self.time += dt;
+        let n = self.frame_len;
+        let mut pen = Pen::new(&mut self.points, n);
+
+        match self.scene.at(self.time) {
+            DemoScene::Clock => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+                let secs = (now + self.utc_offset as f64).rem_euclid(43_200.0) as f32;
+
+                // Face and hour ticks
+                pen.circle((0.0, 0.0), 0.95, n * 35 / 100);
+                for h in 0..12 {
+                    let a = FRAC_PI_2 - h as f32 / 12.0 * TAU;
+                    let inner = if h % 3 == 0 { 0.72 } else { 0.82 };
+                    pen.move_to((a.cos() * inner, a.sin() * inner));
+                    pen.line_to((a.cos() * 0.95, a.sin() * 0.95), n / 60);
+                }
+
+                // Hands, drawn out from the center and back
+                let hands = [
+                    (secs / 43_200.0, 0.5, n * 10 / 100),
+                    (secs / 3600.0 % 1.0, 0.75, n * 12 / 100),
+                    (secs.floor() / 60.0 % 1.0, 0.85, n * 12 / 100),
+                ];
+                pen.move_to((0.0, 0.0));
+                for (turns, length, samples) in hands {
+                    let a = FRAC_PI_2 - turns * TAU;
+                    pen.line_to((a.cos() * length, a.sin() * length), samples / 2);
+                    pen.line_to((0.0, 0.0), samples / 2);
+                }
+            }
+            DemoScene::Bounce => {
+                self.ball.advance(dt, 0.25);
+                self.box_.advance(dt, 0.3);
+
+                pen.circle(self.ball.pos, 0.25, n / 2);
+
+                // Spinning square
+                let spin = self.time * 1.3;
+                let (cx, cy) = self.box_.pos;
+                let corner = |k: usize| {
+                    let a = spin + k as f32 * FRAC_PI_2;
+                    (cx + a.cos() * 0.3, cy + a.sin() * 0.3)
+                };
+                pen.move_to(corner(0));
+                for k in 1..=4 {
+                    pen.line_to(corner(k), n / 10);
+                }
+            }
+            DemoScene::Drift => {
+                // Ratio slowly wanders around 3:2 so the figure keeps morphing
+                let ratio = 1.5 + 0.02 * (self.time * 0.1).sin();
+                let phase = self.time * 0.7;
+                let samples = pen.remaining();
+                for i in 0..samples {
+                    let t = i as f32 / samples as f32 * TAU * 2.0;
+                    pen.push(((t + phase).sin() * 0.9, (t * ratio).sin() * 0.9));
+                }
+            }
+            DemoScene::Wireframe => {
+                let (a, b) = (self.time * 0.5, self.time * 0.8);
+                let project = |(x, y, z): (f32, f32, f32)| {
+                    // Turn about Y, then X, and view from in front
+                    let (x, z) = (x * a.cos() + z * a.sin(), z * a.cos() - x * a.sin());
+                    let (y, z) = (y * b.cos() - z * b.sin(), z * b.cos() + y * b.sin());
+                    let scale = 1.8 / (4.0 + z);
+                    (x * scale, y * scale)
+                };
+                let per_edge = n / 22;
+                for (from, to) in CUBE_EDGES {
+                    pen.move_to(project(CUBE[from]));
+                    pen.line_to(project(CUBE[to]), per_edge);
+                }
+                for (from, to) in OCTAHEDRON_EDGES {
+                    pen.move_to(project(OCTAHEDRON[from]));
+                    pen.line_to(project(OCTAHEDRON[to]), per_edge * 2 / 3);
+                }
+            }
+            DemoScene::Sweep => {
+                // Passes through 1:1, 2:1, 3:1 and back over about a minute
+                let ratio = 2.0 - (self.time * 0.1).cos();
+                let phase = self.time * 0.5;
+                let samples = pen.remaining();
+                for i in 0..samples {
+                    let t = i as f32 / samples as f32 * TAU * 3.0;
+                    pen.push(((t + phase).sin() * 0.9, (t * ratio).sin() * 0.9));
+                }
+            }
+            // `at` always picks one of the scenes above
+            DemoScene::Attract => {}
+        }
+    }
+}
+
+/// Corners of a cube
+const CUBE: [(f32, f32, f32); 8] = [
+    (-1.0, -1.0, -1.0),
+    (1.0, -1.0, -1.0),
+    (1.0, 1.0, -1.0),
+    (-1.0, 1.0, -1.0),
+    (-1.0, -1.0, 1.0),
+    (1.0, -1.0, 1.0),
+    (1.0, 1.0, 1.0),
+    (-1.0, 1.0, 1.0),
+];
+
+const CUBE_EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 0),
+    (4, 5),
+    (5, 6),
+    (6, 7),
+    (7, 4),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// Corners of an octahedron inside the cube
+const OCTAHEDRON: [(f32, f32, f32); 6] = [
+    (0.7, 0.0, 0.0),
+    (-0.7, 0.0, 0.0),
+    (0.0, 0.7, 0.0),
+    (0.0, -0.7, 0.0),
+    (0.0, 0.0, 0.7),
+    (0.0, 0.0, -0.7),
+];
+
+const OCTAHEDRON_EDGES: [(usize, usize); 12] = [
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (0, 5),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (1, 5),
+    (2, 4),
+    (4, 3),
+    (3, 5),
+    (5, 2),
+];
+
+impl SignalSource for DemoSource {
+    fn next_sample(&mut self) -> XYSample {
+        if self.pos >= self.points.len() {
+            // Scenes advance by however long the previous frame took to play
+            let played = self.points.len();
+            self.render_frame(played as f32 / self.sample_rate as f32);
+            self.pos = 0;
+        }
+        let sample = self.points.get(self.pos).copied().unwrap_or_default();
+        self.pos += 1;
+        sample
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.max(1);
+        self.frame_len = (sample_rate / DEMO_REFRESH_HZ).max(64) as usize;
+        // Scenes are drawn into this capacity and never grow it
+        self.points = Vec::with_capacity(self.frame_len);
+        self.pos = 0;
+    }
+}
+
+/// Draws into a frame buffer without ever growing it
+struct Pen<'a> {
+    out: &'a mut Vec<XYSample>,
+    budget: usize,
+    last: (f32, f32),
+}
+
+impl<'a> Pen<'a> {
+    /// Start a new frame of at most `budget` samples
+    fn new(out: &'a mut Vec<XYSample>, budget: usize) -> Self {
+        out.clear();
+        Self {
+            budget: budget.min(out.capacity()),
+            out,
+            last: (0.0, 0.0),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.budget.saturating_sub(self.out.len())
+    }
+
+    fn push(&mut self, p: (f32, f32)) {
+        if self.out.len() < self.budget {
+            self.out.push(XYSample::new(p.0, p.1));
+        }
+        self.last = p;
+    }
+
+    /// Straight line from the current position, in `samples` steps
+    fn line_to(&mut self, p: (f32, f32), samples: usize) {
+        let from = self.last;
+        let samples = samples.max(1);
+        for k in 1..=samples {
+            let t = k as f32 / samples as f32;
+            self.push((from.0 + (p.0 - from.0) * t, from.1 + (p.1 - from.1) * t));
+        }
+    }
+
+    /// Quick move between strokes
+    fn move_to(&mut self, p: (f32, f32)) {
+        self.line_to(p, JUMP_SAMPLES);
+    }
+
+    /// Full circle starting and ending at its rightmost point
+    fn circle(&mut self, center: (f32, f32), radius: f32, samples: usize) {
+        self.move_to((center.0 + radius, center.1));
+        let samples = samples.max(3);
+        for k in 1..=samples {
+            let a = k as f32 / samples as f32 * TAU;
+            self.push((center.0 + a.cos() * radius, center.1 + a.sin() * radius));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_after_the_delay() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let after = Duration::from_secs(300);
+        let mut idle = IdleTimer::default();
+
+        assert!(!idle.update(at(0), true, after));
+        assert!(!idle.update(at(200), false, after));
+        assert!(idle.update(at(300), false, after));
+        // Signal or a touch ends it at once, and the wait starts over
+        assert!(!idle.update(at(301), true, after));
+        assert!(!idle.update(at(500), false, after));
+        assert!(idle.update(at(601), false, after));
+    }
+
+    #[test]
+    fn test_attract_takes_turns() {
+        let scene = DemoScene::Attract;
+        assert_eq!(scene.at(0.0), DemoScene::Wireframe);
+        assert_eq!(scene.at(25.0), DemoScene::Sweep);
+        assert_eq!(scene.at(45.0), DemoScene::Clock);
+        assert_eq!(scene.at(61.0), DemoScene::Wireframe);
+        assert_eq!(DemoScene::Bounce.at(45.0), DemoScene::Bounce);
+
+        // Every turn draws something, all of it on screen
+        let mut source = DemoParams::build_scene(scene);
+        for _ in 0..6 {
+            source.render_frame(ATTRACT_SCENE_SECS / 2.0 + 0.3);
+            assert!(!source.points.is_empty());
+            assert!(source
+                .points
+                .iter()
+                .all(|p| p.x.abs() <= 1.0 && p.y.abs() <= 1.0));
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Real-time generator engine
+//!
+//! Runs a `SignalSource` at a fixed sample rate and pushes its output into the
+//! shared `SampleBuffer`, so generated signals go through exactly the same
+//! display path as live input and file playback.
+//!
+//! Two clocks are possible:
+//! - **Audio output**: the cpal output callback pulls samples from the source
+//!   and plays them, so what you hear is what you see
+//! - **Silent**: a timer thread produces samples at the nominal rate
+//!
+//! On devices with three or more output channels, the third carries a Z
+//! (intensity) signal: 1.0 while drawing, 0.0 while the source is blanked.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use super::SignalSource;
+use crate::audio::{SampleBuffer, XYSample};
+
+/// Sample rate used when no audio device is driving the clock
+const TIMER_SAMPLE_RATE: u32 = 48000;
+
+/// A source that outputs silence (used before anything is selected)
+struct Silence;
+
+impl SignalSource for Silence {
+    fn next_sample(&mut self) -> XYSample {
+        XYSample::default()
+    }
+
+    fn set_sample_rate(&mut self, _sample_rate: u32) {}
+}
+
+/// Generator engine
+pub struct Generator {
+    /// Active source, shared with the audio callback / timer thread
+    source: Arc<Mutex<Box<dyn SignalSource>>>,
+
+    /// Shared sample buffer
+    buffer: SampleBuffer,
+
+    /// Whether the generator is running
+    is_running: Arc<AtomicBool>,
+
+    /// Output stream (when audio output is enabled)
+    stream: Option<cpal::Stream>,
+
+    /// Timer thread handle (when audio output is disabled)
+    thread_handle: Option<thread::JoinHandle<()>>,
+
+    /// Sample rate the current source is running at
+    sample_rate: u32,
+
+    /// Output volume (shared atomically with audio thread)
+    volume_atomic: Arc<AtomicU32>,
+
+    /// Output volume for UI binding
+    pub volume: f32,
+
+    /// Send generated samples to the selected output device
+    pub audio_output: bool,
+
+    /// Available output devices
+    pub output_devices: Vec<String>,
+
+    /// Selected output device, as an index into `output_devices`
+    pub selected_output: usize,
+
+    /// Status message
+    pub status: String,
+}
+
+impl Generator {
+    pub fn new(buffer: SampleBuffer) -> Self {
+        let mut generator = Self {
+            source: Arc::new(Mutex::new(Box::new(Silence))),
+            buffer,
+            is_running: Arc::new(AtomicBool::new(false)),
+            stream: None,
+            thread_handle: None,
+            sample_rate: TIMER_SAMPLE_RATE,
+            volume_atomic: Arc::new(AtomicU32::new(0.5_f32.to_bits())),
+            volume: 0.5,
+            audio_output: false,
+            output_devices: Vec::new(),
+            selected_output: 0,
+            status: "Stopped".to_string(),
+        };
+        generator.scan_output_devices();
+        generator
+    }
+
+    /// Refresh the list of output devices
+    pub fn scan_output_devices(&mut self) {
+        let host = cpal::default_host();
+        self.output_devices = host
+            .output_devices()
+            .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+            .unwrap_or_default();
+
+        // Preselect the system default
+        if let Some(default_name) = host.default_output_device().and_then(|d| d.name().ok()) {
+            if let Some(i) = self.output_devices.iter().position(|n| *n == default_name) {
+                self.selected_output = i;
+            }
+        }
+    }
+
+    /// Check if the generator is running
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::Relaxed)
+    }
+
+    /// Rate sources are currently run at (device rate, or the timer rate)
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Replace the active source
+    pub fn set_source(&mut self, mut source: Box<dyn SignalSource>) {
+        source.set_sample_rate(self.sample_rate);
+        *self.source.lock().unwrap() = source;
+    }
+
+    /// Start generating
+    pub fn start(&mut self) {
+        if self.is_running() {
+            return;
+        }
+
+        self.sync_volume();
+        self.is_running.store(true, Ordering::Relaxed);
+
+        if self.audio_output {
+            match self.start_audio_output() {
+                Ok(device_name) => {
+                    self.status = format!("Playing: {}", device_name);
+                    log::info!("Generator started on {}", device_name);
+                    return;
+                }
+                Err(e) => {
+                    log::warn!("Generator audio output failed ({}), running silent", e);
+                }
+            }
+        }
+
+        self.start_timer();
+        self.status = "Running (silent)".to_string();
+        log::info!("Generator started (silent)");
+    }
+
+    /// Stop generating
+    pub fn stop(&mut self) {
+        self.is_running.store(false, Ordering::Relaxed);
+        self.stream = None;
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+        self.status = "Stopped".to_string();
+    }
+
+    /// Toggle running state
+    pub fn toggle(&mut self) {
+        if self.is_running() {
+            self.stop();
+        } else {
+            self.start();
+        }
+    }
+
+    /// Restart so a changed `audio_output` flag takes effect
+    pub fn restart(&mut self) {
+        if self.is_running() {
+            self.stop();
+            self.start();
+        }
+    }
+
+    /// Sync the UI volume value to the audio thread
+    pub fn sync_volume(&self) {
+        self.volume_atomic
+            .store(self.volume.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Name of the selected output device
+    pub fn output_device(&self) -> Option<&str> {
+        self.output_devices
+            .get(self.selected_output)
+            .map(String::as_str)
+    }
+
+    /// Select the output device called `name`, if it's connected
+    pub fn select_output(&mut self, name: &str) {
+        if let Some(i) = self.output_devices.iter().position(|n| n == name) {
+            self.selected_output = i;
+        }
+    }
+
+    /// Open the selected output device and let its callback drive the source
+    fn start_audio_output(&mut self) -> Result<String, String> {
+        let host = cpal::default_host();
+        // Match by name: devices come and go, so the host's order needn't
+        // be the one scanned into the list
+        let wanted = self.output_device().map(str::to_string);
+        let device = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| {
+                devices.find(|d| d.name().ok().is_some_and(|n| Some(n) == wanted))
+            })
+            .or_else(|| host.default_output_device())
+            .ok_or_else(|| "No output device".to_string())?;
+        let device_name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+        let config = device.default_output_config().map_err(|e| e.to_string())?;
+
+        self.sample_rate = config.sample_rate().0;
+        self.source
+            .lock()
+            .unwrap()
+            .set_sample_rate(self.sample_rate);
+
+        let channels = config.channels() as usize;
+        let source = Arc::clone(&self.source);
+        let buffer = self.buffer.clone_ref();
+        let mut block = Vec::with_capacity(SampleBuffer::BLOCK_CAPACITY);
+        let volume_atomic = Arc::clone(&self.volume_atomic);
+
+        let stream = device
+            .build_output_stream(
+                &config.into(),
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    // Never block the audio thread: if the UI is swapping the
+                    // source right now, output silence for this block
+                    let mut source = source.try_lock();
+                    let volume = match source {
+                        Ok(ref s) if s.calibrated() => 1.0,
+                        _ => f32::from_bits(volume_atomic.load(Ordering::Relaxed)),
+                    };
+                    block.clear();
+                    for frame in data.chunks_mut(channels) {
+                        let (sample, blanked) = match source {
+                            Ok(ref mut s) => (s.next_sample(), s.blanked()),
+                            Err(_) => (XYSample::default(), true),
+                        };
+                        block.push(sample);
+
+                        if channels >= 2 {
+                            frame[0] = sample.x * volume;
+                            frame[1] = sample.y * volume;
+                            for ch in frame.iter_mut().skip(2) {
+                                *ch = 0.0;
+                            }
+                            if channels >= 3 && !blanked {
+                                frame[2] = 1.0;
+                            }
+                        } else {
+                            frame[0] = (sample.x + sample.y) / 2.0 * volume;
+                        }
+                    }
+                    buffer.push_slice(&block);
+                },
+                |err| log::error!("Generator output error: {}", err),
+                None,
+            )
+            .map_err(|e| e.to_string())?;
+
+        stream.play().map_err(|e| e.to_string())?;
+        self.stream = Some(stream);
+        Ok(device_name)
+    }
+
+    /// Produce samples from a timer thread at `TIMER_SAMPLE_RATE`
+    fn start_timer(&mut self) {
+        self.sample_rate = TIMER_SAMPLE_RATE;
+        self.source
+            .lock()
+            .unwrap()
+            .set_sample_rate(self.sample_rate);
+
+        let source = Arc::clone(&self.source);
+        let buffer = self.buffer.clone_ref();
+        let is_running = Arc::clone(&self.is_running);
+
+        self.thread_handle = Some(thread::spawn(move || {
+            let start = Instant::now();
+            let mut produced: u64 = 0;
+            let mut block = Vec::with_capacity(SampleBuffer::BLOCK_CAPACITY);
+
+            while is_running.load(Ordering::Relaxed) {
+                let due = (start.elapsed().as_secs_f64() * TIMER_SAMPLE_RATE as f64) as u64;
+                if let Ok(mut source) = source.try_lock() {
+                    block.clear();
+                    while produced < due {
+                        block.push(source.next_sample());
+                        produced += 1;
+                    }
+                    buffer.push_slice(&block);
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+        }));
+    }
+}
+
+impl Drop for Generator {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
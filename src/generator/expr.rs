@@ -0,0 +1,462 @@
+//! Tiny expression language for parametric equations
+//!
+//! Parses strings like `sin(a*t + c) * 0.8` into a tree that can be evaluated
+//! cheaply on the audio thread. Parsing happens once on the UI thread; the
+//! audio thread only ever calls `eval`.
+//!
+//! ## Grammar
+//!
+//! ```text
+//! expr    = term (('+' | '-') term)*
+//! term    = unary (('*' | '/' | '%') unary)*
+//! unary   = '-' unary | power
+//! power   = atom ('^' unary)?
+//! atom    = number | name | name '(' expr (',' expr)* ')' | '(' expr ')'
+//! ```
+//!
+//! Variables: `t` (phase in radians), `a`, `b`, `c` (user parameters),
+//! plus the constants `pi` and `e`.
+
+use thiserror::Error;
+
+/// Deepest an expression tree may get. Parsing and evaluation recurse once
+/// per level, so this keeps a pasted monster from overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+/// Errors produced while parsing an expression
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExprError {
+    #[error("Unexpected character '{0}'")]
+    UnexpectedChar(char),
+
+    #[error("Unexpected end of expression")]
+    UnexpectedEnd,
+
+    #[error("Unexpected token '{0}'")]
+    UnexpectedToken(String),
+
+    #[error("Unknown name '{0}'")]
+    UnknownName(String),
+
+    #[error("'{0}' expects {1} argument(s)")]
+    WrongArgCount(String, usize),
+
+    #[error("Expression is nested too deeply")]
+    TooDeep,
+}
+
+/// Values of the variables an expression can reference
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Vars {
+    pub t: f32,
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Var {
+    T,
+    A,
+    B,
+    C,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Floor,
+    Sign,
+    Min,
+    Max,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "tan" => Self::Tan,
+            "abs" => Self::Abs,
+            "sqrt" => Self::Sqrt,
+            "exp" => Self::Exp,
+            "ln" | "log" => Self::Ln,
+            "floor" => Self::Floor,
+            "sign" => Self::Sign,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            _ => return None,
+        })
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Self::Min | Self::Max => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// A parsed expression, ready to evaluate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    root: Node,
+}
+
+impl Expr {
+    /// Parse an expression from source text
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let root = parser.expr()?;
+        match parser.peek() {
+            None => Ok(Self { root }),
+            Some(tok) => Err(ExprError::UnexpectedToken(tok.to_string())),
+        }
+    }
+
+    /// Evaluate the expression with the given variables
+    pub fn eval(&self, vars: &Vars) -> f32 {
+        self.root.eval(vars)
+    }
+}
+
+/// Expression tree node
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Num(f32),
+    Var(Var),
+    Neg(Box<Node>),
+    Binary(BinOp, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+impl Node {
+    fn eval(&self, vars: &Vars) -> f32 {
+        match self {
+            Self::Num(n) => *n,
+            Self::Var(v) => match v {
+                Var::T => vars.t,
+                Var::A => vars.a,
+                Var::B => vars.b,
+                Var::C => vars.c,
+            },
+            Self::Neg(e) => -e.eval(vars),
+            Self::Binary(op, l, r) => {
+                let l = l.eval(vars);
+                let r = r.eval(vars);
+                match op {
+                    BinOp::Add => l + r,
+                    BinOp::Sub => l - r,
+                    BinOp::Mul => l * r,
+                    BinOp::Div => l / r,
+                    BinOp::Rem => l % r,
+                    BinOp::Pow => l.powf(r),
+                }
+            }
+            Self::Call(func, args) => {
+                let x = args[0].eval(vars);
+                match func {
+                    Func::Sin => x.sin(),
+                    Func::Cos => x.cos(),
+                    Func::Tan => x.tan(),
+                    Func::Abs => x.abs(),
+                    Func::Sqrt => x.sqrt(),
+                    Func::Exp => x.exp(),
+                    Func::Ln => x.ln(),
+                    Func::Floor => x.floor(),
+                    Func::Sign => x.signum(),
+                    Func::Min => x.min(args[1].eval(vars)),
+                    Func::Max => x.max(args[1].eval(vars)),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f32),
+    Name(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Num(n) => write!(f, "{}", n),
+            Self::Name(s) => write!(f, "{}", s),
+            Self::Op(c) => write!(f, "{}", c),
+            Self::LParen => write!(f, "("),
+            Self::RParen => write!(f, ")"),
+            Self::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut text = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_ascii_digit() || d == '.' {
+                    text.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let n = text
+                .parse::<f32>()
+                .map_err(|_| ExprError::UnexpectedToken(text.clone()))?;
+            tokens.push(Token::Num(n));
+        } else if c.is_ascii_alphabetic() {
+            let mut name = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_ascii_alphanumeric() || d == '_' {
+                    name.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Name(name));
+        } else {
+            chars.next();
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                ',' => Token::Comma,
+                _ => return Err(ExprError::UnexpectedChar(c)),
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Levels of the tree above the node being parsed
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, ExprError> {
+        let tok = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(ExprError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ExprError> {
+        let tok = self.next()?;
+        if tok == expected {
+            Ok(())
+        } else {
+            Err(ExprError::UnexpectedToken(tok.to_string()))
+        }
+    }
+
+    /// Go a level deeper into the tree
+    fn descend(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExprError::TooDeep);
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<Node, ExprError> {
+        let depth = self.depth;
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(c @ ('+' | '-'))) = self.peek() {
+            let op = if *c == '+' { BinOp::Add } else { BinOp::Sub };
+            self.pos += 1;
+            // Each operator nests the terms so far one level down
+            self.descend()?;
+            let rhs = self.term()?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Node, ExprError> {
+        let depth = self.depth;
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(c @ ('*' | '/' | '%'))) = self.peek() {
+            let op = match c {
+                '*' => BinOp::Mul,
+                '/' => BinOp::Div,
+                _ => BinOp::Rem,
+            };
+            self.pos += 1;
+            self.descend()?;
+            let rhs = self.unary()?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Node, ExprError> {
+        // Every nested negation, power and parenthesis comes through here
+        self.descend()?;
+        let node = if let Some(Token::Op('-')) = self.peek() {
+            self.pos += 1;
+            Node::Neg(Box::new(self.unary()?))
+        } else {
+            self.power()?
+        };
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    fn power(&mut self) -> Result<Node, ExprError> {
+        let base = self.atom()?;
+        if let Some(Token::Op('^')) = self.peek() {
+            self.pos += 1;
+            // Right-associative: 2^3^2 = 2^(3^2)
+            let exponent = self.unary()?;
+            return Ok(Node::Binary(BinOp::Pow, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Node, ExprError> {
+        match self.next()? {
+            Token::Num(n) => Ok(Node::Num(n)),
+            Token::LParen => {
+                let inner = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Token::Name(name) => {
+                if let Some(Token::LParen) = self.peek() {
+                    let func =
+                        Func::from_name(&name).ok_or(ExprError::UnknownName(name.clone()))?;
+                    self.pos += 1;
+                    let mut args = vec![self.expr()?];
+                    while let Some(Token::Comma) = self.peek() {
+                        self.pos += 1;
+                        args.push(self.expr()?);
+                    }
+                    self.expect(Token::RParen)?;
+                    if args.len() != func.arity() {
+                        return Err(ExprError::WrongArgCount(name, func.arity()));
+                    }
+                    return Ok(Node::Call(func, args));
+                }
+                match name.as_str() {
+                    "t" => Ok(Node::Var(Var::T)),
+                    "a" => Ok(Node::Var(Var::A)),
+                    "b" => Ok(Node::Var(Var::B)),
+                    "c" => Ok(Node::Var(Var::C)),
+                    "pi" => Ok(Node::Num(std::f32::consts::PI)),
+                    "e" => Ok(Node::Num(std::f32::consts::E)),
+                    _ => Err(ExprError::UnknownName(name)),
+                }
+            }
+            tok => Err(ExprError::UnexpectedToken(tok.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, vars: Vars) -> f32 {
+        Expr::parse(source).unwrap().eval(&vars)
+    }
+
+    #[test]
+    fn test_precedence() {
+        let v = Vars::default();
+        assert_eq!(eval("1 + 2 * 3", v), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", v), 9.0);
+        assert_eq!(eval("-2 ^ 2", v), -4.0);
+        assert_eq!(eval("2 ^ 3 ^ 2", v), 512.0);
+    }
+
+    #[test]
+    fn test_variables_and_functions() {
+        let v = Vars {
+            t: std::f32::consts::FRAC_PI_2,
+            a: 2.0,
+            b: 3.0,
+            c: 0.5,
+        };
+        assert!((eval("sin(t)", v) - 1.0).abs() < 1e-6);
+        assert_eq!(eval("max(a, b) * c", v), 1.5);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Expr::parse("foo(t)"),
+            Err(ExprError::UnknownName("foo".to_string()))
+        );
+        assert_eq!(Expr::parse("1 +"), Err(ExprError::UnexpectedEnd));
+        assert_eq!(Expr::parse("t $ 2"), Err(ExprError::UnexpectedChar('$')));
+        assert!(matches!(
+            Expr::parse("min(t)"),
+            Err(ExprError::WrongArgCount(_, 2))
+        ));
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let parens = format!("{}t{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert_eq!(Expr::parse(&parens), Err(ExprError::TooDeep));
+        let negations = format!("{}t", "-".repeat(10_000));
+        assert_eq!(Expr::parse(&negations), Err(ExprError::TooDeep));
+        let sum = vec!["t"; 10_000].join("+");
+        assert_eq!(Expr::parse(&sum), Err(ExprError::TooDeep));
+        let powers = vec!["1"; 10_000].join("^");
+        assert_eq!(Expr::parse(&powers), Err(ExprError::TooDeep));
+
+        let v = Vars::default();
+        let nested = format!("{}1{}", "(".repeat(50), ")".repeat(50));
+        assert_eq!(eval(&nested, v), 1.0);
+        assert_eq!(eval(&vec!["1"; 100].join("+"), v), 100.0);
+    }
+}
@@ -0,0 +1,200 @@
+//! Two-channel function generator
+//!
+//! Classic bench function generator: each channel has its own waveform,
+//! frequency, level and phase offset. Levels are set in dBFS so the output is
+//! calibrated against digital full scale: a 0 dBFS sine peaks at exactly
+//! ±1.0, whatever the generator's output level.
+//!
+//! Both channels derive their phase from the same sample counter, so the
+//! phase offset between them is exact and does not drift.
+
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use super::SignalSource;
+use crate::audio::XYSample;
+
+/// Basic periodic waveforms
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sine => "Sine",
+            Self::Square => "Square",
+            Self::Triangle => "Triangle",
+            Self::Sawtooth => "Sawtooth",
+        }
+    }
+
+    pub fn all() -> &'static [Waveform] {
+        &[Self::Sine, Self::Square, Self::Triangle, Self::Sawtooth]
+    }
+
+    /// Evaluate at a phase in turns (0.0 - 1.0), peak amplitude 1.0
+    pub fn eval(&self, turns: f64) -> f32 {
+        let p = turns.rem_euclid(1.0);
+        let v = match self {
+            Self::Sine => (p * TAU).sin(),
+            Self::Square => {
+                if p < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            // Starts at 0 rising, like sine
+            Self::Triangle => {
+                if p < 0.25 {
+                    4.0 * p
+                } else if p < 0.75 {
+                    2.0 - 4.0 * p
+                } else {
+                    4.0 * p - 4.0
+                }
+            }
+            Self::Sawtooth => {
+                if p < 0.5 {
+                    2.0 * p
+                } else {
+                    2.0 * p - 2.0
+                }
+            }
+        };
+        v as f32
+    }
+}
+
+/// Settings for one output channel
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelParams {
+    pub waveform: Waveform,
+    /// Frequency in Hz
+    pub frequency: f32,
+    /// Peak level in dBFS
+    pub level_db: f32,
+    /// Phase offset in degrees
+    pub phase_deg: f32,
+    /// Channel enabled (disabled channels output silence)
+    pub enabled: bool,
+}
+
+impl Default for ChannelParams {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Sine,
+            frequency: 440.0,
+            level_db: -6.0,
+            phase_deg: 0.0,
+            enabled: true,
+        }
+    }
+}
+
+impl ChannelParams {
+    /// Linear peak amplitude for `level_db`
+    pub fn amplitude(&self) -> f32 {
+        if self.enabled {
+            10f32.powf(self.level_db / 20.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Function generator settings for both channels (left = X, right = Y)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FunctionParams {
+    pub left: ChannelParams,
+    pub right: ChannelParams,
+}
+
+impl Default for FunctionParams {
+    fn default() -> Self {
+        Self {
+            left: ChannelParams::default(),
+            // 90° offset draws a circle out of the box
+            right: ChannelParams {
+                phase_deg: 90.0,
+                ..ChannelParams::default()
+            },
+        }
+    }
+}
+
+impl FunctionParams {
+    /// Build a source from the current settings
+    pub fn build(&self) -> FunctionSource {
+        FunctionSource {
+            left: ChannelState::new(&self.left),
+            right: ChannelState::new(&self.right),
+            sample: 0,
+            sample_rate: 48000.0,
+        }
+    }
+}
+
+/// Per-channel runtime state
+struct ChannelState {
+    waveform: Waveform,
+    frequency: f64,
+    amplitude: f32,
+    /// Phase offset in turns
+    offset: f64,
+}
+
+impl ChannelState {
+    fn new(params: &ChannelParams) -> Self {
+        Self {
+            waveform: params.waveform,
+            frequency: params.frequency as f64,
+            amplitude: params.amplitude(),
+            offset: params.phase_deg as f64 / 360.0,
+        }
+    }
+
+    fn value(&self, seconds: f64) -> f32 {
+        // Only the fractional part of the phase matters; computing it from the
+        // absolute time keeps both channels locked together
+        let turns = (self.frequency * seconds).fract() + self.offset;
+        self.waveform.eval(turns) * self.amplitude
+    }
+}
+
+/// Running function generator
+pub struct FunctionSource {
+    left: ChannelState,
+    right: ChannelState,
+    /// Samples generated so far
+    sample: u64,
+    sample_rate: f64,
+}
+
+impl SignalSource for FunctionSource {
+    fn next_sample(&mut self) -> XYSample {
+        let seconds = self.sample as f64 / self.sample_rate;
+        self.sample += 1;
+        XYSample::new(self.left.value(seconds), self.right.value(seconds))
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        // Keep the same position in time when the rate changes
+        let seconds = self.sample as f64 / self.sample_rate;
+        self.sample_rate = sample_rate.max(1) as f64;
+        self.sample = (seconds * self.sample_rate) as u64;
+    }
+
+    fn calibrated(&self) -> bool {
+        true
+    }
+}
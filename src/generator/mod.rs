@@ -0,0 +1,123 @@
+//! Generator module - synthesized signal sources
+//!
+//! This module provides:
+//! - `SignalSource` trait for anything that produces XY samples
+//! - `Generator` engine that runs a source in real time
+//! - Parametric shapes (Lissajous, rose, spirograph) with editable equations
+//! - Two-channel function generator
+//! - Log sine sweep with frequency response capture
+//! - Calibrated white and pink noise
+//! - Text drawn with a built-in stroke font
+//! - SVG drawings, with WAV export of any source
+//! - Rotating 3D wireframes loaded from OBJ files
+//! - Demo scenes (clock, bouncing shapes) for idle displays
+//! - osci-render project (`.osci`) import
+//! - Dual-oscillator calibration of the input/output chain
+
+mod calibration;
+mod demo;
+mod engine;
+mod expr;
+mod function;
+mod model;
+mod noise;
+mod osci;
+mod path;
+mod shapes;
+mod svg;
+mod sweep;
+mod text;
+mod wav;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::XYSample;
+
+pub use calibration::{Calibration, CalibrationParams};
+#[allow(unused_imports)]
+pub use demo::{DemoParams, DemoScene, DemoSource, IdleTimer};
+pub use engine::Generator;
+#[allow(unused_imports)]
+pub use expr::{Expr, ExprError, Vars};
+#[allow(unused_imports)]
+pub use function::{ChannelParams, FunctionParams, FunctionSource, Waveform};
+#[allow(unused_imports)]
+pub use model::{Model, ModelError, ModelParams, ModelSource};
+#[allow(unused_imports)]
+pub use noise::{NoiseColor, NoiseParams, NoiseSource};
+#[allow(unused_imports)]
+pub use osci::{OsciError, OsciProject, ProjectFile};
+#[allow(unused_imports)]
+pub use path::{Path, PathSource, Point};
+#[allow(unused_imports)]
+pub use shapes::{ParametricSource, ShapeControls, ShapeParams, ShapePreset};
+#[allow(unused_imports)]
+pub use svg::{parse_svg, SvgError, SvgParams};
+#[allow(unused_imports)]
+pub use sweep::{ResponsePoint, SweepCapture, SweepParams, SweepSource};
+#[allow(unused_imports)]
+pub use text::{text_path, TextParams};
+pub use wav::export_wav;
+
+/// A source of XY samples produced at a fixed sample rate
+///
+/// Sources run on the audio thread, so `next_sample` must not block or
+/// allocate.
+pub trait SignalSource: Send {
+    /// Produce the next sample
+    fn next_sample(&mut self) -> XYSample;
+
+    /// Called before the first sample and whenever the engine's rate changes
+    fn set_sample_rate(&mut self, sample_rate: u32);
+
+    /// Whether the beam should be off for the sample just produced. Sent as
+    /// a Z (intensity) signal on the third output channel, if there is one.
+    fn blanked(&self) -> bool {
+        false
+    }
+
+    /// Whether the source sets its own absolute level, which the output
+    /// level slider then leaves alone
+    fn calibrated(&self) -> bool {
+        false
+    }
+}
+
+/// Which kind of source the Generator mode runs
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum GeneratorKind {
+    #[default]
+    Shape,
+    Function,
+    Sweep,
+    Noise,
+    Text,
+    Svg,
+    Model,
+}
+
+impl GeneratorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Shape => "Shape",
+            Self::Function => "Function",
+            Self::Sweep => "Sweep",
+            Self::Noise => "Noise",
+            Self::Text => "Text",
+            Self::Svg => "SVG",
+            Self::Model => "3D Model",
+        }
+    }
+
+    pub fn all() -> &'static [GeneratorKind] {
+        &[
+            Self::Shape,
+            Self::Function,
+            Self::Sweep,
+            Self::Noise,
+            Self::Text,
+            Self::Svg,
+            Self::Model,
+        ]
+    }
+}
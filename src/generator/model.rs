@@ -0,0 +1,408 @@
+//! 3D wireframe models (OBJ import)
+//!
+//! Loads the vertices and edges of a Wavefront OBJ file (`v`, `f` and `l`
+//! records; everything else is ignored), then rotates and projects the
+//! wireframe in real time. Edges are chained so the beam walks from one edge
+//! to a connected one whenever it can; the remaining moves between separate
+//! chains are reported as blanked, for scopes with a Z (intensity) input.
+//!
+//! Rotation speeds are shared with the UI through atomics, so they can be
+//! changed (or MIDI-mapped) while the model keeps spinning.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::SignalSource;
+use crate::audio::XYSample;
+
+/// Samples spent on each blanked move between edge chains
+const BLANK_SAMPLES: usize = 3;
+
+/// Errors that can occur while loading a model
+#[derive(Debug, Error)]
+pub enum ModelError {
+    #[error("Failed to read file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Line {line}: {message}")]
+    ParseError { line: usize, message: String },
+
+    #[error("Model has no edges")]
+    Empty,
+}
+
+/// A wireframe: vertices plus the edges between them
+#[derive(Clone, Debug, Default)]
+pub struct Model {
+    pub vertices: Vec<[f32; 3]>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl Model {
+    /// Parse OBJ text. Faces contribute their outline edges, shared edges
+    /// are only kept once.
+    pub fn parse_obj(text: &str) -> Result<Self, ModelError> {
+        let mut vertices = Vec::new();
+        let mut edges = Vec::new();
+        let mut seen = HashSet::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| ModelError::ParseError {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let coords: Vec<f32> = tokens
+                        .take(3)
+                        .map(|t| t.parse().map_err(|_| error("invalid vertex")))
+                        .collect::<Result<_, _>>()?;
+                    if coords.len() < 3 {
+                        return Err(error("vertex needs three coordinates"));
+                    }
+                    vertices.push([coords[0], coords[1], coords[2]]);
+                }
+                Some(kind @ ("f" | "l")) => {
+                    let indices: Vec<usize> = tokens
+                        .map(|t| {
+                            resolve_index(t, vertices.len()).ok_or_else(|| error("invalid index"))
+                        })
+                        .collect::<Result<_, _>>()?;
+                    let mut pairs: Vec<(usize, usize)> =
+                        indices.windows(2).map(|w| (w[0], w[1])).collect();
+                    // Faces are closed, polylines are not
+                    if kind == "f" && indices.len() > 2 {
+                        pairs.push((indices[indices.len() - 1], indices[0]));
+                    }
+                    for (a, b) in pairs {
+                        if a != b && seen.insert((a.min(b), a.max(b))) {
+                            edges.push((a, b));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if edges.is_empty() {
+            return Err(ModelError::Empty);
+        }
+        let mut model = Self { vertices, edges };
+        model.normalize();
+        Ok(model)
+    }
+
+    /// Center on the origin and scale to fit in the unit sphere
+    fn normalize(&mut self) {
+        let n = self.vertices.len().max(1) as f32;
+        let mut center = [0.0; 3];
+        for v in &self.vertices {
+            for k in 0..3 {
+                center[k] += v[k] / n;
+            }
+        }
+        let radius = self
+            .vertices
+            .iter()
+            .map(|v| {
+                ((v[0] - center[0]).powi(2)
+                    + (v[1] - center[1]).powi(2)
+                    + (v[2] - center[2]).powi(2))
+                .sqrt()
+            })
+            .fold(0.0, f32::max);
+        let scale = if radius > 0.0 { 1.0 / radius } else { 1.0 };
+        for v in &mut self.vertices {
+            for k in 0..3 {
+                v[k] = (v[k] - center[k]) * scale;
+            }
+        }
+    }
+
+    /// Order edges into chains. Returns (from, to, blanked) moves covering
+    /// every edge once and ending where the first move starts.
+    fn trace_order(&self) -> Vec<(usize, usize, bool)> {
+        let mut adjacency = vec![Vec::new(); self.vertices.len()];
+        for (i, &(a, b)) in self.edges.iter().enumerate() {
+            adjacency[a].push(i);
+            adjacency[b].push(i);
+        }
+
+        let mut used = vec![false; self.edges.len()];
+        let mut moves = Vec::with_capacity(self.edges.len() * 2);
+        let start = self.edges[0].0;
+        let mut current = start;
+
+        for _ in 0..self.edges.len() {
+            let next = adjacency[current]
+                .iter()
+                .copied()
+                .find(|&e| !used[e])
+                .or_else(|| used.iter().position(|u| !u));
+            let Some(e) = next else { break };
+            used[e] = true;
+
+            let (a, b) = self.edges[e];
+            let (from, to) = if a == current {
+                (a, b)
+            } else if b == current {
+                (b, a)
+            } else {
+                moves.push((current, a, true));
+                (a, b)
+            };
+            moves.push((from, to, false));
+            current = to;
+        }
+
+        if current != start {
+            moves.push((current, start, true));
+        }
+        moves
+    }
+}
+
+/// Resolve a 1-based (or negative, relative) OBJ index like `3`, `3/1/2`
+fn resolve_index(token: &str, count: usize) -> Option<usize> {
+    let index: i64 = token.split('/').next()?.parse().ok()?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    (0..count as i64)
+        .contains(&resolved)
+        .then_some(resolved as usize)
+}
+
+/// Wireframe source settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelParams {
+    /// Last imported file
+    pub file: Option<PathBuf>,
+    /// Rotation speed around X, Y and Z in degrees per second
+    pub spin: [f32; 3],
+    /// Camera distance in model radii (smaller = stronger perspective)
+    pub distance: f32,
+    /// How many times per second the wireframe is traced
+    pub refresh_hz: f32,
+    /// Output scale (0.0 - 1.0)
+    pub amplitude: f32,
+
+    /// Spin rates shared with the running source
+    #[serde(skip)]
+    spin_atomic: Arc<[AtomicU32; 3]>,
+}
+
+impl Default for ModelParams {
+    fn default() -> Self {
+        let spin = [15.0, 40.0, 0.0];
+        Self {
+            file: None,
+            spin,
+            distance: 3.0,
+            refresh_hz: 60.0,
+            amplitude: 0.9,
+            spin_atomic: Arc::new(spin.map(|s| AtomicU32::new(s.to_bits()))),
+        }
+    }
+}
+
+impl ModelParams {
+    /// Read and parse `file`
+    pub fn load(&self) -> Result<Model, ModelError> {
+        let Some(file) = &self.file else {
+            return Err(ModelError::Empty);
+        };
+        Model::parse_obj(&std::fs::read_to_string(file)?)
+    }
+
+    /// Sync the UI spin values to the running source
+    pub fn sync_spin(&self) {
+        for (atomic, value) in self.spin_atomic.iter().zip(self.spin) {
+            atomic.store(value.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn build(&self, model: &Model) -> ModelSource {
+        self.sync_spin();
+        let moves = if model.edges.is_empty() {
+            Vec::new()
+        } else {
+            model.trace_order()
+        };
+        let mut source = ModelSource {
+            vertices: model.vertices.clone(),
+            moves,
+            counts: Vec::new(),
+            spin: Arc::clone(&self.spin_atomic),
+            angles: [0.0; 3],
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            distance: self.distance.max(1.5),
+            refresh_hz: self.refresh_hz.max(1.0),
+            amplitude: self.amplitude,
+            pos: 0,
+            step: 0,
+            blanked: false,
+        };
+        source.set_sample_rate(48000);
+        source
+    }
+}
+
+/// Running wireframe renderer
+pub struct ModelSource {
+    vertices: Vec<[f32; 3]>,
+    moves: Vec<(usize, usize, bool)>,
+    /// Samples spent on each move
+    counts: Vec<usize>,
+    spin: Arc<[AtomicU32; 3]>,
+    angles: [f32; 3],
+    rotation: [[f32; 3]; 3],
+    distance: f32,
+    refresh_hz: f32,
+    amplitude: f32,
+    pos: usize,
+    step: usize,
+    blanked: bool,
+}
+
+impl ModelSource {
+    /// Advance the rotation by one refresh period
+    fn update_rotation(&mut self) {
+        let dt = 1.0 / self.refresh_hz;
+        for (angle, rate) in self.angles.iter_mut().zip(self.spin.iter()) {
+            let rate = f32::from_bits(rate.load(Ordering::Relaxed));
+            *angle = (*angle + rate.to_radians() * dt) % std::f32::consts::TAU;
+        }
+
+        let (sx, cx) = self.angles[0].sin_cos();
+        let (sy, cy) = self.angles[1].sin_cos();
+        let (sz, cz) = self.angles[2].sin_cos();
+        // Rz * Ry * Rx
+        self.rotation = [
+            [cz * cy, cz * sy * sx - sz * cx, cz * sy * cx + sz * sx],
+            [sz * cy, sz * sy * sx + cz * cx, sz * sy * cx - cz * sx],
+            [-sy, cy * sx, cy * cx],
+        ];
+    }
+
+    /// Rotate and project a model-space point
+    fn project(&self, p: [f32; 3]) -> XYSample {
+        let r = &self.rotation;
+        let x = r[0][0] * p[0] + r[0][1] * p[1] + r[0][2] * p[2];
+        let y = r[1][0] * p[0] + r[1][1] * p[1] + r[1][2] * p[2];
+        let z = r[2][0] * p[0] + r[2][1] * p[1] + r[2][2] * p[2];
+        // The nearest possible point (z = 1) keeps its size
+        let scale = (self.distance - 1.0) / (self.distance - z) * self.amplitude;
+        XYSample::new(x * scale, y * scale)
+    }
+}
+
+impl SignalSource for ModelSource {
+    fn next_sample(&mut self) -> XYSample {
+        if self.moves.is_empty() {
+            return XYSample::default();
+        }
+        if self.pos == 0 && self.step == 0 {
+            self.update_rotation();
+        }
+
+        let (from, to, blanked) = self.moves[self.pos];
+        let n = self.counts[self.pos];
+        let t = self.step as f32 / n as f32;
+        let (a, b) = (self.vertices[from], self.vertices[to]);
+        let p = [
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+        ];
+        // The first sample of a blanked move still sits on the last edge
+        self.blanked = blanked && self.step > 0;
+
+        self.step += 1;
+        if self.step >= n {
+            self.step = 0;
+            self.pos = (self.pos + 1) % self.moves.len();
+        }
+
+        self.project(p)
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        let total = (sample_rate as f32 / self.refresh_hz).round() as usize;
+        let blank_count = self.moves.iter().filter(|m| m.2).count();
+        let draw_total = total.saturating_sub(blank_count * BLANK_SAMPLES);
+
+        let length = |&(from, to, _): &(usize, usize, bool)| {
+            let (a, b) = (self.vertices[from], self.vertices[to]);
+            ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2) + (b[2] - a[2]).powi(2)).sqrt()
+        };
+        let total_length: f32 = self.moves.iter().filter(|m| !m.2).map(length).sum();
+
+        self.counts = self
+            .moves
+            .iter()
+            .map(|m| {
+                if m.2 {
+                    BLANK_SAMPLES
+                } else {
+                    let share = length(m) / total_length.max(1e-6);
+                    ((share * draw_total as f32).round() as usize).max(1)
+                }
+            })
+            .collect();
+        self.pos = 0;
+        self.step = 0;
+    }
+
+    fn blanked(&self) -> bool {
+        self.blanked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUBE: &str = "
+        v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1
+        v -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+        f 1 2 3 4\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8
+    ";
+
+    #[test]
+    fn test_parse_cube_edges() {
+        let model = Model::parse_obj(CUBE).unwrap();
+        assert_eq!(model.vertices.len(), 8);
+        // Six quads share their edges: 12 unique edges
+        assert_eq!(model.edges.len(), 12);
+
+        let moves = model.trace_order();
+        assert_eq!(moves.iter().filter(|m| !m.2).count(), 12);
+        // Moves are continuous and form a loop
+        for pair in moves.windows(2) {
+            assert_eq!(pair[0].1, pair[1].0);
+        }
+        assert_eq!(moves.last().unwrap().1, moves[0].0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Model::parse_obj("v 0 0 0\nf 1 2"),
+            Err(ModelError::ParseError { line: 2, .. })
+        ));
+        assert!(matches!(
+            Model::parse_obj("v 0 0 0"),
+            Err(ModelError::Empty)
+        ));
+    }
+}
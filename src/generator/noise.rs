@@ -0,0 +1,257 @@
+//! White and pink noise
+//!
+//! Levels are RMS in dBFS, so -20 dBFS white noise and -20 dBFS pink noise
+//! carry the same power. White noise is uniform, pink noise is white noise run
+//! through Paul Kellet's pinking filter (accurate to about ±0.05 dB above
+//! 9 Hz). The filter's gain is measured once when the source is built, so the
+//! pink output is normalized to the requested level as well.
+//!
+//! Noise peaks well above its RMS level, so each color has a highest level
+//! it can be played at without clipping, and louder requests are held there.
+//! The level is absolute, whatever the generator's output level.
+//!
+//! With independent channels, noise fills a square in XY; with identical
+//! channels it collapses to a diagonal line.
+
+use serde::{Deserialize, Serialize};
+
+use super::SignalSource;
+use crate::audio::XYSample;
+
+/// Samples used to measure the pinking filter's RMS gain
+const CALIBRATION_SAMPLES: usize = 1 << 16;
+
+/// Noise spectrum
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum NoiseColor {
+    #[default]
+    White,
+    Pink,
+}
+
+impl NoiseColor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::White => "White",
+            Self::Pink => "Pink",
+        }
+    }
+
+    pub fn all() -> &'static [NoiseColor] {
+        &[Self::White, Self::Pink]
+    }
+
+    /// Peak over RMS. Exact for uniform white noise; pink noise has no hard
+    /// limit, but its peaks only rarely pass this and are clipped when they do.
+    pub fn crest_factor(&self) -> f32 {
+        match self {
+            Self::White => 3f32.sqrt(),
+            Self::Pink => 5.0,
+        }
+    }
+
+    /// Highest RMS level in dBFS that stays clear of clipping
+    pub fn max_level_db(&self) -> f32 {
+        -20.0 * self.crest_factor().log10()
+    }
+}
+
+/// Noise settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseParams {
+    pub color: NoiseColor,
+    /// RMS level in dBFS
+    pub level_db: f32,
+    /// Same noise on both channels instead of independent noise
+    pub correlated: bool,
+}
+
+impl Default for NoiseParams {
+    fn default() -> Self {
+        Self {
+            color: NoiseColor::White,
+            level_db: -20.0,
+            correlated: false,
+        }
+    }
+}
+
+impl NoiseParams {
+    /// RMS level in dBFS actually played: `level_db`, held below clipping
+    pub fn output_level_db(&self) -> f32 {
+        self.level_db.min(self.color.max_level_db())
+    }
+
+    /// Linear RMS level for `output_level_db`
+    pub fn rms(&self) -> f32 {
+        10f32.powf(self.output_level_db() / 20.0)
+    }
+
+    pub fn build(&self) -> NoiseSource {
+        let x = NoiseChannel::new(self.color, 0x9E37_79B9);
+        let y = NoiseChannel::new(self.color, 0x85EB_CA6B);
+
+        // Uniform noise in [-1, 1] has RMS 1/sqrt(3); the pinking filter adds
+        // its own gain, which we measure rather than hard-code
+        let raw_rms = x.measure_rms(CALIBRATION_SAMPLES);
+        let scale = if raw_rms > 0.0 {
+            self.rms() / raw_rms
+        } else {
+            0.0
+        };
+
+        NoiseSource {
+            x,
+            y,
+            scale,
+            correlated: self.correlated,
+        }
+    }
+}
+
+/// Small, fast, allocation-free PRNG (xorshift32)
+struct XorShift(u32);
+
+impl XorShift {
+    /// Uniform value in [-1, 1]
+    #[inline]
+    fn next_bipolar(&mut self) -> f32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+}
+
+/// One channel of noise (generator + optional pinking filter state)
+struct NoiseChannel {
+    color: NoiseColor,
+    rng: XorShift,
+    b: [f32; 7],
+}
+
+impl NoiseChannel {
+    fn new(color: NoiseColor, seed: u32) -> Self {
+        Self {
+            color,
+            rng: XorShift(seed),
+            b: [0.0; 7],
+        }
+    }
+
+    /// Next unscaled sample
+    #[inline]
+    fn next(&mut self) -> f32 {
+        let white = self.rng.next_bipolar();
+        match self.color {
+            NoiseColor::White => white,
+            NoiseColor::Pink => {
+                // Paul Kellet's refined pinking filter
+                let b = &mut self.b;
+                b[0] = 0.99886 * b[0] + white * 0.0555179;
+                b[1] = 0.99332 * b[1] + white * 0.0750759;
+                b[2] = 0.96900 * b[2] + white * 0.153852;
+                b[3] = 0.86650 * b[3] + white * 0.3104856;
+                b[4] = 0.55000 * b[4] + white * 0.5329522;
+                b[5] = -0.7616 * b[5] - white * 0.0168980;
+                let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+                b[6] = white * 0.115926;
+                pink
+            }
+        }
+    }
+
+    /// Measure the unscaled RMS by running a copy of this channel
+    fn measure_rms(&self, samples: usize) -> f32 {
+        let mut probe = Self::new(self.color, self.rng.0);
+        let sum: f64 = (0..samples)
+            .map(|_| {
+                let v = probe.next() as f64;
+                v * v
+            })
+            .sum();
+        (sum / samples as f64).sqrt() as f32
+    }
+}
+
+/// Running noise generator
+pub struct NoiseSource {
+    x: NoiseChannel,
+    y: NoiseChannel,
+    scale: f32,
+    correlated: bool,
+}
+
+impl SignalSource for NoiseSource {
+    fn next_sample(&mut self) -> XYSample {
+        let x = (self.x.next() * self.scale).clamp(-1.0, 1.0);
+        let y = if self.correlated {
+            x
+        } else {
+            (self.y.next() * self.scale).clamp(-1.0, 1.0)
+        };
+        XYSample::new(x, y)
+    }
+
+    fn set_sample_rate(&mut self, _sample_rate: u32) {
+        // Both colors are defined per sample; the pinking filter is tuned for
+        // 44.1-48 kHz, the usual device rates
+    }
+
+    fn calibrated(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measured_db(params: &NoiseParams) -> f32 {
+        let mut source = params.build();
+        let n = 200_000;
+        let sum: f64 = (0..n)
+            .map(|_| {
+                let s = source.next_sample();
+                (s.x as f64).powi(2)
+            })
+            .sum();
+        20.0 * ((sum / n as f64).sqrt() as f32).log10()
+    }
+
+    #[test]
+    fn test_white_level_is_calibrated() {
+        let params = NoiseParams {
+            color: NoiseColor::White,
+            level_db: -20.0,
+            correlated: false,
+        };
+        assert!((measured_db(&params) - -20.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_pink_level_is_calibrated() {
+        let params = NoiseParams {
+            color: NoiseColor::Pink,
+            level_db: -18.0,
+            correlated: true,
+        };
+        assert!((measured_db(&params) - -18.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_level_is_held_below_clipping() {
+        let params = NoiseParams {
+            color: NoiseColor::White,
+            level_db: -3.0,
+            correlated: false,
+        };
+        let max = NoiseColor::White.max_level_db();
+        assert!((max - -4.77).abs() < 0.01);
+        assert_eq!(params.output_level_db(), max);
+        assert!((measured_db(&params) - max).abs() < 0.2);
+    }
+}
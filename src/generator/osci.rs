@@ -0,0 +1,208 @@
+//! osci-render project import
+//!
+//! osci-render saves projects (`.osci`) as XML that embeds every file the
+//! project uses. We pull those files out and hand the active one to the
+//! matching source here: SVG drawings, OBJ models and text. Lua scripts,
+//! effects and animation settings have no equivalent in scope-rs and are
+//! skipped.
+//!
+//! Embedded data comes in two encodings depending on the osci-render version:
+//! standard base64, or JUCE's `MemoryBlock` format (`<size>.<chars>`, with its
+//! own alphabet and little-endian bit packing). Both are handled.
+
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::{alphabet, Engine};
+use roxmltree::Node;
+use thiserror::Error;
+
+/// Alphabet used by JUCE's `MemoryBlock::toBase64Encoding`
+const JUCE_BASE64: &[u8] = b".ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+";
+
+/// Standard base64, with or without padding
+const STD_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Errors that can occur while importing a project
+#[derive(Debug, Error)]
+pub enum OsciError {
+    #[error("Failed to read file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid project: {0}")]
+    XmlError(#[from] roxmltree::Error),
+
+    #[error("Could not decode embedded file '{0}'")]
+    DecodeError(String),
+
+    #[error("Project contains no files")]
+    Empty,
+}
+
+/// A file embedded in a project
+#[derive(Clone, Debug)]
+pub struct ProjectFile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl ProjectFile {
+    /// Lowercase file extension
+    pub fn extension(&self) -> String {
+        std::path::Path::new(&self.name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+}
+
+/// The parts of an osci-render project scope-rs can use
+#[derive(Clone, Debug)]
+pub struct OsciProject {
+    pub files: Vec<ProjectFile>,
+    /// Index of the file that was active when the project was saved
+    pub current: usize,
+}
+
+impl OsciProject {
+    pub fn load(path: &std::path::Path) -> Result<Self, OsciError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, OsciError> {
+        let doc = roxmltree::Document::parse(text)?;
+        let root = doc.root_element();
+
+        let mut files = Vec::new();
+        for node in doc.descendants().filter(|n| n.has_tag_name("file")) {
+            let name = node
+                .attribute("name")
+                .map(str::to_string)
+                .or_else(|| child_text(node, "name"))
+                .unwrap_or_default();
+            let encoded = child_text(node, "data")
+                .or_else(|| node.text().map(str::to_string))
+                .unwrap_or_default();
+            let data =
+                decode(encoded.trim()).ok_or_else(|| OsciError::DecodeError(name.clone()))?;
+            files.push(ProjectFile { name, data });
+        }
+
+        if files.is_empty() {
+            return Err(OsciError::Empty);
+        }
+
+        let current = root
+            .attribute("currentFile")
+            .map(str::to_string)
+            .or_else(|| child_text(root, "currentFile"))
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|&i| i < files.len())
+            .unwrap_or(0);
+
+        Ok(Self { files, current })
+    }
+
+    /// The active file, or the first one scope-rs can display
+    pub fn preferred_file(&self) -> &ProjectFile {
+        let current = &self.files[self.current];
+        if is_supported(current) {
+            return current;
+        }
+        self.files
+            .iter()
+            .find(|f| is_supported(f))
+            .unwrap_or(current)
+    }
+}
+
+/// Whether scope-rs has a source for this kind of file
+pub fn is_supported(file: &ProjectFile) -> bool {
+    matches!(file.extension().as_str(), "svg" | "obj" | "txt")
+}
+
+fn child_text(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|c| c.has_tag_name(name))
+        .and_then(|c| c.text())
+        .map(str::to_string)
+}
+
+/// Decode either JUCE `MemoryBlock` base64 or standard base64
+fn decode(encoded: &str) -> Option<Vec<u8>> {
+    match encoded.split_once('.') {
+        Some((size, chars)) if !size.is_empty() && size.bytes().all(|b| b.is_ascii_digit()) => {
+            decode_juce(size.parse().ok()?, chars)
+        }
+        _ => decode_standard(encoded),
+    }
+}
+
+/// JUCE packs 6-bit groups starting from the least significant bit, which
+/// no base64 engine does
+fn decode_juce(size: usize, chars: &str) -> Option<Vec<u8>> {
+    // The size comes from the file; it can't be more than the characters hold
+    if size > chars.len() * 6 / 8 {
+        return None;
+    }
+    let mut data = vec![0u8; size];
+    for (i, c) in chars.bytes().enumerate() {
+        let value = JUCE_BASE64.iter().position(|&a| a == c)? as u32;
+        for bit in 0..6 {
+            let pos = i * 6 + bit;
+            if pos / 8 < size && value & (1 << bit) != 0 {
+                data[pos / 8] |= 1 << (pos % 8);
+            }
+        }
+    }
+    Some(data)
+}
+
+fn decode_standard(encoded: &str) -> Option<Vec<u8>> {
+    // Long files are wrapped across lines in the XML
+    let chars: String = encoded
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    STD_BASE64.decode(chars).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_both_encodings() {
+        assert_eq!(decode("1.A.").unwrap(), vec![1]);
+        assert_eq!(decode("aGk=").unwrap(), b"hi");
+        assert_eq!(decode("aG\n  k").unwrap(), b"hi");
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_juce_block() {
+        // Would otherwise allocate most of the address space up front
+        assert_eq!(decode("999999999999999.A."), None);
+        assert_eq!(decode("2.A."), None);
+    }
+
+    #[test]
+    fn test_parse_project() {
+        let xml = r#"<project currentFile="1">
+            <files>
+                <file name="script.lua">cHJpbnQoKQ==</file>
+                <file name="hello.txt">aGk=</file>
+            </files>
+        </project>"#;
+        let project = OsciProject::parse(xml).unwrap();
+        assert_eq!(project.files.len(), 2);
+        assert_eq!(project.preferred_file().name, "hello.txt");
+        assert_eq!(project.preferred_file().text(), "hi");
+    }
+}
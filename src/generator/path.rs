@@ -0,0 +1,216 @@
+//! Vector paths and looped path playback
+//!
+//! Oscilloscope art is drawn by tracing polylines with the beam over and over.
+//! A `Path` is a set of strokes (polylines); `to_loop` turns it into exactly
+//! one refresh's worth of samples, spacing points evenly along the strokes so
+//! the beam moves at constant speed and every line is equally bright.
+//!
+//! Between strokes the beam has to jump. We spend a couple of samples on each
+//! jump so the retrace is fast (and dim) without producing a hard step that
+//! clicks in the speakers.
+
+use crate::audio::XYSample;
+
+use super::SignalSource;
+
+/// Samples spent moving between strokes
+const JUMP_SAMPLES: usize = 3;
+
+/// A point in path space
+pub type Point = (f32, f32);
+
+/// A drawing made of polylines
+#[derive(Clone, Debug, Default)]
+pub struct Path {
+    pub strokes: Vec<Vec<Point>>,
+}
+
+impl Path {
+    pub fn new(strokes: Vec<Vec<Point>>) -> Self {
+        Self {
+            strokes: strokes.into_iter().filter(|s| !s.is_empty()).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty()
+    }
+
+    /// Bounding box as (min_x, min_y, max_x, max_y)
+    pub fn bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        let mut points = self.strokes.iter().flatten();
+        let &(x, y) = points.next()?;
+        Some(points.fold((x, y, x, y), |(x0, y0, x1, y1), &(x, y)| {
+            (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+        }))
+    }
+
+    /// Scale and center the path into [-extent, extent] keeping aspect ratio
+    pub fn normalized(&self, extent: f32) -> Path {
+        let Some((x0, y0, x1, y1)) = self.bounds() else {
+            return self.clone();
+        };
+        let size = (x1 - x0).max(y1 - y0);
+        let scale = if size > 0.0 { 2.0 * extent / size } else { 1.0 };
+        let cx = (x0 + x1) / 2.0;
+        let cy = (y0 + y1) / 2.0;
+        Path {
+            strokes: self
+                .strokes
+                .iter()
+                .map(|s| {
+                    s.iter()
+                        .map(|&(x, y)| ((x - cx) * scale, (y - cy) * scale))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Total drawn length (jumps excluded)
+    pub fn length(&self) -> f32 {
+        self.strokes.iter().map(|s| stroke_length(s)).sum()
+    }
+
+    /// Flatten into a loop of about `samples` points with even spacing
+    pub fn to_loop(&self, samples: usize) -> Vec<XYSample> {
+        if self.is_empty() {
+            return vec![XYSample::default(); samples.max(1)];
+        }
+
+        let jump_total = self.strokes.len() * JUMP_SAMPLES;
+        let draw_total = samples.saturating_sub(jump_total).max(self.strokes.len());
+        let total_length = self.length();
+        let mut out = Vec::with_capacity(samples);
+
+        for (i, stroke) in self.strokes.iter().enumerate() {
+            let len = stroke_length(stroke);
+            let share = if total_length > 0.0 {
+                len / total_length
+            } else {
+                1.0 / self.strokes.len() as f32
+            };
+            let n = ((share * draw_total as f32).round() as usize).max(1);
+            trace_stroke(stroke, n, &mut out);
+
+            // Jump to the start of the next stroke (wrapping to the first)
+            let from = *stroke.last().unwrap();
+            let to = self.strokes[(i + 1) % self.strokes.len()][0];
+            for k in 1..=JUMP_SAMPLES {
+                let t = k as f32 / (JUMP_SAMPLES + 1) as f32;
+                out.push(XYSample::new(
+                    from.0 + (to.0 - from.0) * t,
+                    from.1 + (to.1 - from.1) * t,
+                ));
+            }
+        }
+
+        out
+    }
+}
+
+fn stroke_length(stroke: &[Point]) -> f32 {
+    stroke
+        .windows(2)
+        .map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt())
+        .sum()
+}
+
+/// Append `n` points evenly spaced (by arc length) along a stroke
+fn trace_stroke(stroke: &[Point], n: usize, out: &mut Vec<XYSample>) {
+    let len = stroke_length(stroke);
+    if stroke.len() < 2 || len <= 0.0 {
+        let (x, y) = stroke[0];
+        out.extend(std::iter::repeat_n(XYSample::new(x, y), n));
+        return;
+    }
+
+    let step = len / (n.max(2) - 1) as f32;
+    let mut seg = 0;
+    let mut seg_start = 0.0;
+    for k in 0..n {
+        let target = (k as f32 * step).min(len);
+        // Advance to the segment containing `target`
+        loop {
+            let (a, b) = (stroke[seg], stroke[seg + 1]);
+            let seg_len = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+            if target <= seg_start + seg_len || seg + 2 >= stroke.len() {
+                let t = if seg_len > 0.0 {
+                    ((target - seg_start) / seg_len).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                out.push(XYSample::new(a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+                break;
+            }
+            seg_start += seg_len;
+            seg += 1;
+        }
+    }
+}
+
+/// Plays a path as a repeating loop, `refresh_hz` times per second
+pub struct PathSource {
+    path: Path,
+    refresh_hz: f32,
+    amplitude: f32,
+    points: Vec<XYSample>,
+    pos: usize,
+}
+
+impl PathSource {
+    /// `path` should already be normalized to roughly [-1, 1]
+    pub fn new(path: Path, refresh_hz: f32, amplitude: f32) -> Self {
+        let mut source = Self {
+            path,
+            refresh_hz: refresh_hz.max(1.0),
+            amplitude,
+            points: Vec::new(),
+            pos: 0,
+        };
+        source.set_sample_rate(48000);
+        source
+    }
+}
+
+impl SignalSource for PathSource {
+    fn next_sample(&mut self) -> XYSample {
+        let p = self.points[self.pos];
+        self.pos = (self.pos + 1) % self.points.len();
+        XYSample::new(p.x * self.amplitude, p.y * self.amplitude)
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        let samples = (sample_rate as f32 / self.refresh_hz).round() as usize;
+        self.points = self.path.to_loop(samples.max(1));
+        self.pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_fits_extent() {
+        let path = Path::new(vec![vec![(10.0, 10.0), (30.0, 20.0)]]);
+        let (x0, y0, x1, y1) = path.normalized(1.0).bounds().unwrap();
+        assert!((x0 + 1.0).abs() < 1e-6 && (x1 - 1.0).abs() < 1e-6);
+        // Aspect ratio kept: height is half the width
+        assert!((y0 + 0.5).abs() < 1e-6 && (y1 - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_loop_length_and_spacing() {
+        // Two strokes of equal length get an equal share of samples
+        let path = Path::new(vec![
+            vec![(0.0, 0.0), (1.0, 0.0)],
+            vec![(0.0, 1.0), (1.0, 1.0)],
+        ]);
+        let points = path.to_loop(100);
+        assert!((points.len() as i32 - 100).abs() <= 2);
+        let top = points.iter().filter(|p| p.y == 1.0).count();
+        let bottom = points.iter().filter(|p| p.y == 0.0).count();
+        assert_eq!(top, bottom);
+    }
+}
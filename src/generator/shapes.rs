@@ -0,0 +1,266 @@
+//! Parametric shape generator
+//!
+//! Each shape is a pair of equations `x(t)`, `y(t)` evaluated as `t` sweeps
+//! around the circle `frequency` times per second. Presets are just
+//! pre-filled equations, so every preset can be tweaked by hand afterwards.
+//! The sliders reach a running shape through `ShapeControls`, so moving them
+//! bends the figure without starting it over.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::expr::{Expr, ExprError, Vars};
+use super::SignalSource;
+use crate::audio::XYSample;
+
+/// Phase wraps after this many turns. 2520 is divisible by every integer
+/// 1-10, so ratios with small denominators still close cleanly on wrap.
+const WRAP_TURNS: f64 = 2520.0;
+
+/// Built-in shape presets
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum ShapePreset {
+    #[default]
+    Circle,
+    Lissajous,
+    Rose,
+    Spirograph,
+    /// User-edited equations
+    Custom,
+}
+
+impl ShapePreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Circle => "Circle",
+            Self::Lissajous => "Lissajous",
+            Self::Rose => "Rose",
+            Self::Spirograph => "Spirograph",
+            Self::Custom => "Custom",
+        }
+    }
+
+    pub fn all() -> &'static [ShapePreset] {
+        &[
+            Self::Circle,
+            Self::Lissajous,
+            Self::Rose,
+            Self::Spirograph,
+            Self::Custom,
+        ]
+    }
+
+    /// Short description of what the parameters do
+    pub fn help(&self) -> &'static str {
+        match self {
+            Self::Circle => "a, b, c unused",
+            Self::Lissajous => "a:b = frequency ratio, c = phase (radians)",
+            Self::Rose => "a/b = petal ratio",
+            Self::Spirograph => "a = rolling circle radius, b = pen distance",
+            Self::Custom => "Variables: t, a, b, c, pi, e",
+        }
+    }
+
+    /// Equations and default (a, b, c) for this preset.
+    /// Returns `None` for `Custom`, which keeps whatever is being edited.
+    fn definition(&self) -> Option<(&'static str, &'static str, [f32; 3])> {
+        match self {
+            Self::Circle => Some(("sin(t)", "cos(t)", [1.0, 1.0, 0.0])),
+            Self::Lissajous => Some((
+                "sin(a*t + c)",
+                "sin(b*t)",
+                [3.0, 2.0, std::f32::consts::FRAC_PI_2],
+            )),
+            Self::Rose => Some((
+                "cos(a/b*t) * cos(t)",
+                "cos(a/b*t) * sin(t)",
+                [5.0, 1.0, 0.0],
+            )),
+            Self::Spirograph => Some((
+                "((1-a)*cos(t) + b*cos((1-a)/a*t)) / (1-a+b)",
+                "((1-a)*sin(t) - b*sin((1-a)/a*t)) / (1-a+b)",
+                [0.3, 0.5, 0.0],
+            )),
+            Self::Custom => None,
+        }
+    }
+}
+
+/// Editable shape parameters (UI state, persisted in settings)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShapeParams {
+    pub preset: ShapePreset,
+    pub x_expr: String,
+    pub y_expr: String,
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    /// Turns of `t` per second (Hz)
+    pub frequency: f32,
+    /// Output scale (0.0 - 1.0)
+    pub amplitude: f32,
+}
+
+impl Default for ShapeParams {
+    fn default() -> Self {
+        let mut params = Self {
+            preset: ShapePreset::Circle,
+            x_expr: String::new(),
+            y_expr: String::new(),
+            a: 1.0,
+            b: 1.0,
+            c: 0.0,
+            frequency: 100.0,
+            amplitude: 0.8,
+        };
+        params.apply_preset(ShapePreset::Circle);
+        params
+    }
+}
+
+impl ShapeParams {
+    /// Load a preset's equations and default parameters
+    pub fn apply_preset(&mut self, preset: ShapePreset) {
+        self.preset = preset;
+        if let Some((x, y, [a, b, c])) = preset.definition() {
+            self.x_expr = x.to_string();
+            self.y_expr = y.to_string();
+            self.a = a;
+            self.b = b;
+            self.c = c;
+        }
+    }
+
+    /// Compile the equations into a source the generator can run
+    pub fn build(&self) -> Result<ParametricSource, ExprError> {
+        let controls = Arc::new(ShapeControls::default());
+        controls.set(self);
+        Ok(ParametricSource {
+            x: Expr::parse(&self.x_expr)?,
+            y: Expr::parse(&self.y_expr)?,
+            vars: Vars::default(),
+            controls,
+            amplitude: self.amplitude,
+            sample_rate: 1,
+            phase: 0.0,
+            phase_step: 0.0,
+        })
+    }
+}
+
+/// Slider values shared with a running `ParametricSource`, which picks them
+/// up on its next sample
+#[derive(Debug, Default)]
+pub struct ShapeControls {
+    a: AtomicU32,
+    b: AtomicU32,
+    c: AtomicU32,
+    frequency: AtomicU32,
+    amplitude: AtomicU32,
+}
+
+impl ShapeControls {
+    /// Take the parameters from `params`; the equations stay as built
+    pub fn set(&self, params: &ShapeParams) {
+        self.a.store(params.a.to_bits(), Ordering::Relaxed);
+        self.b.store(params.b.to_bits(), Ordering::Relaxed);
+        self.c.store(params.c.to_bits(), Ordering::Relaxed);
+        self.frequency
+            .store(params.frequency.to_bits(), Ordering::Relaxed);
+        self.amplitude
+            .store(params.amplitude.to_bits(), Ordering::Relaxed);
+    }
+}
+
+fn load(value: &AtomicU32) -> f32 {
+    f32::from_bits(value.load(Ordering::Relaxed))
+}
+
+/// A compiled parametric shape
+pub struct ParametricSource {
+    x: Expr,
+    y: Expr,
+    vars: Vars,
+    controls: Arc<ShapeControls>,
+    amplitude: f32,
+    sample_rate: u32,
+    /// Current phase in radians (kept in f64 to avoid drift)
+    phase: f64,
+    /// Phase advance per sample
+    phase_step: f64,
+}
+
+impl ParametricSource {
+    /// Handle for changing the parameters while the shape runs
+    pub fn controls(&self) -> Arc<ShapeControls> {
+        self.controls.clone()
+    }
+
+    /// Pick up parameters changed through `controls`
+    fn update_vars(&mut self) {
+        self.vars.a = load(&self.controls.a);
+        self.vars.b = load(&self.controls.b);
+        self.vars.c = load(&self.controls.c);
+        self.amplitude = load(&self.controls.amplitude);
+        self.phase_step =
+            std::f64::consts::TAU * load(&self.controls.frequency) as f64 / self.sample_rate as f64;
+    }
+}
+
+impl SignalSource for ParametricSource {
+    fn next_sample(&mut self) -> XYSample {
+        self.update_vars();
+        self.vars.t = self.phase as f32;
+        let x = self.x.eval(&self.vars);
+        let y = self.y.eval(&self.vars);
+
+        self.phase += self.phase_step;
+        let wrap = std::f64::consts::TAU * WRAP_TURNS;
+        if self.phase >= wrap {
+            self.phase -= wrap;
+        }
+
+        // Guard against NaN/inf from user equations (e.g. division by zero)
+        let clean = |v: f32| {
+            if v.is_finite() {
+                v * self.amplitude
+            } else {
+                0.0
+            }
+        };
+        XYSample::new(clean(x), clean(y))
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.max(1);
+        self.update_vars();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_controls_keep_the_phase() {
+        let mut params = ShapeParams::default();
+        let mut source = params.build().unwrap();
+        source.set_sample_rate(48000);
+        for _ in 0..1000 {
+            source.next_sample();
+        }
+        let phase = source.phase;
+
+        params.amplitude = 0.5;
+        params.frequency = 200.0;
+        source.controls().set(&params);
+        let sample = source.next_sample();
+        assert_eq!(sample.x, 0.5 * (phase as f32).sin());
+        assert_eq!(sample.y, 0.5 * (phase as f32).cos());
+        let step = std::f64::consts::TAU * 200.0 / 48000.0;
+        assert!((source.phase - phase - step).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,290 @@
+//! SVG import
+//!
+//! Flattens the geometry of an SVG file into a `Path`: `<path>`, `<line>`,
+//! `<polyline>`, `<polygon>`, `<rect>`, `<circle>` and `<ellipse>` elements,
+//! with `transform` attributes applied. Curves and arcs are split into short
+//! line segments. Fill, stroke, styles, text and images are ignored; the scope
+//! only ever draws outlines.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use svgtypes::{PointsParser, SimplePathSegment, SimplifyingPathParser, Transform};
+use thiserror::Error;
+
+use super::path::{Path, PathSource, Point};
+
+/// Line segments per Bezier curve
+const CURVE_SEGMENTS: usize = 16;
+
+/// Line segments per full circle or ellipse
+const ELLIPSE_SEGMENTS: usize = 64;
+
+/// Errors that can occur while importing an SVG
+#[derive(Debug, Error)]
+pub enum SvgError {
+    #[error("Failed to read file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid SVG: {0}")]
+    XmlError(#[from] roxmltree::Error),
+
+    #[error("Invalid path data: {0}")]
+    PathDataError(#[from] svgtypes::Error),
+
+    #[error("No drawable shapes found")]
+    Empty,
+}
+
+/// SVG source settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SvgParams {
+    /// Last imported file
+    pub file: Option<PathBuf>,
+    /// How many times per second the drawing is traced
+    pub refresh_hz: f32,
+    /// Output scale (0.0 - 1.0)
+    pub amplitude: f32,
+}
+
+impl Default for SvgParams {
+    fn default() -> Self {
+        Self {
+            file: None,
+            refresh_hz: 60.0,
+            amplitude: 0.9,
+        }
+    }
+}
+
+impl SvgParams {
+    /// Read and flatten `file`, normalized to [-1, 1]
+    pub fn load(&self) -> Result<Path, SvgError> {
+        let Some(file) = &self.file else {
+            return Err(SvgError::Empty);
+        };
+        let text = std::fs::read_to_string(file)?;
+        Ok(parse_svg(&text)?.normalized(1.0))
+    }
+
+    pub fn build(&self, drawing: &Path) -> PathSource {
+        PathSource::new(drawing.clone(), self.refresh_hz, self.amplitude)
+    }
+}
+
+/// Flatten an SVG document into a path (in SVG units, Y up)
+pub fn parse_svg(text: &str) -> Result<Path, SvgError> {
+    let doc = roxmltree::Document::parse(text)?;
+    let mut strokes = Vec::new();
+
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        let shape_strokes = match node.tag_name().name() {
+            "path" => path_strokes(node.attribute("d").unwrap_or(""))?,
+            "line" => vec![vec![
+                (num(node, "x1"), num(node, "y1")),
+                (num(node, "x2"), num(node, "y2")),
+            ]],
+            "polyline" | "polygon" => {
+                let mut points: Vec<Point> =
+                    PointsParser::from(node.attribute("points").unwrap_or(""))
+                        .map(|(x, y)| (x as f32, y as f32))
+                        .collect();
+                if node.tag_name().name() == "polygon" {
+                    if let Some(&first) = points.first() {
+                        points.push(first);
+                    }
+                }
+                vec![points]
+            }
+            "rect" => {
+                let (x, y) = (num(node, "x"), num(node, "y"));
+                let (w, h) = (num(node, "width"), num(node, "height"));
+                vec![vec![(x, y), (x + w, y), (x + w, y + h), (x, y + h), (x, y)]]
+            }
+            "circle" => {
+                let r = num(node, "r");
+                vec![ellipse(num(node, "cx"), num(node, "cy"), r, r)]
+            }
+            "ellipse" => vec![ellipse(
+                num(node, "cx"),
+                num(node, "cy"),
+                num(node, "rx"),
+                num(node, "ry"),
+            )],
+            _ => continue,
+        };
+
+        let transform = node_transform(node);
+        strokes.extend(shape_strokes.into_iter().map(|stroke| {
+            stroke
+                .into_iter()
+                .map(|p| {
+                    let (x, y) = apply(&transform, p);
+                    // SVG's Y axis points down
+                    (x, -y)
+                })
+                .collect::<Vec<_>>()
+        }));
+    }
+
+    let path = Path::new(strokes);
+    if path.is_empty() {
+        return Err(SvgError::Empty);
+    }
+    Ok(path)
+}
+
+/// Numeric attribute, 0 if missing or unparseable (units are ignored)
+fn num(node: roxmltree::Node, name: &str) -> f32 {
+    node.attribute(name)
+        .and_then(|v| v.parse::<svgtypes::Length>().ok())
+        .map(|l| l.number as f32)
+        .unwrap_or(0.0)
+}
+
+/// Flatten SVG path data into strokes
+fn path_strokes(data: &str) -> Result<Vec<Vec<Point>>, SvgError> {
+    let mut strokes: Vec<Vec<Point>> = Vec::new();
+    let mut current: Vec<Point> = Vec::new();
+    let mut last = (0.0, 0.0);
+
+    for segment in SimplifyingPathParser::from(data) {
+        match segment? {
+            SimplePathSegment::MoveTo { x, y } => {
+                if current.len() > 1 {
+                    strokes.push(std::mem::take(&mut current));
+                }
+                last = (x as f32, y as f32);
+                current = vec![last];
+            }
+            SimplePathSegment::LineTo { x, y } => {
+                last = (x as f32, y as f32);
+                current.push(last);
+            }
+            SimplePathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                let (p0, p1, p2, p3) = (
+                    last,
+                    (x1 as f32, y1 as f32),
+                    (x2 as f32, y2 as f32),
+                    (x as f32, y as f32),
+                );
+                for i in 1..=CURVE_SEGMENTS {
+                    let t = i as f32 / CURVE_SEGMENTS as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    current.push((
+                        a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                        a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+                    ));
+                }
+                last = p3;
+            }
+            SimplePathSegment::Quadratic { x1, y1, x, y } => {
+                let (p0, p1, p2) = (last, (x1 as f32, y1 as f32), (x as f32, y as f32));
+                for i in 1..=CURVE_SEGMENTS {
+                    let t = i as f32 / CURVE_SEGMENTS as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c) = (u * u, 2.0 * u * t, t * t);
+                    current.push((
+                        a * p0.0 + b * p1.0 + c * p2.0,
+                        a * p0.1 + b * p1.1 + c * p2.1,
+                    ));
+                }
+                last = p2;
+            }
+            SimplePathSegment::ClosePath => {
+                if let Some(&first) = current.first() {
+                    current.push(first);
+                    last = first;
+                }
+            }
+        }
+    }
+
+    if current.len() > 1 {
+        strokes.push(current);
+    }
+    Ok(strokes)
+}
+
+/// Closed polyline approximating an ellipse
+fn ellipse(cx: f32, cy: f32, rx: f32, ry: f32) -> Vec<Point> {
+    (0..=ELLIPSE_SEGMENTS)
+        .map(|i| {
+            let a = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+            (cx + rx * a.cos(), cy + ry * a.sin())
+        })
+        .collect()
+}
+
+/// Combined transform of a node and all its ancestors
+fn node_transform(node: roxmltree::Node) -> Transform {
+    // Ancestors start at the node itself, so outermost comes last
+    node.ancestors()
+        .filter_map(|n| n.attribute("transform"))
+        .filter_map(|t| t.parse::<Transform>().ok())
+        .fold(Transform::default(), |inner, outer| {
+            multiply(&outer, &inner)
+        })
+}
+
+/// Matrix product `a * b` (apply `b` first, then `a`)
+fn multiply(a: &Transform, b: &Transform) -> Transform {
+    Transform::new(
+        a.a * b.a + a.c * b.b,
+        a.b * b.a + a.d * b.b,
+        a.a * b.c + a.c * b.d,
+        a.b * b.c + a.d * b.d,
+        a.a * b.e + a.c * b.f + a.e,
+        a.b * b.e + a.d * b.f + a.f,
+    )
+}
+
+fn apply(t: &Transform, (x, y): Point) -> Point {
+    let (x, y) = (x as f64, y as f64);
+    (
+        (t.a * x + t.c * y + t.e) as f32,
+        (t.b * x + t.d * y + t.f) as f32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes_and_transforms() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <g transform="translate(10 0)">
+                <rect x="0" y="0" width="4" height="2"/>
+                <path d="M 0 0 Q 5 5 10 0 Z"/>
+            </g>
+            <text>ignored</text>
+        </svg>"#;
+        let path = parse_svg(svg).unwrap();
+        assert_eq!(path.strokes.len(), 2);
+
+        // Rect is translated and flipped to Y up
+        assert_eq!(path.strokes[0][0], (10.0, 0.0));
+        assert_eq!(path.strokes[0][2], (14.0, -2.0));
+
+        // Curve is flattened and closed back to its start
+        let curve = &path.strokes[1];
+        assert_eq!(curve.len(), CURVE_SEGMENTS + 2);
+        assert_eq!(curve.first(), curve.last());
+    }
+
+    #[test]
+    fn test_empty_svg() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><text>hi</text></svg>"#;
+        assert!(matches!(parse_svg(svg), Err(SvgError::Empty)));
+    }
+}
@@ -0,0 +1,247 @@
+//! Logarithmic sine sweep and frequency response capture
+//!
+//! The sweep source plays an exponential chirp from `start_hz` to `end_hz`
+//! over `duration` seconds. Because the instantaneous frequency is a known
+//! function of time, the response can be measured without an FFT: record the
+//! input while the sweep plays, cut the recording into windows, and map each
+//! window's RMS level back to the frequency that was playing at that moment.
+//!
+//! Round-trip latency shifts the frequency axis slightly (a few ms out of a
+//! multi-second sweep), which is fine for a quick look at a device's response.
+
+use std::f64::consts::TAU;
+
+use ringbuf::{
+    traits::{Consumer, Split},
+    HeapRb,
+};
+use serde::{Deserialize, Serialize};
+
+use super::SignalSource;
+use crate::audio::XYSample;
+
+/// Number of points in a measured response curve
+const RESPONSE_POINTS: usize = 200;
+
+/// Extra recording time after the sweep ends, to catch latency
+const CAPTURE_TAIL_SECS: f32 = 0.3;
+
+/// Fade in/out length to avoid clicks at the sweep edges
+const FADE_SECS: f64 = 0.005;
+
+/// Sweep settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SweepParams {
+    pub start_hz: f32,
+    pub end_hz: f32,
+    /// Sweep length in seconds
+    pub duration: f32,
+    /// Peak level in dBFS
+    pub level_db: f32,
+    /// Restart the sweep when it finishes
+    pub repeat: bool,
+}
+
+impl Default for SweepParams {
+    fn default() -> Self {
+        Self {
+            start_hz: 20.0,
+            end_hz: 20000.0,
+            duration: 5.0,
+            level_db: -12.0,
+            repeat: false,
+        }
+    }
+}
+
+impl SweepParams {
+    /// Linear peak amplitude for `level_db`
+    pub fn amplitude(&self) -> f32 {
+        10f32.powf(self.level_db / 20.0)
+    }
+
+    /// Frequency playing `t` seconds into the sweep
+    pub fn frequency_at(&self, t: f32) -> f32 {
+        let ratio = self.end_hz / self.start_hz;
+        self.start_hz * ratio.powf((t / self.duration).clamp(0.0, 1.0))
+    }
+
+    /// Time (seconds) at which the sweep passes `freq`. A sweep that starts
+    /// and ends on the same frequency plays it throughout, so that's the
+    /// middle.
+    pub fn time_at(&self, freq: f32) -> f32 {
+        let start = self.start_hz.max(1.0);
+        let ratio = (self.end_hz.max(1.0) / start).ln();
+        if ratio.abs() < 1e-6 {
+            return self.duration / 2.0;
+        }
+        (self.duration * (freq / start).ln() / ratio).clamp(0.0, self.duration)
+    }
+
+    /// Build a source that plays this sweep from the beginning
+    pub fn build(&self) -> SweepSource {
+        SweepSource {
+            params: self.clone(),
+            amplitude: self.amplitude(),
+            sample: 0,
+            sample_rate: 48000.0,
+        }
+    }
+}
+
+/// Running sweep (same signal on both channels)
+pub struct SweepSource {
+    params: SweepParams,
+    amplitude: f32,
+    sample: u64,
+    sample_rate: f64,
+}
+
+impl SignalSource for SweepSource {
+    fn next_sample(&mut self) -> XYSample {
+        let duration = self.params.duration.max(0.1) as f64;
+        let mut t = self.sample as f64 / self.sample_rate;
+        if t >= duration {
+            if self.params.repeat {
+                self.sample = 0;
+                t = 0.0;
+            } else {
+                return XYSample::default();
+            }
+        }
+        self.sample += 1;
+
+        // Exponential chirp: phase is the integral of f0 * k^(t/T)
+        let f0 = self.params.start_hz.max(1.0) as f64;
+        let ln_k = (self.params.end_hz.max(1.0) as f64 / f0).ln();
+        let phase = if ln_k.abs() < 1e-9 {
+            TAU * f0 * t
+        } else {
+            TAU * f0 * duration / ln_k * ((t / duration * ln_k).exp() - 1.0)
+        };
+
+        let fade = (t / FADE_SECS).min((duration - t) / FADE_SECS).min(1.0);
+        let v = (phase.sin() * fade) as f32 * self.amplitude;
+        XYSample::new(v, v)
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.max(1) as f64;
+    }
+}
+
+/// One point of a measured response
+#[derive(Clone, Copy, Debug)]
+pub struct ResponsePoint {
+    pub freq: f32,
+    /// Left/X channel level relative to the sweep level (dB)
+    pub db_x: f32,
+    /// Right/Y channel level relative to the sweep level (dB)
+    pub db_y: f32,
+}
+
+/// Records the input during a sweep and turns it into a response curve
+pub struct SweepCapture {
+    params: SweepParams,
+    /// Expected RMS of a perfect loopback, for normalizing to 0 dB
+    reference_rms: f32,
+    sample_rate: u32,
+    consumer: ringbuf::HeapCons<XYSample>,
+    recorded: Vec<XYSample>,
+    target_len: usize,
+}
+
+impl SweepCapture {
+    /// Prepare a capture. Returns the capture plus the producer the audio
+    /// input should feed (see `AudioInput::set_tap`).
+    ///
+    /// `output_level` is the generator's linear output level, so the result
+    /// is relative to what was actually sent.
+    pub fn new(
+        params: &SweepParams,
+        output_level: f32,
+        sample_rate: u32,
+    ) -> (Self, ringbuf::HeapProd<XYSample>) {
+        let target_len = ((params.duration + CAPTURE_TAIL_SECS) * sample_rate as f32) as usize;
+        let rb = HeapRb::<XYSample>::new(target_len + sample_rate as usize);
+        let (prod, cons) = rb.split();
+
+        let capture = Self {
+            params: params.clone(),
+            reference_rms: params.amplitude() * output_level / std::f32::consts::SQRT_2,
+            sample_rate,
+            consumer: cons,
+            recorded: Vec::with_capacity(target_len),
+            target_len,
+        };
+        (capture, prod)
+    }
+
+    /// Drain newly captured samples. Call once per frame.
+    pub fn update(&mut self) {
+        while let Some(sample) = self.consumer.try_pop() {
+            if self.recorded.len() < self.target_len {
+                self.recorded.push(sample);
+            }
+        }
+    }
+
+    /// Recording progress (0.0 - 1.0)
+    pub fn progress(&self) -> f32 {
+        self.recorded.len() as f32 / self.target_len.max(1) as f32
+    }
+
+    /// Whether enough input has been recorded to compute the response
+    pub fn is_complete(&self) -> bool {
+        self.recorded.len() >= self.target_len
+    }
+
+    /// Compute the magnitude response from the recording
+    pub fn response(&self) -> Vec<ResponsePoint> {
+        let p = &self.params;
+        let sr = self.sample_rate as f32;
+        let log_start = p.start_hz.max(1.0).ln();
+        let log_end = p.end_hz.max(1.0).ln();
+        let reference = self.reference_rms.max(1e-9);
+
+        (0..RESPONSE_POINTS)
+            .map(|i| {
+                let frac = (i as f32 + 0.5) / RESPONSE_POINTS as f32;
+                let freq = (log_start + frac * (log_end - log_start)).exp();
+
+                // Window spanning this point's share of the sweep, but at
+                // least two periods so low frequencies average properly
+                let center = p.time_at(freq);
+                let bin_secs = p.duration / RESPONSE_POINTS as f32;
+                let half = (bin_secs.max(2.0 / freq)) / 2.0;
+                let from = (((center - half) * sr).max(0.0)) as usize;
+                let to = (((center + half) * sr) as usize).min(self.recorded.len());
+
+                let (rms_x, rms_y) = rms(&self.recorded[from.min(to)..to]);
+                ResponsePoint {
+                    freq,
+                    db_x: to_db(rms_x / reference),
+                    db_y: to_db(rms_y / reference),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Per-channel RMS of a slice of samples
+fn rms(samples: &[XYSample]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let (sx, sy) = samples
+        .iter()
+        .fold((0.0, 0.0), |acc, s| (acc.0 + s.x * s.x, acc.1 + s.y * s.y));
+    let n = samples.len() as f32;
+    ((sx / n).sqrt(), (sy / n).sqrt())
+}
+
+/// Linear ratio to dB, floored at -120 dB
+fn to_db(ratio: f32) -> f32 {
+    (20.0 * ratio.max(1e-6).log10()).max(-120.0)
+}
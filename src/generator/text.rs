@@ -0,0 +1,270 @@
+//! Text rendering with a built-in stroke font
+//!
+//! Glyphs are single-stroke polylines on a small grid (4 units wide, 6 tall,
+//! baseline at 0), in the spirit of the Hershey fonts used by plotters and
+//! vector displays. Lowercase letters are drawn as capitals; characters
+//! without a glyph render as `?`.
+
+use serde::{Deserialize, Serialize};
+
+use super::path::{Path, PathSource, Point};
+
+/// Horizontal distance between glyph origins
+const ADVANCE: f32 = 6.0;
+
+/// Vertical distance between lines
+const LINE_HEIGHT: f32 = 9.0;
+
+type Glyph = &'static [&'static [(i8, i8)]];
+
+/// Glyph strokes for a character
+fn glyph(c: char) -> Glyph {
+    match c.to_ascii_uppercase() {
+        'A' => &[&[(0, 0), (2, 6), (4, 0)], &[(1, 3), (3, 3)]],
+        'B' => &[
+            &[(0, 0), (0, 6), (3, 6), (4, 5), (4, 4), (3, 3), (0, 3)],
+            &[(3, 3), (4, 2), (4, 1), (3, 0), (0, 0)],
+        ],
+        'C' => &[&[
+            (4, 5),
+            (3, 6),
+            (1, 6),
+            (0, 5),
+            (0, 1),
+            (1, 0),
+            (3, 0),
+            (4, 1),
+        ]],
+        'D' => &[&[(0, 0), (0, 6), (2, 6), (4, 4), (4, 2), (2, 0), (0, 0)]],
+        'E' => &[&[(4, 6), (0, 6), (0, 0), (4, 0)], &[(0, 3), (3, 3)]],
+        'F' => &[&[(4, 6), (0, 6), (0, 0)], &[(0, 3), (3, 3)]],
+        'G' => &[&[
+            (4, 5),
+            (3, 6),
+            (1, 6),
+            (0, 5),
+            (0, 1),
+            (1, 0),
+            (3, 0),
+            (4, 1),
+            (4, 3),
+            (2, 3),
+        ]],
+        'H' => &[&[(0, 0), (0, 6)], &[(4, 0), (4, 6)], &[(0, 3), (4, 3)]],
+        'I' => &[&[(1, 6), (3, 6)], &[(2, 6), (2, 0)], &[(1, 0), (3, 0)]],
+        'J' => &[&[(4, 6), (4, 1), (3, 0), (1, 0), (0, 1)]],
+        'K' => &[&[(0, 0), (0, 6)], &[(4, 6), (0, 2)], &[(1, 3), (4, 0)]],
+        'L' => &[&[(0, 6), (0, 0), (4, 0)]],
+        'M' => &[&[(0, 0), (0, 6), (2, 3), (4, 6), (4, 0)]],
+        'N' => &[&[(0, 0), (0, 6), (4, 0), (4, 6)]],
+        'O' => &[&[
+            (1, 0),
+            (0, 1),
+            (0, 5),
+            (1, 6),
+            (3, 6),
+            (4, 5),
+            (4, 1),
+            (3, 0),
+            (1, 0),
+        ]],
+        'P' => &[&[(0, 0), (0, 6), (3, 6), (4, 5), (4, 4), (3, 3), (0, 3)]],
+        'Q' => &[
+            &[
+                (1, 0),
+                (0, 1),
+                (0, 5),
+                (1, 6),
+                (3, 6),
+                (4, 5),
+                (4, 1),
+                (3, 0),
+                (1, 0),
+            ],
+            &[(2, 2), (4, 0)],
+        ],
+        'R' => &[
+            &[(0, 0), (0, 6), (3, 6), (4, 5), (4, 4), (3, 3), (0, 3)],
+            &[(2, 3), (4, 0)],
+        ],
+        'S' => &[&[
+            (4, 5),
+            (3, 6),
+            (1, 6),
+            (0, 5),
+            (0, 4),
+            (1, 3),
+            (3, 3),
+            (4, 2),
+            (4, 1),
+            (3, 0),
+            (1, 0),
+            (0, 1),
+        ]],
+        'T' => &[&[(0, 6), (4, 6)], &[(2, 6), (2, 0)]],
+        'U' => &[&[(0, 6), (0, 1), (1, 0), (3, 0), (4, 1), (4, 6)]],
+        'V' => &[&[(0, 6), (2, 0), (4, 6)]],
+        'W' => &[&[(0, 6), (1, 0), (2, 4), (3, 0), (4, 6)]],
+        'X' => &[&[(0, 6), (4, 0)], &[(4, 6), (0, 0)]],
+        'Y' => &[&[(0, 6), (2, 3), (4, 6)], &[(2, 3), (2, 0)]],
+        'Z' => &[&[(0, 6), (4, 6), (0, 0), (4, 0)]],
+        '0' => &[
+            &[
+                (1, 0),
+                (0, 1),
+                (0, 5),
+                (1, 6),
+                (3, 6),
+                (4, 5),
+                (4, 1),
+                (3, 0),
+                (1, 0),
+            ],
+            &[(0, 1), (4, 5)],
+        ],
+        '1' => &[&[(1, 5), (2, 6), (2, 0)], &[(1, 0), (3, 0)]],
+        '2' => &[&[(0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (0, 0), (4, 0)]],
+        '3' => &[
+            &[
+                (0, 5),
+                (1, 6),
+                (3, 6),
+                (4, 5),
+                (4, 4),
+                (3, 3),
+                (4, 2),
+                (4, 1),
+                (3, 0),
+                (1, 0),
+                (0, 1),
+            ],
+            &[(1, 3), (3, 3)],
+        ],
+        '4' => &[&[(3, 0), (3, 6), (0, 2), (4, 2)]],
+        '5' => &[&[
+            (4, 6),
+            (0, 6),
+            (0, 3),
+            (3, 3),
+            (4, 2),
+            (4, 1),
+            (3, 0),
+            (1, 0),
+            (0, 1),
+        ]],
+        '6' => &[&[
+            (4, 5),
+            (3, 6),
+            (1, 6),
+            (0, 5),
+            (0, 1),
+            (1, 0),
+            (3, 0),
+            (4, 1),
+            (4, 2),
+            (3, 3),
+            (0, 3),
+        ]],
+        '7' => &[&[(0, 6), (4, 6), (1, 0)]],
+        '8' => &[&[
+            (1, 3),
+            (0, 4),
+            (0, 5),
+            (1, 6),
+            (3, 6),
+            (4, 5),
+            (4, 4),
+            (3, 3),
+            (1, 3),
+            (0, 2),
+            (0, 1),
+            (1, 0),
+            (3, 0),
+            (4, 1),
+            (4, 2),
+            (3, 3),
+        ]],
+        '9' => &[&[
+            (4, 3),
+            (1, 3),
+            (0, 4),
+            (0, 5),
+            (1, 6),
+            (3, 6),
+            (4, 5),
+            (4, 1),
+            (3, 0),
+            (1, 0),
+            (0, 1),
+        ]],
+        ' ' => &[],
+        '.' => &[&[(2, 0), (2, 1)]],
+        ',' => &[&[(2, 1), (1, -1)]],
+        '!' => &[&[(2, 6), (2, 2)], &[(2, 0), (2, 1)]],
+        '?' => &[
+            &[(0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (2, 3), (2, 2)],
+            &[(2, 0), (2, 1)],
+        ],
+        '-' => &[&[(1, 3), (3, 3)]],
+        '+' => &[&[(0, 3), (4, 3)], &[(2, 1), (2, 5)]],
+        '=' => &[&[(0, 2), (4, 2)], &[(0, 4), (4, 4)]],
+        ':' => &[&[(2, 4), (2, 5)], &[(2, 1), (2, 2)]],
+        '\'' => &[&[(2, 6), (2, 4)]],
+        '/' => &[&[(0, 0), (4, 6)]],
+        '(' => &[&[(3, 6), (1, 4), (1, 2), (3, 0)]],
+        ')' => &[&[(1, 6), (3, 4), (3, 2), (1, 0)]],
+        '*' => &[&[(0, 1), (4, 5)], &[(4, 1), (0, 5)], &[(2, 0), (2, 6)]],
+        _ => glyph('?'),
+    }
+}
+
+/// Lay out text (multiple lines allowed) as a path in font units
+pub fn text_path(text: &str) -> Path {
+    let mut strokes = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let base_y = -(line_idx as f32) * LINE_HEIGHT;
+        for (col, c) in line.chars().enumerate() {
+            let base_x = col as f32 * ADVANCE;
+            for stroke in glyph(c) {
+                strokes.push(
+                    stroke
+                        .iter()
+                        .map(|&(x, y)| -> Point { (base_x + x as f32, base_y + y as f32) })
+                        .collect(),
+                );
+            }
+        }
+    }
+    Path::new(strokes)
+}
+
+/// Text generator settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextParams {
+    pub text: String,
+    /// How many times per second the whole text is traced
+    pub refresh_hz: f32,
+    /// Output scale (0.0 - 1.0)
+    pub amplitude: f32,
+}
+
+impl Default for TextParams {
+    fn default() -> Self {
+        Self {
+            text: "SCOPE-RS".to_string(),
+            refresh_hz: 50.0,
+            amplitude: 0.9,
+        }
+    }
+}
+
+impl TextParams {
+    pub fn build(&self) -> PathSource {
+        PathSource::new(
+            text_path(&self.text).normalized(1.0),
+            self.refresh_hz,
+            self.amplitude,
+        )
+    }
+}
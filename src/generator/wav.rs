@@ -0,0 +1,38 @@
+//! WAV export of generator output
+//!
+//! Renders a source offline, so the file is sample-exact regardless of the
+//! audio device. X goes to the left channel, Y to the right.
+
+use std::path::Path;
+
+use super::SignalSource;
+
+/// Render `seconds` of `source` at `sample_rate` into a 16-bit stereo WAV
+pub fn export_wav(
+    source: &mut dyn SignalSource,
+    path: &Path,
+    sample_rate: u32,
+    seconds: f32,
+) -> Result<(), hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+
+    source.set_sample_rate(sample_rate);
+    let frames = (seconds.max(0.0) * sample_rate as f32).round() as usize;
+    for _ in 0..frames {
+        let s = source.next_sample();
+        writer.write_sample(to_i16(s.x))?;
+        writer.write_sample(to_i16(s.y))?;
+    }
+
+    writer.finalize()
+}
+
+fn to_i16(v: f32) -> i16 {
+    (v.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
@@ -0,0 +1,155 @@
+//! Grid of scopes for sources with more than two channels
+//!
+//! The main display only shows a source's first two channels. For surround
+//! files or multitrack inputs the grid draws a pane for each pair of
+//! channels instead, or one for each channel against a chosen reference,
+//! all with the main display's settings.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{ChannelBuffer, XYSample};
+use crate::i18n::trf;
+use crate::render::{Oscilloscope, OscilloscopeSettings};
+
+/// Gap between panes, in points
+const PANE_GAP: f32 = 4.0;
+
+/// Which channels share a pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pairing {
+    /// 1 and 2, 3 and 4, and so on
+    Pairs,
+    /// Every other channel against the reference on X
+    Reference,
+}
+
+impl Pairing {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pairs => "Channel pairs",
+            Self::Reference => "Against a reference",
+        }
+    }
+
+    pub fn all() -> &'static [Pairing] {
+        &[Self::Pairs, Self::Reference]
+    }
+}
+
+/// One scope per pane, each fed from the source's channel buffer
+pub struct ScopeGrid {
+    pub enabled: bool,
+    pub pairing: Pairing,
+    /// Channel on X with `Pairing::Reference`, from 0
+    pub reference: usize,
+    scopes: Vec<Oscilloscope>,
+    /// Interleaved frames from the channel buffer
+    frames: Vec<f32>,
+    /// One pane's samples
+    pane: Vec<XYSample>,
+}
+
+impl Default for ScopeGrid {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pairing: Pairing::Pairs,
+            reference: 0,
+            scopes: Vec::new(),
+            frames: Vec::new(),
+            pane: Vec::new(),
+        }
+    }
+}
+
+impl ScopeGrid {
+    /// The X and Y channel of each pane for `channels` channels. With pairs
+    /// an odd last channel is drawn against itself.
+    pub fn pairs(&self, channels: usize) -> Vec<(usize, usize)> {
+        match self.pairing {
+            Pairing::Pairs => (0..channels)
+                .step_by(2)
+                .map(|x| (x, (x + 1).min(channels - 1)))
+                .collect(),
+            Pairing::Reference => {
+                let reference = self.reference.min(channels.saturating_sub(1));
+                (0..channels)
+                    .filter(|&y| y != reference)
+                    .map(|y| (reference, y))
+                    .collect()
+            }
+        }
+    }
+
+    /// Draw the grid into `size`, or return None if `channels` has nothing
+    /// for it
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        channels: &ChannelBuffer,
+        settings: &OscilloscopeSettings,
+        size: egui::Vec2,
+    ) -> Option<egui::Response> {
+        let count = channels.copy_latest_into(settings.sample_count, &mut self.frames);
+        if count <= 2 {
+            return None;
+        }
+        let pairs = self.pairs(count);
+        self.scopes.resize_with(pairs.len(), Oscilloscope::new);
+
+        let columns = (pairs.len() as f32).sqrt().ceil() as usize;
+        let rows = pairs.len().div_ceil(columns);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+        let pane_size = egui::vec2(
+            (rect.width() - PANE_GAP * (columns - 1) as f32) / columns as f32,
+            (rect.height() - PANE_GAP * (rows - 1) as f32) / rows as f32,
+        );
+
+        for (i, (&(x, y), scope)) in pairs.iter().zip(&mut self.scopes).enumerate() {
+            self.pane.clear();
+            self.pane.extend(
+                self.frames
+                    .chunks_exact(count)
+                    .map(|frame| XYSample::new(frame[x], frame[y])),
+            );
+            scope.settings = settings.clone();
+            let min = rect.min
+                + egui::vec2(
+                    (i % columns) as f32 * (pane_size.x + PANE_GAP),
+                    (i / columns) as f32 * (pane_size.y + PANE_GAP),
+                );
+            let pane_rect = egui::Rect::from_min_size(min, pane_size);
+            ui.allocate_new_ui(egui::UiBuilder::new().max_rect(pane_rect), |ui| {
+                scope.show(ui, &self.pane, Some(pane_size));
+            });
+            ui.painter().text(
+                pane_rect.left_top() + egui::vec2(6.0, 4.0),
+                egui::Align2::LEFT_TOP,
+                trf("{} vs {}", &[&(x + 1), &(y + 1)]),
+                egui::FontId::proportional(11.0),
+                settings.color.gamma_multiply(0.7),
+            );
+        }
+        Some(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        let mut grid = ScopeGrid::default();
+        assert_eq!(grid.pairs(6), vec![(0, 1), (2, 3), (4, 5)]);
+        assert_eq!(grid.pairs(5), vec![(0, 1), (2, 3), (4, 4)]);
+
+        grid.pairing = Pairing::Reference;
+        grid.reference = 2;
+        assert_eq!(grid.pairs(4), vec![(2, 0), (2, 1), (2, 3)]);
+        // A reference past the last channel is the last channel
+        grid.reference = 9;
+        assert_eq!(grid.pairs(3), vec![(2, 0), (2, 1)]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Offscreen rendering harness
+//!
+//! Runs a deterministic generated signal through the path live audio takes:
+//! pushed into a `SampleBuffer` a frame's worth at a time, read back through
+//! its consumer and drawn by the `Rasterizer`. No window, audio device or
+//! clock is involved, so the same frames come out on every run. The golden
+//! image tests compare them against reference images, and the benchmarks
+//! time the same stages.
+
+use crate::audio::{SampleBuffer, SampleConsumer, XYSample};
+use crate::generator::{FunctionParams, NoiseParams, ShapeParams, ShapePreset, SignalSource};
+use crate::render::{OscilloscopeSettings, Rasterizer};
+
+/// Sample rate the signals are generated at
+pub const SAMPLE_RATE: u32 = 48_000;
+
+/// Display frame rate the harness steps at
+pub const FRAME_RATE: u32 = 60;
+
+/// Signals the harness can generate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestSignal {
+    /// 440 Hz sines 90° apart: a circle
+    Sine,
+    /// 3:2 Lissajous figure
+    Lissajous,
+    /// Independent white noise on both channels
+    Noise,
+}
+
+impl TestSignal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sine => "sine",
+            Self::Lissajous => "lissajous",
+            Self::Noise => "noise",
+        }
+    }
+
+    pub fn all() -> &'static [TestSignal] {
+        &[Self::Sine, Self::Lissajous, Self::Noise]
+    }
+
+    /// A fresh source, starting from the same state every time
+    pub fn source(&self) -> Box<dyn SignalSource> {
+        let mut source: Box<dyn SignalSource> = match self {
+            Self::Sine => Box::new(FunctionParams::default().build()),
+            Self::Lissajous => {
+                let mut params = ShapeParams::default();
+                params.apply_preset(ShapePreset::Lissajous);
+                Box::new(params.build().expect("preset equations parse"))
+            }
+            Self::Noise => Box::new(NoiseParams::default().build()),
+        };
+        source.set_sample_rate(SAMPLE_RATE);
+        source
+    }
+
+    /// `count` samples from the start of the signal
+    pub fn samples(&self, count: usize) -> Vec<XYSample> {
+        let mut source = self.source();
+        (0..count).map(|_| source.next_sample()).collect()
+    }
+}
+
+/// A signal source wired to a buffer and a rasterizer
+pub struct RenderHarness {
+    pub settings: OscilloscopeSettings,
+    source: Box<dyn SignalSource>,
+    buffer: SampleBuffer,
+    consumer: SampleConsumer,
+    rasterizer: Rasterizer,
+    /// One frame of generated samples, reused
+    block: Vec<XYSample>,
+    /// The consumer's samples for the frame being drawn, reused
+    samples: Vec<XYSample>,
+}
+
+impl RenderHarness {
+    /// Render `signal` at `width` x `height` with the default settings
+    pub fn new(signal: TestSignal, width: usize, height: usize) -> Self {
+        let buffer = SampleBuffer::new(SampleBuffer::DEFAULT_CAPACITY);
+        let consumer = buffer
+            .take_consumer()
+            .expect("a new buffer still has its consumer");
+        Self {
+            settings: OscilloscopeSettings::default(),
+            source: signal.source(),
+            buffer,
+            consumer,
+            rasterizer: Rasterizer::new(width, height),
+            block: Vec::with_capacity(Self::frame_len()),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Samples generated per display frame
+    pub fn frame_len() -> usize {
+        (SAMPLE_RATE / FRAME_RATE) as usize
+    }
+
+    /// Generate one frame of samples and push them into the buffer, as an
+    /// audio callback would
+    pub fn feed(&mut self) {
+        self.block.clear();
+        for _ in 0..Self::frame_len() {
+            self.block.push(self.source.next_sample());
+        }
+        self.buffer.push_slice(&self.block);
+    }
+
+    /// Read the buffer as the UI does and rasterize the result
+    pub fn render(&mut self) -> &[u8] {
+        self.consumer.update();
+        self.consumer.copy_samples_into(&mut self.samples);
+        self.rasterizer.render(&self.samples, &self.settings)
+    }
+
+    /// Feed and render `frames` frames, returning the last
+    pub fn run(&mut self, frames: usize) -> &[u8] {
+        for _ in 0..frames {
+            self.feed();
+            self.render();
+        }
+        self.rasterizer.pixels()
+    }
+
+    pub fn width(&self) -> usize {
+        self.rasterizer.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rasterizer.height()
+    }
+}
+
+/// How far a frame is from a reference
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageDiff {
+    /// Largest difference in any channel of any pixel
+    pub max_delta: u8,
+    /// Fraction of pixels with any channel off by more than the tolerance
+    pub mismatched: f32,
+}
+
+impl ImageDiff {
+    /// Compare two RGBA8 images of the same size, counting pixels off by
+    /// more than `tolerance` in any channel
+    pub fn compare(actual: &[u8], expected: &[u8], tolerance: u8) -> Self {
+        assert_eq!(actual.len(), expected.len(), "image sizes differ");
+        let mut max_delta = 0;
+        let mut mismatched = 0;
+        for (a, e) in actual.chunks_exact(4).zip(expected.chunks_exact(4)) {
+            let delta = a
+                .iter()
+                .zip(e)
+                .map(|(a, e)| a.abs_diff(*e))
+                .max()
+                .unwrap_or(0);
+            max_delta = max_delta.max(delta);
+            if delta > tolerance {
+                mismatched += 1;
+            }
+        }
+        Self {
+            max_delta,
+            mismatched: mismatched as f32 / (actual.len() / 4).max(1) as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_are_repeatable() {
+        for &signal in TestSignal::all() {
+            let mut harness = RenderHarness::new(signal, 64, 64);
+            let first = harness.run(10).to_vec();
+            let second = RenderHarness::new(signal, 64, 64).run(10).to_vec();
+            assert_eq!(first, second, "{}", signal.name());
+
+            // Something besides the background was drawn
+            let background = harness.settings.background.to_array();
+            let lit = first.chunks_exact(4).filter(|p| *p != background).count();
+            assert!(lit > 40, "{}: {lit} pixels lit", signal.name());
+        }
+    }
+}
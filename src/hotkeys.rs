@@ -0,0 +1,245 @@
+//! System-wide hotkeys
+//!
+//! A few actions can also be bound to hotkeys the desktop hands to scope-rs
+//! whichever app has focus, so playback, freeze, screenshots and presets can
+//! be worked from a DAW or DJ software. They're off by default, since they
+//! take their key combinations away from every other app, and default to
+//! Ctrl+Alt combinations that rarely clash.
+//!
+//! On Linux this needs X11; a Wayland session only passes them on while an
+//! X11 app has focus.
+
+use std::sync::mpsc;
+
+use eframe::egui::{self, Key, Modifiers};
+use global_hotkey::hotkey::{self, HotKey};
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+use crate::keybindings::{self, Action, KeyBinding, Shortcut};
+
+/// Actions that can be bound to a global hotkey
+pub const ACTIONS: &[Action] = &[
+    Action::PlayPause,
+    Action::Freeze,
+    Action::Screenshot,
+    Action::NextPreset,
+    Action::PreviousPreset,
+];
+
+/// Global hotkey used when the user hasn't chosen one
+fn default_shortcut(action: Action) -> Option<Shortcut> {
+    let key = match action {
+        Action::PlayPause => Key::Space,
+        Action::Freeze => Key::F,
+        Action::Screenshot => Key::P,
+        Action::NextPreset => Key::PageDown,
+        Action::PreviousPreset => Key::PageUp,
+        _ => return None,
+    };
+    Some(Shortcut::new(Modifiers::COMMAND | Modifiers::ALT, key))
+}
+
+fn default_bindings() -> Vec<KeyBinding> {
+    ACTIONS
+        .iter()
+        .map(|&action| KeyBinding {
+            action,
+            shortcut: default_shortcut(action),
+        })
+        .collect()
+}
+
+/// The hotkey for `shortcut`, if the platform layer knows its key
+fn hotkey(shortcut: Shortcut) -> Option<HotKey> {
+    // The hotkey parser reads the same key names egui writes
+    let code = shortcut.key.name().parse::<HotKey>().ok()?.key;
+    let mut modifiers = hotkey::Modifiers::empty();
+    if shortcut.modifiers.command {
+        modifiers |= if cfg!(target_os = "macos") {
+            hotkey::Modifiers::SUPER
+        } else {
+            hotkey::Modifiers::CONTROL
+        };
+    }
+    if shortcut.modifiers.alt {
+        modifiers |= hotkey::Modifiers::ALT;
+    }
+    if shortcut.modifiers.shift {
+        modifiers |= hotkey::Modifiers::SHIFT;
+    }
+    Some(HotKey::new(Some(modifiers), code))
+}
+
+/// System-wide hotkeys and the actions bound to them
+pub struct GlobalHotkeys {
+    /// Status message
+    pub status: String,
+    bindings: Vec<KeyBinding>,
+    manager: Option<GlobalHotKeyManager>,
+    /// Hotkeys registered with the desktop, and what they trigger
+    registered: Vec<(HotKey, Action)>,
+    events: mpsc::Receiver<GlobalHotKeyEvent>,
+}
+
+impl GlobalHotkeys {
+    /// Hotkeys that wake the UI in `ctx` when pressed. Hotkey events have a
+    /// single process-wide handler, so make only one of these.
+    pub fn new(ctx: egui::Context) -> Self {
+        let (sender, events) = mpsc::channel();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+            let _ = sender.send(event);
+            ctx.request_repaint();
+        }));
+        Self {
+            status: "Off".to_string(),
+            bindings: default_bindings(),
+            manager: None,
+            registered: Vec::new(),
+            events,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.manager.is_some()
+    }
+
+    /// Grab or release the hotkeys
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.start();
+        } else {
+            self.stop();
+        }
+    }
+
+    /// Grab the hotkeys; grabs them again if already enabled
+    pub fn start(&mut self) {
+        self.stop();
+        // Without an X display the listener thread quits without a word
+        if cfg!(target_os = "linux") && std::env::var_os("DISPLAY").is_none() {
+            self.status = "Error: Global hotkeys need an X11 display".to_string();
+            return;
+        }
+        match GlobalHotKeyManager::new() {
+            Ok(manager) => {
+                self.manager = Some(manager);
+                self.register();
+            }
+            Err(e) => {
+                log::error!("Global hotkeys failed: {}", e);
+                self.status = format!("Error: {}", e);
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.unregister();
+        if self.manager.take().is_some() {
+            self.status = "Off".to_string();
+        }
+    }
+
+    pub fn bindings(&self) -> &[KeyBinding] {
+        &self.bindings
+    }
+
+    /// Bind `action` to `shortcut`, unbinding any other action that used it
+    pub fn set(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        keybindings::rebind(&mut self.bindings, action, shortcut);
+        self.register();
+    }
+
+    /// Use saved bindings; actions added since they were saved get their
+    /// default hotkey
+    pub fn set_bindings(&mut self, saved: &[KeyBinding]) {
+        self.bindings = default_bindings();
+        for binding in saved {
+            keybindings::rebind(&mut self.bindings, binding.action, binding.shortcut);
+        }
+        self.register();
+    }
+
+    pub fn reset(&mut self) {
+        self.set_bindings(&[]);
+    }
+
+    /// Release the registered hotkeys and grab the current bindings
+    fn register(&mut self) {
+        self.unregister();
+        let Some(manager) = &self.manager else {
+            return;
+        };
+        let mut failed = Vec::new();
+        for binding in &self.bindings {
+            let Some(shortcut) = binding.shortcut else {
+                continue;
+            };
+            let Some(hotkey) = hotkey(shortcut) else {
+                failed.push(format!("{} (unsupported key)", shortcut));
+                continue;
+            };
+            match manager.register(hotkey) {
+                Ok(()) => self.registered.push((hotkey, binding.action)),
+                Err(e) => {
+                    log::warn!("Couldn't register global hotkey {}: {}", shortcut, e);
+                    failed.push(shortcut.to_string());
+                }
+            }
+        }
+        self.status = if failed.is_empty() {
+            format!("{} hotkeys active", self.registered.len())
+        } else {
+            format!("Taken or unsupported: {}", failed.join(", "))
+        };
+    }
+
+    fn unregister(&mut self) {
+        if let Some(manager) = &self.manager {
+            for (hotkey, _) in self.registered.drain(..) {
+                let _ = manager.unregister(hotkey);
+            }
+        }
+        self.registered.clear();
+    }
+
+    /// Actions whose hotkeys were pressed since the last call
+    pub fn triggered(&self) -> Vec<Action> {
+        self.events
+            .try_iter()
+            .filter(|event| event.state() == HotKeyState::Pressed)
+            .filter_map(|event| {
+                self.registered
+                    .iter()
+                    .find(|(hotkey, _)| hotkey.id() == event.id())
+                    .map(|&(_, action)| action)
+            })
+            .collect()
+    }
+}
+
+impl Drop for GlobalHotkeys {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_hotkeys_map_to_platform_keys() {
+        for binding in default_bindings() {
+            let shortcut = binding.shortcut.unwrap();
+            assert!(hotkey(shortcut).is_some(), "{}", shortcut);
+        }
+        let arrow = Shortcut::new(Modifiers::SHIFT, Key::ArrowUp);
+        assert_eq!(
+            hotkey(arrow),
+            Some(HotKey::new(
+                Some(hotkey::Modifiers::SHIFT),
+                hotkey::Code::ArrowUp
+            ))
+        );
+    }
+}
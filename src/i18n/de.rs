@@ -0,0 +1,635 @@
+//! German translation
+
+pub const STRINGS: &[(&str, &str)] = &[
+    // Top bar and status
+    ("Live", "Live"),
+    ("File", "Datei"),
+    ("Generator", "Generator"),
+    ("▶ Capture", "▶ Aufnehmen"),
+    ("▶ Start", "▶ Start"),
+    ("⏹ Stop", "⏹ Stopp"),
+    ("📂 Open", "📂 Öffnen"),
+    ("Audio out", "Audioausgang"),
+    ("Default", "Standard"),
+    ("None", "Keins"),
+    ("⚙ Settings", "⚙ Einstellungen"),
+    ("❄ Freeze", "❄ Einfrieren"),
+    ("Save screenshot ({})", "Bildschirmfoto speichern ({})"),
+    ("Vol:", "Lautst.:"),
+    ("Speed:", "Tempo:"),
+    ("Loop", "Schleife"),
+    ("Samples: {}", "Samples: {}"),
+    ("Total: {}", "Gesamt: {}"),
+    ("Mode: {} | Display: {}", "Modus: {} | Anzeige: {}"),
+    ("Live Input", "Live-Eingang"),
+    ("File Playback", "Dateiwiedergabe"),
+    ("Demo", "Demo"),
+    ("Frozen", "Eingefroren"),
+    ("Error: {}", "Fehler: {}"),
+    ("Saved {}", "{} gespeichert"),
+    ("Screenshot failed: {}", "Bildschirmfoto fehlgeschlagen: {}"),
+    // Settings sections
+    ("Settings", "Einstellungen"),
+    ("Presets", "Presets"),
+    ("Audio", "Audio"),
+    ("Demo Mode", "Demomodus"),
+    ("Display", "Anzeige"),
+    ("Channel", "Kanal"),
+    ("Color", "Farbe"),
+    ("MIDI", "MIDI"),
+    ("Keyboard", "Tastatur"),
+    ("Output", "Ausgabe"),
+    ("Interface", "Oberfläche"),
+    ("Startup", "Programmstart"),
+    ("Import / Export", "Import / Export"),
+    // Audio, display, channel, color
+    ("Gain:", "Verstärkung:"),
+    ("Show a demo when idle", "Demo zeigen, wenn nichts läuft"),
+    ("Scene:", "Szene:"),
+    (
+        "Runs whenever the current input is stopped",
+        "Läuft, solange die aktuelle Quelle gestoppt ist",
+    ),
+    ("Mode:", "Modus:"),
+    ("Zoom:", "Zoom:"),
+    ("Line width:", "Linienbreite:"),
+    ("Intensity:", "Intensität:"),
+    ("Persistence:", "Nachleuchten:"),
+    ("Show grid", "Raster anzeigen"),
+    ("Clear persistence", "Nachleuchten löschen"),
+    ("Swap X/Y", "X/Y tauschen"),
+    ("Invert X", "X invertieren"),
+    ("Invert Y", "Y invertieren"),
+    ("X offset:", "X-Versatz:"),
+    ("Y offset:", "Y-Versatz:"),
+    ("Reset offsets", "Versatz zurücksetzen"),
+    ("Theme:", "Farbschema:"),
+    // MIDI
+    ("Port:", "Port:"),
+    ("Connect", "Verbinden"),
+    ("Disconnect", "Trennen"),
+    ("Refresh", "Aktualisieren"),
+    ("Mappings:", "Zuordnungen:"),
+    ("Cancel", "Abbrechen"),
+    ("Learn", "Lernen"),
+    ("+ Add", "+ Hinzufügen"),
+    // Keyboard
+    ("Click to rebind", "Klicken zum Neubelegen"),
+    ("Remove shortcut", "Tastenkürzel entfernen"),
+    ("Press a key…", "Taste drücken…"),
+    ("Reset to defaults", "Auf Standard zurücksetzen"),
+    ("Shortcut: {}", "Tastenkürzel: {}"),
+    ("No shortcut", "Kein Tastenkürzel"),
+    // Output, interface, startup, import/export
+    ("Shared memory frames", "Frames über Shared Memory"),
+    ("Name:", "Name:"),
+    ("Size:", "Größe:"),
+    ("Frames: {}", "Frames: {}"),
+    ("UI scale:", "UI-Skalierung:"),
+    ("Font size:", "Schriftgröße:"),
+    ("Reset", "Zurücksetzen"),
+    (
+        "Start capture on the last device",
+        "Aufnahme am letzten Gerät starten",
+    ),
+    ("Reopen the last file", "Letzte Datei wieder öffnen"),
+    (
+        "Restore window, panels and mode",
+        "Fenster, Bereiche und Modus wiederherstellen",
+    ),
+    ("Window:", "Fenster:"),
+    (
+        "Settings, MIDI mappings, key bindings and presets",
+        "Einstellungen, MIDI-Zuordnungen, Tastenkürzel und Presets",
+    ),
+    ("Export settings…", "Einstellungen exportieren…"),
+    ("Import settings…", "Einstellungen importieren…"),
+    ("Exported to {}", "Exportiert nach {}"),
+    ("Imported {}", "{} importiert"),
+    // Presets
+    ("(none)", "(keins)"),
+    ("Load", "Laden"),
+    ("Save", "Speichern"),
+    ("Delete preset", "Preset löschen"),
+    ("Preset name", "Preset-Name"),
+    ("Loaded '{}'", "'{}' geladen"),
+    ("Deleted '{}'", "'{}' gelöscht"),
+    ("Saved '{}'", "'{}' gespeichert"),
+    ("Morph to:", "Überblenden zu:"),
+    ("over", "über"),
+    ("Morph", "Überblenden"),
+    (
+        "Each morph swaps the two presets, so morphing again goes back",
+        "Jede Überblendung tauscht die beiden Presets, erneutes Überblenden geht zurück",
+    ),
+    ("Separate look per source", "Eigenes Aussehen pro Quelle"),
+    ("Profile: {}", "Profil: {}"),
+    ("Compare:", "Vergleich:"),
+    ("Copy to {}", "Nach {} kopieren"),
+    // Generator
+    ("Source:", "Quelle:"),
+    ("📂 Import .osci", "📂 .osci importieren"),
+    ("Output level:", "Ausgangspegel:"),
+    ("Calibration…", "Kalibrierung…"),
+    ("Shape:", "Form:"),
+    ("Frequency:", "Frequenz:"),
+    ("Amplitude:", "Amplitude:"),
+    ("From:", "Von:"),
+    ("To:", "Bis:"),
+    ("Duration:", "Dauer:"),
+    ("Level:", "Pegel:"),
+    ("Repeat", "Wiederholen"),
+    ("Measuring...", "Messung läuft..."),
+    ("Measure response", "Frequenzgang messen"),
+    (
+        "Start live capture on the return input first",
+        "Zuerst die Live-Aufnahme am Rückkanal starten",
+    ),
+    ("Show last response", "Letzten Frequenzgang zeigen"),
+    (
+        "Start live capture to measure",
+        "Zum Messen die Live-Aufnahme starten",
+    ),
+    ("Text:", "Text:"),
+    ("Refresh:", "Wiederholrate:"),
+    (
+        "Longer text needs a lower refresh rate to stay sharp",
+        "Längerer Text braucht eine niedrigere Wiederholrate, um scharf zu bleiben",
+    ),
+    ("📂 Import SVG", "📂 SVG importieren"),
+    ("{} strokes", "{} Striche"),
+    (
+        "{} points per frame at {} Hz",
+        "{} Punkte pro Bild bei {} Hz",
+    ),
+    ("💾 Export WAV (10 s)", "💾 WAV exportieren (10 s)"),
+    ("Exported {}", "{} exportiert"),
+    ("📂 Import OBJ", "📂 OBJ importieren"),
+    ("{} vertices, {} edges", "{} Punkte, {} Kanten"),
+    ("Spin {}:", "Drehung {}:"),
+    ("Camera distance:", "Kameraabstand:"),
+    ("{} (from project)", "{} (aus Projekt)"),
+    ("'{}' is not supported", "'{}' wird nicht unterstützt"),
+    ("Loaded {} of {} files", "{} von {} Dateien geladen"),
+    ("Color:", "Farbe:"),
+    (
+        "Same noise on both channels",
+        "Gleiches Rauschen auf beiden Kanälen",
+    ),
+    ("Left (X)", "Links (X)"),
+    ("Right (Y)", "Rechts (Y)"),
+    ("Freq:", "Freq.:"),
+    ("Phase:", "Phase:"),
+    ("Output peak: {} dBFS", "Ausgangsspitze: {} dBFS"),
+    ("a, b, c unused", "a, b, c ungenutzt"),
+    (
+        "a:b = frequency ratio, c = phase (radians)",
+        "a:b = Frequenzverhältnis, c = Phase (Bogenmaß)",
+    ),
+    ("a/b = petal ratio", "a/b = Blütenblattverhältnis"),
+    (
+        "a = rolling circle radius, b = pen distance",
+        "a = Radius des Rollkreises, b = Stiftabstand",
+    ),
+    (
+        "Variables: t, a, b, c, pi, e",
+        "Variablen: t, a, b, c, pi, e",
+    ),
+    // Windows
+    ("Calibration", "Kalibrierung"),
+    ("Frequency Response", "Frequenzgang"),
+    ("■ Left (X)", "■ Links (X)"),
+    ("■ Right (Y)", "■ Rechts (Y)"),
+    ("Command Palette", "Befehlspalette"),
+    (
+        "Type a command or parameter…",
+        "Befehl oder Parameter eingeben…",
+    ),
+    ("No matches", "Keine Treffer"),
+    (
+        "Connect the outputs to the inputs (loopback) and start live capture",
+        "Ausgänge mit den Eingängen verbinden (Loopback) und Live-Aufnahme starten",
+    ),
+    (
+        "Start live capture to calibrate",
+        "Zum Kalibrieren die Live-Aufnahme starten",
+    ),
+    ("Phase (Y vs X):", "Phase (Y zu X):"),
+    ("Gain mismatch (Y vs X):", "Pegelabweichung (Y zu X):"),
+    ("Input level X:", "Eingangspegel X:"),
+    ("Input level Y:", "Eingangspegel Y:"),
+    ("Waiting for signal...", "Warte auf Signal..."),
+    ("Not running", "Läuft nicht"),
+    // Display modes and themes
+    ("Dots", "Punkte"),
+    ("Lines", "Linien"),
+    ("Gradient", "Verlauf"),
+    ("Points", "Einzelpunkte"),
+    ("Green", "Grün"),
+    ("Amber", "Bernstein"),
+    ("Blue", "Blau"),
+    ("White", "Weiß"),
+    ("Purple", "Lila"),
+    ("Cyan", "Cyan"),
+    ("Red", "Rot"),
+    // Generator kinds, shapes, waveforms, noise, demo scenes
+    ("Shape", "Form"),
+    ("Function", "Funktion"),
+    ("Sweep", "Sweep"),
+    ("Noise", "Rauschen"),
+    ("Text", "Text"),
+    ("SVG", "SVG"),
+    ("3D Model", "3D-Modell"),
+    ("Circle", "Kreis"),
+    ("Lissajous", "Lissajous"),
+    ("Rose", "Rose"),
+    ("Spirograph", "Spirograph"),
+    ("Custom", "Eigene"),
+    ("Sine", "Sinus"),
+    ("Square", "Rechteck"),
+    ("Triangle", "Dreieck"),
+    ("Sawtooth", "Sägezahn"),
+    ("Pink", "Rosa"),
+    ("Clock", "Uhr"),
+    ("Bounce", "Abprallen"),
+    ("Drift", "Treiben"),
+    ("Normal", "Normal"),
+    ("Minimized", "Minimiert"),
+    ("Fullscreen", "Vollbild"),
+    // Actions
+    ("Play / Pause", "Wiedergabe / Pause"),
+    ("Stop", "Stopp"),
+    ("Freeze Display", "Anzeige einfrieren"),
+    ("Screenshot", "Bildschirmfoto"),
+    ("Next Theme", "Nächstes Farbschema"),
+    ("Previous Theme", "Vorheriges Farbschema"),
+    ("Clear Persistence", "Nachleuchten löschen"),
+    ("Settings Panel", "Einstellungsbereich"),
+    ("Performance Overlay", "Leistungsanzeige"),
+    ("Log Panel", "Protokollbereich"),
+    ("Preset Morph", "Preset-Überblendung"),
+    ("A/B Compare", "A/B-Vergleich"),
+    // MIDI parameters
+    ("Gain", "Verstärkung"),
+    ("Volume", "Lautstärke"),
+    ("Speed", "Tempo"),
+    ("Line Width", "Linienbreite"),
+    ("Intensity", "Intensität"),
+    ("Persistence", "Nachleuchten"),
+    ("Zoom", "Zoom"),
+    ("DC Offset X", "DC-Versatz X"),
+    ("DC Offset Y", "DC-Versatz Y"),
+    ("Model Spin X", "Modelldrehung X"),
+    ("Model Spin Y", "Modelldrehung Y"),
+    ("Model Spin Z", "Modelldrehung Z"),
+    // Settings
+    ("Language:", "Sprache:"),
+    // Layout
+    ("Layout", "Anordnung"),
+    ("Playback", "Wiedergabe"),
+    ("Left", "Links"),
+    ("Right", "Rechts"),
+    ("Top", "Oben"),
+    ("Bottom", "Unten"),
+    ("Floating", "Schwebend"),
+    ("Workspace:", "Arbeitsbereich:"),
+    ("Workspace name", "Name des Arbeitsbereichs"),
+    ("Delete workspace", "Arbeitsbereich löschen"),
+    ("Save as", "Speichern als"),
+    (
+        "Move towards the window edge",
+        "Zum Fensterrand verschieben",
+    ),
+    ("Close", "Schließen"),
+    // Scope readout
+    ("Sample {} ({} ms ago)", "Sample {} (vor {} ms)"),
+    // Performance overlay
+    ("Performance overlay", "Leistungsanzeige"),
+    ("FPS: {} ({} ms)", "Bilder/s: {} ({} ms)"),
+    ("Sample fetch: {} ms", "Samples lesen: {} ms"),
+    ("Persistence: {} ms", "Nachleuchten: {} ms"),
+    ("Painting: {} ms", "Zeichnen: {} ms"),
+    ("Throughput: {} samples/s", "Durchsatz: {} Samples/s"),
+    ("Buffer fill: {}%", "Pufferfüllung: {} %"),
+    ("Dropped: {}", "Verworfen: {}"),
+    // Help
+    ("Help", "Hilfe"),
+    ("Help ({})", "Hilfe ({})"),
+    ("Shortcuts can be changed under Settings → Keyboard.", "Tastenkürzel lassen sich unter Einstellungen → Tastatur ändern."),
+    ("Mouse and touch", "Maus und Touch"),
+    ("Wheel", "Mausrad"),
+    ("Ctrl+Wheel", "Strg+Mausrad"),
+    ("Drag", "Ziehen"),
+    ("Double-click", "Doppelklick"),
+    ("Two fingers", "Zwei Finger"),
+    ("Hover", "Darüberfahren"),
+    ("Zoom around the cursor", "Um den Mauszeiger zoomen"),
+    ("Zoom in finer steps", "In feineren Schritten zoomen"),
+    ("Pan the view", "Ansicht verschieben"),
+    ("Re-center the view", "Ansicht zentrieren"),
+    ("Pan, and pinch to zoom", "Verschieben, mit Spreizen zoomen"),
+    ("Show the value under the cursor", "Wert unter dem Mauszeiger anzeigen"),
+    ("Display modes", "Darstellungsarten"),
+    ("A dot at each sample. Fast movements look sparse.", "Ein Punkt pro Sample. Schnelle Bewegungen wirken lückenhaft."),
+    ("Samples joined by lines, the usual XY look.", "Mit Linien verbundene Samples, die übliche XY-Darstellung."),
+    ("Lines that are brighter where the beam moves slowly, like a CRT.", "Linien, die wie auf einer Bildröhre dort heller sind, wo sich der Strahl langsam bewegt."),
+    ("Larger points, without the persistence trail.", "Größere Punkte ohne Nachleuchten."),
+    ("Channel controls", "Kanaleinstellungen"),
+    ("Range shown across the display. Below 1 zooms in, above 1 zooms out.", "Angezeigter Bereich. Unter 1 wird hineingezoomt, über 1 herausgezoomt."),
+    ("Plot the right channel horizontally and the left channel vertically.", "Rechten Kanal horizontal und linken Kanal vertikal darstellen."),
+    ("Invert X / Y", "X / Y invertieren"),
+    ("Flip a channel, e.g. to undo a polarity-reversed cable.", "Einen Kanal spiegeln, z. B. bei einem verpolten Kabel."),
+    ("X / Y offset", "X- / Y-Versatz"),
+    ("Shift a channel to cancel DC on the input or to move the figure.", "Einen Kanal verschieben, um Gleichspannung am Eingang auszugleichen oder die Figur zu bewegen."),
+    ("How long old traces keep glowing, like the phosphor of an analog scope.", "Wie lange alte Spuren nachleuchten, wie der Leuchtstoff eines analogen Oszilloskops."),
+    // Session
+    ("Start fresh next launch", "Beim nächsten Start neu beginnen"),
+    ("Forget the open file, device, window and selected preset when closing. Running with --fresh does the same for one launch.", "Beim Schließen geöffnete Datei, Gerät, Fenster und gewähltes Preset vergessen. Der Start mit --fresh bewirkt dasselbe für einen Start."),
+    // Appearance
+    ("Appearance:", "Erscheinungsbild:"),
+    ("Dark", "Dunkel"),
+    ("Light", "Hell"),
+    ("Panels and controls only; the scope keeps its color theme", "Nur Bedienelemente; das Oszilloskop behält sein Farbschema"),
+    // Log
+    ("Log", "Protokoll"),
+    ("Log ({})", "Protokoll ({})"),
+    ("Minimum level:", "Mindeststufe:"),
+    ("Copy", "Kopieren"),
+    ("Clear", "Leeren"),
+    ("{} entries", "{} Einträge"),
+    // Settings recovery
+    ("Settings could not be loaded", "Einstellungen konnten nicht geladen werden"),
+    ("The settings file is damaged: {}", "Die Einstellungsdatei ist beschädigt: {}"),
+    ("It was kept as {}", "Sie wurde als {} aufbewahrt"),
+    ("There are no backups, so the defaults are in use.", "Es gibt keine Sicherungen, daher gelten die Standardwerte."),
+    ("Restore a backup, or keep the defaults:", "Eine Sicherung wiederherstellen oder die Standardwerte behalten:"),
+    ("Restore", "Wiederherstellen"),
+    ("Keep defaults", "Standardwerte behalten"),
+    // Frame rate
+    ("Frame rate:", "Bildrate:"),
+    ("Cap while playing; 0 runs at the display's refresh rate", "Obergrenze während der Wiedergabe; 0 folgt der Bildwiederholrate"),
+    ("VSync (after restart)", "VSync (nach Neustart)"),
+    ("Slow down when idle", "Im Leerlauf verlangsamen"),
+    ("Redraw a few times per second when nothing is playing", "Nur einige Male pro Sekunde neu zeichnen, wenn nichts läuft"),
+    // Decimation
+    ("Thin out long sample windows", "Lange Sample-Fenster ausdünnen"),
+    ("Draw only the extremes of each run of samples when there are more than the display can show", "Nur die Extremwerte jedes Abschnitts zeichnen, wenn es mehr Samples gibt, als die Anzeige darstellen kann"),
+    // Buffer size
+    ("Buffer size:", "Puffergröße:"),
+    ("Samples kept for the display; the longest window you can show", "Für die Anzeige vorgehaltene Samples; das längste darstellbare Fenster"),
+    ("Samples shown:", "Angezeigte Samples:"),
+    // A/V sync
+    ("Sync display to audio output", "Anzeige mit Audioausgabe synchronisieren"),
+    ("When playing a file, hold samples back until they are heard", "Bei der Dateiwiedergabe Samples erst anzeigen, wenn sie zu hören sind"),
+    ("Display offset:", "Anzeigeversatz:"),
+    ("Added to the measured delay; negative values show samples earlier", "Wird zur gemessenen Verzögerung addiert; negative Werte zeigen Samples früher"),
+    // Frame pacing
+    ("Steady frame pacing", "Gleichmäßiger Bildtakt"),
+    ("During file playback, move the picture on by the same number of samples every frame, so figures don't jitter with decoding", "Bei der Dateiwiedergabe das Bild jedes Mal um gleich viele Samples weiterbewegen, damit Figuren nicht mit dem Dekodieren zittern"),
+    // File loading
+    ("Loading {} ({}%)", "Lade {} ({} %)"),
+    ("Loading {}", "Lade {}"),
+    // Adaptive quality
+    ("Reduce quality under load", "Qualität bei Last reduzieren"),
+    ("Draw fewer samples and a thinner trail while frames take longer than the frame rate allows, and restore them when there is room", "Weniger Samples und eine dünnere Spur zeichnen, solange Bilder länger brauchen, als die Bildrate erlaubt, und sie wiederherstellen, sobald Luft ist"),
+    ("Quality: {}%", "Qualität: {} %"),
+    // Analysis readout
+    ("Level: {} / {} dBFS", "Pegel: {} / {} dBFS"),
+    ("Correlation: {}", "Korrelation: {}"),
+    ("Loudness: {} LUFS", "Lautheit: {} LUFS"),
+    ("Pitch: {} Hz", "Tonhöhe: {} Hz"),
+    // Phase readout
+    ("Phase readout", "Phasenanzeige"),
+    ("Fit an ellipse to two sines of one frequency and show their phase difference and amplitude ratio", "Eine Ellipse an zwei Sinus gleicher Frequenz anpassen und ihre Phasendifferenz und ihr Amplitudenverhältnis anzeigen"),
+    ("Needs two sines of one frequency", "Braucht zwei Sinus gleicher Frequenz"),
+    ("Phase: {}°", "Phase: {}°"),
+    ("Amplitude ratio Y/X: {} ({} dB)", "Amplitudenverhältnis Y/X: {} ({} dB)"),
+    // Frequency ratio
+    ("Ratio: {}:{}", "Verhältnis: {}:{}"),
+    ("X: {} Hz, Y: {} Hz", "X: {} Hz, Y: {} Hz"),
+    ("● Locked", "● Eingerastet"),
+    ("Drifting {} Hz", "Driftet {} Hz"),
+    ("How fast the figure rolls through its shapes", "Wie schnell die Figur ihre Formen durchläuft"),
+    // Auto-centering
+    ("Auto-center", "Automatisch zentrieren"),
+    ("Keep the middle of the figure centered, following drifting DC or lopsided material, on top of the offsets below", "Die Mitte der Figur zentriert halten, auch bei driftendem Gleichanteil oder unsymmetrischem Material, zusätzlich zu den Offsets unten"),
+    // DC nulling
+    ("Null DC", "DC nullen"),
+    ("Set the offsets to cancel the mean of the last second of input", "Die Offsets so setzen, dass sie den Mittelwert der letzten Sekunde Eingang aufheben"),
+    // Silence
+    ("Silence", "Stille"),
+    ("When silent:", "Bei Stille:"),
+    ("Off", "Aus"),
+    ("Hold last figure", "Letzte Figur halten"),
+    ("Fade to black", "Ausblenden"),
+    ("Show demo", "Demo zeigen"),
+    ("Below:", "Unter:"),
+    ("For:", "Für:"),
+    ("Applies while an input is running but its peak level stays below the threshold", "Greift, solange ein Eingang läuft, sein Spitzenpegel aber unter der Schwelle bleibt"),
+    // Trigger
+    ("Trigger", "Trigger"),
+    ("Start the window at a trigger, so it starts at the same point of the figure every frame. Needs a buffer longer than the window.", "Das Fenster an einem Trigger beginnen, damit es jedes Bild am selben Punkt der Figur beginnt. Braucht einen Puffer, der länger als das Fenster ist."),
+    ("Signal (X)", "Signal (X)"),
+    ("External event", "Externes Ereignis"),
+    ("Hysteresis:", "Hysterese:"),
+    ("How far X has to fall below the level before the trigger re-arms; raise it if noise makes the picture jump", "Wie weit X unter den Pegel fallen muss, bevor der Trigger wieder scharf wird; erhöhen, wenn Rauschen das Bild springen lässt"),
+    ("Holdoff:", "Holdoff:"),
+    ("Ignore further triggers for this long after one, to lock onto the same edge of signals that cross the level several times per period", "Weitere Trigger so lange danach ignorieren, um bei Signalen, die den Pegel mehrmals pro Periode kreuzen, immer dieselbe Flanke zu treffen"),
+    ("Triggered", "Getriggert"),
+    ("No trigger, showing the newest samples", "Kein Trigger, zeige die neuesten Samples"),
+    ("MIDI note:", "MIDI-Note:"),
+    ("Note-on on any channel of the connected MIDI port", "Note-On auf beliebigem Kanal des verbundenen MIDI-Ports"),
+    ("OSC on port", "OSC auf Port"),
+    ("Address:", "Adresse:"),
+    ("Fire trigger", "Trigger auslösen"),
+    ("Fire Trigger", "Trigger auslösen"),
+    // Spectrum
+    ("Spectrum", "Spektrum"),
+    ("FFT size:", "FFT-Größe:"),
+    ("Overlap:", "Überlappung:"),
+    ("Averaging:", "Mittelung:"),
+    ("Frames in the exponential average; more gives a steadier noise floor but follows changes more slowly", "Frames im exponentiellen Mittel; mehr ergibt ein ruhigeres Grundrauschen, folgt Änderungen aber langsamer"),
+    ("Peak hold", "Spitzenwert halten"),
+    ("Hann", "Hann"),
+    ("Blackman-Harris", "Blackman-Harris"),
+    ("Flat-top", "Flat-Top"),
+    ("1/3 octave (RTA)", "1/3 Oktave (RTA)"),
+    ("Band level time weighting: fast 125 ms, slow 1 s", "Zeitbewertung der Bandpegel: schnell 125 ms, langsam 1 s"),
+    ("Fast", "Schnell"),
+    ("Slow", "Langsam"),
+    ("View:", "Ansicht:"),
+    ("Ballistics:", "Zeitbewertung:"),
+    ("3D waterfall", "3D-Wasserfall"),
+    ("Drag to turn", "Ziehen zum Drehen"),
+    ("Turn:", "Drehung:"),
+    ("Tilt:", "Neigung:"),
+    ("Spin:", "Rotation:"),
+    ("Keep the view turning, for projection", "Die Ansicht ständig drehen, für Projektionen"),
+    ("Modulation", "Modulation"),
+    ("Audio-reactive", "Audioreaktiv"),
+    (
+        "Let the level of the music push the display around; the sliders keep their own values",
+        "Der Pegel der Musik bewegt die Anzeige; die Regler behalten ihre eigenen Werte",
+    ),
+    ("Full range", "Gesamter Bereich"),
+    ("Lows (< 200 Hz)", "Tiefen (< 200 Hz)"),
+    ("Mids", "Mitten"),
+    ("Highs (> 2 kHz)", "Höhen (> 2 kHz)"),
+    ("Hue", "Farbton"),
+    ("Depth:", "Tiefe:"),
+    ("Attack:", "Anstieg:"),
+    ("Release:", "Abfall:"),
+    ("Reactivity", "Reaktivität"),
+    ("Beat-synced actions", "Aktionen im Takt"),
+    ("Tempo:", "Tempo:"),
+    ("Beats per bar:", "Schläge pro Takt:"),
+    ("Downbeat", "Taktanfang"),
+    ("Make the latest beat the first of the bar", "Den letzten Schlag zum ersten des Takts machen"),
+    ("Pulse:", "Puls:"),
+    ("Every", "Alle"),
+    ("Kicks", "Kicks"),
+    ("Beats", "Schläge"),
+    ("Bars", "Takte"),
+    ("Next theme", "Nächstes Farbschema"),
+    ("Pulse intensity", "Intensität pulsieren"),
+    ("Artwork", "Cover"),
+    ("Cover art:", "Cover:"),
+    ("Apply", "Anwenden"),
+    ("Color the scope after the cover art", "Das Oszilloskop in den Farben des Covers einfärben"),
+    ("Grid of scopes", "Raster aus Oszilloskopen"),
+    ("For sources with more than two channels, draw a scope for each pair of channels instead of just the first two", "Bei Quellen mit mehr als zwei Kanälen für jedes Kanalpaar ein eigenes Oszilloskop zeichnen statt nur für die ersten beiden"),
+    ("Panes:", "Felder:"),
+    ("Channel pairs", "Kanalpaare"),
+    ("Against a reference", "Gegen einen Referenzkanal"),
+    ("Reference channel:", "Referenzkanal:"),
+    ("Source channels: {}", "Kanäle der Quelle: {}"),
+    ("{} vs {}", "{} gegen {}"),
+    ("Export folder:", "Exportordner:"),
+    ("Choose…", "Auswählen…"),
+    ("Pictures folder", "Bilderordner"),
+    ("File name:", "Dateiname:"),
+    ("Slashes make subfolders. Tokens:", "Schrägstriche legen Unterordner an. Platzhalter:"),
+    ("Next: {}", "Nächste: {}"),
+    ("Track title, or the input's name", "Titel des Stücks oder Name des Eingangs"),
+    ("Preset last loaded or saved", "Zuletzt geladenes oder gespeichertes Preset"),
+    ("Playback position in the file", "Wiedergabeposition in der Datei"),
+    ("Date and time", "Datum und Uhrzeit"),
+    ("Date", "Datum"),
+    ("Time of day", "Uhrzeit"),
+    ("Image sequence", "Bildsequenz"),
+    ("to:", "bis:"),
+    ("Whole file", "Ganze Datei"),
+    ("Export PNG sequence", "PNG-Sequenz exportieren"),
+    ("Load a file to render from first", "Zuerst eine Datei laden, aus der gerendert wird"),
+    ("{} frames", "{} Bilder"),
+    ("Wrote {} frames to {}", "{} Bilder nach {} geschrieben"),
+    ("Render failed: {}", "Rendern fehlgeschlagen: {}"),
+    ("Long exposure", "Langzeitbelichtung"),
+    ("Add up the beam over the whole range into one image, like a photo of the screen with the shutter held open", "Den Strahl über den ganzen Bereich in einem Bild aufsummieren, wie ein Foto des Bildschirms bei offenem Verschluss"),
+    ("Format:", "Format:"),
+    ("EXR (HDR)", "EXR (HDR)"),
+    ("16-bit PNG", "16-Bit-PNG"),
+    ("Expose", "Belichten"),
+    ("Recording", "Aufnahme"),
+    ("Record what the scope draws to MP4 with ffmpeg. Frames follow the audio, so nothing is recorded while it's paused.", "Aufzeichnen, was das Oszilloskop zeichnet, als MP4 mit ffmpeg. Die Bilder folgen dem Ton, pausiert wird also nichts aufgenommen."),
+    ("Include audio", "Ton einbinden"),
+    ("ffmpeg:", "ffmpeg:"),
+    ("⏹ Stop recording", "⏹ Aufnahme beenden"),
+    ("⏺ Record", "⏺ Aufnehmen"),
+    ("Wait for the render to finish first", "Erst das Rendern abwarten"),
+    ("Recording failed: {}", "Aufnahme fehlgeschlagen: {}"),
+    ("Record ({})", "Aufnehmen ({})"),
+    ("Record", "Aufnehmen"),
+    ("Track info overlay", "Titel-Einblendung"),
+    ("Timecode, title and tempo over the scope, also in recordings and shared memory frames", "Timecode, Titel und Tempo über dem Oszilloskop, auch in Aufnahmen und Shared-Memory-Bildern"),
+    ("Timecode", "Timecode"),
+    ("Title", "Titel"),
+    ("BPM", "BPM"),
+    ("Corner:", "Ecke:"),
+    ("Top left", "Oben links"),
+    ("Top right", "Oben rechts"),
+    ("Bottom left", "Unten links"),
+    ("Bottom right", "Unten rechts"),
+    ("Text size", "Textgröße"),
+    ("Stroke weight", "Strichstärke"),
+    ("Opacity", "Deckkraft"),
+    ("Branding", "Branding"),
+    ("Your own text and logo over the scope, also in screenshots, recordings and shared memory frames", "Eigener Text und Logo über dem Oszilloskop, auch in Screenshots, Aufnahmen und Shared-Memory-Bildern"),
+    ("Logo:", "Logo:"),
+    ("Remove", "Entfernen"),
+    ("Scale", "Größe"),
+    ("Clean feed", "Sauberes Signal"),
+    ("Only the scope, without the overlay and branding shown on screen", "Nur das Oszilloskop, ohne die Einblendungen und das Branding vom Bildschirm"),
+    ("OBS remote", "OBS-Fernsteuerung"),
+    ("Connect to OBS (Tools → WebSocket Server Settings) to record in step with playback", "Mit OBS verbinden (Werkzeuge → WebSocket-Server-Einstellungen), um im Takt mit der Wiedergabe aufzunehmen"),
+    ("Host:", "Host:"),
+    ("Password:", "Passwort:"),
+    ("Record while playing", "Während der Wiedergabe aufnehmen"),
+    ("Start OBS recording when the input starts and stop it when it stops", "OBS-Aufnahme starten, wenn die Eingabe startet, und beenden, wenn sie stoppt"),
+    ("Switch scene with preset", "Szene mit Preset wechseln"),
+    ("Switch OBS to the scene named like the preset that's loaded", "OBS zur Szene wechseln, die wie das geladene Preset heißt"),
+    ("Scenes: {}", "Szenen: {}"),
+    ("Wireframe", "Drahtgitter"),
+    ("Lissajous sweep", "Lissajous-Durchlauf"),
+    ("Attract sequence", "Attract-Sequenz"),
+    ("Attract sequence after", "Attract-Sequenz nach"),
+    ("Take over with wireframes, Lissajous sweeps and a clock once there's been no signal and no mouse or keyboard input for a while. Ends as soon as either comes back.", "Zeigt Drahtgitter, Lissajous-Durchläufe und eine Uhr, sobald eine Weile weder Signal noch Maus- oder Tastatureingaben kamen. Endet, sobald eins davon zurückkehrt."),
+    ("Attract sequence running", "Attract-Sequenz läuft"),
+    ("System Tray", "Infobereich"),
+    ("Tray icon", "Symbol im Infobereich"),
+    ("Show, hide, capture and recall presets from the tray. Capture and outputs keep running while the window is hidden.", "Fenster zeigen und verbergen, Aufnahme steuern und Presets laden vom Infobereich aus. Aufnahme und Ausgaben laufen weiter, während das Fenster verborgen ist."),
+    ("Close to tray", "Beim Schließen in den Infobereich"),
+    ("Closing the window hides it; quit from the tray menu", "Schließen verbirgt das Fenster; beendet wird über das Menü im Infobereich"),
+    ("Hide window", "Fenster verbergen"),
+    ("Show window", "Fenster zeigen"),
+    ("Start capture", "Aufnahme starten"),
+    ("Stop capture", "Aufnahme stoppen"),
+    ("Quit", "Beenden"),
+    ("Next Preset", "Nächstes Preset"),
+    ("Previous Preset", "Vorheriges Preset"),
+    ("Global Hotkeys", "Globale Tastenkürzel"),
+    ("Global hotkeys", "Globale Tastenkürzel"),
+    (
+        "Control playback, freeze, screenshots and presets while another app, such as a DAW, has focus",
+        "Wiedergabe, Einfrieren, Bildschirmfotos und Presets steuern, während eine andere App wie eine DAW den Fokus hat",
+    ),
+    ("Projector", "Projektor"),
+    ("Send to display", "Auf Bildschirm ausgeben"),
+    (
+        "Show the scope fullscreen on another display, such as a projector. Escape on that display closes it.",
+        "Das Oszilloskop im Vollbild auf einem anderen Bildschirm zeigen, etwa einem Projektor. Escape auf diesem Bildschirm schließt es.",
+    ),
+    ("Display:", "Bildschirm:"),
+    ("Any", "Beliebig"),
+    ("Refresh displays", "Bildschirme neu suchen"),
+    ("Match display", "Wie Bildschirm"),
+    (
+        "Render at the display's own resolution",
+        "In der Auflösung des Bildschirms rendern",
+    ),
+    ("Rotation:", "Drehung:"),
+    ("90° clockwise", "90° im Uhrzeigersinn"),
+    ("180°", "180°"),
+    ("90° counter-clockwise", "90° gegen den Uhrzeigersinn"),
+    ("Levels are set in dBFS above", "Die Pegel werden oben in dBFS eingestellt"),
+    (
+        "Output: {} dBFS RMS, peaks up to {} dBFS",
+        "Ausgang: {} dBFS RMS, Spitzen bis {} dBFS",
+    ),
+    ("'{}' exists. Replace it?", "'{}' gibt es schon. Ersetzen?"),
+    ("Replace", "Ersetzen"),
+    (
+        "Exported to {}, without the unreadable presets {}",
+        "Nach {} exportiert, ohne die unlesbaren Presets {}",
+    ),
+    (
+        "The file has presets you already have: {}",
+        "Die Datei enthält Presets, die es schon gibt: {}",
+    ),
+    ("Replace mine", "Meine ersetzen"),
+    ("Keep mine", "Meine behalten"),
+    ("{} frames dropped while the encoder caught up", "{} Bilder verworfen, während der Encoder aufholte"),
+    ("The tray icon is only available on Linux for now", "Das Symbol im Infobereich gibt es vorerst nur unter Linux"),
+    ("Auto", "Automatisch"),
+    ("Sample {}", "Sample {}"),
+];
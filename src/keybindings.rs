@@ -0,0 +1,447 @@
+//! Keyboard shortcuts
+//!
+//! Every keyboard-triggerable action is listed in `Action`, and the `Keymap`
+//! maps each one to at most one shortcut. The keymap is part of the saved
+//! settings, and shortcuts are stored as readable text ("Ctrl+Shift+S") so the
+//! settings file can be edited by hand. A binding that doesn't parse, say a
+//! typo made there, is skipped on load rather than failing the whole file.
+
+use std::fmt;
+
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::i18n::{tr, trf};
+
+/// Something the user can trigger from the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    PlayPause,
+    Stop,
+    LiveMode,
+    FileMode,
+    GeneratorMode,
+    Freeze,
+    Screenshot,
+    Record,
+    NextTheme,
+    PreviousTheme,
+    ClearPersistence,
+    ToggleSettings,
+    PerfOverlay,
+    Help,
+    LogPanel,
+    PresetMorph,
+    AbCompare,
+    CommandPalette,
+    FireTrigger,
+    NextPreset,
+    PreviousPreset,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PlayPause => "Play / Pause",
+            Self::Stop => "Stop",
+            Self::LiveMode => "Live Input",
+            Self::FileMode => "File Playback",
+            Self::GeneratorMode => "Generator",
+            Self::Freeze => "Freeze Display",
+            Self::Screenshot => "Screenshot",
+            Self::Record => "Record",
+            Self::NextTheme => "Next Theme",
+            Self::PreviousTheme => "Previous Theme",
+            Self::ClearPersistence => "Clear Persistence",
+            Self::ToggleSettings => "Settings Panel",
+            Self::PerfOverlay => "Performance Overlay",
+            Self::Help => "Help",
+            Self::LogPanel => "Log Panel",
+            Self::PresetMorph => "Preset Morph",
+            Self::AbCompare => "A/B Compare",
+            Self::CommandPalette => "Command Palette",
+            Self::FireTrigger => "Fire Trigger",
+            Self::NextPreset => "Next Preset",
+            Self::PreviousPreset => "Previous Preset",
+        }
+    }
+
+    pub fn all() -> &'static [Action] {
+        &[
+            Self::PlayPause,
+            Self::Stop,
+            Self::LiveMode,
+            Self::FileMode,
+            Self::GeneratorMode,
+            Self::Freeze,
+            Self::Screenshot,
+            Self::Record,
+            Self::NextTheme,
+            Self::PreviousTheme,
+            Self::ClearPersistence,
+            Self::ToggleSettings,
+            Self::PerfOverlay,
+            Self::Help,
+            Self::LogPanel,
+            Self::PresetMorph,
+            Self::AbCompare,
+            Self::CommandPalette,
+            Self::FireTrigger,
+            Self::NextPreset,
+            Self::PreviousPreset,
+        ]
+    }
+
+    /// Shortcut used when the user hasn't chosen one
+    pub fn default_shortcut(&self) -> Option<Shortcut> {
+        let (modifiers, key) = match self {
+            Self::PlayPause => (Modifiers::NONE, Key::Space),
+            Self::Stop => (Modifiers::NONE, Key::X),
+            Self::LiveMode => (Modifiers::NONE, Key::Num1),
+            Self::FileMode => (Modifiers::NONE, Key::Num2),
+            Self::GeneratorMode => (Modifiers::NONE, Key::Num3),
+            Self::Freeze => (Modifiers::NONE, Key::F),
+            Self::Screenshot => (Modifiers::NONE, Key::P),
+            Self::Record => (Modifiers::NONE, Key::R),
+            Self::NextTheme => (Modifiers::NONE, Key::T),
+            Self::PreviousTheme => (Modifiers::SHIFT, Key::T),
+            Self::ClearPersistence => (Modifiers::NONE, Key::Delete),
+            Self::ToggleSettings => (Modifiers::NONE, Key::S),
+            Self::PerfOverlay => (Modifiers::NONE, Key::F3),
+            Self::Help => (Modifiers::NONE, Key::Questionmark),
+            Self::LogPanel => (Modifiers::NONE, Key::L),
+            Self::PresetMorph => (Modifiers::NONE, Key::M),
+            Self::AbCompare => (Modifiers::NONE, Key::B),
+            Self::CommandPalette => (Modifiers::COMMAND, Key::P),
+            Self::FireTrigger => (Modifiers::NONE, Key::Enter),
+            Self::NextPreset => (Modifiers::NONE, Key::PageDown),
+            Self::PreviousPreset => (Modifiers::NONE, Key::PageUp),
+        };
+        Some(Shortcut::new(modifiers, key))
+    }
+}
+
+/// A key plus modifiers, stored as text like "Ctrl+Shift+S"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Shortcut {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Shortcut {
+    /// Shortcut with modifiers reduced to Ctrl (or Cmd), Alt and Shift
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        let mut canonical = Modifiers::NONE;
+        if modifiers.ctrl || modifiers.command || modifiers.mac_cmd {
+            canonical = canonical | Modifiers::COMMAND;
+        }
+        if modifiers.alt {
+            canonical = canonical | Modifiers::ALT;
+        }
+        if modifiers.shift {
+            canonical = canonical | Modifiers::SHIFT;
+        }
+        Self {
+            modifiers: canonical,
+            key,
+        }
+    }
+
+    /// Number of modifiers held, used to match the most specific shortcut first
+    fn modifier_count(&self) -> usize {
+        [
+            self.modifiers.command,
+            self.modifiers.shift,
+            self.modifiers.alt,
+        ]
+        .iter()
+        .filter(|&&held| held)
+        .count()
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.command {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key.name())
+    }
+}
+
+impl TryFrom<String> for Shortcut {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let mut modifiers = Modifiers::NONE;
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key_name = parts.pop().unwrap_or_default();
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => modifiers = modifiers | Modifiers::COMMAND,
+                "alt" | "option" => modifiers = modifiers | Modifiers::ALT,
+                "shift" => modifiers = modifiers | Modifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier '{}'", part)),
+            }
+        }
+        let key = Key::from_name(key_name).ok_or_else(|| format!("Unknown key '{}'", key_name))?;
+        Ok(Self::new(modifiers, key))
+    }
+}
+
+impl From<Shortcut> for String {
+    fn from(shortcut: Shortcut) -> Self {
+        shortcut.to_string()
+    }
+}
+
+/// The shortcut bound to one action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub action: Action,
+    pub shortcut: Option<Shortcut>,
+}
+
+/// A saved binding, or whatever stands in its place if it can't be read
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedBinding {
+    Binding(KeyBinding),
+    Unreadable(serde::de::IgnoredAny),
+}
+
+/// Read a list of bindings, skipping the ones with an unknown action or
+/// shortcut. Use with `#[serde(deserialize_with)]`.
+pub fn deserialize_bindings<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<KeyBinding>, D::Error> {
+    let saved = Vec::<SavedBinding>::deserialize(deserializer)?;
+    let skipped = saved
+        .iter()
+        .filter(|binding| matches!(binding, SavedBinding::Unreadable(_)))
+        .count();
+    if skipped > 0 {
+        log::warn!("Skipped {} unreadable key bindings", skipped);
+    }
+    Ok(saved
+        .into_iter()
+        .filter_map(|binding| match binding {
+            SavedBinding::Binding(binding) => Some(binding),
+            SavedBinding::Unreadable(_) => None,
+        })
+        .collect())
+}
+
+/// All key bindings, one per action
+#[derive(Debug, Clone, Serialize)]
+#[serde(into = "Vec<KeyBinding>")]
+pub struct Keymap {
+    bindings: Vec<KeyBinding>,
+}
+
+impl<'de> Deserialize<'de> for Keymap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_bindings(deserializer).map(Self::from)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::all()
+                .iter()
+                .map(|&action| KeyBinding {
+                    action,
+                    shortcut: action.default_shortcut(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Vec<KeyBinding>> for Keymap {
+    /// Saved bindings win; actions added since the file was written get
+    /// their default shortcut
+    fn from(saved: Vec<KeyBinding>) -> Self {
+        let mut keymap = Self::default();
+        for binding in saved {
+            keymap.set(binding.action, binding.shortcut);
+        }
+        keymap
+    }
+}
+
+impl From<Keymap> for Vec<KeyBinding> {
+    fn from(keymap: Keymap) -> Self {
+        keymap.bindings
+    }
+}
+
+impl Keymap {
+    pub fn bindings(&self) -> &[KeyBinding] {
+        &self.bindings
+    }
+
+    pub fn shortcut(&self, action: Action) -> Option<Shortcut> {
+        self.bindings
+            .iter()
+            .find(|b| b.action == action)
+            .and_then(|b| b.shortcut)
+    }
+
+    /// Bind `action` to `shortcut`, unbinding any other action that used it
+    pub fn set(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        rebind(&mut self.bindings, action, shortcut);
+    }
+
+    /// Shortcut label for tooltips, e.g. "Shortcut: Space"
+    pub fn hint(&self, action: Action) -> String {
+        match self.shortcut(action) {
+            Some(shortcut) => trf("Shortcut: {}", &[&shortcut]),
+            None => tr("No shortcut").to_string(),
+        }
+    }
+
+    /// Consume this frame's key presses and return the actions they trigger
+    pub fn triggered(&self, ctx: &egui::Context) -> Vec<Action> {
+        // egui ignores extra Shift/Alt when matching, so try "Shift+T" before "T"
+        let mut bindings: Vec<(Action, Shortcut)> = self
+            .bindings
+            .iter()
+            .filter_map(|b| b.shortcut.map(|s| (b.action, s)))
+            .collect();
+        bindings.sort_by_key(|(_, s)| std::cmp::Reverse(s.modifier_count()));
+
+        ctx.input_mut(|input| {
+            bindings
+                .into_iter()
+                .filter(|(_, s)| input.consume_shortcut(&KeyboardShortcut::new(s.modifiers, s.key)))
+                .map(|(action, _)| action)
+                .collect()
+        })
+    }
+}
+
+/// Bind `action` to `shortcut` in `bindings`, unbinding any other action
+/// that used it
+pub fn rebind(bindings: &mut [KeyBinding], action: Action, shortcut: Option<Shortcut>) {
+    for binding in bindings {
+        if binding.action == action {
+            binding.shortcut = shortcut;
+        } else if shortcut.is_some() && binding.shortcut == shortcut {
+            binding.shortcut = None;
+        }
+    }
+}
+
+/// A click in a `bindings_grid`
+pub enum GridEdit {
+    Rebind(Action),
+    Clear(Action),
+}
+
+/// One row per binding with a button to rebind it and one to clear it;
+/// `rebinding` is the action waiting for a key press
+pub fn bindings_grid(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    bindings: &[KeyBinding],
+    rebinding: Option<Action>,
+) -> Option<GridEdit> {
+    let mut edit = None;
+    egui::Grid::new(id_salt)
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            for binding in bindings {
+                ui.label(tr(binding.action.name()));
+                let text = if rebinding == Some(binding.action) {
+                    tr("Press a key…").to_string()
+                } else {
+                    binding
+                        .shortcut
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| "—".to_string())
+                };
+                let response = ui.button(text).on_hover_text(tr("Click to rebind"));
+                if response.clicked() {
+                    // Keep Space/Enter from clicking the button again
+                    response.surrender_focus();
+                    edit = Some(GridEdit::Rebind(binding.action));
+                }
+                if ui
+                    .small_button("X")
+                    .on_hover_text(tr("Remove shortcut"))
+                    .clicked()
+                {
+                    edit = Some(GridEdit::Clear(binding.action));
+                }
+                ui.end_row();
+            }
+        });
+    edit
+}
+
+/// The first key pressed this frame, for rebinding
+pub fn pressed_shortcut(ctx: &egui::Context) -> Option<Shortcut> {
+    ctx.input(|input| {
+        input.events.iter().find_map(|event| match event {
+            egui::Event::Key {
+                key,
+                pressed: true,
+                modifiers,
+                ..
+            } => Some(Shortcut::new(*modifiers, *key)),
+            _ => None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcut_text_round_trip() {
+        for text in ["Space", "Shift+T", "Ctrl+Alt+Shift+S", "F12", "Ctrl+Plus"] {
+            let shortcut = Shortcut::try_from(text.to_string()).unwrap();
+            assert_eq!(shortcut.to_string(), text);
+        }
+        assert!(Shortcut::try_from("Hyper+A".to_string()).is_err());
+        assert!(Shortcut::try_from("Ctrl+NoSuchKey".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_rebinding_steals_shortcut() {
+        let mut keymap = Keymap::default();
+        let space = Action::PlayPause.default_shortcut();
+        keymap.set(Action::Freeze, space);
+        assert_eq!(keymap.shortcut(Action::Freeze), space);
+        assert_eq!(keymap.shortcut(Action::PlayPause), None);
+    }
+
+    #[test]
+    fn test_bad_bindings_are_skipped() {
+        let json = r#"[
+            {"action": "Freeze", "shortcut": "Ctrl+NoSuchKey"},
+            {"action": "NoSuchAction", "shortcut": "F"},
+            {"action": "Screenshot", "shortcut": "F9"}
+        ]"#;
+        let keymap: Keymap = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            keymap.shortcut(Action::Screenshot),
+            Some(Shortcut::new(Modifiers::NONE, Key::F9))
+        );
+        assert_eq!(
+            keymap.shortcut(Action::Freeze),
+            Action::Freeze.default_shortcut()
+        );
+    }
+}
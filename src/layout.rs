@@ -0,0 +1,306 @@
+//! Panel layout and workspaces
+//!
+//! Every panel around the scope can be docked to any edge of the window or
+//! float as its own window. A `Layout` is the dock, order and size of each
+//! panel; panels listed first sit outermost. Layouts are kept per named
+//! workspace so a measurement setup and a performance setup can coexist.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A panel that can be docked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Panel {
+    Settings,
+    Playback,
+    Calibration,
+    Response,
+    Spectrum,
+    Reactivity,
+    Log,
+}
+
+impl Panel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Settings => "Settings",
+            Self::Playback => "Playback",
+            Self::Calibration => "Calibration",
+            Self::Response => "Frequency Response",
+            Self::Spectrum => "Spectrum",
+            Self::Reactivity => "Reactivity",
+            Self::Log => "Log",
+        }
+    }
+
+    pub fn all() -> &'static [Panel] {
+        &[
+            Self::Playback,
+            Self::Settings,
+            Self::Calibration,
+            Self::Response,
+            Self::Spectrum,
+            Self::Reactivity,
+            Self::Log,
+        ]
+    }
+
+    /// Where the panel goes in a fresh layout
+    fn default_dock(&self) -> Dock {
+        match self {
+            Self::Settings => Dock::Right,
+            Self::Playback | Self::Log => Dock::Bottom,
+            Self::Calibration | Self::Response | Self::Spectrum | Self::Reactivity => {
+                Dock::Floating
+            }
+        }
+    }
+
+    /// Width when docked to a side or floating, until the user resizes it
+    pub fn default_width(&self) -> f32 {
+        match self {
+            Self::Settings => 280.0,
+            Self::Calibration | Self::Reactivity => 320.0,
+            Self::Playback | Self::Response | Self::Spectrum | Self::Log => 480.0,
+        }
+    }
+
+    /// Whether the panel's contents go in a scroll area when docked. The
+    /// log scrolls itself so it can stick to the newest line.
+    pub fn scrolls(&self) -> bool {
+        *self != Self::Log
+    }
+
+    fn id(&self) -> &'static str {
+        match self {
+            Self::Settings => "settings_panel",
+            Self::Playback => "playback_panel",
+            Self::Calibration => "calibration_panel",
+            Self::Response => "response_panel",
+            Self::Spectrum => "spectrum_panel",
+            Self::Reactivity => "reactivity_panel",
+            Self::Log => "log_panel",
+        }
+    }
+}
+
+/// Where a panel is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Dock {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Floating,
+}
+
+impl Dock {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Left => "Left",
+            Self::Right => "Right",
+            Self::Top => "Top",
+            Self::Bottom => "Bottom",
+            Self::Floating => "Floating",
+        }
+    }
+
+    pub fn all() -> &'static [Dock] {
+        &[
+            Self::Left,
+            Self::Right,
+            Self::Top,
+            Self::Bottom,
+            Self::Floating,
+        ]
+    }
+}
+
+/// One panel's place in a layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelPlacement {
+    pub panel: Panel,
+    pub dock: Dock,
+    /// Width when docked left/right or floating, height when docked top/bottom
+    pub size: Option<f32>,
+}
+
+impl PanelPlacement {
+    /// egui id for the panel; it changes with the dock and workspace so a
+    /// rearranged panel starts at its saved size instead of egui's memory of
+    /// the old one
+    pub fn id(&self, workspace: &str) -> String {
+        format!("{}_{:?}_{}", self.panel.id(), self.dock, workspace)
+    }
+}
+
+/// The placement of every panel, outermost first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "Vec<PanelPlacement>", into = "Vec<PanelPlacement>")]
+pub struct Layout {
+    placements: Vec<PanelPlacement>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            placements: Panel::all()
+                .iter()
+                .map(|&panel| PanelPlacement {
+                    panel,
+                    dock: panel.default_dock(),
+                    size: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Vec<PanelPlacement>> for Layout {
+    /// Saved placements keep their order; panels added since the file was
+    /// written go at the end in their default place
+    fn from(saved: Vec<PanelPlacement>) -> Self {
+        let mut placements: Vec<PanelPlacement> = Vec::new();
+        for placement in saved {
+            if !placements.iter().any(|p| p.panel == placement.panel) {
+                placements.push(placement);
+            }
+        }
+        for placement in Self::default().placements {
+            if !placements.iter().any(|p| p.panel == placement.panel) {
+                placements.push(placement);
+            }
+        }
+        Self { placements }
+    }
+}
+
+impl From<Layout> for Vec<PanelPlacement> {
+    fn from(layout: Layout) -> Self {
+        layout.placements
+    }
+}
+
+impl Layout {
+    pub fn placements(&self) -> &[PanelPlacement] {
+        &self.placements
+    }
+
+    pub fn placement_mut(&mut self, panel: Panel) -> Option<&mut PanelPlacement> {
+        self.placements.iter_mut().find(|p| p.panel == panel)
+    }
+
+    /// Move a panel one step outward (towards the window edge)
+    pub fn move_outward(&mut self, panel: Panel) {
+        if let Some(i) = self.placements.iter().position(|p| p.panel == panel) {
+            if i > 0 {
+                self.placements.swap(i, i - 1);
+            }
+        }
+    }
+
+    pub fn set_dock(&mut self, panel: Panel, dock: Dock) {
+        if let Some(placement) = self.placement_mut(panel) {
+            if placement.dock != dock {
+                placement.dock = dock;
+                // A width makes no sense as a height and vice versa
+                placement.size = None;
+            }
+        }
+    }
+}
+
+/// Named layouts, one of them active
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workspaces {
+    pub layouts: BTreeMap<String, Layout>,
+    pub active: String,
+}
+
+impl Default for Workspaces {
+    fn default() -> Self {
+        let active = "Default".to_string();
+        Self {
+            layouts: BTreeMap::from([(active.clone(), Layout::default())]),
+            active,
+        }
+    }
+}
+
+impl Workspaces {
+    /// The active workspace's layout
+    pub fn layout(&self) -> Layout {
+        self.layouts.get(&self.active).cloned().unwrap_or_default()
+    }
+
+    pub fn layout_mut(&mut self) -> &mut Layout {
+        self.layouts.entry(self.active.clone()).or_default()
+    }
+
+    /// Copy the active layout to `name` (replacing any existing one) and switch to it
+    pub fn save_as(&mut self, name: &str) {
+        let layout = self.layout();
+        self.layouts.insert(name.to_string(), layout);
+        self.active = name.to_string();
+    }
+
+    /// Remove the active workspace, switching to another. The last one stays.
+    pub fn remove_active(&mut self) {
+        if self.layouts.len() > 1 {
+            self.layouts.remove(&self.active);
+            if let Some(name) = self.layouts.keys().next() {
+                self.active = name.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_layout_keeps_order_and_adds_new_panels() {
+        let saved = vec![PanelPlacement {
+            panel: Panel::Response,
+            dock: Dock::Left,
+            size: Some(300.0),
+        }];
+        let layout = Layout::from(saved);
+        assert_eq!(layout.placements().len(), Panel::all().len());
+        assert_eq!(layout.placements()[0].panel, Panel::Response);
+        assert_eq!(layout.placements()[0].dock, Dock::Left);
+
+        let mut layout = layout;
+        layout.move_outward(Panel::Playback);
+        assert_eq!(layout.placements()[0].panel, Panel::Playback);
+    }
+
+    #[test]
+    fn test_workspaces() {
+        let mut workspaces = Workspaces::default();
+        workspaces
+            .layout_mut()
+            .set_dock(Panel::Settings, Dock::Left);
+        workspaces.save_as("Measure");
+        workspaces
+            .layout_mut()
+            .set_dock(Panel::Settings, Dock::Floating);
+
+        assert_eq!(
+            workspaces.layouts["Default"].placements()[1].dock,
+            Dock::Left
+        );
+        assert_eq!(
+            workspaces.layouts["Measure"].placements()[1].dock,
+            Dock::Floating
+        );
+
+        workspaces.remove_active();
+        assert_eq!(workspaces.active, "Default");
+        workspaces.remove_active();
+        assert_eq!(workspaces.layouts.len(), 1);
+    }
+}
@@ -0,0 +1,11 @@
+//! scope-rs library
+//!
+//! The signal path, from sample sources through the shared buffer and
+//! analysis to rendering, without the UI. The application builds on it, and
+//! the benchmarks and integration tests drive it directly.
+
+pub mod analysis;
+pub mod audio;
+pub mod generator;
+pub mod harness;
+pub mod render;
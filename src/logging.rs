@@ -0,0 +1,99 @@
+//! Logging
+//!
+//! Log records go to env_logger as before (controlled by `RUST_LOG`) and are
+//! also kept in a ring buffer so the log panel can show them. The buffer
+//! always records Info and above, even when env_logger prints only errors,
+//! so device and playback problems can be reported without a terminal.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Number of records kept; older ones are dropped
+const CAPACITY: usize = 2000;
+
+/// One captured log record
+#[derive(Clone, Debug)]
+pub struct LogEntry {
+    pub time: String,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:<5} {}: {}",
+            self.time, self.level, self.target, self.message
+        )
+    }
+}
+
+static ENTRIES: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+struct Logger {
+    terminal: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info || self.terminal.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.terminal.matches(record) {
+            self.terminal.log(record);
+        }
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let entry = LogEntry {
+            time: jiff::Zoned::now().strftime("%H:%M:%S%.3f").to_string(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        if let Ok(mut entries) = ENTRIES.lock() {
+            if entries.len() == CAPACITY {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+    }
+
+    fn flush(&self) {
+        self.terminal.flush();
+    }
+}
+
+/// Install the logger. Call once, first thing in `main`.
+pub fn init() {
+    let terminal = env_logger::Builder::from_default_env().build();
+    let max_level = terminal.filter().max(LevelFilter::Info);
+    if log::set_boxed_logger(Box::new(Logger { terminal })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Captured records at `level` or more severe, oldest first
+pub fn entries(level: LevelFilter) -> Vec<LogEntry> {
+    ENTRIES
+        .lock()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|e| e.level <= level)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn clear() {
+    if let Ok(mut entries) = ENTRIES.lock() {
+        entries.clear();
+    }
+}
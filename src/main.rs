@@ -13,95 +13,4726 @@
 //! - Waveform overview display
 
 use eframe::egui;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-mod audio;
+mod grid;
+mod hotkeys;
+mod i18n;
+mod keybindings;
+mod layout;
+mod logging;
 mod midi;
-mod render;
+mod modulation;
+mod obs;
+mod osc;
+mod output;
+mod palette;
+mod perf;
+mod presets;
+mod projector;
+mod quality;
+mod reactivity;
 mod settings;
+mod tray;
 
-use audio::{AudioFilePlayer, AudioInput, PlaybackState, SampleBuffer};
-use render::{ColorTheme, DisplayMode, Oscilloscope};
+use scope_rs::{analysis, audio, generator, render};
+
+use audio::{
+    AudioFilePlayer, AudioInput, ExternalTrigger, FramePacer, PlaybackState, SampleBuffer,
+    SampleConsumer, Trigger, TriggerSource, XYSample,
+};
+use i18n::{tr, trf};
+use keybindings::{Action, GridEdit};
+use layout::{Dock, Panel};
+use render::{ColorTheme, DisplayMode, FrequencyPlot, Oscilloscope, OscilloscopeSettings};
 
 /// Input source mode
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
 enum InputMode {
     #[default]
     Live,
     File,
+    Generator,
+}
+
+/// How the spectrum panel shows the spectrum
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+enum SpectrumView {
+    /// Level over frequency, per channel
+    #[default]
+    Lines,
+    /// Third-octave bands
+    ThirdOctave,
+    /// History of the spectrum in 3D
+    Waterfall,
+}
+
+impl SpectrumView {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Lines => "Spectrum",
+            Self::ThirdOctave => "1/3 octave (RTA)",
+            Self::Waterfall => "3D waterfall",
+        }
+    }
+
+    fn all() -> &'static [SpectrumView] {
+        &[Self::Lines, Self::ThirdOctave, Self::Waterfall]
+    }
+}
+
+/// What a measurement took over to play its test signal and record the
+/// input, given back when it ends
+struct Borrowed {
+    audio_output: bool,
+    tap: Option<ringbuf::HeapProd<XYSample>>,
 }
 
-const BUFFER_SIZE: usize = 2048;
+/// egui's default body text size
+const DEFAULT_FONT_SIZE: f32 = 12.5;
+
+/// What a clean feed leaves out
+const CLEAN_FEED_HINT: &str = "Only the scope, without the overlay and branding shown on screen";
 
 fn main() -> eframe::Result<()> {
-    env_logger::init();
+    logging::init();
     log::info!("Starting scope-rs");
 
+    // Loaded before the window opens so it can set the window geometry
+    let (mut settings, recovery) = settings::AppSettings::load();
+    if std::env::args().any(|arg| arg == "--fresh") {
+        log::info!("Starting fresh, ignoring the saved session");
+        settings.forget_session();
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([800.0, 600.0])
-            .with_title("scope-rs"),
+        viewport: settings.viewport(),
+        vsync: settings.vsync,
         ..Default::default()
     };
 
-    eframe::run_native(
-        "scope-rs",
-        options,
-        Box::new(|cc| Ok(Box::new(ScopeApp::new(cc)))),
-    )
-}
+    eframe::run_native(
+        "scope-rs",
+        options,
+        Box::new(|cc| {
+            let mut app = ScopeApp::new(cc, settings);
+            app.settings_recovery = recovery;
+            Ok(Box::new(app))
+        }),
+    )
+}
+
+struct ScopeApp {
+    /// Shared with the sources, which push into it
+    buffer: SampleBuffer,
+    /// Read end of `buffer`, owned here so drawing never locks
+    consumer: SampleConsumer,
+    pacer: FramePacer,
+    /// Starts the display window at an edge of X or an external event
+    trigger: Trigger,
+    /// Fires `trigger` from MIDI, OSC or the keyboard
+    external_trigger: ExternalTrigger,
+    osc: osc::OscListener,
+    audio: AudioInput,
+    file_player: AudioFilePlayer,
+    oscilloscope: Oscilloscope,
+    midi: midi::MidiController,
+    presets: presets::PresetManager,
+    ab: presets::AbCompare,
+    profiles: presets::SourceProfiles,
+    /// Last MIDI morph trigger state, to fire only on a rising edge
+    morph_cc_high: bool,
+    frame_output: output::SharedFrameOutput,
+    projector: projector::Projector,
+    generator: generator::Generator,
+    generator_kind: generator::GeneratorKind,
+    shape: generator::ShapeParams,
+    function: generator::FunctionParams,
+    sweep: generator::SweepParams,
+    noise: generator::NoiseParams,
+    text: generator::TextParams,
+    svg: generator::SvgParams,
+    svg_drawing: generator::Path,
+    svg_status: String,
+    model: generator::ModelParams,
+    model_mesh: generator::Model,
+    model_status: String,
+    project_status: String,
+    demo: generator::DemoParams,
+    demo_engine: generator::Generator,
+    /// Scene the demo engine was last given
+    demo_scene: generator::DemoScene,
+    /// Time since the last signal or user input, for the attract sequence
+    idle: generator::IdleTimer,
+    attracting: bool,
+    sweep_capture: Option<generator::SweepCapture>,
+    /// Generator output and input tap as they were before the sweep
+    sweep_borrowed: Option<Borrowed>,
+    sweep_response: Vec<generator::ResponsePoint>,
+    show_sweep_response: bool,
+    show_spectrum: bool,
+    /// Window, overlap and averaging for the spectrum panel
+    spectrum: analysis::SpectrumSettings,
+    spectrum_view: SpectrumView,
+    rta_ballistics: analysis::Ballistics,
+    waterfall: render::Waterfall,
+    calibration: generator::CalibrationParams,
+    calibration_run: Option<generator::Calibration>,
+    /// Generator output and input tap as they were before calibrating
+    calibration_borrowed: Option<Borrowed>,
+    show_calibration: bool,
+    shape_error: Option<String>,
+    /// Slider values of the running shape, if it's been built
+    shape_controls: Option<std::sync::Arc<generator::ShapeControls>>,
+    show_settings: bool,
+    workspaces: layout::Workspaces,
+    /// Name typed for a new workspace
+    workspace_name: String,
+    input_mode: InputMode,
+    keymap: keybindings::Keymap,
+    palette: palette::CommandPalette,
+    /// Action waiting for a new shortcut in the keyboard settings
+    rebinding: Option<Action>,
+    hotkeys: hotkeys::GlobalHotkeys,
+    /// Action waiting for a new global hotkey
+    rebinding_hotkey: Option<Action>,
+    /// Samples held on screen while the display is frozen
+    frozen: Option<Vec<XYSample>>,
+    perf: perf::PerfStats,
+    /// Levels, correlation, ellipse phase and frequency ratio, measured on
+    /// their own thread
+    analysis: analysis::AnalysisWorker,
+    /// Mean input over the last second, for nulling the DC offsets
+    dc_meter: analysis::DcMeter,
+    /// What to show while the input is silent
+    silence: analysis::SilenceDetector,
+    /// Envelope followers that push display parameters with the music
+    modulation: modulation::Modulation,
+    /// Actions fired on kicks, beats and bars
+    reactivity: reactivity::Reactivity,
+    show_reactivity: bool,
+    scope_grid: grid::ScopeGrid,
+    export_naming: output::ExportNaming,
+    /// Next export name shown under the template
+    naming_preview: output::NamePreview,
+    sequence: output::SequenceParams,
+    exposure: output::ExposureParams,
+    render_job: Option<output::RenderJob>,
+    render_status: String,
+    record_params: output::RecordingParams,
+    recording: Option<output::Recording>,
+    /// Starts OBS recording with playback and switches its scenes
+    obs: obs::ObsRemote,
+    /// Playback and preset OBS last followed, to act only on a change
+    obs_playing: bool,
+    obs_preset: Option<String>,
+    /// Timecode, title and tempo over the scope and in frame outputs
+    overlay: render::Overlay,
+    /// The user's own text and logo over the scope
+    branding: render::Branding,
+    branding_status: String,
+    /// Lowers render quality while frames run over budget
+    quality: quality::QualityGovernor,
+    /// Per-frame sample copy, kept to avoid reallocating every frame
+    scratch: Vec<XYSample>,
+    show_perf: bool,
+    /// Phase and amplitude ratio readout over the scope
+    show_phase: bool,
+    show_help: bool,
+    /// Set when the settings file couldn't be read, to offer a backup
+    settings_recovery: Option<settings::SettingsRecovery>,
+    show_log: bool,
+    /// Most verbose level shown in the log panel
+    log_level: log::LevelFilter,
+    /// Save without the session on exit, so the next launch starts fresh
+    fresh_next_launch: bool,
+    screenshot_status: String,
+    settings_status: String,
+    /// Bundle waiting for the user to decide about presets it would replace
+    pending_import: Option<(std::path::PathBuf, settings::SettingsBundle, Vec<String>)>,
+    autosave: Option<settings::AutoSave>,
+    startup: settings::StartupOptions,
+    /// Last seen window geometry (logical points), saved for the next start
+    window_size: Option<[f32; 2]>,
+    window_pos: Option<[f32; 2]>,
+    /// Minimizing only works once the window exists, so it waits for the first frame
+    minimize_pending: bool,
+    /// Whole-UI zoom on top of the display's own scaling
+    ui_scale: f32,
+    /// Body text size in points (before `ui_scale`)
+    font_size: f32,
+    ui_theme: settings::UiTheme,
+    target_fps: u32,
+    vsync: bool,
+    idle_throttle: bool,
+    /// Reduce render quality when frames take too long
+    auto_quality: bool,
+    /// Scale and font size currently applied to the egui context
+    applied_ui: Option<(f32, f32)>,
+    tray: tray::TrayIcon,
+    /// Window hidden to the tray; the app keeps running behind it
+    window_hidden: bool,
+    /// Quit picked from the tray, so closing isn't turned into hiding
+    quitting: bool,
+}
+
+impl ScopeApp {
+    fn new(cc: &eframe::CreationContext<'_>, settings: settings::AppSettings) -> Self {
+        let buffer = SampleBuffer::new(SampleBuffer::DEFAULT_CAPACITY);
+        let audio = AudioInput::new(buffer.clone_ref());
+        let file_player = AudioFilePlayer::new(buffer.clone_ref());
+        let generator = generator::Generator::new(buffer.clone_ref());
+        // The demo can also cover for a silent input, see `update_silence`
+        let demo_engine = generator::Generator::new(buffer.stand_in_ref());
+        let consumer = buffer
+            .take_consumer()
+            .expect("a new buffer still has its consumer");
+        let analysis = analysis::AnalysisWorker::start(&buffer);
+        let trigger = Trigger::default();
+        let external_trigger = trigger.external(&buffer);
+        let mut midi = midi::MidiController::new();
+        midi.set_trigger(external_trigger.clone());
+
+        let mut app = Self {
+            buffer,
+            consumer,
+            pacer: FramePacer::default(),
+            trigger,
+            osc: osc::OscListener::new(external_trigger.clone()),
+            external_trigger,
+            audio,
+            file_player,
+            oscilloscope: Oscilloscope::new(),
+            midi,
+            presets: presets::PresetManager::new(),
+            ab: presets::AbCompare::default(),
+            profiles: presets::SourceProfiles::default(),
+            morph_cc_high: false,
+            frame_output: output::SharedFrameOutput::new(),
+            projector: projector::Projector::default(),
+            generator,
+            generator_kind: generator::GeneratorKind::default(),
+            shape: generator::ShapeParams::default(),
+            function: generator::FunctionParams::default(),
+            sweep: generator::SweepParams::default(),
+            noise: generator::NoiseParams::default(),
+            text: generator::TextParams::default(),
+            svg: generator::SvgParams::default(),
+            svg_drawing: generator::Path::default(),
+            svg_status: String::new(),
+            model: generator::ModelParams::default(),
+            model_mesh: generator::Model::default(),
+            model_status: String::new(),
+            project_status: String::new(),
+            demo: generator::DemoParams::default(),
+            demo_engine,
+            demo_scene: generator::DemoScene::default(),
+            idle: generator::IdleTimer::default(),
+            attracting: false,
+            sweep_capture: None,
+            sweep_borrowed: None,
+            sweep_response: Vec::new(),
+            show_sweep_response: false,
+            show_spectrum: false,
+            spectrum: analysis::SpectrumSettings::default(),
+            spectrum_view: SpectrumView::default(),
+            rta_ballistics: analysis::Ballistics::default(),
+            waterfall: render::Waterfall::default(),
+            calibration: generator::CalibrationParams::default(),
+            calibration_run: None,
+            calibration_borrowed: None,
+            show_calibration: false,
+            shape_error: None,
+            shape_controls: None,
+            show_settings: false,
+            workspaces: layout::Workspaces::default(),
+            workspace_name: String::new(),
+            input_mode: InputMode::default(),
+            keymap: keybindings::Keymap::default(),
+            palette: palette::CommandPalette::default(),
+            rebinding: None,
+            hotkeys: hotkeys::GlobalHotkeys::new(cc.egui_ctx.clone()),
+            rebinding_hotkey: None,
+            frozen: None,
+            perf: perf::PerfStats::default(),
+            analysis,
+            dc_meter: analysis::DcMeter::default(),
+            silence: analysis::SilenceDetector::default(),
+            modulation: modulation::Modulation::default(),
+            reactivity: reactivity::Reactivity::default(),
+            show_reactivity: false,
+            scope_grid: grid::ScopeGrid::default(),
+            export_naming: output::ExportNaming::default(),
+            naming_preview: output::NamePreview::default(),
+            sequence: output::SequenceParams {
+                start: Duration::ZERO,
+                end: Duration::ZERO,
+                fps: 30,
+                width: 1920,
+                height: 1080,
+            },
+            exposure: output::ExposureParams {
+                start: Duration::ZERO,
+                end: Duration::ZERO,
+                width: 4096,
+                height: 4096,
+                format: output::ExposureFormat::Png16,
+            },
+            render_job: None,
+            render_status: String::new(),
+            record_params: output::RecordingParams::default(),
+            recording: None,
+            obs: obs::ObsRemote::default(),
+            obs_playing: false,
+            obs_preset: None,
+            tray: tray::TrayIcon::new(cc.egui_ctx.clone()),
+            window_hidden: false,
+            quitting: false,
+            overlay: render::Overlay::default(),
+            branding: render::Branding::default(),
+            branding_status: String::new(),
+            quality: quality::QualityGovernor::default(),
+            scratch: Vec::new(),
+            show_perf: false,
+            show_phase: false,
+            show_help: false,
+            settings_recovery: None,
+            show_log: false,
+            log_level: log::LevelFilter::Info,
+            fresh_next_launch: false,
+            screenshot_status: String::new(),
+            settings_status: String::new(),
+            pending_import: None,
+            autosave: None,
+            startup: settings::StartupOptions::default(),
+            window_size: None,
+            window_pos: None,
+            minimize_pending: false,
+            ui_scale: 1.0,
+            font_size: DEFAULT_FONT_SIZE,
+            ui_theme: settings::UiTheme::default(),
+            target_fps: 0,
+            vsync: true,
+            idle_throttle: true,
+            auto_quality: false,
+            applied_ui: None,
+        };
+
+        settings.apply(&mut app);
+        app.reload_sources();
+        app.run_startup(&settings);
+        app.autosave = Some(settings::AutoSave::new(&app));
+
+        app
+    }
+
+    /// Restore the session: reselect the preset, reopen the last file where
+    /// it left off, start capture
+    fn run_startup(&mut self, settings: &settings::AppSettings) {
+        if let Some(index) = settings
+            .active_preset
+            .as_ref()
+            .and_then(|name| self.presets.names.iter().position(|n| n == name))
+        {
+            self.presets.selected = index;
+        }
+        if self.startup.reopen_file {
+            if let Some(path) = settings.last_file.as_ref().filter(|p| p.exists()) {
+                // Applied once the file has loaded
+                self.file_player.load(path);
+                self.file_player.seek(settings.last_file_position);
+                self.input_mode = InputMode::File;
+            }
+        }
+        // Only a device from a previous session counts as "the last device"
+        if self.startup.auto_capture
+            && settings.input_device.is_some()
+            && !self.audio.devices.is_empty()
+        {
+            self.input_mode = InputMode::Live;
+            self.audio.start();
+        }
+        self.minimize_pending = self.startup.window == settings::StartupWindow::Minimized;
+    }
+
+    /// Remember the window geometry, unless it is minimized or fullscreen
+    fn track_window(&mut self, ctx: &egui::Context) {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            if viewport.minimized == Some(true) || viewport.fullscreen == Some(true) {
+                return;
+            }
+            if let Some(rect) = viewport.inner_rect {
+                self.window_size = Some([rect.width(), rect.height()]);
+            }
+            if let Some(rect) = viewport.outer_rect {
+                self.window_pos = Some([rect.min.x, rect.min.y]);
+            }
+        });
+    }
+
+    /// Apply UI scale and font size when they change
+    fn apply_ui_style(&mut self, ctx: &egui::Context) {
+        let theme = self.ui_theme.preference();
+        if ctx.options(|o| o.theme_preference) != theme {
+            ctx.set_theme(theme);
+        }
+
+        // egui's own Ctrl+/Ctrl- zoom changes the scale too; keep the setting in sync
+        if let Some((scale, _)) = self.applied_ui {
+            let zoom = ctx.zoom_factor();
+            if (zoom - scale).abs() > 1e-4 {
+                self.ui_scale = zoom;
+                self.applied_ui = Some((zoom, self.font_size));
+            }
+        }
+
+        // Rescaling under a dragged slider makes it jump, so wait for release
+        if self.applied_ui == Some((self.ui_scale, self.font_size)) || ctx.is_using_pointer() {
+            return;
+        }
+        ctx.set_zoom_factor(self.ui_scale);
+        let factor = self.font_size / DEFAULT_FONT_SIZE;
+        ctx.all_styles_mut(|style| {
+            style.text_styles = egui::style::default_text_styles()
+                .into_iter()
+                .map(|(text_style, font)| {
+                    (
+                        text_style,
+                        egui::FontId::new(font.size * factor, font.family),
+                    )
+                })
+                .collect();
+        });
+        self.applied_ui = Some((self.ui_scale, self.font_size));
+    }
+
+    /// Settings panel section for UI size
+    fn interface_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Language:"));
+            let current = i18n::language();
+            egui::ComboBox::from_id_salt("language")
+                .selected_text(current.name())
+                .show_ui(ui, |ui| {
+                    for language in i18n::Language::all() {
+                        if ui
+                            .selectable_label(current == *language, language.name())
+                            .clicked()
+                        {
+                            i18n::set_language(*language);
+                        }
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Appearance:"));
+            egui::ComboBox::from_id_salt("ui_theme")
+                .selected_text(tr(self.ui_theme.name()))
+                .show_ui(ui, |ui| {
+                    for theme in settings::UiTheme::all() {
+                        ui.selectable_value(&mut self.ui_theme, *theme, tr(theme.name()));
+                    }
+                });
+        })
+        .response
+        .on_hover_text(tr(
+            "Panels and controls only; the scope keeps its color theme",
+        ));
+        ui.horizontal(|ui| {
+            ui.label(tr("UI scale:"));
+            ui.add(
+                egui::Slider::new(&mut self.ui_scale, 0.5..=3.0)
+                    .step_by(0.05)
+                    .suffix("x"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Font size:"));
+            ui.add(egui::Slider::new(&mut self.font_size, 8.0..=24.0).suffix(" pt"));
+        });
+        if ui.button(tr("Reset")).clicked() {
+            self.ui_scale = 1.0;
+            self.font_size = DEFAULT_FONT_SIZE;
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Frame rate:"));
+            ui.add(
+                egui::Slider::new(&mut self.target_fps, 0..=240).custom_formatter(|fps, _| {
+                    if fps == 0.0 {
+                        tr("Auto").to_string()
+                    } else {
+                        format!("{}", fps)
+                    }
+                }),
+            )
+            .on_hover_text(tr(
+                "Cap while playing; 0 runs at the display's refresh rate",
+            ));
+        });
+        ui.checkbox(&mut self.vsync, tr("VSync (after restart)"));
+        ui.checkbox(&mut self.idle_throttle, tr("Slow down when idle"))
+            .on_hover_text(tr("Redraw a few times per second when nothing is playing"));
+        ui.checkbox(&mut self.auto_quality, tr("Reduce quality under load"))
+            .on_hover_text(tr(
+                "Draw fewer samples and a thinner trail while frames take longer \
+                 than the frame rate allows, and restore them when there is room",
+            ));
+    }
+
+    /// Settings panel section for startup behavior
+    fn startup_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(
+            &mut self.startup.auto_capture,
+            tr("Start capture on the last device"),
+        );
+        ui.checkbox(&mut self.startup.reopen_file, tr("Reopen the last file"));
+        ui.checkbox(
+            &mut self.startup.restore_window,
+            tr("Restore window, panels and mode"),
+        );
+        ui.checkbox(&mut self.fresh_next_launch, tr("Start fresh next launch"))
+            .on_hover_text(tr(
+                "Forget the open file, device, window and selected preset when closing. \
+                 Running with --fresh does the same for one launch.",
+            ));
+        ui.horizontal(|ui| {
+            ui.label(tr("Window:"));
+            egui::ComboBox::from_id_salt("startup_window")
+                .selected_text(tr(self.startup.window.name()))
+                .show_ui(ui, |ui| {
+                    for state in settings::StartupWindow::all() {
+                        ui.selectable_value(&mut self.startup.window, *state, tr(state.name()));
+                    }
+                });
+        });
+    }
+
+    /// Settings panel section for the tray icon
+    fn tray_settings_ui(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.tray.is_running();
+        if ui
+            .add_enabled(
+                tray::SUPPORTED,
+                egui::Checkbox::new(&mut enabled, tr("Tray icon")),
+            )
+            .on_hover_text(tr(
+                "Show, hide, capture and recall presets from the tray. Capture and \
+                 outputs keep running while the window is hidden.",
+            ))
+            .on_disabled_hover_text(tr("The tray icon is only available on Linux for now"))
+            .changed()
+        {
+            self.tray.set_enabled(enabled);
+        }
+        ui.add_enabled(
+            enabled,
+            egui::Checkbox::new(&mut self.tray.close_to_tray, tr("Close to tray")),
+        )
+        .on_hover_text(tr("Closing the window hides it; quit from the tray menu"));
+        ui.small(&self.tray.status);
+    }
+
+    /// Hide the window to the tray, or bring it back
+    fn set_window_hidden(&mut self, ctx: &egui::Context, hidden: bool) {
+        self.window_hidden = hidden;
+        // Wayland doesn't let a window hide itself, only minimize
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(hidden));
+        } else {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(!hidden));
+        }
+        if !hidden {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+    }
+
+    /// Run what was picked from the tray, hide instead of closing if asked
+    /// to, and keep the menu in step with the app
+    fn update_tray(&mut self, ctx: &egui::Context) {
+        while let Some(action) = self.tray.poll() {
+            match action {
+                tray::TrayAction::ToggleWindow => {
+                    self.set_window_hidden(ctx, !self.window_hidden);
+                }
+                tray::TrayAction::ToggleCapture => {
+                    if !self.audio.devices.is_empty() || self.audio.is_capturing() {
+                        self.input_mode = InputMode::Live;
+                        self.audio.toggle();
+                    }
+                }
+                tray::TrayAction::LoadPreset(name) => self.load_preset(&name),
+                tray::TrayAction::Quit => {
+                    self.quitting = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            }
+        }
+        if !self.tray.is_running() {
+            // Nothing would bring a hidden window back
+            if self.window_hidden {
+                self.set_window_hidden(ctx, false);
+            }
+            return;
+        }
+        let close_requested = ctx.input(|i| i.viewport().close_requested());
+        if close_requested && self.tray.close_to_tray && !self.quitting {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.set_window_hidden(ctx, true);
+        }
+        self.tray.update(
+            !self.window_hidden,
+            self.audio.is_capturing(),
+            !self.audio.devices.is_empty(),
+            &self.presets.names,
+            self.presets.current.as_deref(),
+        );
+    }
+
+    /// Reload file-backed sources and rebuild the generator after settings change
+    fn reload_sources(&mut self) {
+        if self.svg.file.is_some() {
+            self.load_svg();
+        }
+        if self.model.file.is_some() {
+            self.load_model();
+        }
+        self.rebuild_generator();
+    }
+
+    /// Write all settings and presets to a single file
+    fn export_settings(&mut self, path: &std::path::Path) {
+        let (bundle, unreadable) = settings::SettingsBundle::from_app(self);
+        self.settings_status = match bundle.export(path) {
+            Ok(()) if unreadable.is_empty() => trf("Exported to {}", &[&path.display()]),
+            Ok(()) => trf(
+                "Exported to {}, without the unreadable presets {}",
+                &[&path.display(), &unreadable.join(", ")],
+            ),
+            Err(e) => {
+                log::error!("Settings export failed: {}", e);
+                trf("Error: {}", &[&e])
+            }
+        };
+    }
+
+    /// Load settings and presets from a file written by `export_settings`.
+    /// If presets of the same name exist, the user is asked first.
+    fn import_settings(&mut self, path: &std::path::Path) {
+        match settings::SettingsBundle::import(path) {
+            Ok(bundle) => {
+                let conflicts = bundle.conflicts(self);
+                if conflicts.is_empty() {
+                    self.apply_import(path, &bundle, false);
+                } else {
+                    self.pending_import = Some((path.to_path_buf(), bundle, conflicts));
+                }
+            }
+            Err(e) => {
+                log::error!("Settings import failed: {}", e);
+                self.settings_status = trf("Error: {}", &[&e]);
+            }
+        }
+    }
+
+    fn apply_import(
+        &mut self,
+        path: &std::path::Path,
+        bundle: &settings::SettingsBundle,
+        replace: bool,
+    ) {
+        self.pending_import = None;
+        self.settings_status = match bundle.apply(self, replace) {
+            Ok(()) => {
+                self.reload_sources();
+                trf("Imported {}", &[&path.display()])
+            }
+            Err(e) => {
+                log::error!("Settings import failed: {}", e);
+                trf("Error: {}", &[&e])
+            }
+        };
+    }
+
+    /// Build the selected generator source and hand it to the generator
+    fn rebuild_generator(&mut self) {
+        match self.generator_kind {
+            generator::GeneratorKind::Shape => match self.shape.build() {
+                Ok(source) => {
+                    self.shape_controls = Some(source.controls());
+                    self.generator.set_source(Box::new(source));
+                    self.shape_error = None;
+                }
+                Err(e) => self.shape_error = Some(e.to_string()),
+            },
+            generator::GeneratorKind::Function => {
+                self.generator.set_source(Box::new(self.function.build()));
+            }
+            generator::GeneratorKind::Sweep => {
+                self.generator.set_source(Box::new(self.sweep.build()));
+            }
+            generator::GeneratorKind::Noise => {
+                self.generator.set_source(Box::new(self.noise.build()));
+            }
+            generator::GeneratorKind::Text => {
+                self.generator.set_source(Box::new(self.text.build()));
+            }
+            generator::GeneratorKind::Svg => {
+                self.generator
+                    .set_source(Box::new(self.svg.build(&self.svg_drawing)));
+            }
+            generator::GeneratorKind::Model => {
+                self.generator
+                    .set_source(Box::new(self.model.build(&self.model_mesh)));
+            }
+        }
+    }
+
+    /// Whether the selected input is currently producing samples
+    fn source_active(&self) -> bool {
+        match self.input_mode {
+            InputMode::Live => self.audio.is_capturing(),
+            InputMode::File => self.file_player.state() == PlaybackState::Playing,
+            InputMode::Generator => self.generator.is_running(),
+        }
+    }
+
+    /// Every channel of the selected input, for the grid of scopes
+    fn source_channels(&self) -> Option<&audio::ChannelBuffer> {
+        match self.input_mode {
+            InputMode::Live => Some(&self.audio.channels),
+            InputMode::File => Some(&self.file_player.channels),
+            InputMode::Generator => None,
+        }
+    }
+
+    /// Watch the input for silence, and hand the display to the demo while
+    /// it lasts if that's the chosen action
+    fn update_silence(&mut self) {
+        let peak = self
+            .analysis
+            .latest()
+            .levels
+            .map(|levels| levels.peak_x_db.max(levels.peak_y_db));
+        let silent = if self.source_active() {
+            self.silence.update(Instant::now(), peak)
+        } else {
+            self.silence.reset();
+            false
+        };
+        let stand_in = (silent && self.silence.action == analysis::SilenceAction::Demo)
+            || (self.attracting && self.source_active());
+        if stand_in != self.buffer.is_standing_in() {
+            self.buffer.set_standing_in(stand_in);
+        }
+    }
+
+    /// Start the attract sequence once there's been no signal and nobody
+    /// at the controls for the chosen time, and end it as soon as either
+    /// comes back
+    fn update_attract(&mut self, ctx: &egui::Context) {
+        if !self.demo.attract {
+            self.attracting = false;
+            return;
+        }
+        let touched = ctx.input(|i| {
+            i.pointer.delta() != egui::Vec2::ZERO
+                || i.pointer.any_down()
+                || i.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        egui::Event::Key { .. }
+                            | egui::Event::MouseWheel { .. }
+                            | egui::Event::Touch { .. }
+                    )
+                })
+        });
+        let signal = self.source_active()
+            && self.analysis.latest().levels.is_some_and(|levels| {
+                levels.peak_x_db.max(levels.peak_y_db) >= self.silence.threshold_db
+            });
+        let attracting =
+            self.idle
+                .update(Instant::now(), touched || signal, self.demo.attract_delay());
+        if attracting != self.attracting {
+            self.attracting = attracting;
+            log::info!(
+                "{} the attract sequence",
+                if attracting { "Starting" } else { "Leaving" }
+            );
+        }
+    }
+
+    /// Run the demo scene while nothing else is feeding the display, while
+    /// it stands in for a silent input, or as the attract sequence
+    fn update_demo(&mut self) {
+        let want_demo = (self.demo.enabled && !self.source_active())
+            || self.buffer.is_standing_in()
+            || self.attracting;
+        if !want_demo {
+            if self.demo_engine.is_running() {
+                self.demo_engine.stop();
+            }
+            return;
+        }
+        let scene = if self.attracting {
+            generator::DemoScene::Attract
+        } else {
+            self.demo.scene
+        };
+        if self.demo_engine.is_running() && scene == self.demo_scene {
+            return;
+        }
+        self.demo_engine
+            .set_source(Box::new(generator::DemoParams::build_scene(scene)));
+        self.demo_scene = scene;
+        if !self.demo_engine.is_running() {
+            self.demo_engine.start();
+        }
+    }
+
+    /// Load an osci-render project and switch to a source for its active file
+    fn import_osci_project(&mut self, path: &std::path::Path) {
+        let project = match generator::OsciProject::load(path) {
+            Ok(project) => project,
+            Err(e) => {
+                log::error!("Failed to import project: {}", e);
+                self.project_status = trf("Error: {}", &[&e]);
+                return;
+            }
+        };
+
+        let file = project.preferred_file();
+        let result = match file.extension().as_str() {
+            "svg" => generator::parse_svg(&file.text())
+                .map(|drawing| {
+                    self.svg.file = None;
+                    self.svg_drawing = drawing.normalized(1.0);
+                    self.svg_status = trf("{} (from project)", &[&file.name]);
+                    self.generator_kind = generator::GeneratorKind::Svg;
+                })
+                .map_err(|e| e.to_string()),
+            "obj" => generator::Model::parse_obj(&file.text())
+                .map(|mesh| {
+                    self.model.file = None;
+                    self.model_mesh = mesh;
+                    self.model_status = trf("{} (from project)", &[&file.name]);
+                    self.generator_kind = generator::GeneratorKind::Model;
+                })
+                .map_err(|e| e.to_string()),
+            "txt" => {
+                self.text.text = file.text().trim().to_string();
+                self.generator_kind = generator::GeneratorKind::Text;
+                Ok(())
+            }
+            _ => Err(trf("'{}' is not supported", &[&file.name])),
+        };
+
+        self.project_status = match result {
+            Ok(()) => {
+                self.rebuild_generator();
+                trf("Loaded {} of {} files", &[&file.name, &project.files.len()])
+            }
+            Err(e) => trf("Error: {}", &[&e]),
+        };
+    }
+
+    /// Import the OBJ file named in the settings
+    fn load_model(&mut self) {
+        match self.model.load() {
+            Ok(mesh) => {
+                self.model_status = trf(
+                    "{} vertices, {} edges",
+                    &[&mesh.vertices.len(), &mesh.edges.len()],
+                );
+                self.model_mesh = mesh;
+            }
+            Err(e) => {
+                log::error!("Failed to load model: {}", e);
+                self.model_status = trf("Error: {}", &[&e]);
+                self.model_mesh = generator::Model::default();
+            }
+        }
+    }
+
+    /// Import the SVG file named in the settings
+    fn load_svg(&mut self) {
+        match self.svg.load() {
+            Ok(drawing) => {
+                self.svg_status = trf("{} strokes", &[&drawing.strokes.len()]);
+                self.svg_drawing = drawing;
+            }
+            Err(e) => {
+                log::error!("Failed to import SVG: {}", e);
+                self.svg_status = trf("Error: {}", &[&e]);
+                self.svg_drawing = generator::Path::default();
+            }
+        }
+    }
+
+    /// Render a few seconds of the SVG loop to a WAV file
+    fn export_svg_wav(&mut self, sample_rate: u32, seconds: f32) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("WAV", &["wav"])
+            .set_file_name("drawing.wav")
+            .save_file()
+        else {
+            return;
+        };
+
+        let mut source = self.svg.build(&self.svg_drawing);
+        match generator::export_wav(&mut source, &path, sample_rate, seconds) {
+            Ok(()) => self.svg_status = trf("Exported {}", &[&path.display()]),
+            Err(e) => {
+                log::error!("Failed to export WAV: {}", e);
+                self.svg_status = trf("Error: {}", &[&e]);
+            }
+        }
+    }
+
+    /// Play a sweep and record the live input to measure the response
+    fn start_sweep_measurement(&mut self) {
+        let Some(sample_rate) = self.audio.sample_rate() else {
+            self.generator.status = tr("Start live capture to measure").to_string();
+            return;
+        };
+
+        let mut params = self.sweep.clone();
+        params.repeat = false;
+        let (capture, tap) =
+            generator::SweepCapture::new(&params, self.generator.volume, sample_rate);
+        if self.sweep_borrowed.is_none() {
+            self.sweep_borrowed = Some(self.borrow_output(tap));
+        } else {
+            self.audio.set_tap(Some(tap));
+        }
+        self.sweep_capture = Some(capture);
+
+        // Restart the sweep from the top
+        self.generator_kind = generator::GeneratorKind::Sweep;
+        self.generator.set_source(Box::new(params.build()));
+        self.generator.start();
+    }
+
+    /// Send the generator to the output device, so a test signal reaches
+    /// the gear under test, and feed the input to `tap`
+    fn borrow_output(&mut self, tap: ringbuf::HeapProd<XYSample>) -> Borrowed {
+        let borrowed = Borrowed {
+            audio_output: self.generator.audio_output,
+            tap: self.audio.set_tap(Some(tap)),
+        };
+        if !self.generator.audio_output {
+            self.generator.audio_output = true;
+            self.generator.restart();
+        }
+        borrowed
+    }
+
+    /// Undo `borrow_output`
+    fn give_back(&mut self, borrowed: Borrowed) {
+        self.audio.set_tap(borrowed.tap);
+        if self.generator.audio_output != borrowed.audio_output {
+            self.generator.audio_output = borrowed.audio_output;
+            self.generator.restart();
+        }
+    }
+
+    /// Poll a running sweep measurement, finishing it when complete
+    fn update_sweep_measurement(&mut self) {
+        let Some(capture) = &mut self.sweep_capture else {
+            return;
+        };
+        capture.update();
+        if capture.is_complete() {
+            self.sweep_response = capture.response();
+            self.sweep_capture = None;
+            if let Some(borrowed) = self.sweep_borrowed.take() {
+                self.give_back(borrowed);
+            }
+            self.show_sweep_response = true;
+            log::info!("Sweep measurement complete");
+        }
+    }
+
+    /// Drive both outputs with the calibration sine and measure the input
+    fn start_calibration(&mut self) {
+        let Some(sample_rate) = self.audio.sample_rate() else {
+            self.generator.status = tr("Start live capture to calibrate").to_string();
+            return;
+        };
+
+        let (run, tap) = generator::Calibration::new(sample_rate);
+        if self.calibration_borrowed.is_none() {
+            self.calibration_borrowed = Some(self.borrow_output(tap));
+        } else {
+            self.audio.set_tap(Some(tap));
+        }
+        self.calibration_run = Some(run);
+
+        self.generator
+            .set_source(Box::new(self.calibration.build()));
+        self.generator.start();
+    }
+
+    /// End calibration and put the selected generator source back
+    fn stop_calibration(&mut self) {
+        self.calibration_run = None;
+        if let Some(borrowed) = self.calibration_borrowed.take() {
+            self.give_back(borrowed);
+        }
+        self.generator.stop();
+        self.rebuild_generator();
+    }
+
+    /// Calibration window contents
+    fn calibration_ui(&mut self, ui: &mut egui::Ui) {
+        let running = self.calibration_run.is_some();
+
+        ui.add_enabled_ui(!running, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Frequency:"));
+                ui.add(
+                    egui::Slider::new(&mut self.calibration.frequency, 20.0..=20000.0)
+                        .logarithmic(true)
+                        .suffix(" Hz"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Level:"));
+                ui.add(
+                    egui::Slider::new(&mut self.calibration.level_db, -60.0..=0.0).suffix(" dBFS"),
+                );
+            });
+        });
+        ui.small(tr(
+            "Connect the outputs to the inputs (loopback) and start live capture",
+        ));
+
+        if running {
+            if ui.button(tr("⏹ Stop")).clicked() {
+                self.stop_calibration();
+            }
+        } else if ui.button(tr("▶ Start")).clicked() {
+            self.start_calibration();
+        }
+
+        ui.separator();
+
+        match self.calibration_run.as_ref().and_then(|run| run.result) {
+            Some(m) => {
+                egui::Grid::new("calibration_results").show(ui, |ui| {
+                    ui.label(tr("Phase (Y vs X):"));
+                    ui.monospace(format!("{:+.2}°", m.phase_deg));
+                    ui.end_row();
+                    ui.label(tr("Gain mismatch (Y vs X):"));
+                    ui.monospace(format!("{:+.2} dB", m.gain_db));
+                    ui.end_row();
+                    ui.label(tr("Input level X:"));
+                    ui.monospace(format!("{:.1} dBFS", m.level_x_db));
+                    ui.end_row();
+                    ui.label(tr("Input level Y:"));
+                    ui.monospace(format!("{:.1} dBFS", m.level_y_db));
+                    ui.end_row();
+                });
+            }
+            None if running => {
+                ui.label(tr("Waiting for signal..."));
+            }
+            None => {
+                ui.label(tr("Not running"));
+            }
+        }
+    }
+
+    /// Run a keyboard action
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::PlayPause => match self.input_mode {
+                InputMode::Live => {
+                    if !self.audio.devices.is_empty() || self.audio.is_capturing() {
+                        self.audio.toggle();
+                    }
+                }
+                InputMode::File => {
+                    if self.file_player.has_file() {
+                        self.file_player.toggle();
+                    }
+                }
+                InputMode::Generator => self.generator.toggle(),
+            },
+            Action::Stop => match self.input_mode {
+                InputMode::Live => self.audio.stop(),
+                InputMode::File => self.file_player.stop(),
+                InputMode::Generator => self.generator.stop(),
+            },
+            Action::LiveMode => self.input_mode = InputMode::Live,
+            Action::FileMode => self.input_mode = InputMode::File,
+            Action::GeneratorMode => self.input_mode = InputMode::Generator,
+            Action::Freeze => self.toggle_freeze(),
+            Action::Screenshot => self.save_screenshot(),
+            Action::Record => self.toggle_recording(),
+            Action::NextTheme | Action::PreviousTheme => {
+                let themes = ColorTheme::all();
+                let current = themes
+                    .iter()
+                    .position(|t| *t == self.oscilloscope.settings.theme)
+                    .unwrap_or(0);
+                let next = if action == Action::NextTheme {
+                    (current + 1) % themes.len()
+                } else {
+                    (current + themes.len() - 1) % themes.len()
+                };
+                self.oscilloscope.settings.apply_theme(themes[next]);
+            }
+            Action::ClearPersistence => self.oscilloscope.clear_persistence(),
+            Action::ToggleSettings => self.show_settings = !self.show_settings,
+            Action::PerfOverlay => self.show_perf = !self.show_perf,
+            Action::Help => self.show_help = !self.show_help,
+            Action::LogPanel => self.show_log = !self.show_log,
+            Action::PresetMorph => self.start_preset_morph(),
+            Action::AbCompare => self.switch_ab(),
+            Action::FireTrigger => self.external_trigger.fire(),
+            Action::CommandPalette => self.palette.toggle(),
+            Action::NextPreset => self.step_preset(true),
+            Action::PreviousPreset => self.step_preset(false),
+        }
+    }
+
+    /// Load the preset after (or before) the selected one, wrapping around
+    fn step_preset(&mut self, forward: bool) {
+        let count = self.presets.names.len();
+        if count == 0 {
+            return;
+        }
+        let current = self.presets.selected.min(count - 1);
+        self.presets.selected = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        let name = self.presets.names[self.presets.selected].clone();
+        self.load_preset(&name);
+    }
+
+    /// Current value of a MIDI-controllable parameter
+    fn param_value(&self, param: midi::MidiParam) -> f32 {
+        midi::current_value(
+            param,
+            &self.oscilloscope,
+            &self.audio,
+            &self.file_player,
+            &self.model,
+        )
+    }
+
+    fn set_param(&mut self, param: midi::MidiParam, value: f32) {
+        midi::apply_updates(
+            &[(param, value)],
+            &mut self.oscilloscope,
+            &mut self.audio,
+            &mut self.file_player,
+            &mut self.model,
+        );
+    }
+
+    /// Ctrl+P palette: search actions and parameters, edit values inline
+    fn command_palette_ui(&mut self, ctx: &egui::Context) {
+        // Take navigation keys before the search field sees them
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.palette.open = false;
+            return;
+        }
+
+        let matches = self.palette.matches();
+        if down {
+            self.palette.selected += 1;
+        }
+        if up {
+            self.palette.selected = self.palette.selected.saturating_sub(1);
+        }
+        self.palette.selected = self.palette.selected.min(matches.len().saturating_sub(1));
+
+        let mut run = None;
+        if enter {
+            if let Some(&palette::PaletteItem::Action(action)) = matches.get(self.palette.selected)
+            {
+                run = Some(action);
+            }
+        }
+
+        let mut open = self.palette.open;
+        egui::Window::new(tr("Command Palette"))
+            .open(&mut open)
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .fixed_size([360.0, 0.0])
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .show(ctx, |ui| {
+                let search = ui.add(
+                    egui::TextEdit::singleline(&mut self.palette.query)
+                        .hint_text(tr("Type a command or parameter…"))
+                        .desired_width(f32::INFINITY),
+                );
+                search.request_focus();
+                if search.changed() {
+                    self.palette.selected = 0;
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for (i, item) in matches.iter().enumerate() {
+                            let highlighted = i == self.palette.selected;
+                            ui.horizontal(|ui| match *item {
+                                palette::PaletteItem::Action(action) => {
+                                    let row = ui.selectable_label(highlighted, tr(action.name()));
+                                    if highlighted {
+                                        row.scroll_to_me(None);
+                                    }
+                                    if row.clicked() {
+                                        run = Some(action);
+                                    }
+                                    if let Some(shortcut) = self.keymap.shortcut(action) {
+                                        ui.with_layout(
+                                            egui::Layout::right_to_left(egui::Align::Center),
+                                            |ui| ui.weak(shortcut.to_string()),
+                                        );
+                                    }
+                                }
+                                palette::PaletteItem::Param(param) => {
+                                    let row = ui.selectable_label(highlighted, tr(param.name()));
+                                    if highlighted {
+                                        row.scroll_to_me(None);
+                                    }
+                                    let (min, max) = param.range();
+                                    let mut value = self.param_value(param);
+                                    ui.with_layout(
+                                        egui::Layout::right_to_left(egui::Align::Center),
+                                        |ui| {
+                                            if ui
+                                                .add(egui::Slider::new(&mut value, min..=max))
+                                                .changed()
+                                            {
+                                                self.set_param(param, value);
+                                            }
+                                        },
+                                    );
+                                }
+                            });
+                        }
+                        if matches.is_empty() {
+                            ui.weak(tr("No matches"));
+                        }
+                    });
+            });
+
+        self.palette.open = open;
+        if let Some(action) = run {
+            self.palette.open = false;
+            self.run_action(action);
+        }
+    }
+
+    /// Hold the current samples on screen, or release them
+    fn toggle_freeze(&mut self) {
+        self.frozen = match self.frozen {
+            Some(_) => None,
+            None => {
+                self.consumer.update();
+                Some(self.consumer.get_samples())
+            }
+        };
+    }
+
+    /// Track title, or the name of whatever else is playing
+    fn source_title(&self) -> String {
+        match self.input_mode {
+            _ if self.demo_engine.is_running() => tr("Demo").to_string(),
+            InputMode::File => self
+                .file_player
+                .info
+                .as_ref()
+                .map(|info| {
+                    info.title.clone().unwrap_or_else(|| {
+                        info.path
+                            .file_stem()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                })
+                .unwrap_or_default(),
+            InputMode::Live => tr("Live Input").to_string(),
+            InputMode::Generator => tr("Generator").to_string(),
+        }
+    }
+
+    /// Values for the export file name tokens
+    fn name_fields(&self) -> output::NameFields {
+        let file = self.file_player.info.as_ref();
+        output::NameFields {
+            title: self.source_title(),
+            preset: self.presets.current.clone(),
+            position: (self.input_mode == InputMode::File && file.is_some())
+                .then(|| self.file_player.position_duration()),
+            time: jiff::Zoned::now(),
+        }
+    }
+
+    /// Save what is on screen as a PNG, named after the export template
+    fn save_screenshot(&mut self) {
+        let samples = match &self.frozen {
+            Some(samples) => samples.clone(),
+            None => {
+                self.consumer.update();
+                self.consumer.get_samples()
+            }
+        };
+        let path = self.export_naming.path(&self.name_fields(), "png");
+        self.screenshot_status = match output::save_screenshot(
+            &samples,
+            &self.oscilloscope.settings,
+            &[&self.overlay, &self.branding],
+            &path,
+        ) {
+            Ok(()) => trf("Saved {}", &[&path.display()]),
+            Err(e) => {
+                log::error!("Screenshot failed: {}", e);
+                trf("Screenshot failed: {}", &[&e])
+            }
+        };
+    }
+
+    /// Settings panel section for rebinding keyboard shortcuts
+    fn keybindings_ui(&mut self, ui: &mut egui::Ui) {
+        // A pending rebind takes the next key press; Escape cancels
+        if let Some(action) = self.rebinding {
+            if let Some(shortcut) = keybindings::pressed_shortcut(ui.ctx()) {
+                if shortcut.key != egui::Key::Escape {
+                    self.keymap.set(action, Some(shortcut));
+                }
+                self.rebinding = None;
+            }
+        }
+
+        match keybindings::bindings_grid(ui, "keybindings", self.keymap.bindings(), self.rebinding)
+        {
+            Some(GridEdit::Rebind(action)) => self.rebinding = Some(action),
+            Some(GridEdit::Clear(action)) => self.keymap.set(action, None),
+            None => {}
+        }
+        if ui.button(tr("Reset to defaults")).clicked() {
+            self.keymap = keybindings::Keymap::default();
+            self.rebinding = None;
+        }
+    }
+
+    /// Settings panel section for hotkeys that work while other apps have focus
+    fn hotkeys_ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(action) = self.rebinding_hotkey {
+            if let Some(shortcut) = keybindings::pressed_shortcut(ui.ctx()) {
+                if shortcut.key != egui::Key::Escape {
+                    self.hotkeys.set(action, Some(shortcut));
+                }
+                self.rebinding_hotkey = None;
+            }
+        }
+
+        let mut enabled = self.hotkeys.is_enabled();
+        if ui
+            .checkbox(&mut enabled, tr("Global hotkeys"))
+            .on_hover_text(tr(
+                "Control playback, freeze, screenshots and presets while another \
+                 app, such as a DAW, has focus",
+            ))
+            .changed()
+        {
+            self.hotkeys.set_enabled(enabled);
+        }
+        match keybindings::bindings_grid(
+            ui,
+            "global_hotkeys",
+            self.hotkeys.bindings(),
+            self.rebinding_hotkey,
+        ) {
+            Some(GridEdit::Rebind(action)) => self.rebinding_hotkey = Some(action),
+            Some(GridEdit::Clear(action)) => self.hotkeys.set(action, None),
+            None => {}
+        }
+        if ui.button(tr("Reset to defaults")).clicked() {
+            self.hotkeys.reset();
+            self.rebinding_hotkey = None;
+        }
+        ui.small(&self.hotkeys.status);
+    }
+
+    /// Morph from the current look to the morph target preset
+    fn start_preset_morph(&mut self) {
+        let current = presets::Preset::from_app(self);
+        if let Err(e) = self.presets.start_morph(current) {
+            self.presets.status = trf("Error: {}", &[&e]);
+        }
+    }
+
+    /// Profile key for the current source
+    fn source_key(&self) -> String {
+        match self.input_mode {
+            InputMode::Live => format!(
+                "Live: {}",
+                self.audio
+                    .devices
+                    .get(self.audio.selected_device)
+                    .map(String::as_str)
+                    .unwrap_or("None")
+            ),
+            InputMode::File => "File".to_string(),
+            InputMode::Generator => "Generator".to_string(),
+        }
+    }
+
+    /// Swap in the remembered look when the source changes
+    fn update_source_profile(&mut self) {
+        if !self.profiles.enabled {
+            return;
+        }
+        let key = self.source_key();
+        if self.profiles.active.as_deref() == Some(key.as_str()) {
+            return;
+        }
+        let current = presets::Preset::from_app(self);
+        if let Some(profile) = self.profiles.switch(&key, current) {
+            self.presets.morph = None;
+            profile.apply(self);
+        }
+    }
+
+    /// Flip between the A and B looks
+    fn switch_ab(&mut self) {
+        // A running morph would overwrite the look we switch to
+        self.presets.morph = None;
+        let next = self.ab.switch(presets::Preset::from_app(self));
+        next.apply(self);
+    }
+
+    /// Advance a running preset morph
+    fn update_preset_morph(&mut self) {
+        if let Some(morph) = self.presets.morph.take() {
+            if !morph.step(self) {
+                self.presets.morph = Some(morph);
+            }
+        }
+    }
+
+    /// Load the preset called `name` and make it the current one
+    fn load_preset(&mut self, name: &str) {
+        match self.presets.load(name) {
+            Ok(preset) => {
+                preset.apply(self);
+                self.presets.current = Some(name.to_string());
+                self.presets.status = trf("Loaded '{}'", &[&name]);
+            }
+            Err(e) => self.presets.status = trf("Error: {}", &[&e]),
+        }
+    }
+
+    /// Save the current look as the preset called `name`
+    fn save_preset(&mut self, name: &str) {
+        let preset = presets::Preset::from_app(self);
+        self.presets.pending_overwrite = None;
+        self.presets.status = match self.presets.save(name, &preset) {
+            Ok(()) => {
+                self.presets.new_name.clear();
+                self.presets.current = Some(name.to_string());
+                trf("Saved '{}'", &[&name])
+            }
+            Err(e) => trf("Error: {}", &[&e]),
+        };
+    }
+
+    /// Settings panel section for saving and recalling presets
+    fn presets_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let selected_text = self
+                .presets
+                .selected_name()
+                .unwrap_or(tr("(none)"))
+                .to_string();
+            egui::ComboBox::from_id_salt("preset_select")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for (i, name) in self.presets.names.iter().enumerate() {
+                        ui.selectable_value(&mut self.presets.selected, i, name);
+                    }
+                });
+
+            let name = self.presets.selected_name().map(str::to_string);
+            if ui
+                .add_enabled(name.is_some(), egui::Button::new(tr("Load")))
+                .clicked()
+            {
+                if let Some(name) = &name {
+                    self.load_preset(name);
+                }
+            }
+            if ui
+                .add_enabled(name.is_some(), egui::Button::new("🗑"))
+                .on_hover_text(tr("Delete preset"))
+                .clicked()
+            {
+                if let Some(name) = &name {
+                    self.presets.status = match self.presets.delete(name) {
+                        Ok(()) => trf("Deleted '{}'", &[&name]),
+                        Err(e) => trf("Error: {}", &[&e]),
+                    };
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.presets.new_name)
+                    .hint_text(tr("Preset name"))
+                    .desired_width(120.0),
+            );
+            if ui.button(tr("Save")).clicked() {
+                let name = self.presets.new_name.trim().to_string();
+                if self.presets.exists(&name) {
+                    self.presets.pending_overwrite = Some(name);
+                } else {
+                    self.save_preset(&name);
+                }
+            }
+        });
+
+        // Ask before replacing a preset, for as long as that name is typed
+        let typed = self.presets.new_name.trim().to_string();
+        if let Some(name) = self.presets.pending_overwrite.clone() {
+            if name != typed {
+                self.presets.pending_overwrite = None;
+            } else {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        trf("'{}' exists. Replace it?", &[&name]),
+                    );
+                    if ui.button(tr("Replace")).clicked() {
+                        self.save_preset(&name);
+                    }
+                    if ui.button(tr("Cancel")).clicked() {
+                        self.presets.pending_overwrite = None;
+                    }
+                });
+            }
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Morph to:"));
+            let target_text = self
+                .presets
+                .names
+                .get(self.presets.morph_target)
+                .map(String::as_str)
+                .unwrap_or(tr("(none)"))
+                .to_string();
+            egui::ComboBox::from_id_salt("preset_morph_target")
+                .selected_text(target_text)
+                .show_ui(ui, |ui| {
+                    for (i, name) in self.presets.names.iter().enumerate() {
+                        ui.selectable_value(&mut self.presets.morph_target, i, name);
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::Slider::new(&mut self.presets.morph_seconds, 0.0..=30.0)
+                    .suffix(" s")
+                    .text(tr("over")),
+            );
+            if ui
+                .add_enabled(
+                    !self.presets.names.is_empty(),
+                    egui::Button::new(tr("Morph")),
+                )
+                .on_hover_text(self.keymap.hint(Action::PresetMorph))
+                .clicked()
+            {
+                self.start_preset_morph();
+            }
+        });
+        ui.small(tr(
+            "Each morph swaps the two presets, so morphing again goes back",
+        ));
+
+        ui.separator();
+
+        if ui
+            .checkbox(&mut self.profiles.enabled, tr("Separate look per source"))
+            .on_hover_text(
+                "Remember display, gain and volume for each input device, \
+                 file playback and the generator, and restore them when switching",
+            )
+            .changed()
+        {
+            // Start tracking from whatever is shown now
+            self.profiles.active = None;
+        }
+        if self.profiles.enabled {
+            ui.small(trf("Profile: {}", &[&self.source_key()]));
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Compare:"));
+            for (slot, name) in ["A", "B"].into_iter().enumerate() {
+                if ui
+                    .selectable_label(self.ab.active == slot, name)
+                    .on_hover_text(self.keymap.hint(Action::AbCompare))
+                    .clicked()
+                    && self.ab.active != slot
+                {
+                    self.switch_ab();
+                }
+            }
+            let other = if self.ab.active == 0 { "B" } else { "A" };
+            if ui.button(trf("Copy to {}", &[&other])).clicked() {
+                self.ab.copy_to_other(presets::Preset::from_app(self));
+            }
+        });
+
+        if !self.presets.status.is_empty() {
+            ui.small(&self.presets.status);
+        }
+    }
+
+    /// Settings panel section for the generator
+    fn generator_settings_ui(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Source:"));
+            egui::ComboBox::from_id_salt("generator_kind")
+                .selected_text(tr(self.generator_kind.name()))
+                .show_ui(ui, |ui| {
+                    for kind in generator::GeneratorKind::all() {
+                        changed |= ui
+                            .selectable_value(&mut self.generator_kind, *kind, tr(kind.name()))
+                            .changed();
+                    }
+                });
+
+            if ui.button(tr("📂 Import .osci")).clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("osci-render project", &["osci"])
+                    .pick_file()
+                {
+                    self.import_osci_project(&path);
+                }
+            }
+        });
+        if !self.project_status.is_empty() {
+            ui.small(&self.project_status);
+        }
+
+        ui.separator();
+
+        changed |= match self.generator_kind {
+            generator::GeneratorKind::Shape => self.shape_settings_ui(ui),
+            generator::GeneratorKind::Function => self.function_settings_ui(ui),
+            generator::GeneratorKind::Sweep => self.sweep_settings_ui(ui),
+            generator::GeneratorKind::Noise => self.noise_settings_ui(ui),
+            generator::GeneratorKind::Text => self.text_settings_ui(ui),
+            generator::GeneratorKind::Svg => self.svg_settings_ui(ui),
+            generator::GeneratorKind::Model => self.model_settings_ui(ui),
+        };
+
+        ui.separator();
+
+        // Function and noise levels are absolute
+        let calibrated = matches!(
+            self.generator_kind,
+            generator::GeneratorKind::Function | generator::GeneratorKind::Noise
+        );
+        ui.add_enabled_ui(!calibrated, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Output level:"));
+                if ui
+                    .add(egui::Slider::new(&mut self.generator.volume, 0.0..=1.0))
+                    .on_disabled_hover_text(tr("Levels are set in dBFS above"))
+                    .changed()
+                {
+                    self.generator.sync_volume();
+                }
+            });
+        });
+
+        if ui.button(tr("Calibration…")).clicked() {
+            self.show_calibration = true;
+        }
+
+        if changed {
+            self.rebuild_generator();
+        }
+    }
+
+    /// Parametric shape controls. Returns true if the shape has to be
+    /// rebuilt; slider moves go straight to the running one.
+    fn shape_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Shape:"));
+            egui::ComboBox::from_id_salt("shape_preset")
+                .selected_text(tr(self.shape.preset.name()))
+                .show_ui(ui, |ui| {
+                    for preset in generator::ShapePreset::all() {
+                        if ui
+                            .selectable_label(self.shape.preset == *preset, tr(preset.name()))
+                            .clicked()
+                        {
+                            self.shape.apply_preset(*preset);
+                            changed = true;
+                        }
+                    }
+                });
+        });
+        ui.small(tr(self.shape.preset.help()));
+
+        ui.horizontal(|ui| {
+            ui.label("x(t) =");
+            if ui.text_edit_singleline(&mut self.shape.x_expr).changed() {
+                self.shape.preset = generator::ShapePreset::Custom;
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("y(t) =");
+            if ui.text_edit_singleline(&mut self.shape.y_expr).changed() {
+                self.shape.preset = generator::ShapePreset::Custom;
+                changed = true;
+            }
+        });
+        if let Some(err) = &self.shape_error {
+            ui.colored_label(egui::Color32::LIGHT_RED, err);
+        }
+
+        let mut tuned = false;
+        ui.horizontal(|ui| {
+            ui.label("a:");
+            tuned |= ui
+                .add(egui::Slider::new(&mut self.shape.a, 0.0..=10.0))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("b:");
+            tuned |= ui
+                .add(egui::Slider::new(&mut self.shape.b, 0.0..=10.0))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("c:");
+            tuned |= ui
+                .add(egui::Slider::new(
+                    &mut self.shape.c,
+                    0.0..=std::f32::consts::TAU,
+                ))
+                .changed();
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Frequency:"));
+            tuned |= ui
+                .add(
+                    egui::Slider::new(&mut self.shape.frequency, 1.0..=2000.0)
+                        .logarithmic(true)
+                        .suffix(" Hz"),
+                )
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Amplitude:"));
+            tuned |= ui
+                .add(egui::Slider::new(&mut self.shape.amplitude, 0.0..=1.0))
+                .changed();
+        });
+
+        // The equations stay as they are, so the running shape can carry on
+        if tuned && !changed {
+            match &self.shape_controls {
+                Some(controls) => controls.set(&self.shape),
+                None => changed = true,
+            }
+        }
+
+        changed
+    }
+
+    /// Sweep controls. Returns true if anything changed.
+    fn sweep_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label(tr("From:"));
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.sweep.start_hz)
+                        .range(1.0..=20000.0)
+                        .suffix(" Hz"),
+                )
+                .changed();
+            ui.label(tr("To:"));
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.sweep.end_hz)
+                        .range(1.0..=24000.0)
+                        .suffix(" Hz"),
+                )
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Duration:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.sweep.duration, 0.5..=30.0).suffix(" s"))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Level:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.sweep.level_db, -60.0..=0.0).suffix(" dBFS"))
+                .changed();
+        });
+        changed |= ui.checkbox(&mut self.sweep.repeat, tr("Repeat")).changed();
+
+        ui.separator();
+
+        if let Some(capture) = &self.sweep_capture {
+            ui.add(egui::ProgressBar::new(capture.progress()).text(tr("Measuring...")));
+        } else {
+            let can_measure = self.audio.is_capturing();
+            if ui
+                .add_enabled(can_measure, egui::Button::new(tr("Measure response")))
+                .on_disabled_hover_text(tr("Start live capture on the return input first"))
+                .clicked()
+            {
+                self.start_sweep_measurement();
+            }
+            if !self.sweep_response.is_empty() && ui.button(tr("Show last response")).clicked() {
+                self.show_sweep_response = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Text controls. Returns true if anything changed.
+    fn text_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.label(tr("Text:"));
+        changed |= ui
+            .add(egui::TextEdit::multiline(&mut self.text.text).desired_rows(2))
+            .changed();
+        ui.horizontal(|ui| {
+            ui.label(tr("Refresh:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.text.refresh_hz, 10.0..=200.0).suffix(" Hz"))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Size:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.text.amplitude, 0.1..=1.0))
+                .changed();
+        });
+        ui.small(tr("Longer text needs a lower refresh rate to stay sharp"));
+
+        changed
+    }
+
+    /// SVG import and export controls. Returns true if anything changed.
+    fn svg_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            if ui.button(tr("📂 Import SVG")).clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("SVG", &["svg"])
+                    .pick_file()
+                {
+                    self.svg.file = Some(path);
+                    self.load_svg();
+                    changed = true;
+                }
+            }
+            if let Some(name) = self.svg.file.as_ref().and_then(|f| f.file_name()) {
+                ui.label(name.to_string_lossy());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Refresh:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.svg.refresh_hz, 10.0..=200.0).suffix(" Hz"))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Size:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.svg.amplitude, 0.1..=1.0))
+                .changed();
+        });
+
+        let rate = self.generator.sample_rate();
+        ui.small(trf(
+            "{} points per frame at {} Hz",
+            &[&(rate as f32 / self.svg.refresh_hz).round(), &rate],
+        ));
+
+        ui.horizontal(|ui| {
+            let enabled = !self.svg_drawing.is_empty();
+            if ui
+                .add_enabled(enabled, egui::Button::new(tr("💾 Export WAV (10 s)")))
+                .clicked()
+            {
+                self.export_svg_wav(rate, 10.0);
+            }
+        });
+
+        if !self.svg_status.is_empty() {
+            ui.small(&self.svg_status);
+        }
+
+        changed
+    }
+
+    /// 3D model controls. Returns true if the source needs rebuilding.
+    fn model_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            if ui.button(tr("📂 Import OBJ")).clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Wavefront OBJ", &["obj"])
+                    .pick_file()
+                {
+                    self.model.file = Some(path);
+                    self.load_model();
+                    changed = true;
+                }
+            }
+            if let Some(name) = self.model.file.as_ref().and_then(|f| f.file_name()) {
+                ui.label(name.to_string_lossy());
+            }
+        });
+
+        // Spin changes apply to the running source without a rebuild
+        let mut spin_changed = false;
+        for (axis, spin) in ["X", "Y", "Z"].iter().zip(self.model.spin.iter_mut()) {
+            ui.horizontal(|ui| {
+                ui.label(trf("Spin {}:", &[&axis]));
+                spin_changed |= ui
+                    .add(egui::Slider::new(spin, -180.0..=180.0).suffix("°/s"))
+                    .changed();
+            });
+        }
+        if spin_changed {
+            self.model.sync_spin();
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Camera distance:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.model.distance, 1.5..=10.0))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Refresh:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.model.refresh_hz, 10.0..=200.0).suffix(" Hz"))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Size:"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.model.amplitude, 0.1..=1.0))
+                .changed();
+        });
+
+        if !self.model_status.is_empty() {
+            ui.small(&self.model_status);
+        }
+
+        changed
+    }
+
+    /// Noise controls. Returns true if anything changed.
+    fn noise_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Color:"));
+            for color in generator::NoiseColor::all() {
+                changed |= ui
+                    .selectable_value(&mut self.noise.color, *color, tr(color.name()))
+                    .changed();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Level:"));
+            let max = self.noise.color.max_level_db();
+            changed |= ui
+                .add(egui::Slider::new(&mut self.noise.level_db, -60.0..=max).suffix(" dBFS RMS"))
+                .changed();
+        });
+        // Switching to pink can leave the level above what it can play
+        ui.weak(trf(
+            "Output: {} dBFS RMS, peaks up to {} dBFS",
+            &[
+                &format!("{:.1}", self.noise.output_level_db()),
+                &format!(
+                    "{:.1}",
+                    self.noise.output_level_db() + 20.0 * self.noise.color.crest_factor().log10()
+                ),
+            ],
+        ));
+        changed |= ui
+            .checkbox(
+                &mut self.noise.correlated,
+                tr("Same noise on both channels"),
+            )
+            .changed();
+
+        changed
+    }
+
+    /// Function generator controls. Returns true if anything changed.
+    fn function_settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let volume = self.generator.volume;
+
+        for (label, params) in [
+            (tr("Left (X)"), &mut self.function.left),
+            (tr("Right (Y)"), &mut self.function.right),
+        ] {
+            ui.push_id(label, |ui| {
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(&mut params.enabled, label).changed();
+                    egui::ComboBox::from_id_salt("waveform")
+                        .selected_text(tr(params.waveform.name()))
+                        .show_ui(ui, |ui| {
+                            for waveform in generator::Waveform::all() {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut params.waveform,
+                                        *waveform,
+                                        tr(waveform.name()),
+                                    )
+                                    .changed();
+                            }
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Freq:"));
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut params.frequency, 1.0..=20000.0)
+                                .logarithmic(true)
+                                .suffix(" Hz"),
+                        )
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Level:"));
+                    changed |= ui
+                        .add(egui::Slider::new(&mut params.level_db, -60.0..=0.0).suffix(" dBFS"))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Phase:"));
+                    changed |= ui
+                        .add(egui::Slider::new(&mut params.phase_deg, -180.0..=180.0).suffix("°"))
+                        .changed();
+                });
+
+                // Actual peak at the output, including the output level control
+                let peak = params.amplitude() * volume;
+                if peak > 0.0 {
+                    ui.small(trf(
+                        "Output peak: {} dBFS",
+                        &[&format!("{:.1}", 20.0 * peak.log10())],
+                    ));
+                }
+            });
+            ui.add_space(4.0);
+        }
+
+        changed
+    }
+
+    /// Waveform overview, transport and loop controls for the loaded file
+    fn playback_ui(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(4.0);
+
+        // Waveform overview / seek bar
+        let available_width = ui.available_width();
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(available_width, 40.0),
+            egui::Sense::click_and_drag(),
+        );
+        let rect = response.rect;
+
+        // Draw background
+        painter.rect_filled(rect, 4.0, egui::Color32::from_gray(30));
+
+        // Draw waveform
+        if !self.file_player.waveform.is_empty() {
+            let waveform = &self.file_player.waveform;
+            let center_y = rect.center().y;
+            let height = rect.height() * 0.4;
+
+            for (i, (x, y)) in waveform.iter().enumerate() {
+                let t = i as f32 / waveform.len() as f32;
+                let screen_x = rect.left() + t * rect.width();
+
+                // Draw both channels
+                let amp_x = x.abs().min(1.0) * height;
+                let amp_y = y.abs().min(1.0) * height;
+
+                painter.line_segment(
+                    [
+                        egui::pos2(screen_x, center_y - amp_x),
+                        egui::pos2(screen_x, center_y + amp_y),
+                    ],
+                    egui::Stroke::new(1.0, egui::Color32::from_rgb(80, 120, 80)),
+                );
+            }
+        }
+
+        // Draw playhead
+        let position = self.file_player.position_fraction();
+        let playhead_x = rect.left() + position * rect.width();
+        painter.line_segment(
+            [
+                egui::pos2(playhead_x, rect.top()),
+                egui::pos2(playhead_x, rect.bottom()),
+            ],
+            egui::Stroke::new(2.0, egui::Color32::WHITE),
+        );
+
+        // Two-finger swipes (touchscreen, or touchpad scrolling) nudge the
+        // position; the width of the bar is the whole file
+        let swipe = ui.input(|i| match i.multi_touch() {
+            Some(touch) if rect.contains(touch.start_pos) => touch.translation_delta.x,
+            None if response.hovered() => i.smooth_scroll_delta.x,
+            _ => 0.0,
+        });
+        if swipe != 0.0 {
+            self.file_player
+                .seek((position + swipe / rect.width()).clamp(0.0, 1.0));
+        }
+
+        // Handle seeking
+        let multi_touch = ui.input(|i| i.multi_touch().is_some());
+        if (response.dragged() || response.clicked()) && !multi_touch {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let seek_fraction = (pos.x - rect.left()) / rect.width();
+                self.file_player.seek(seek_fraction);
+            }
+        }
+
+        ui.add_space(4.0);
+
+        // Playback controls
+        ui.horizontal(|ui| {
+            // Play/Pause button
+            let play_text = match self.file_player.state() {
+                PlaybackState::Playing => "⏸",
+                _ => "▶",
+            };
+            if ui.button(play_text).clicked() {
+                self.file_player.toggle();
+            }
+
+            // Stop button
+            if ui.button("⏹").clicked() {
+                self.file_player.stop();
+            }
+
+            ui.separator();
+
+            // Time display
+            let current = self.file_player.position_duration();
+            let total = self
+                .file_player
+                .info
+                .as_ref()
+                .map(|i| i.duration)
+                .unwrap_or(Duration::ZERO);
+            ui.label(format!(
+                "{} / {}",
+                format_duration(current),
+                format_duration(total)
+            ));
+
+            ui.separator();
+
+            // Volume
+            ui.label(tr("Vol:"));
+            if ui
+                .add(egui::Slider::new(&mut self.file_player.volume, 0.0..=2.0).show_value(false))
+                .changed()
+            {
+                self.file_player.sync_volume();
+            }
+
+            ui.separator();
+
+            // Speed
+            ui.label(tr("Speed:"));
+            ui.add(egui::Slider::new(&mut self.file_player.speed, 0.25..=2.0).show_value(false));
+            ui.label(format!("{:.1}x", self.file_player.speed));
+
+            ui.separator();
+
+            // Loop toggle
+            ui.checkbox(&mut self.file_player.loop_playback, tr("Loop"));
+        });
+
+        ui.add_space(4.0);
+    }
+
+    /// Everything in the settings panel
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing(tr("Presets"), |ui| {
+            self.presets_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Audio"), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Gain:"));
+                if ui
+                    .add(egui::Slider::new(&mut self.audio.gain, 0.1..=10.0).logarithmic(true))
+                    .changed()
+                {
+                    self.audio.sync_gain();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Buffer size:"));
+                let current = self.consumer.capacity();
+                egui::ComboBox::from_id_salt("buffer_size")
+                    .selected_text(format_sample_count(current))
+                    .show_ui(ui, |ui| {
+                        for &size in SampleBuffer::CAPACITIES {
+                            if ui
+                                .selectable_label(size == current, format_sample_count(size))
+                                .clicked()
+                            {
+                                self.set_buffer_size(size);
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text(tr(
+                        "Samples kept for the display; the longest window you can show",
+                    ));
+            });
+
+            if ui
+                .checkbox(
+                    &mut self.file_player.av_sync,
+                    tr("Sync display to audio output"),
+                )
+                .on_hover_text(tr(
+                    "When playing a file, hold samples back until they are heard",
+                ))
+                .changed()
+            {
+                self.file_player.sync_display_delay();
+            }
+            ui.add_enabled_ui(self.file_player.av_sync, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Display offset:"));
+                    if ui
+                        .add(
+                            egui::Slider::new(
+                                &mut self.file_player.display_offset_ms,
+                                -250.0..=250.0,
+                            )
+                            .suffix(" ms"),
+                        )
+                        .on_hover_text(tr(
+                            "Added to the measured delay; negative values show samples earlier",
+                        ))
+                        .changed()
+                    {
+                        self.file_player.sync_display_delay();
+                    }
+                });
+            });
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Generator"), |ui| {
+            self.generator_settings_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Demo Mode"), |ui| {
+            ui.checkbox(&mut self.demo.enabled, tr("Show a demo when idle"));
+            // A new scene takes over in `update_demo`
+            ui.horizontal(|ui| {
+                ui.label(tr("Scene:"));
+                egui::ComboBox::from_id_salt("demo_scene")
+                    .selected_text(tr(self.demo.scene.name()))
+                    .show_ui(ui, |ui| {
+                        for scene in generator::DemoScene::all() {
+                            ui.selectable_value(&mut self.demo.scene, *scene, tr(scene.name()));
+                        }
+                    });
+            });
+            ui.small(tr("Runs whenever the current input is stopped"));
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.demo.attract, tr("Attract sequence after"))
+                    .on_hover_text(tr(
+                        "Take over with wireframes, Lissajous sweeps and a clock once \
+                         there's been no signal and no mouse or keyboard input for a \
+                         while. Ends as soon as either comes back.",
+                    ));
+                ui.add_enabled(
+                    self.demo.attract,
+                    egui::DragValue::new(&mut self.demo.attract_after)
+                        .range(0.5..=240.0)
+                        .speed(0.1)
+                        .suffix(" min"),
+                );
+            });
+            if self.attracting {
+                ui.small(tr("Attract sequence running"));
+            }
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Silence"), |ui| {
+            let silence = &mut self.silence;
+            ui.horizontal(|ui| {
+                ui.label(tr("When silent:"));
+                egui::ComboBox::from_id_salt("silence_action")
+                    .selected_text(tr(silence.action.name()))
+                    .show_ui(ui, |ui| {
+                        for action in analysis::SilenceAction::all() {
+                            ui.selectable_value(&mut silence.action, *action, tr(action.name()));
+                        }
+                    });
+            });
+            ui.add_enabled_ui(silence.action != analysis::SilenceAction::Off, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Below:"));
+                    ui.add(
+                        egui::Slider::new(&mut silence.threshold_db, -90.0..=-20.0).suffix(" dBFS"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("For:"));
+                    ui.add(egui::Slider::new(&mut silence.delay, 0.5..=30.0).suffix(" s"));
+                });
+            });
+            ui.small(tr(
+                "Applies while an input is running but its peak level stays below the threshold",
+            ));
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Display"), |ui| {
+            // Display mode selector
+            ui.horizontal(|ui| {
+                ui.label(tr("Mode:"));
+                egui::ComboBox::from_id_salt("display_mode")
+                    .selected_text(tr(self.oscilloscope.settings.display_mode.name()))
+                    .show_ui(ui, |ui| {
+                        for mode in DisplayMode::all() {
+                            ui.selectable_value(
+                                &mut self.oscilloscope.settings.display_mode,
+                                *mode,
+                                tr(mode.name()),
+                            );
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Zoom:"));
+                ui.add(egui::Slider::new(
+                    &mut self.oscilloscope.settings.zoom,
+                    0.1..=2.0,
+                ));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Samples shown:"));
+                ui.add(
+                    egui::Slider::new(
+                        &mut self.oscilloscope.settings.sample_count,
+                        64..=self.consumer.capacity(),
+                    )
+                    .logarithmic(true),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Line width:"));
+                ui.add(egui::Slider::new(
+                    &mut self.oscilloscope.settings.line_width,
+                    0.5..=5.0,
+                ));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Intensity:"));
+                ui.add(egui::Slider::new(
+                    &mut self.oscilloscope.settings.intensity,
+                    0.1..=1.0,
+                ));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Persistence:"));
+                ui.add(egui::Slider::new(
+                    &mut self.oscilloscope.settings.persistence,
+                    0.0..=0.99,
+                ));
+            });
+
+            ui.checkbox(
+                &mut self.oscilloscope.settings.show_graticule,
+                tr("Show grid"),
+            );
+            ui.checkbox(
+                &mut self.oscilloscope.settings.decimate,
+                tr("Thin out long sample windows"),
+            )
+            .on_hover_text(tr(
+                "Draw only the extremes of each run of samples when there are \
+                 more than the display can show",
+            ));
+            ui.checkbox(
+                &mut self.oscilloscope.settings.steady_pacing,
+                tr("Steady frame pacing"),
+            )
+            .on_hover_text(tr(
+                "During file playback, move the picture on by the same number of \
+                 samples every frame, so figures don't jitter with decoding",
+            ));
+            ui.checkbox(&mut self.show_perf, tr("Performance overlay"))
+                .on_hover_text(self.keymap.hint(Action::PerfOverlay));
+            ui.checkbox(&mut self.show_phase, tr("Phase readout"))
+                .on_hover_text(tr(
+                    "Fit an ellipse to two sines of one frequency and show their \
+                     phase difference and amplitude ratio",
+                ));
+
+            if ui.button(tr("Clear persistence")).clicked() {
+                self.oscilloscope.clear_persistence();
+            }
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Channel"), |ui| {
+            ui.checkbox(&mut self.oscilloscope.settings.swap_xy, tr("Swap X/Y"));
+            ui.checkbox(&mut self.oscilloscope.settings.invert_x, tr("Invert X"));
+            ui.checkbox(&mut self.oscilloscope.settings.invert_y, tr("Invert Y"));
+
+            ui.separator();
+
+            let settings = &mut self.oscilloscope.settings;
+            ui.checkbox(&mut settings.auto_center, tr("Auto-center"))
+                .on_hover_text(tr(
+                    "Keep the middle of the figure centered, following drifting \
+                     DC or lopsided material, on top of the offsets below",
+                ));
+            let manual = !settings.auto_center;
+
+            ui.horizontal(|ui| {
+                ui.label(tr("X offset:"));
+                ui.add(egui::Slider::new(&mut settings.dc_offset_x, -1.0..=1.0));
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Y offset:"));
+                ui.add(egui::Slider::new(&mut settings.dc_offset_y, -1.0..=1.0));
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button(tr("Reset offsets")).clicked() {
+                    self.oscilloscope.settings.dc_offset_x = 0.0;
+                    self.oscilloscope.settings.dc_offset_y = 0.0;
+                }
+                let mean = self.dc_meter.mean();
+                let null = ui
+                    .add_enabled(manual && mean.is_some(), egui::Button::new(tr("Null DC")))
+                    .on_hover_text(tr(
+                        "Set the offsets to cancel the mean of the last second of input",
+                    ));
+                if let Some((x, y)) = mean.filter(|_| null.clicked()) {
+                    let settings = &mut self.oscilloscope.settings;
+                    settings.dc_offset_x = (-x).clamp(-1.0, 1.0);
+                    settings.dc_offset_y = (-y).clamp(-1.0, 1.0);
+                }
+            });
+
+            ui.separator();
+
+            let channels = self.source_channels().map_or(0, |c| c.channels());
+            let grid = &mut self.scope_grid;
+            ui.checkbox(&mut grid.enabled, tr("Grid of scopes"))
+                .on_hover_text(tr(
+                    "For sources with more than two channels, draw a scope for each \
+                     pair of channels instead of just the first two",
+                ));
+            ui.add_enabled_ui(grid.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Panes:"));
+                    egui::ComboBox::from_id_salt("grid_pairing")
+                        .selected_text(tr(grid.pairing.name()))
+                        .show_ui(ui, |ui| {
+                            for &pairing in grid::Pairing::all() {
+                                ui.selectable_value(&mut grid.pairing, pairing, tr(pairing.name()));
+                            }
+                        });
+                });
+                ui.add_enabled_ui(grid.pairing == grid::Pairing::Reference, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Reference channel:"));
+                        let mut reference = grid.reference + 1;
+                        let max = channels.max(grid.reference + 1).max(1);
+                        if ui
+                            .add(egui::DragValue::new(&mut reference).range(1..=max))
+                            .changed()
+                        {
+                            grid.reference = reference - 1;
+                        }
+                    });
+                });
+            });
+            if channels > 2 {
+                ui.small(trf("Source channels: {}", &[&channels]));
+            }
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Trigger"), |ui| {
+            let trigger = &mut self.trigger;
+            ui.checkbox(&mut trigger.enabled, tr("Trigger"))
+                .on_hover_text(tr(
+                    "Start the window at a trigger, so it starts at the same point of \
+                     the figure every frame. Needs a buffer longer than the window.",
+                ));
+            ui.add_enabled_ui(trigger.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Source:"));
+                    egui::ComboBox::from_id_salt("trigger_source")
+                        .selected_text(tr(trigger.source.name()))
+                        .show_ui(ui, |ui| {
+                            for &source in TriggerSource::all() {
+                                ui.selectable_value(&mut trigger.source, source, tr(source.name()));
+                            }
+                        });
+                });
+                let signal = trigger.source == TriggerSource::Signal;
+                ui.add_enabled_ui(signal, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Level:"));
+                        ui.add(egui::Slider::new(&mut trigger.level, -1.0..=1.0));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Hysteresis:"));
+                        ui.add(egui::Slider::new(&mut trigger.hysteresis, 0.0..=0.5))
+                            .on_hover_text(tr(
+                                "How far X has to fall below the level before the trigger \
+                                 re-arms; raise it if noise makes the picture jump",
+                            ));
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Holdoff:"));
+                    let mut ms = trigger.holdoff * 1000.0;
+                    if ui
+                        .add(egui::Slider::new(&mut ms, 0.0..=500.0).suffix(" ms"))
+                        .on_hover_text(tr(
+                            "Ignore further triggers for this long after one, to lock \
+                             onto the same edge of signals that cross the level several \
+                             times per period",
+                        ))
+                        .changed()
+                    {
+                        trigger.holdoff = ms / 1000.0;
+                    }
+                });
+                if trigger.is_triggered() {
+                    ui.small(tr("Triggered"));
+                } else {
+                    ui.small(tr("No trigger, showing the newest samples"));
+                }
+            });
+            if self.trigger.enabled && self.trigger.source == TriggerSource::External {
+                self.external_trigger_ui(ui);
+            }
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Color"), |ui| {
+            // Theme selector
+            ui.horizontal(|ui| {
+                ui.label(tr("Theme:"));
+                egui::ComboBox::from_id_salt("color_theme")
+                    .selected_text(tr(self.oscilloscope.settings.theme.name()))
+                    .show_ui(ui, |ui| {
+                        for theme in ColorTheme::all() {
+                            if ui
+                                .selectable_label(
+                                    self.oscilloscope.settings.theme == *theme,
+                                    tr(theme.name()),
+                                )
+                                .clicked()
+                            {
+                                self.oscilloscope.settings.apply_theme(*theme);
+                            }
+                        }
+                    });
+            });
+
+            // A theme after the loaded file's cover art
+            let artwork = self
+                .file_player
+                .info
+                .as_ref()
+                .map(|info| info.artwork.as_slice())
+                .filter(|colors| !colors.is_empty());
+            if let Some(colors) = artwork {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Cover art:"));
+                    for &[r, g, b] in colors {
+                        let (rect, _) =
+                            ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                        ui.painter()
+                            .rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+                    }
+                    if let Some(theme) = ColorTheme::from_palette(colors) {
+                        if ui
+                            .button(tr("Apply"))
+                            .on_hover_text(tr("Color the scope after the cover art"))
+                            .clicked()
+                        {
+                            self.oscilloscope.settings.apply_theme(theme);
+                        }
+                    }
+                });
+            }
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Modulation"), |ui| {
+            self.modulation_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("MIDI"), |ui| {
+            // Port selector
+            ui.horizontal(|ui| {
+                ui.label(tr("Port:"));
+                egui::ComboBox::from_id_salt("midi_port")
+                    .selected_text(
+                        self.midi
+                            .ports
+                            .get(self.midi.selected_port)
+                            .cloned()
+                            .unwrap_or_else(|| tr("None").to_string()),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (i, name) in self.midi.ports.iter().enumerate() {
+                            ui.selectable_value(&mut self.midi.selected_port, i, name);
+                        }
+                    });
+            });
+
+            ui.horizontal(|ui| {
+                let button_text = if self.midi.is_connected {
+                    tr("Disconnect")
+                } else {
+                    tr("Connect")
+                };
+                if ui.button(button_text).clicked() {
+                    self.midi.toggle();
+                }
+                if ui.button(tr("Refresh")).clicked() {
+                    self.midi.scan_ports();
+                }
+            });
+
+            ui.small(&self.midi.status);
+            ui.separator();
+
+            // Mappings
+            ui.label(tr("Mappings:"));
+
+            // Snapshot mapping info to avoid borrow conflicts
+            let mapping_info: Vec<_> = self
+                .midi
+                .mappings
+                .iter()
+                .enumerate()
+                .map(|(i, m)| (i, m.cc, tr(m.param.name())))
+                .collect();
+            let learning = self.midi.learning;
+
+            let mut remove_idx = None;
+            let mut learn_idx = None;
+            let mut cancel_learn = false;
+
+            for (i, cc, param_name) in &mapping_info {
+                ui.horizontal(|ui| {
+                    let is_learning = learning == Some(*i);
+                    let label = if is_learning {
+                        format!("CC ? -> {}", param_name)
+                    } else {
+                        format!("CC {:>3} -> {}", cc, param_name)
+                    };
+                    ui.monospace(&label);
+
+                    if is_learning {
+                        if ui.small_button(tr("Cancel")).clicked() {
+                            cancel_learn = true;
+                        }
+                    } else if ui.small_button(tr("Learn")).clicked() {
+                        learn_idx = Some(*i);
+                    }
+
+                    if ui.small_button("X").clicked() {
+                        remove_idx = Some(*i);
+                    }
+                });
+            }
+
+            // Apply deferred actions
+            if cancel_learn {
+                self.midi.cancel_learn();
+            }
+            if let Some(idx) = learn_idx {
+                self.midi.start_learn(idx);
+            }
+            if let Some(idx) = remove_idx {
+                self.midi.remove_mapping(idx);
+            }
+
+            // Add new mapping
+            let unmapped = self.midi.unmapped_params();
+            if !unmapped.is_empty() && ui.button(tr("+ Add")).clicked() {
+                self.midi.add_mapping(0, unmapped[0]);
+            }
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Keyboard"), |ui| {
+            self.keybindings_ui(ui);
+        });
+
+        ui.collapsing(tr("Global Hotkeys"), |ui| {
+            self.hotkeys_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Output"), |ui| {
+            self.overlay_ui(ui);
+
+            ui.separator();
+
+            self.branding_ui(ui);
+
+            ui.separator();
+
+            self.export_naming_ui(ui);
+
+            ui.separator();
+
+            self.file_render_ui(ui);
+
+            ui.separator();
+
+            self.recording_ui(ui);
+
+            ui.separator();
+
+            self.obs_ui(ui);
+
+            ui.separator();
+
+            ui.checkbox(&mut self.frame_output.enabled, tr("Shared memory frames"));
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Name:"));
+                ui.text_edit_singleline(&mut self.frame_output.name);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Size:"));
+                let range =
+                    output::SharedFrameOutput::MIN_SIZE..=output::SharedFrameOutput::MAX_SIZE;
+                ui.add(egui::DragValue::new(&mut self.frame_output.width).range(range.clone()));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut self.frame_output.height).range(range));
+            });
+
+            ui.checkbox(&mut self.frame_output.clean_feed, tr("Clean feed"))
+                .on_hover_text(tr(CLEAN_FEED_HINT));
+
+            ui.small(&self.frame_output.status);
+            if self.frame_output.enabled {
+                ui.small(trf("Frames: {}", &[&self.frame_output.frames_published()]));
+            }
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Projector"), |ui| {
+            self.projector_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Interface"), |ui| {
+            self.interface_settings_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Layout"), |ui| {
+            self.layout_settings_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Startup"), |ui| {
+            self.startup_settings_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("System Tray"), |ui| {
+            self.tray_settings_ui(ui);
+        });
+
+        ui.separator();
+
+        ui.collapsing(tr("Import / Export"), |ui| {
+            ui.small(tr("Settings, MIDI mappings, key bindings and presets"));
+            ui.horizontal(|ui| {
+                if ui.button(tr("Export settings…")).clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("scope-rs settings", &["json"])
+                        .set_file_name("scope-rs-settings.json")
+                        .save_file()
+                    {
+                        self.export_settings(&path);
+                    }
+                }
+                if ui.button(tr("Import settings…")).clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("scope-rs settings", &["json"])
+                        .pick_file()
+                    {
+                        self.import_settings(&path);
+                    }
+                }
+            });
+            if let Some((path, bundle, conflicts)) = self.pending_import.take() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    trf(
+                        "The file has presets you already have: {}",
+                        &[&conflicts.join(", ")],
+                    ),
+                );
+                let (replace, keep, cancel) = ui
+                    .horizontal(|ui| {
+                        (
+                            ui.button(tr("Replace mine")).clicked(),
+                            ui.button(tr("Keep mine")).clicked(),
+                            ui.button(tr("Cancel")).clicked(),
+                        )
+                    })
+                    .inner;
+                if replace || keep {
+                    self.apply_import(&path, &bundle, replace);
+                } else if !cancel {
+                    self.pending_import = Some((path, bundle, conflicts));
+                }
+            }
+            if !self.settings_status.is_empty() {
+                ui.small(&self.settings_status);
+            }
+        });
+    }
+
+    /// Settings panel section for the track info overlay
+    fn overlay_ui(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.overlay.settings;
+        ui.checkbox(&mut settings.enabled, tr("Track info overlay"))
+            .on_hover_text(tr(
+                "Timecode, title and tempo over the scope, also in recordings \
+                     and shared memory frames",
+            ));
+        ui.add_enabled_ui(settings.enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.timecode, tr("Timecode"));
+                ui.checkbox(&mut settings.title, tr("Title"));
+                ui.checkbox(&mut settings.bpm, tr("BPM"));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Corner:"));
+                egui::ComboBox::from_id_salt("overlay_corner")
+                    .selected_text(tr(settings.corner.name()))
+                    .show_ui(ui, |ui| {
+                        for &corner in render::OverlayCorner::all() {
+                            ui.selectable_value(&mut settings.corner, corner, tr(corner.name()));
+                        }
+                    });
+            });
+            ui.add(
+                egui::Slider::new(&mut settings.size, 0.01..=0.1)
+                    .text(tr("Text size"))
+                    .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+            );
+            ui.add(egui::Slider::new(&mut settings.weight, 0.05..=0.3).text(tr("Stroke weight")));
+            ui.add(egui::Slider::new(&mut settings.opacity, 0.0..=1.0).text(tr("Opacity")));
+        });
+    }
+
+    /// Settings panel section for the branding text and logo
+    fn branding_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.branding.settings.enabled, tr("Branding"))
+            .on_hover_text(tr(
+                "Your own text and logo over the scope, also in screenshots, \
+                     recordings and shared memory frames",
+            ));
+        ui.add_enabled_ui(self.branding.settings.enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Text:"));
+                ui.text_edit_singleline(&mut self.branding.settings.text);
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Logo:"));
+                match &self.branding.settings.logo {
+                    Some(path) => ui.label(
+                        path.file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into_owned(),
+                    ),
+                    None => ui.label(tr("None")),
+                };
+                if ui.button(tr("Choose…")).clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("PNG / JPEG", &["png", "jpg", "jpeg"])
+                        .pick_file()
+                    {
+                        self.branding_status = match self.branding.set_logo(Some(path)) {
+                            Ok(()) => String::new(),
+                            Err(e) => {
+                                log::error!("Couldn't load logo: {}", e);
+                                e.to_string()
+                            }
+                        };
+                    }
+                }
+                if ui
+                    .add_enabled(
+                        self.branding.settings.logo.is_some(),
+                        egui::Button::new(tr("Remove")),
+                    )
+                    .clicked()
+                {
+                    let _ = self.branding.set_logo(None);
+                    self.branding_status.clear();
+                }
+            });
+            let settings = &mut self.branding.settings;
+            ui.horizontal(|ui| {
+                ui.label(tr("Corner:"));
+                egui::ComboBox::from_id_salt("branding_corner")
+                    .selected_text(tr(settings.corner.name()))
+                    .show_ui(ui, |ui| {
+                        for &corner in render::OverlayCorner::all() {
+                            ui.selectable_value(&mut settings.corner, corner, tr(corner.name()));
+                        }
+                    });
+            });
+            ui.add(
+                egui::Slider::new(&mut settings.scale, 0.02..=0.3)
+                    .text(tr("Scale"))
+                    .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+            );
+            ui.add(egui::Slider::new(&mut settings.opacity, 0.0..=1.0).text(tr("Opacity")));
+        });
+        if !self.branding_status.is_empty() {
+            ui.small(&self.branding_status);
+        }
+    }
+
+    /// This frame's overlay text
+    fn update_overlay(&mut self) {
+        if !self.overlay.settings.enabled {
+            return;
+        }
+        let position = (self.input_mode == InputMode::File
+            && self.file_player.info.is_some()
+            && !self.demo_engine.is_running())
+        .then(|| self.file_player.position_duration());
+        // Other sources count from when they started
+        let timecode = position.or_else(|| {
+            let rate = self.display_sample_rate()?;
+            Some(Duration::from_secs_f64(
+                self.consumer.drained() as f64 / rate.max(1) as f64,
+            ))
+        });
+        let title = self.source_title();
+        let bpm = self.analysis.latest().beats.bpm;
+        self.overlay.set_text(Some(&title), timecode, bpm);
+    }
+
+    /// Settings panel section for where exports go and their names
+    fn export_naming_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Export folder:"));
+            let directory = self.export_naming.directory();
+            ui.label(directory.display().to_string());
+        });
+        ui.horizontal(|ui| {
+            if ui.button(tr("Choose…")).clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_directory(self.export_naming.directory())
+                    .pick_folder()
+                {
+                    self.export_naming.directory = Some(path);
+                }
+            }
+            if ui
+                .add_enabled(
+                    self.export_naming.directory.is_some(),
+                    egui::Button::new(tr("Pictures folder")),
+                )
+                .clicked()
+            {
+                self.export_naming.directory = None;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("File name:"));
+            ui.text_edit_singleline(&mut self.export_naming.template)
+                .on_hover_ui(|ui| {
+                    ui.label(tr("Slashes make subfolders. Tokens:"));
+                    for (token, meaning) in output::TOKENS {
+                        ui.small(format!("{token}  {}", tr(meaning)));
+                    }
+                });
+        });
+        let now = Instant::now();
+        if self.naming_preview.is_stale(&self.export_naming, now) {
+            let fields = self.name_fields();
+            self.naming_preview
+                .refresh(&self.export_naming, &fields, now);
+        }
+        let next = self.naming_preview.path();
+        let relative = next
+            .strip_prefix(self.export_naming.directory())
+            .unwrap_or(next);
+        ui.small(trf("Next: {}", &[&relative.display()]));
+    }
+
+    /// Settings panel section for rendering part of the file offline, as
+    /// an image sequence or a long exposure
+    fn file_render_ui(&mut self, ui: &mut egui::Ui) {
+        let info = self.file_player.info.as_ref();
+        let duration = info.map_or(Duration::ZERO, |info| info.duration);
+        let rate = info.map_or(0, |info| info.sample_rate);
+        let sequence = &mut self.sequence;
+        // A new file, or none yet: the whole of it
+        if sequence.end.is_zero() || sequence.end > duration {
+            sequence.start = Duration::ZERO;
+            sequence.end = duration;
+        }
+
+        let mut start = sequence.start.as_secs_f64();
+        let mut end = sequence.end.as_secs_f64();
+        let length = duration.as_secs_f64();
+        ui.horizontal(|ui| {
+            ui.label(tr("From:"));
+            ui.add(
+                egui::DragValue::new(&mut start)
+                    .range(0.0..=end)
+                    .speed(0.1)
+                    .suffix(" s"),
+            );
+            ui.label(tr("to:"));
+            ui.add(
+                egui::DragValue::new(&mut end)
+                    .range(start..=length)
+                    .speed(0.1)
+                    .suffix(" s"),
+            );
+            if ui.button(tr("Whole file")).clicked() {
+                (start, end) = (0.0, length);
+            }
+        });
+        sequence.start = Duration::from_secs_f64(start);
+        sequence.end = Duration::from_secs_f64(end);
+        let frames = if rate > 0 {
+            sequence.frame_count(rate)
+        } else {
+            0
+        };
+        let idle = self.render_job.is_none() && self.recording.is_none();
+
+        ui.label(tr("Image sequence"));
+        ui.horizontal(|ui| {
+            ui.label(tr("Frame rate:"));
+            ui.add(
+                egui::DragValue::new(&mut sequence.fps)
+                    .range(1..=240)
+                    .suffix(" fps"),
+            );
+            ui.label(tr("Size:"));
+            ui.add(egui::DragValue::new(&mut sequence.width).range(16..=8192));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut sequence.height).range(16..=8192));
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    idle && frames > 0,
+                    egui::Button::new(tr("Export PNG sequence")),
+                )
+                .on_disabled_hover_text(tr("Load a file to render from first"))
+                .clicked()
+            {
+                self.start_sequence();
+            }
+            if frames > 0 {
+                ui.small(trf("{} frames", &[&frames]));
+            }
+        });
+
+        ui.label(tr("Long exposure")).on_hover_text(tr(
+            "Add up the beam over the whole range into one image, like a \
+                 photo of the screen with the shutter held open",
+        ));
+        let exposure = &mut self.exposure;
+        ui.horizontal(|ui| {
+            ui.label(tr("Format:"));
+            egui::ComboBox::from_id_salt("exposure_format")
+                .selected_text(tr(exposure.format.name()))
+                .show_ui(ui, |ui| {
+                    for &format in output::ExposureFormat::all() {
+                        ui.selectable_value(&mut exposure.format, format, tr(format.name()));
+                    }
+                });
+            ui.label(tr("Size:"));
+            ui.add(egui::DragValue::new(&mut exposure.width).range(16..=16384));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut exposure.height).range(16..=16384));
+        });
+        if ui
+            .add_enabled(idle && frames > 0, egui::Button::new(tr("Expose")))
+            .on_disabled_hover_text(tr("Load a file to render from first"))
+            .clicked()
+        {
+            self.start_exposure();
+        }
+
+        if let Some(job) = &self.render_job {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::ProgressBar::new(job.progress())
+                        .show_percentage()
+                        .desired_width(180.0),
+                );
+                if ui.button(tr("Cancel")).clicked() {
+                    job.cancel();
+                }
+            });
+        }
+        if !self.render_status.is_empty() {
+            ui.small(&self.render_status);
+        }
+    }
+
+    /// Render the chosen stretch of the file as PNGs on a background
+    /// thread, into a directory named after the export template
+    fn start_sequence(&mut self) {
+        let Some(info) = &self.file_player.info else {
+            return;
+        };
+        let dir = self.export_naming.path(&self.name_fields(), "");
+        self.render_status.clear();
+        self.render_job = Some(output::start_sequence(
+            info.path.clone(),
+            dir,
+            self.sequence,
+            self.oscilloscope.settings.clone(),
+        ));
+    }
+
+    /// Expose the chosen stretch of the file on a background thread, to a
+    /// file named after the export template
+    fn start_exposure(&mut self) {
+        let Some(info) = &self.file_player.info else {
+            return;
+        };
+        let params = output::ExposureParams {
+            start: self.sequence.start,
+            end: self.sequence.end,
+            ..self.exposure
+        };
+        let path = self
+            .export_naming
+            .path(&self.name_fields(), params.format.extension());
+        self.render_status.clear();
+        self.render_job = Some(output::start_exposure(
+            info.path.clone(),
+            path,
+            params,
+            self.oscilloscope.settings.clone(),
+        ));
+    }
+
+    /// Pick up a finished sequence or exposure
+    fn update_render_job(&mut self) {
+        let Some(job) = &self.render_job else {
+            return;
+        };
+        let Some(result) = job.finished() else {
+            return;
+        };
+        self.render_status = match (result, job.kind) {
+            (Ok(frames), output::RenderKind::Sequence) => {
+                trf("Wrote {} frames to {}", &[&frames, &job.output.display()])
+            }
+            (Ok(_), output::RenderKind::Exposure | output::RenderKind::Recording) => {
+                trf("Saved {}", &[&job.output.display()])
+            }
+            (Err(e), _) => {
+                log::error!("Render failed: {}", e);
+                trf("Render failed: {}", &[&e])
+            }
+        };
+        self.render_job = None;
+    }
+
+    /// Settings panel section for recording the display to video
+    fn recording_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("Recording")).on_hover_text(tr(
+            "Record what the scope draws to MP4 with ffmpeg. Frames follow \
+                 the audio, so nothing is recorded while it's paused.",
+        ));
+        let recording = self.recording.is_some();
+        let params = &mut self.record_params;
+        ui.add_enabled_ui(!recording, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Frame rate:"));
+                ui.add(
+                    egui::DragValue::new(&mut params.fps)
+                        .range(1..=240)
+                        .suffix(" fps"),
+                );
+                ui.label(tr("Size:"));
+                ui.add(egui::DragValue::new(&mut params.width).range(16..=8192));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut params.height).range(16..=8192));
+            });
+            ui.checkbox(&mut params.audio, tr("Include audio"));
+            ui.checkbox(&mut params.clean_feed, tr("Clean feed"))
+                .on_hover_text(tr(CLEAN_FEED_HINT));
+            ui.horizontal(|ui| {
+                ui.label(tr("ffmpeg:"));
+                ui.text_edit_singleline(&mut params.ffmpeg);
+            });
+        });
+        let label = if recording {
+            tr("⏹ Stop recording")
+        } else {
+            tr("⏺ Record")
+        };
+        if ui
+            .add_enabled(
+                recording || self.render_job.is_none(),
+                egui::Button::new(label),
+            )
+            .on_hover_text(self.keymap.hint(Action::Record))
+            .on_disabled_hover_text(tr("Wait for the render to finish first"))
+            .clicked()
+        {
+            self.toggle_recording();
+        }
+    }
+
+    /// Start recording to a file named after the export template, or stop
+    /// and mux the recording in the background
+    fn toggle_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            self.render_job = Some(recording.finish());
+            return;
+        }
+        if self.render_job.is_some() {
+            self.render_status = tr("Wait for the render to finish first").to_string();
+            return;
+        }
+        let path = self.export_naming.path(&self.name_fields(), "mp4");
+        let rate = self.display_sample_rate().unwrap_or(48_000);
+        self.render_status.clear();
+        match output::Recording::start(path, self.record_params.clone(), rate) {
+            Ok(recording) => self.recording = Some(recording),
+            Err(e) => {
+                log::error!("Recording failed: {}", e);
+                self.render_status = trf("Recording failed: {}", &[&e]);
+            }
+        }
+    }
+
+    /// Hand this frame's audio and picture to the recording, if there is
+    /// one, and stop it if that fails
+    fn update_recording(&mut self, samples: &[XYSample], settings: &OscilloscopeSettings) {
+        let rate = self.display_sample_rate();
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+        // The WAV has one rate; anything else would play at the wrong pitch
+        if rate.is_some_and(|rate| rate != recording.sample_rate()) {
+            log::warn!("Recording stopped: the sample rate changed");
+            if let Some(recording) = self.recording.take() {
+                self.render_job = Some(recording.finish());
+            }
+            return;
+        }
+        let layers: [&dyn render::FrameLayer; 2] = [&self.overlay, &self.branding];
+        if let Err(e) = recording.record(&self.consumer, samples, settings, &layers) {
+            log::error!("Recording failed: {}", e);
+            self.render_status = trf("Recording failed: {}", &[&e]);
+            self.recording = None;
+        }
+    }
+
+    /// Settings for the scope sent to a display of its own
+    fn projector_ui(&mut self, ui: &mut egui::Ui) {
+        let mut open = self.projector.is_open();
+        if ui
+            .checkbox(&mut open, tr("Send to display"))
+            .on_hover_text(tr(
+                "Show the scope fullscreen on another display, such as a projector. \
+                 Escape on that display closes it.",
+            ))
+            .changed()
+        {
+            self.projector.set_open(open);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Display:"));
+            let selected = match self.projector.display.as_str() {
+                "" => tr("Any").to_string(),
+                name => name.to_string(),
+            };
+            egui::ComboBox::from_id_salt("projector_display")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.projector.display, String::new(), tr("Any"));
+                    for (i, display) in self.projector.displays.iter().enumerate() {
+                        let label = format!(
+                            "{}: {} ({}×{})",
+                            i + 1,
+                            display.name,
+                            display.size[0],
+                            display.size[1]
+                        );
+                        ui.selectable_value(
+                            &mut self.projector.display,
+                            display.name.clone(),
+                            label,
+                        );
+                    }
+                });
+            if ui
+                .button("⟳")
+                .on_hover_text(tr("Refresh displays"))
+                .clicked()
+            {
+                self.projector.refresh_displays();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Size:"));
+            let range = projector::MIN_SIZE..=projector::MAX_SIZE;
+            ui.add(egui::DragValue::new(&mut self.projector.width).range(range.clone()));
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut self.projector.height).range(range));
+            if let Some(display) = self.projector.target() {
+                let [mut width, mut height] = display.size;
+                if self.projector.rotation.is_sideways() {
+                    std::mem::swap(&mut width, &mut height);
+                }
+                if ui
+                    .button(tr("Match display"))
+                    .on_hover_text(tr("Render at the display's own resolution"))
+                    .clicked()
+                {
+                    // Larger displays get the same shape at the largest size
+                    let fit = (projector::MAX_SIZE as f32 / width.max(height) as f32).min(1.0);
+                    self.projector.width = (width as f32 * fit).round() as u32;
+                    self.projector.height = (height as f32 * fit).round() as u32;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Rotation:"));
+            egui::ComboBox::from_id_salt("projector_rotation")
+                .selected_text(tr(self.projector.rotation.name()))
+                .show_ui(ui, |ui| {
+                    for &rotation in projector::Rotation::all() {
+                        ui.selectable_value(
+                            &mut self.projector.rotation,
+                            rotation,
+                            tr(rotation.name()),
+                        );
+                    }
+                });
+        });
+
+        ui.checkbox(&mut self.projector.clean_feed, tr("Clean feed"))
+            .on_hover_text(tr(CLEAN_FEED_HINT));
+
+        ui.small(&self.projector.status);
+    }
+
+    /// Settings panel section for driving OBS over obs-websocket
+    fn obs_ui(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.obs.is_enabled();
+        if ui
+            .checkbox(&mut enabled, tr("OBS remote"))
+            .on_hover_text(tr(
+                "Connect to OBS (Tools → WebSocket Server Settings) to record \
+                 in step with playback",
+            ))
+            .changed()
+        {
+            self.obs.set_enabled(enabled);
+        }
+        ui.add_enabled_ui(!enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Host:"));
+                ui.add(egui::TextEdit::singleline(&mut self.obs.host).desired_width(120.0));
+                ui.label(tr("Port:"));
+                ui.add(egui::DragValue::new(&mut self.obs.port).range(1..=65535));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Password:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.obs.password)
+                        .password(true)
+                        .desired_width(140.0),
+                );
+            });
+        });
+        ui.checkbox(&mut self.obs.follow_playback, tr("Record while playing"))
+            .on_hover_text(tr(
+                "Start OBS recording when the input starts and stop it when it stops",
+            ));
+        ui.checkbox(
+            &mut self.obs.scene_per_preset,
+            tr("Switch scene with preset"),
+        )
+        .on_hover_text(tr(
+            "Switch OBS to the scene named like the preset that's loaded",
+        ));
+        if !self.obs.scenes.is_empty() {
+            ui.small(trf("Scenes: {}", &[&self.obs.scenes.join(", ")]));
+        }
+        ui.small(&self.obs.status);
+    }
+
+    /// Keep OBS recording while the input plays, and on the scene named
+    /// after the current preset. Only changes are sent, so starting or
+    /// stopping in OBS itself isn't undone every frame.
+    fn update_obs(&mut self) {
+        self.obs.poll();
+        let playing = self.source_active();
+        if playing != self.obs_playing {
+            self.obs_playing = playing;
+            if self.obs.follow_playback {
+                self.obs.set_recording(playing);
+            }
+        }
+        if self.presets.current != self.obs_preset {
+            self.obs_preset = self.presets.current.clone();
+            if let (true, Some(preset)) = (self.obs.scene_per_preset, &self.obs_preset) {
+                self.obs.set_scene(preset);
+            }
+        }
+    }
+
+    /// Measured left/right response of the last sweep
+    fn sweep_response_ui(&mut self, ui: &mut egui::Ui) {
+        let plot = FrequencyPlot {
+            min_freq: self.sweep.start_hz.min(self.sweep.end_hz),
+            max_freq: self.sweep.end_hz.max(self.sweep.start_hz),
+            ..FrequencyPlot::default()
+        };
+        let size = egui::vec2(ui.available_width(), 200.0);
+        let (response, painter) = plot.begin(ui, size);
+        let rect = response.rect;
+        plot.curve(
+            &painter,
+            rect,
+            self.sweep_response.iter().map(|p| (p.freq, p.db_x)),
+            egui::Color32::from_rgb(100, 255, 100),
+        );
+        plot.curve(
+            &painter,
+            rect,
+            self.sweep_response.iter().map(|p| (p.freq, p.db_y)),
+            egui::Color32::from_rgb(255, 176, 0),
+        );
+        ui.horizontal(|ui| {
+            ui.colored_label(egui::Color32::from_rgb(100, 255, 100), tr("■ Left (X)"));
+            ui.colored_label(egui::Color32::from_rgb(255, 176, 0), tr("■ Right (Y)"));
+        });
+    }
+
+    /// Envelope follower routes, each with a meter of its envelope
+    fn modulation_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.modulation.enabled, tr("Audio-reactive"))
+            .on_hover_text(tr(
+                "Let the level of the music push the display around; the sliders \
+                 keep their own values",
+            ));
+        let mut remove = None;
+        ui.add_enabled_ui(self.modulation.enabled, |ui| {
+            for (i, route) in self.modulation.routes.iter_mut().enumerate() {
+                ui.push_id(i, |ui| {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("band")
+                            .selected_text(tr(route.band.name()))
+                            .show_ui(ui, |ui| {
+                                for &band in analysis::Band::all() {
+                                    ui.selectable_value(&mut route.band, band, tr(band.name()));
+                                }
+                            });
+                        ui.label("→");
+                        egui::ComboBox::from_id_salt("target")
+                            .selected_text(tr(route.target.name()))
+                            .show_ui(ui, |ui| {
+                                for &target in modulation::ModTarget::all() {
+                                    ui.selectable_value(
+                                        &mut route.target,
+                                        target,
+                                        tr(target.name()),
+                                    );
+                                }
+                            });
+                        if ui.small_button("X").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Depth:"));
+                        ui.add(egui::Slider::new(&mut route.depth, -1.0..=1.0));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Attack:"));
+                        let mut ms = route.attack * 1000.0;
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut ms, 1.0..=500.0)
+                                    .logarithmic(true)
+                                    .suffix(" ms"),
+                            )
+                            .changed()
+                        {
+                            route.attack = ms / 1000.0;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Release:"));
+                        let mut ms = route.release * 1000.0;
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut ms, 10.0..=2000.0)
+                                    .logarithmic(true)
+                                    .suffix(" ms"),
+                            )
+                            .changed()
+                        {
+                            route.release = ms / 1000.0;
+                        }
+                    });
+                    ui.add(egui::ProgressBar::new(route.envelope()).desired_height(4.0));
+                });
+                ui.separator();
+            }
+            if ui.button(tr("+ Add")).clicked() {
+                self.modulation.routes.push(modulation::ModRoute::default());
+            }
+        });
+        if let Some(i) = remove {
+            self.modulation.routes.remove(i);
+        }
+    }
+
+    /// Tempo and beat readout, and the rules that act on the beat
+    fn reactivity_ui(&mut self, ui: &mut egui::Ui) {
+        let beats = self.analysis.latest().beats;
+        ui.checkbox(&mut self.reactivity.enabled, tr("Beat-synced actions"));
+        ui.horizontal(|ui| {
+            ui.label(tr("Tempo:"));
+            match beats.bpm {
+                Some(bpm) => ui.monospace(format!("{bpm:.1} BPM")),
+                None => ui.monospace("—"),
+            };
+            ui.separator();
+            // One light per beat of the bar
+            let current = self.reactivity.beat_in_bar();
+            for beat in 0..self.reactivity.beats_per_bar as u64 {
+                let light = if beat == current { "●" } else { "○" };
+                ui.label(egui::RichText::new(light).strong());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Beats per bar:"));
+            ui.add(egui::DragValue::new(&mut self.reactivity.beats_per_bar).range(2..=16));
+            if ui
+                .button(tr("Downbeat"))
+                .on_hover_text(tr("Make the latest beat the first of the bar"))
+                .clicked()
+            {
+                self.reactivity.mark_downbeat();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Pulse:"));
+            ui.add(egui::Slider::new(
+                &mut self.reactivity.pulse_depth,
+                0.0..=1.0,
+            ));
+        });
+        ui.separator();
+
+        let mut remove = None;
+        for (i, rule) in self.reactivity.rules.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Every"));
+                    ui.add(egui::DragValue::new(&mut rule.every).range(1..=64));
+                    egui::ComboBox::from_id_salt("event")
+                        .width(70.0)
+                        .selected_text(tr(rule.event.name()))
+                        .show_ui(ui, |ui| {
+                            for &event in reactivity::BeatEvent::all() {
+                                ui.selectable_value(&mut rule.event, event, tr(event.name()));
+                            }
+                        });
+                    ui.label("→");
+                    egui::ComboBox::from_id_salt("action")
+                        .selected_text(tr(rule.action.name()))
+                        .show_ui(ui, |ui| {
+                            for &action in reactivity::BeatAction::all() {
+                                ui.selectable_value(&mut rule.action, action, tr(action.name()));
+                            }
+                        });
+                    if ui.small_button("X").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            });
+        }
+        if let Some(i) = remove {
+            self.reactivity.rules.remove(i);
+        }
+        if ui.button(tr("+ Add")).clicked() {
+            self.reactivity.rules.push(reactivity::BeatRule {
+                every: 1,
+                event: reactivity::BeatEvent::Beat,
+                action: reactivity::BeatAction::PulseIntensity,
+            });
+        }
+    }
+
+    /// Live spectrum of both channels, with the analyzer's options
+    fn spectrum_ui(&mut self, ui: &mut egui::Ui) {
+        let x_color = egui::Color32::from_rgb(100, 255, 100);
+        let y_color = egui::Color32::from_rgb(255, 176, 0);
+        ui.horizontal(|ui| {
+            ui.label(tr("View:"));
+            egui::ComboBox::from_id_salt("spectrum_view")
+                .selected_text(tr(self.spectrum_view.name()))
+                .show_ui(ui, |ui| {
+                    for &view in SpectrumView::all() {
+                        ui.selectable_value(&mut self.spectrum_view, view, tr(view.name()));
+                    }
+                });
+        });
+
+        let size = egui::vec2(ui.available_width(), 200.0);
+        let rate = self.display_sample_rate();
+        let spectrum = self.analysis.spectrum();
+        match self.spectrum_view {
+            SpectrumView::Waterfall => {
+                if let Some(rate) = rate {
+                    self.waterfall.push(spectrum, rate);
+                }
+                self.waterfall.advance(self.perf.frame_time);
+                let size = egui::vec2(size.x, size.y * 1.5);
+                self.waterfall
+                    .show(ui, size, x_color)
+                    .on_hover_text(tr("Drag to turn"));
+            }
+            view => {
+                let plot = FrequencyPlot {
+                    min_db: -120.0,
+                    max_db: 0.0,
+                    ..FrequencyPlot::default()
+                };
+                let (response, painter) = plot.begin(ui, size);
+                let rect = response.rect;
+                if view == SpectrumView::ThirdOctave {
+                    // Side by side in each band, X on the left
+                    let channels = [(0, x_color), (1, y_color)];
+                    for (channel, color) in channels {
+                        for (band, &db) in spectrum.bands[channel].iter().enumerate() {
+                            let (low, high) = analysis::band_edges(band);
+                            let mid = (low * high).sqrt();
+                            let (low, high) = if channel == 0 {
+                                (low, mid)
+                            } else {
+                                (mid, high)
+                            };
+                            // A sliver of gap between bands
+                            let (low, high) = (low * 1.01, high / 1.01);
+                            plot.bar(&painter, rect, (low, high), db, color);
+                        }
+                    }
+                } else if let Some(rate) = rate.filter(|_| spectrum.size > 0) {
+                    let bin_hz = rate as f32 / spectrum.size as f32;
+                    let points = |levels: &[f32]| -> Vec<(f32, f32)> {
+                        levels
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &db)| (i as f32 * bin_hz, db))
+                            .collect()
+                    };
+                    for (trace, color) in [(&spectrum.x, x_color), (&spectrum.y, y_color)] {
+                        let dim = color.gamma_multiply(0.4);
+                        plot.curve(&painter, rect, points(&trace.peak).into_iter(), dim);
+                        plot.curve(&painter, rect, points(&trace.average).into_iter(), color);
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.colored_label(x_color, tr("■ Left (X)"));
+                    ui.colored_label(y_color, tr("■ Right (Y)"));
+                });
+            }
+        }
+
+        match self.spectrum_view {
+            SpectrumView::Lines => {}
+            SpectrumView::ThirdOctave => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Ballistics:"));
+                    egui::ComboBox::from_id_salt("rta_ballistics")
+                        .selected_text(tr(self.rta_ballistics.name()))
+                        .show_ui(ui, |ui| {
+                            for &ballistics in analysis::Ballistics::all() {
+                                ui.selectable_value(
+                                    &mut self.rta_ballistics,
+                                    ballistics,
+                                    tr(ballistics.name()),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text(tr("Band level time weighting: fast 125 ms, slow 1 s"));
+                });
+            }
+            SpectrumView::Waterfall => {
+                let waterfall = &mut self.waterfall;
+                ui.horizontal(|ui| {
+                    ui.label(tr("Turn:"));
+                    ui.add(egui::Slider::new(&mut waterfall.yaw, -180.0..=180.0).suffix("°"));
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Tilt:"));
+                    ui.add(egui::Slider::new(&mut waterfall.pitch, -10.0..=89.0).suffix("°"));
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr("Spin:"));
+                    ui.add(egui::Slider::new(&mut waterfall.spin, -90.0..=90.0).suffix("°/s"))
+                        .on_hover_text(tr("Keep the view turning, for projection"));
+                });
+            }
+        }
+
+        let settings = &mut self.spectrum;
+        ui.horizontal(|ui| {
+            ui.label(tr("Window:"));
+            egui::ComboBox::from_id_salt("spectrum_window")
+                .selected_text(tr(settings.window.name()))
+                .show_ui(ui, |ui| {
+                    for &window in analysis::WindowFunction::all() {
+                        ui.selectable_value(&mut settings.window, window, tr(window.name()));
+                    }
+                });
+            ui.label(tr("FFT size:"));
+            egui::ComboBox::from_id_salt("spectrum_size")
+                .selected_text(settings.size.to_string())
+                .show_ui(ui, |ui| {
+                    for &size in analysis::SPECTRUM_SIZES {
+                        ui.selectable_value(&mut settings.size, size, size.to_string());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Overlap:"));
+            let mut percent = settings.overlap * 100.0;
+            if ui
+                .add(
+                    egui::Slider::new(&mut percent, 0.0..=analysis::MAX_OVERLAP * 100.0)
+                        .suffix("%"),
+                )
+                .changed()
+            {
+                settings.overlap = percent / 100.0;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Averaging:"));
+            ui.add(egui::Slider::new(&mut settings.averages, 1..=64).logarithmic(true))
+                .on_hover_text(tr(
+                    "Frames in the exponential average; more gives a steadier noise \
+                     floor but follows changes more slowly",
+                ));
+        });
+        let mut reset = false;
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.peak_hold, tr("Peak hold"));
+            reset = ui.button(tr("Reset")).clicked();
+        });
+        if reset {
+            self.analysis.reset_spectrum();
+        }
+    }
+
+    /// Whether a panel has anything to show right now
+    fn panel_open(&self, panel: Panel) -> bool {
+        match panel {
+            Panel::Settings => self.show_settings,
+            Panel::Playback => self.input_mode == InputMode::File && self.file_player.has_file(),
+            Panel::Calibration => self.show_calibration,
+            Panel::Response => self.show_sweep_response,
+            Panel::Spectrum => self.show_spectrum,
+            Panel::Reactivity => self.show_reactivity,
+            Panel::Log => self.show_log,
+        }
+    }
+
+    fn close_panel(&mut self, panel: Panel) {
+        match panel {
+            Panel::Settings => self.show_settings = false,
+            Panel::Playback => {}
+            Panel::Calibration => {
+                self.show_calibration = false;
+                if self.calibration_run.is_some() {
+                    self.stop_calibration();
+                }
+            }
+            Panel::Response => self.show_sweep_response = false,
+            Panel::Spectrum => self.show_spectrum = false,
+            Panel::Reactivity => self.show_reactivity = false,
+            Panel::Log => self.show_log = false,
+        }
+    }
+
+    fn panel_ui(&mut self, ui: &mut egui::Ui, panel: Panel) {
+        match panel {
+            Panel::Settings => self.settings_ui(ui),
+            Panel::Playback => self.playback_ui(ui),
+            Panel::Calibration => self.calibration_ui(ui),
+            Panel::Response => self.sweep_response_ui(ui),
+            Panel::Spectrum => self.spectrum_ui(ui),
+            Panel::Reactivity => self.reactivity_ui(ui),
+            Panel::Log => self.log_ui(ui),
+        }
+    }
+
+    /// Contents of a panel docked to an edge, with a title and close button
+    /// for the panels that can be closed
+    fn docked_panel_ui(&mut self, ui: &mut egui::Ui, panel: Panel) {
+        if panel != Panel::Playback {
+            ui.horizontal(|ui| {
+                ui.heading(tr(panel.name()));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("✖").on_hover_text(tr("Close")).clicked() {
+                        self.close_panel(panel);
+                    }
+                });
+            });
+            ui.separator();
+        }
+        if panel.scrolls() {
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, true])
+                .show(ui, |ui| self.panel_ui(ui, panel));
+        } else {
+            self.panel_ui(ui, panel);
+        }
+    }
+
+    /// Show every open panel where the active workspace puts it
+    fn show_panels(&mut self, ctx: &egui::Context) {
+        let workspace = self.workspaces.active.clone();
+        for placement in self.workspaces.layout().placements() {
+            let panel = placement.panel;
+            if !self.panel_open(panel) {
+                continue;
+            }
+            let id = placement.id(&workspace);
+            let width = placement.size.unwrap_or(panel.default_width());
+            let size = match placement.dock {
+                Dock::Left | Dock::Right => {
+                    let side = if placement.dock == Dock::Left {
+                        egui::panel::Side::Left
+                    } else {
+                        egui::panel::Side::Right
+                    };
+                    let response = egui::SidePanel::new(side, id)
+                        .min_width(200.0)
+                        .default_width(width)
+                        .show(ctx, |ui| self.docked_panel_ui(ui, panel))
+                        .response;
+                    Some(response.rect.width())
+                }
+                Dock::Top | Dock::Bottom => {
+                    let side = if placement.dock == Dock::Top {
+                        egui::panel::TopBottomSide::Top
+                    } else {
+                        egui::panel::TopBottomSide::Bottom
+                    };
+                    // Panels shrink to their contents, so this only matters
+                    // for the long ones
+                    let response = egui::TopBottomPanel::new(side, id)
+                        .resizable(true)
+                        .default_height(placement.size.unwrap_or(240.0))
+                        .show(ctx, |ui| self.docked_panel_ui(ui, panel))
+                        .response;
+                    Some(response.rect.height())
+                }
+                Dock::Floating => {
+                    let mut open = true;
+                    let response = egui::Window::new(tr(panel.name()))
+                        .id(egui::Id::new(&id))
+                        .open(&mut open)
+                        .default_width(width)
+                        .show(ctx, |ui| self.panel_ui(ui, panel));
+                    if !open {
+                        self.close_panel(panel);
+                    }
+                    response.map(|r| r.response.rect.width())
+                }
+            };
+            if let (Some(size), Some(placement)) =
+                (size, self.workspaces.layout_mut().placement_mut(panel))
+            {
+                placement.size = Some(size.round());
+            }
+        }
+    }
+
+    /// Workspace selection and where each panel is docked
+    fn layout_settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("Workspace:"));
+            egui::ComboBox::from_id_salt("workspace_select")
+                .selected_text(self.workspaces.active.clone())
+                .show_ui(ui, |ui| {
+                    let names: Vec<String> = self.workspaces.layouts.keys().cloned().collect();
+                    for name in names {
+                        ui.selectable_value(&mut self.workspaces.active, name.clone(), name);
+                    }
+                });
+            if ui
+                .add_enabled(self.workspaces.layouts.len() > 1, egui::Button::new("🗑"))
+                .on_hover_text(tr("Delete workspace"))
+                .clicked()
+            {
+                self.workspaces.remove_active();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.workspace_name)
+                    .hint_text(tr("Workspace name"))
+                    .desired_width(120.0),
+            );
+            let name = self.workspace_name.trim().to_string();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new(tr("Save as")))
+                .clicked()
+            {
+                self.workspaces.save_as(&name);
+                self.workspace_name.clear();
+            }
+        });
+
+        ui.add_space(4.0);
+
+        let layout = self.workspaces.layout();
+        egui::Grid::new("layout_grid").show(ui, |ui| {
+            for (i, placement) in layout.placements().iter().enumerate() {
+                let panel = placement.panel;
+                ui.label(tr(panel.name()));
+                let mut dock = placement.dock;
+                egui::ComboBox::from_id_salt(("panel_dock", i))
+                    .selected_text(tr(dock.name()))
+                    .show_ui(ui, |ui| {
+                        for d in Dock::all() {
+                            ui.selectable_value(&mut dock, *d, tr(d.name()));
+                        }
+                    });
+                if dock != placement.dock {
+                    self.workspaces.layout_mut().set_dock(panel, dock);
+                }
+                if ui
+                    .add_enabled(i > 0, egui::Button::new("⏶").small())
+                    .on_hover_text(tr("Move towards the window edge"))
+                    .clicked()
+                {
+                    self.workspaces.layout_mut().move_outward(panel);
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Mouse and touch navigation on the scope: drag to pan, double-click to
+    /// re-center, wheel to zoom around the cursor (Ctrl+wheel for finer
+    /// steps), and two-finger pan and pinch-to-zoom
+    fn scope_navigation(&mut self, ui: &egui::Ui, response: &egui::Response) {
+        let rect = response.rect;
+        // Screen points to view units (-1 to 1 across the display, Y up)
+        let to_view = |delta: egui::Vec2| {
+            egui::vec2(
+                delta.x / (rect.width() / 2.0),
+                -delta.y / (rect.height() / 2.0),
+            )
+        };
+        let settings = &mut self.oscilloscope.settings;
+
+        let touch = ui
+            .input(|i| i.multi_touch())
+            .filter(|touch| rect.contains(touch.start_pos));
+        if let Some(touch) = touch {
+            settings.pan(to_view(touch.translation_delta));
+            let anchor = to_view(touch.start_pos - rect.center());
+            settings.zoom_around(settings.zoom / touch.zoom_delta, anchor);
+            return;
+        }
+
+        if response.double_clicked() {
+            settings.dc_offset_x = 0.0;
+            settings.dc_offset_y = 0.0;
+        } else if response.dragged_by(egui::PointerButton::Primary) {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+            settings.pan(to_view(response.drag_delta()));
+        }
+
+        let Some(pointer) = response.hover_pos() else {
+            return;
+        };
+        // egui turns Ctrl+wheel into a zoom gesture instead of a scroll
+        let (scroll, ctrl_zoom) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+        let factor = if ctrl_zoom != 1.0 {
+            ctrl_zoom.powf(0.25)
+        } else if scroll != 0.0 {
+            (scroll / 200.0).exp()
+        } else {
+            return;
+        };
+        // A smaller zoom value shows a smaller area, i.e. zooms in
+        settings.zoom_around(settings.zoom / factor, to_view(pointer - rect.center()));
+    }
+
+    /// Sample rate of whatever is feeding the display
+    fn display_sample_rate(&self) -> Option<u32> {
+        if self.demo_engine.is_running() {
+            return Some(self.demo_engine.sample_rate());
+        }
+        match self.input_mode {
+            InputMode::Live => self.audio.sample_rate(),
+            InputMode::File => self.file_player.info.as_ref().map(|i| i.sample_rate),
+            InputMode::Generator => Some(self.generator.sample_rate()),
+        }
+    }
+
+    /// Sample rate to pace the display at, if frames should be extracted at
+    /// a fixed rate. Only file playback arrives in uneven bursts; live input
+    /// and the generators already deliver at their clock.
+    fn pacing_rate(&self) -> Option<u32> {
+        let playing = self.input_mode == InputMode::File
+            && self.file_player.state() == PlaybackState::Playing
+            && !self.demo_engine.is_running();
+        // A triggered window is steady already
+        if playing && self.oscilloscope.settings.steady_pacing && !self.trigger.enabled {
+            self.display_sample_rate()
+        } else {
+            None
+        }
+    }
+
+    /// MIDI note, OSC and keyboard options for the external trigger
+    fn external_trigger_ui(&mut self, ui: &mut egui::Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            let mut note = self.midi.trigger_note();
+            let mut on = note.is_some();
+            if ui.checkbox(&mut on, tr("MIDI note:")).changed() {
+                // Middle C to start with
+                note = on.then_some(60);
+            }
+            if let Some(note) = note.as_mut() {
+                ui.add(egui::DragValue::new(note).range(0..=127));
+            }
+            self.midi.set_trigger_note(note);
+        })
+        .response
+        .on_hover_text(tr("Note-on on any channel of the connected MIDI port"));
+
+        let mut osc = self.osc.is_running();
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut osc, tr("OSC on port")).changed() {
+                self.osc.set_enabled(osc);
+            }
+            ui.add_enabled(
+                !osc,
+                egui::DragValue::new(&mut self.osc.port).range(1..=65535),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Address:"));
+            ui.add_enabled(
+                !osc,
+                egui::TextEdit::singleline(&mut self.osc.address).desired_width(140.0),
+            );
+        });
+        ui.small(&self.osc.status);
+
+        if ui
+            .button(tr("Fire trigger"))
+            .on_hover_text(self.keymap.hint(Action::FireTrigger))
+            .clicked()
+        {
+            self.external_trigger.fire();
+        }
+    }
+
+    /// Cut `samples` down to the window that starts at the trigger, if it
+    /// found one
+    fn apply_trigger(&mut self, samples: &mut Vec<XYSample>) {
+        let len = self.oscilloscope.settings.sample_count;
+        let rate = self.display_sample_rate();
+        if let Some(start) = self
+            .trigger
+            .find(samples, self.consumer.drained(), len, rate)
+        {
+            samples.drain(..start);
+            samples.truncate(len);
+        }
+    }
+
+    /// Tooltip with the signal value under the cursor and the nearest sample
+    fn scope_readout(&self, response: &egui::Response, samples: &[XYSample]) {
+        let Some(pointer) = response.hover_pos() else {
+            return;
+        };
+        let rect = response.rect;
+        let value = self.oscilloscope.screen_to_sample(pointer, rect);
+        let nearest = self
+            .oscilloscope
+            .nearest_sample(samples, rect, pointer, 8.0);
+        let rate = self.display_sample_rate();
+
+        response.clone().on_hover_ui_at_pointer(|ui| {
+            ui.small(format!("X {:+.3}  ({})", value.x, format_dbfs(value.x)));
+            ui.small(format!("Y {:+.3}  ({})", value.y, format_dbfs(value.y)));
+            if let Some(index) = nearest {
+                let text = match rate {
+                    Some(rate) => {
+                        // Samples run oldest to newest, so the last one is "now"
+                        let ago = (samples.len() - 1 - index) as f32 / rate as f32;
+                        trf(
+                            "Sample {} ({} ms ago)",
+                            &[&index, &format!("{:.2}", ago * 1000.0)],
+                        )
+                    }
+                    None => trf("Sample {}", &[&index]),
+                };
+                ui.small(text);
+            }
+        });
+    }
+
+    /// FPS, frame time breakdown and buffer health in the scope's corner
+    fn perf_overlay_ui(&self, ctx: &egui::Context, rect: egui::Rect) {
+        let ms = |seconds: f32| format!("{:.2}", seconds * 1000.0);
+        egui::Area::new(egui::Id::new("perf_overlay"))
+            .fixed_pos(rect.left_top() + egui::vec2(8.0, 8.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let perf = &self.perf;
+                    ui.small(trf(
+                        "FPS: {} ({} ms)",
+                        &[&format!("{:.0}", perf.fps()), &ms(perf.frame_time)],
+                    ));
+                    ui.small(trf("Sample fetch: {} ms", &[&ms(perf.fetch)]));
+                    ui.small(trf("Persistence: {} ms", &[&ms(perf.persistence)]));
+                    ui.small(trf("Painting: {} ms", &[&ms(perf.painting)]));
+                    ui.small(trf(
+                        "Throughput: {} samples/s",
+                        &[&format!("{:.0}", perf.throughput)],
+                    ));
+                    ui.small(trf(
+                        "Buffer fill: {}%",
+                        &[&format!("{:.0}", self.consumer.fill_level() * 100.0)],
+                    ));
+                    ui.small(trf("Dropped: {}", &[&self.buffer.samples_dropped()]));
+                    if self.auto_quality {
+                        ui.small(trf(
+                            "Quality: {}%",
+                            &[&format!("{:.0}", self.quality.scale() * 100.0)],
+                        ));
+                    }
+                });
+            });
+    }
+
+    /// Phase difference and amplitude ratio from the ellipse fit, in the
+    /// scope's top right corner
+    fn phase_readout_ui(&mut self, ctx: &egui::Context, rect: egui::Rect) {
+        let ellipse = self.analysis.latest().ellipse;
+        egui::Area::new(egui::Id::new("phase_readout"))
+            .fixed_pos(rect.right_top() + egui::vec2(-8.0, 8.0))
+            .pivot(egui::Align2::RIGHT_TOP)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let Some(m) = ellipse else {
+                        ui.small(tr("Needs two sines of one frequency"));
+                        return;
+                    };
+                    ui.monospace(trf("Phase: {}°", &[&format!("{:+.1}", m.phase_deg)]));
+                    ui.monospace(trf(
+                        "Amplitude ratio Y/X: {} ({} dB)",
+                        &[
+                            &format!("{:.3}", 10f32.powf(m.gain_db / 20.0)),
+                            &format!("{:+.2}", m.gain_db),
+                        ],
+                    ));
+                });
+            });
+    }
+
+    /// X:Y frequency ratio with a lock indicator, for the status bar
+    fn ratio_readout_ui(&self, ui: &mut egui::Ui, ratio: analysis::FrequencyRatio) {
+        let rate = self.display_sample_rate();
+        let label = ui.small(trf("Ratio: {}:{}", &[&ratio.x, &ratio.y]));
+        let Some(rate) = rate else {
+            return;
+        };
+        let hz = |freq: f64| format!("{:.1}", freq * rate as f64);
+        label.on_hover_text(trf(
+            "X: {} Hz, Y: {} Hz",
+            &[&hz(ratio.freq_x), &hz(ratio.freq_y)],
+        ));
+        if ratio.is_locked(rate) {
+            ui.colored_label(egui::Color32::from_rgb(100, 255, 100), tr("● Locked"));
+        } else {
+            ui.small(trf(
+                "Drifting {} Hz",
+                &[&format!("{:+.2}", ratio.drift_hz(rate))],
+            ))
+            .on_hover_text(tr("How fast the figure rolls through its shapes"));
+        }
+    }
+
+    /// Shortcut list and a short guide to the display and channel controls
+    fn help_ui(&mut self, ctx: &egui::Context) {
+        let mouse = [
+            ("Wheel", "Zoom around the cursor"),
+            ("Ctrl+Wheel", "Zoom in finer steps"),
+            ("Drag", "Pan the view"),
+            ("Double-click", "Re-center the view"),
+            ("Two fingers", "Pan, and pinch to zoom"),
+            ("Hover", "Show the value under the cursor"),
+        ];
+        let channel = [
+            (
+                "Zoom",
+                "Range shown across the display. Below 1 zooms in, above 1 zooms out.",
+            ),
+            (
+                "Swap X/Y",
+                "Plot the right channel horizontally and the left channel vertically.",
+            ),
+            (
+                "Invert X / Y",
+                "Flip a channel, e.g. to undo a polarity-reversed cable.",
+            ),
+            (
+                "X / Y offset",
+                "Shift a channel to cancel DC on the input or to move the figure.",
+            ),
+            (
+                "Persistence",
+                "How long old traces keep glowing, like the phosphor of an analog scope.",
+            ),
+        ];
+
+        egui::Window::new(tr("Help"))
+            .open(&mut self.show_help)
+            .default_width(440.0)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                ui.heading(tr("Keyboard"));
+                egui::Grid::new("help_keys").striped(true).show(ui, |ui| {
+                    for binding in self.keymap.bindings() {
+                        if let Some(shortcut) = binding.shortcut {
+                            ui.monospace(shortcut.to_string());
+                            ui.label(tr(binding.action.name()));
+                            ui.end_row();
+                        }
+                    }
+                });
+                ui.small(tr("Shortcuts can be changed under Settings → Keyboard."));
+
+                ui.separator();
+                ui.heading(tr("Mouse and touch"));
+                egui::Grid::new("help_mouse").striped(true).show(ui, |ui| {
+                    for (input, effect) in mouse {
+                        ui.monospace(tr(input));
+                        ui.label(tr(effect));
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.heading(tr("Display modes"));
+                for mode in DisplayMode::all() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.strong(tr(mode.name()));
+                        ui.label(tr(mode.description()));
+                    });
+                }
+
+                ui.separator();
+                ui.heading(tr("Channel controls"));
+                for (control, effect) in channel {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.strong(tr(control));
+                        ui.label(tr(effect));
+                    });
+                }
+            });
+    }
+
+    /// Captured log records, filtered by level
+    fn log_ui(&mut self, ui: &mut egui::Ui) {
+        let entries = logging::entries(self.log_level);
+        ui.horizontal(|ui| {
+            ui.label(tr("Minimum level:"));
+            egui::ComboBox::from_id_salt("log_level")
+                .selected_text(self.log_level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in log::LevelFilter::iter().skip(1) {
+                        ui.selectable_value(&mut self.log_level, level, level.as_str());
+                    }
+                });
+            if ui.button(tr("Copy")).clicked() {
+                let text: Vec<String> = entries.iter().map(ToString::to_string).collect();
+                ui.ctx().copy_text(text.join("\n"));
+            }
+            if ui.button(tr("Clear")).clicked() {
+                logging::clear();
+            }
+            ui.small(trf("{} entries", &[&entries.len()]));
+        });
+        ui.separator();
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, entries.len(), |ui, rows| {
+                for entry in &entries[rows] {
+                    let color = match entry.level {
+                        log::Level::Error => egui::Color32::from_rgb(255, 100, 100),
+                        log::Level::Warn => egui::Color32::from_rgb(255, 176, 0),
+                        _ => ui.visuals().text_color(),
+                    };
+                    ui.label(
+                        egui::RichText::new(entry.to_string())
+                            .monospace()
+                            .color(color),
+                    );
+                }
+            });
+    }
+
+    /// Offer the settings backups after the settings file failed to load
+    fn settings_recovery_ui(&mut self, ctx: &egui::Context) {
+        let Some(recovery) = self.settings_recovery.clone() else {
+            return;
+        };
+        let mut restore = None;
+        let mut dismiss = false;
+        egui::Window::new(tr("Settings could not be loaded"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(trf("The settings file is damaged: {}", &[&recovery.error]));
+                if let Some(path) = &recovery.moved_to {
+                    ui.small(trf("It was kept as {}", &[&path.display()]));
+                }
+                ui.add_space(4.0);
+                if recovery.backups.is_empty() {
+                    ui.label(tr("There are no backups, so the defaults are in use."));
+                } else {
+                    ui.label(tr("Restore a backup, or keep the defaults:"));
+                    for backup in &recovery.backups {
+                        ui.horizontal(|ui| {
+                            ui.label(&backup.saved);
+                            if ui.button(tr("Restore")).clicked() {
+                                restore = Some(backup.clone());
+                            }
+                        });
+                    }
+                }
+                ui.add_space(4.0);
+                if ui.button(tr("Keep defaults")).clicked() {
+                    dismiss = true;
+                }
+            });
+
+        if let Some(backup) = restore {
+            match settings::AppSettings::load_backup(&backup) {
+                Ok(settings) => {
+                    settings.apply(self);
+                    self.reload_sources();
+                    settings.save();
+                    dismiss = true;
+                }
+                Err(e) => {
+                    log::error!("Failed to restore {}: {}", backup.path.display(), e);
+                    if let Some(recovery) = &mut self.settings_recovery {
+                        recovery.error = e.to_string();
+                        recovery.backups.retain(|b| b.path != backup.path);
+                    }
+                }
+            }
+        }
+        if dismiss {
+            self.settings_recovery = None;
+        }
+    }
 
-struct ScopeApp {
-    buffer: SampleBuffer,
-    audio: AudioInput,
-    file_player: AudioFilePlayer,
-    oscilloscope: Oscilloscope,
-    midi: midi::MidiController,
-    show_settings: bool,
-    input_mode: InputMode,
-}
+    /// Whether anything is producing samples or animating, so the display
+    /// needs to run at full rate
+    fn is_active(&self) -> bool {
+        self.audio.is_capturing()
+            || self.file_player.state() == PlaybackState::Playing
+            || self.file_player.loading().is_some()
+            || self.generator.is_running()
+            || self.demo_engine.is_running()
+            || self.sweep_capture.is_some()
+            || self.calibration_run.is_some()
+            || self.presets.morph.is_some()
+            || self.render_job.is_some()
+            || self.recording.is_some()
+            || self.midi.is_connected
+            || self.frame_output.enabled
+            || self.projector.is_open()
+    }
 
-impl ScopeApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        let buffer = SampleBuffer::new(BUFFER_SIZE);
-        let audio = AudioInput::new(buffer.clone_ref());
-        let file_player = AudioFilePlayer::new(buffer.clone_ref());
+    /// Ask for the next frame: as soon as possible (paced by vsync), at the
+    /// target frame rate, or slowly when idle. Input always wakes egui up.
+    fn schedule_repaint(&self, ctx: &egui::Context) {
+        /// Idle redraw rate, enough for persistence to fade out
+        const IDLE_FPS: f32 = 4.0;
+        /// Redraw rate while hidden to the tray, for the outputs; vsync
+        /// doesn't pace a window nobody sees
+        const HIDDEN_FPS: f32 = 60.0;
 
-        let mut app = Self {
-            buffer,
-            audio,
-            file_player,
-            oscilloscope: Oscilloscope::new(),
-            midi: midi::MidiController::new(),
-            show_settings: false,
-            input_mode: InputMode::default(),
-        };
+        if self.idle_throttle && !self.is_active() {
+            ctx.request_repaint_after(Duration::from_secs_f32(1.0 / IDLE_FPS));
+        } else if self.target_fps > 0 {
+            ctx.request_repaint_after(Duration::from_secs_f32(1.0 / self.target_fps as f32));
+        } else if self.window_hidden {
+            ctx.request_repaint_after(Duration::from_secs_f32(1.0 / HIDDEN_FPS));
+        } else {
+            ctx.request_repaint();
+        }
+    }
 
-        let settings = settings::AppSettings::load();
-        settings.apply(&mut app);
+    /// Let the quality governor follow the CPU time per frame. The time
+    /// between frames includes waiting for vsync or the frame rate cap, so
+    /// it would read as over budget on any display slower than the budget.
+    /// Idle frames are throttled on purpose, so only frames of a running
+    /// source count.
+    fn update_quality(&mut self) {
+        /// Frame time to aim for when the rate follows the display
+        const DEFAULT_BUDGET: f32 = 1.0 / 60.0;
 
-        app
+        if !self.auto_quality {
+            self.quality.reset();
+        } else if self.is_active() && self.perf.cpu_time > 0.0 {
+            let budget = if self.target_fps > 0 {
+                1.0 / self.target_fps as f32
+            } else {
+                DEFAULT_BUDGET
+            };
+            self.quality
+                .update(Instant::now(), self.perf.cpu_time, budget);
+        } else {
+            self.quality.hold(Instant::now());
+        }
+        self.oscilloscope.quality = self.quality.scale();
     }
-}
 
-impl Drop for ScopeApp {
-    fn drop(&mut self) {
-        settings::AppSettings::from_app(self).save();
+    /// Resize the display buffer to the nearest offered capacity
+    fn set_buffer_size(&mut self, size: usize) {
+        let size = SampleBuffer::CAPACITIES
+            .iter()
+            .copied()
+            .min_by_key(|c| c.abs_diff(size))
+            .unwrap_or(SampleBuffer::DEFAULT_CAPACITY);
+        if size != self.consumer.capacity() {
+            log::info!("Display buffer resized to {} samples", size);
+            self.buffer.resize(size, &mut self.consumer);
+        }
     }
 }
 
 impl eframe::App for ScopeApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint();
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let mut settings = settings::AppSettings::from_app(self);
+        if self.fresh_next_launch {
+            settings.forget_session();
+        }
+        settings.save();
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.perf.record_cpu(frame.info().cpu_usage);
+        self.schedule_repaint(ctx);
+
+        if self.minimize_pending {
+            self.minimize_pending = false;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        }
+        self.track_window(ctx);
+        self.apply_ui_style(ctx);
+
+        if let Some(mut autosave) = self.autosave.take() {
+            autosave.update(self);
+            self.autosave = Some(autosave);
+        }
+
+        if let Some(Err(e)) = self.file_player.poll_loading() {
+            log::error!("Failed to load file: {}", e);
+            self.file_player.status = trf("Error: {}", &[&e]);
+        }
+        self.update_sweep_measurement();
+        if let Some(run) = &mut self.calibration_run {
+            run.update();
+        }
+        self.update_attract(ctx);
+        self.update_silence();
+        self.update_demo();
+        self.update_source_profile();
+        self.update_preset_morph();
+        self.update_render_job();
+        self.update_obs();
+        self.update_tray(ctx);
+
+        // Global hotkeys arrive whichever app has focus
+        for action in self.hotkeys.triggered() {
+            self.run_action(action);
+        }
+
+        // Keyboard shortcuts (ignored while typing or rebinding a key)
+        if self.rebinding.is_none()
+            && self.rebinding_hotkey.is_none()
+            && !ctx.wants_keyboard_input()
+        {
+            for action in self.keymap.triggered(ctx) {
+                self.run_action(action);
+            }
+        }
 
         // Poll MIDI and apply parameter updates
         let midi_updates = self.midi.poll();
+        for &(param, value) in &midi_updates {
+            if param == midi::MidiParam::PresetMorph {
+                let high = value >= 0.5;
+                if high && !self.morph_cc_high {
+                    self.start_preset_morph();
+                }
+                self.morph_cc_high = high;
+            }
+        }
         if !midi_updates.is_empty() {
             midi::apply_updates(
                 &midi_updates,
                 &mut self.oscilloscope,
                 &mut self.audio,
                 &mut self.file_player,
+                &mut self.model,
             );
         }
 
@@ -112,8 +4743,9 @@ impl eframe::App for ScopeApp {
                 ui.separator();
 
                 // Input mode selector
-                ui.selectable_value(&mut self.input_mode, InputMode::Live, "Live");
-                ui.selectable_value(&mut self.input_mode, InputMode::File, "File");
+                ui.selectable_value(&mut self.input_mode, InputMode::Live, tr("Live"));
+                ui.selectable_value(&mut self.input_mode, InputMode::File, tr("File"));
+                ui.selectable_value(&mut self.input_mode, InputMode::Generator, tr("Generator"));
                 ui.separator();
 
                 match self.input_mode {
@@ -125,7 +4757,7 @@ impl eframe::App for ScopeApp {
                                     .devices
                                     .get(self.audio.selected_device)
                                     .cloned()
-                                    .unwrap_or_else(|| "None".to_string()),
+                                    .unwrap_or_else(|| tr("None").to_string()),
                             )
                             .show_ui(ui, |ui| {
                                 for (i, name) in self.audio.devices.iter().enumerate() {
@@ -137,9 +4769,9 @@ impl eframe::App for ScopeApp {
 
                         // Capture button
                         let button_text = if self.audio.is_capturing() {
-                            "⏹ Stop"
+                            tr("⏹ Stop")
                         } else {
-                            "▶ Capture"
+                            tr("▶ Capture")
                         };
 
                         let enabled = !self.audio.devices.is_empty() || self.audio.is_capturing();
@@ -155,7 +4787,7 @@ impl eframe::App for ScopeApp {
                     }
                     InputMode::File => {
                         // File open button
-                        if ui.button("📂 Open").clicked() {
+                        if ui.button(tr("📂 Open")).clicked() {
                             if let Some(path) = rfd::FileDialog::new()
                                 .add_filter(
                                     "Audio",
@@ -163,415 +4795,325 @@ impl eframe::App for ScopeApp {
                                 )
                                 .pick_file()
                             {
-                                if let Err(e) = self.file_player.load(&path) {
-                                    log::error!("Failed to load file: {}", e);
-                                    self.file_player.status = format!("Error: {}", e);
-                                }
+                                self.file_player.load(&path);
                             }
                         }
 
                         ui.separator();
 
+                        if let Some((path, progress)) = self.file_player.loading() {
+                            ui.spinner();
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            ui.label(match progress {
+                                Some(progress) => trf(
+                                    "Loading {} ({}%)",
+                                    &[&name, &format!("{:.0}", progress * 100.0)],
+                                ),
+                                None => trf("Loading {}", &[&name]),
+                            });
+                            if ui.button(tr("Cancel")).clicked() {
+                                self.file_player.cancel_loading();
+                            }
+                            ui.separator();
+                        }
+
                         // File info
                         if let Some(info) = &self.file_player.info {
                             ui.label(&info.filename);
                             ui.separator();
                         }
 
-                        ui.label(&self.file_player.status);
+                        if self.file_player.loading().is_none() {
+                            ui.label(&self.file_player.status);
+                        }
                     }
-                }
-
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    ui.toggle_value(&mut self.show_settings, "⚙ Settings");
-                });
-            });
-        });
-
-        // Bottom panel for file playback controls
-        if self.input_mode == InputMode::File && self.file_player.has_file() {
-            egui::TopBottomPanel::bottom("playback_panel").show(ctx, |ui| {
-                ui.add_space(4.0);
-
-                // Waveform overview / seek bar
-                let available_width = ui.available_width();
-                let (response, painter) = ui.allocate_painter(
-                    egui::vec2(available_width, 40.0),
-                    egui::Sense::click_and_drag(),
-                );
-                let rect = response.rect;
-
-                // Draw background
-                painter.rect_filled(rect, 4.0, egui::Color32::from_gray(30));
+                    InputMode::Generator => {
+                        // Output device selector
+                        let mut device_changed = false;
+                        egui::ComboBox::from_id_salt("output_device")
+                            .selected_text(
+                                self.generator
+                                    .output_device()
+                                    .unwrap_or(tr("Default"))
+                                    .to_string(),
+                            )
+                            .show_ui(ui, |ui| {
+                                for (i, name) in self.generator.output_devices.iter().enumerate() {
+                                    device_changed |= ui
+                                        .selectable_value(
+                                            &mut self.generator.selected_output,
+                                            i,
+                                            name,
+                                        )
+                                        .changed();
+                                }
+                            });
+                        if device_changed {
+                            self.generator.restart();
+                        }
 
-                // Draw waveform
-                if !self.file_player.waveform.is_empty() {
-                    let waveform = &self.file_player.waveform;
-                    let center_y = rect.center().y;
-                    let height = rect.height() * 0.4;
+                        ui.separator();
 
-                    for (i, (x, y)) in waveform.iter().enumerate() {
-                        let t = i as f32 / waveform.len() as f32;
-                        let screen_x = rect.left() + t * rect.width();
+                        let button_text = if self.generator.is_running() {
+                            tr("⏹ Stop")
+                        } else {
+                            tr("▶ Start")
+                        };
+                        if ui.button(button_text).clicked() {
+                            self.generator.toggle();
+                        }
 
-                        // Draw both channels
-                        let amp_x = x.abs().min(1.0) * height;
-                        let amp_y = y.abs().min(1.0) * height;
+                        if ui
+                            .checkbox(&mut self.generator.audio_output, tr("Audio out"))
+                            .changed()
+                        {
+                            self.generator.restart();
+                        }
 
-                        painter.line_segment(
-                            [
-                                egui::pos2(screen_x, center_y - amp_x),
-                                egui::pos2(screen_x, center_y + amp_y),
-                            ],
-                            egui::Stroke::new(1.0, egui::Color32::from_rgb(80, 120, 80)),
-                        );
+                        ui.separator();
+                        ui.label(&self.generator.status);
                     }
                 }
 
-                // Draw playhead
-                let position = self.file_player.position_fraction();
-                let playhead_x = rect.left() + position * rect.width();
-                painter.line_segment(
-                    [
-                        egui::pos2(playhead_x, rect.top()),
-                        egui::pos2(playhead_x, rect.bottom()),
-                    ],
-                    egui::Stroke::new(2.0, egui::Color32::WHITE),
-                );
-
-                // Handle seeking
-                if response.dragged() || response.clicked() {
-                    if let Some(pos) = response.interact_pointer_pos() {
-                        let seek_fraction = (pos.x - rect.left()) / rect.width();
-                        self.file_player.seek(seek_fraction);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.toggle_value(&mut self.show_help, "?")
+                        .on_hover_text(trf("Help ({})", &[&self.keymap.hint(Action::Help)]));
+                    ui.toggle_value(&mut self.show_spectrum, "📊")
+                        .on_hover_text(tr("Spectrum"));
+                    ui.toggle_value(&mut self.show_reactivity, "🥁")
+                        .on_hover_text(tr("Reactivity"));
+                    ui.toggle_value(&mut self.show_log, "📜")
+                        .on_hover_text(trf("Log ({})", &[&self.keymap.hint(Action::LogPanel)]));
+                    ui.toggle_value(&mut self.show_settings, tr("⚙ Settings"))
+                        .on_hover_text(self.keymap.hint(Action::ToggleSettings));
+                    if ui
+                        .button("📷")
+                        .on_hover_text(trf(
+                            "Save screenshot ({})",
+                            &[&self.keymap.hint(Action::Screenshot)],
+                        ))
+                        .clicked()
+                    {
+                        self.save_screenshot();
                     }
-                }
-
-                ui.add_space(4.0);
-
-                // Playback controls
-                ui.horizontal(|ui| {
-                    // Play/Pause button
-                    let play_text = match self.file_player.state() {
-                        PlaybackState::Playing => "⏸",
-                        _ => "▶",
-                    };
-                    if ui.button(play_text).clicked() {
-                        self.file_player.toggle();
+                    if ui
+                        .selectable_label(self.recording.is_some(), "⏺")
+                        .on_hover_text(trf("Record ({})", &[&self.keymap.hint(Action::Record)]))
+                        .clicked()
+                    {
+                        self.toggle_recording();
                     }
-
-                    // Stop button
-                    if ui.button("⏹").clicked() {
-                        self.file_player.stop();
+                    if let Some(recording) = &self.recording {
+                        let label = ui.colored_label(
+                            egui::Color32::from_rgb(255, 100, 100),
+                            format_duration(recording.duration()),
+                        );
+                        if recording.dropped() > 0 {
+                            label.on_hover_text(trf(
+                                "{} frames dropped while the encoder caught up",
+                                &[&recording.dropped()],
+                            ));
+                        }
                     }
-
-                    ui.separator();
-
-                    // Time display
-                    let current = self.file_player.position_duration();
-                    let total = self
-                        .file_player
-                        .info
-                        .as_ref()
-                        .map(|i| i.duration)
-                        .unwrap_or(Duration::ZERO);
-                    ui.label(format!(
-                        "{} / {}",
-                        format_duration(current),
-                        format_duration(total)
-                    ));
-
-                    ui.separator();
-
-                    // Volume
-                    ui.label("Vol:");
                     if ui
-                        .add(
-                            egui::Slider::new(&mut self.file_player.volume, 0.0..=2.0)
-                                .show_value(false),
-                        )
-                        .changed()
+                        .selectable_label(self.frozen.is_some(), tr("❄ Freeze"))
+                        .on_hover_text(self.keymap.hint(Action::Freeze))
+                        .clicked()
                     {
-                        self.file_player.sync_volume();
+                        self.toggle_freeze();
                     }
-
-                    ui.separator();
-
-                    // Speed
-                    ui.label("Speed:");
-                    ui.add(
-                        egui::Slider::new(&mut self.file_player.speed, 0.25..=2.0)
-                            .show_value(false),
-                    );
-                    ui.label(format!("{:.1}x", self.file_player.speed));
-
-                    ui.separator();
-
-                    // Loop toggle
-                    ui.checkbox(&mut self.file_player.loop_playback, "Loop");
                 });
-
-                ui.add_space(4.0);
             });
-        }
-
-        // Settings panel
-        if self.show_settings {
-            egui::SidePanel::right("settings_panel")
-                .min_width(200.0)
-                .show(ctx, |ui| {
-                    ui.heading("Settings");
-                    ui.separator();
-
-                    ui.collapsing("Audio", |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Gain:");
-                            if ui
-                                .add(
-                                    egui::Slider::new(&mut self.audio.gain, 0.1..=10.0)
-                                        .logarithmic(true),
-                                )
-                                .changed()
-                            {
-                                self.audio.sync_gain();
-                            }
-                        });
-                    });
-
-                    ui.separator();
-
-                    ui.collapsing("Display", |ui| {
-                        // Display mode selector
-                        ui.horizontal(|ui| {
-                            ui.label("Mode:");
-                            egui::ComboBox::from_id_salt("display_mode")
-                                .selected_text(self.oscilloscope.settings.display_mode.name())
-                                .show_ui(ui, |ui| {
-                                    for mode in DisplayMode::all() {
-                                        ui.selectable_value(
-                                            &mut self.oscilloscope.settings.display_mode,
-                                            *mode,
-                                            mode.name(),
-                                        );
-                                    }
-                                });
-                        });
-
-                        ui.horizontal(|ui| {
-                            ui.label("Zoom:");
-                            ui.add(egui::Slider::new(
-                                &mut self.oscilloscope.settings.zoom,
-                                0.1..=2.0,
-                            ));
-                        });
-
-                        ui.horizontal(|ui| {
-                            ui.label("Line width:");
-                            ui.add(egui::Slider::new(
-                                &mut self.oscilloscope.settings.line_width,
-                                0.5..=5.0,
-                            ));
-                        });
-
-                        ui.horizontal(|ui| {
-                            ui.label("Intensity:");
-                            ui.add(egui::Slider::new(
-                                &mut self.oscilloscope.settings.intensity,
-                                0.1..=1.0,
-                            ));
-                        });
-
-                        ui.horizontal(|ui| {
-                            ui.label("Persistence:");
-                            ui.add(egui::Slider::new(
-                                &mut self.oscilloscope.settings.persistence,
-                                0.0..=0.99,
-                            ));
-                        });
-
-                        ui.checkbox(&mut self.oscilloscope.settings.show_graticule, "Show grid");
-
-                        if ui.button("Clear persistence").clicked() {
-                            self.oscilloscope.clear_persistence();
-                        }
-                    });
-
-                    ui.separator();
-
-                    ui.collapsing("Channel", |ui| {
-                        ui.checkbox(&mut self.oscilloscope.settings.swap_xy, "Swap X/Y");
-                        ui.checkbox(&mut self.oscilloscope.settings.invert_x, "Invert X");
-                        ui.checkbox(&mut self.oscilloscope.settings.invert_y, "Invert Y");
-
-                        ui.separator();
-
-                        ui.horizontal(|ui| {
-                            ui.label("X offset:");
-                            ui.add(egui::Slider::new(
-                                &mut self.oscilloscope.settings.dc_offset_x,
-                                -1.0..=1.0,
-                            ));
-                        });
-
-                        ui.horizontal(|ui| {
-                            ui.label("Y offset:");
-                            ui.add(egui::Slider::new(
-                                &mut self.oscilloscope.settings.dc_offset_y,
-                                -1.0..=1.0,
-                            ));
-                        });
-
-                        if ui.button("Reset offsets").clicked() {
-                            self.oscilloscope.settings.dc_offset_x = 0.0;
-                            self.oscilloscope.settings.dc_offset_y = 0.0;
-                        }
-                    });
-
-                    ui.separator();
-
-                    ui.collapsing("Color", |ui| {
-                        // Theme selector
-                        ui.horizontal(|ui| {
-                            ui.label("Theme:");
-                            egui::ComboBox::from_id_salt("color_theme")
-                                .selected_text(self.oscilloscope.settings.theme.name())
-                                .show_ui(ui, |ui| {
-                                    for theme in ColorTheme::all() {
-                                        if ui
-                                            .selectable_label(
-                                                self.oscilloscope.settings.theme == *theme,
-                                                theme.name(),
-                                            )
-                                            .clicked()
-                                        {
-                                            self.oscilloscope.settings.apply_theme(*theme);
-                                        }
-                                    }
-                                });
-                        });
-                    });
-
-                    ui.separator();
-
-                    ui.collapsing("MIDI", |ui| {
-                        // Port selector
-                        ui.horizontal(|ui| {
-                            ui.label("Port:");
-                            egui::ComboBox::from_id_salt("midi_port")
-                                .selected_text(
-                                    self.midi
-                                        .ports
-                                        .get(self.midi.selected_port)
-                                        .cloned()
-                                        .unwrap_or_else(|| "None".to_string()),
-                                )
-                                .show_ui(ui, |ui| {
-                                    for (i, name) in self.midi.ports.iter().enumerate() {
-                                        ui.selectable_value(&mut self.midi.selected_port, i, name);
-                                    }
-                                });
-                        });
-
-                        ui.horizontal(|ui| {
-                            let button_text = if self.midi.is_connected {
-                                "Disconnect"
-                            } else {
-                                "Connect"
-                            };
-                            if ui.button(button_text).clicked() {
-                                self.midi.toggle();
-                            }
-                            if ui.button("Refresh").clicked() {
-                                self.midi.scan_ports();
-                            }
-                        });
-
-                        ui.small(&self.midi.status);
-                        ui.separator();
-
-                        // Mappings
-                        ui.label("Mappings:");
+        });
 
-                        // Snapshot mapping info to avoid borrow conflicts
-                        let mapping_info: Vec<_> = self
-                            .midi
-                            .mappings
-                            .iter()
-                            .enumerate()
-                            .map(|(i, m)| (i, m.cc, m.param.name()))
-                            .collect();
-                        let learning = self.midi.learning;
-
-                        let mut remove_idx = None;
-                        let mut learn_idx = None;
-                        let mut cancel_learn = false;
-
-                        for (i, cc, param_name) in &mapping_info {
-                            ui.horizontal(|ui| {
-                                let is_learning = learning == Some(*i);
-                                let label = if is_learning {
-                                    format!("CC ? -> {}", param_name)
-                                } else {
-                                    format!("CC {:>3} -> {}", cc, param_name)
-                                };
-                                ui.monospace(&label);
-
-                                if is_learning {
-                                    if ui.small_button("Cancel").clicked() {
-                                        cancel_learn = true;
-                                    }
-                                } else if ui.small_button("Learn").clicked() {
-                                    learn_idx = Some(*i);
-                                }
+        self.show_panels(ctx);
 
-                                if ui.small_button("X").clicked() {
-                                    remove_idx = Some(*i);
-                                }
-                            });
-                        }
+        if self.palette.open {
+            self.command_palette_ui(ctx);
+        }
 
-                        // Apply deferred actions
-                        if cancel_learn {
-                            self.midi.cancel_learn();
-                        }
-                        if let Some(idx) = learn_idx {
-                            self.midi.start_learn(idx);
-                        }
-                        if let Some(idx) = remove_idx {
-                            self.midi.remove_mapping(idx);
-                        }
+        if self.show_help {
+            self.help_ui(ctx);
+        }
 
-                        // Add new mapping
-                        let unmapped = self.midi.unmapped_params();
-                        if !unmapped.is_empty() && ui.button("+ Add").clicked() {
-                            self.midi.add_mapping(0, unmapped[0]);
-                        }
-                    });
-                });
+        if self.settings_recovery.is_some() {
+            self.settings_recovery_ui(ctx);
         }
 
         // Main oscilloscope display
         egui::CentralPanel::default().show(ctx, |ui| {
-            let samples = self.buffer.get_samples();
-            self.oscilloscope.show(ui, &samples, None);
+            // Reuse last frame's allocation; put back at the end of the frame
+            let fetch_started = Instant::now();
+            let mut samples = std::mem::take(&mut self.scratch);
+            // The attract sequence is drawn whatever silence would do
+            let silence = self.silence.active().filter(|_| !self.attracting);
+            match (&self.frozen, silence) {
+                (Some(frozen), _) => {
+                    samples.clear();
+                    samples.extend_from_slice(frozen);
+                }
+                // Keep draining the ring, but draw last frame's samples again
+                (None, Some(analysis::SilenceAction::Hold)) => self.consumer.update(),
+                // Nothing new to draw, so the persistence trail fades out
+                (None, Some(analysis::SilenceAction::Blank)) => {
+                    self.consumer.update();
+                    samples.clear();
+                }
+                _ => {
+                    self.consumer.update();
+                    match self.pacing_rate() {
+                        Some(rate) => self.pacer.extract(
+                            &self.consumer,
+                            rate,
+                            self.oscilloscope.settings.sample_count,
+                            &mut samples,
+                        ),
+                        None => {
+                            self.pacer.reset();
+                            self.consumer.copy_samples_into(&mut samples);
+                            self.apply_trigger(&mut samples);
+                        }
+                    }
+                }
+            }
+            let fetch = fetch_started.elapsed();
+            self.dc_meter.record(Instant::now(), self.analysis.latest());
+            let spectrum = self.show_spectrum.then_some(self.spectrum);
+            self.analysis.set_spectrum(spectrum);
+            let third_octave =
+                self.show_spectrum && self.spectrum_view == SpectrumView::ThirdOctave;
+            self.analysis
+                .set_rta(third_octave.then_some(self.rta_ballistics));
+            self.analysis.set_sample_rate(self.display_sample_rate());
+            let bands = self.analysis.latest().bands;
+            self.modulation.update(&bands, self.perf.frame_time);
+            let beats = self.analysis.latest().beats;
+            for action in self.reactivity.update(&beats, self.perf.frame_time) {
+                match action {
+                    reactivity::BeatAction::ClearPersistence => {
+                        self.run_action(Action::ClearPersistence)
+                    }
+                    reactivity::BeatAction::NextTheme => self.run_action(Action::NextTheme),
+                    // Drawn through `apply` below
+                    reactivity::BeatAction::PulseIntensity => {}
+                }
+            }
+            if self.oscilloscope.settings.auto_center {
+                let settings = &mut self.oscilloscope.settings;
+                settings.track_center(settings.window(&samples), self.perf.frame_time);
+            }
+            self.oscilloscope.trigger_level = (self.trigger.enabled
+                && self.trigger.source == TriggerSource::Signal)
+                .then_some(self.trigger.level);
+            // The modulated settings are only drawn, never kept
+            let modulated = self
+                .reactivity
+                .apply(&self.modulation.apply(&self.oscilloscope.settings));
+            let settings = std::mem::replace(&mut self.oscilloscope.settings, modulated);
+            let grid = match self.source_channels().cloned() {
+                Some(channels)
+                    if self.scope_grid.enabled
+                        && self.frozen.is_none()
+                        && !self.demo_engine.is_running() =>
+                {
+                    let size = ui.available_size();
+                    self.scope_grid
+                        .show(ui, &channels, &self.oscilloscope.settings, size)
+                }
+                _ => None,
+            };
+            let gridded = grid.is_some();
+            let response = grid.unwrap_or_else(|| self.oscilloscope.show(ui, &samples, None));
+            let modulated = std::mem::replace(&mut self.oscilloscope.settings, settings);
+            self.perf.record(
+                Instant::now(),
+                fetch,
+                self.oscilloscope.timings,
+                self.buffer.samples_written(),
+            );
+            self.update_quality();
+            if self.show_perf {
+                self.perf_overlay_ui(ctx, response.rect);
+            }
+            if self.show_phase {
+                self.phase_readout_ui(ctx, response.rect);
+            }
+            self.scope_navigation(ui, &response);
+            if !response.dragged() && !gridded {
+                self.scope_readout(&response, &samples);
+            }
+            self.update_overlay();
+            self.overlay
+                .paint(ui.painter(), response.rect, modulated.color);
+            self.branding
+                .paint(ui.painter(), response.rect, modulated.color);
+            self.frame_output
+                .publish(&samples, &modulated, &[&self.overlay, &self.branding]);
+            self.projector
+                .show(ctx, &samples, &modulated, &[&self.overlay, &self.branding]);
+            self.update_recording(&samples, &modulated);
 
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 ui.horizontal(|ui| {
-                    ui.small(format!("Samples: {}", samples.len()));
+                    ui.small(trf("Samples: {}", &[&samples.len()]));
                     ui.separator();
-                    ui.small(format!("Total: {}", self.buffer.samples_written()));
+                    ui.small(trf("Total: {}", &[&self.buffer.samples_written()]));
+                    let analysis = *self.analysis.latest();
+                    if let Some(levels) = analysis.levels {
+                        ui.separator();
+                        ui.small(trf(
+                            "Level: {} / {} dBFS",
+                            &[
+                                &format!("{:.1}", levels.rms_x_db),
+                                &format!("{:.1}", levels.rms_y_db),
+                            ],
+                        ));
+                        ui.separator();
+                        ui.small(trf(
+                            "Correlation: {}",
+                            &[&format!("{:+.2}", levels.correlation)],
+                        ));
+                    }
+                    if let Some(lufs) = analysis.loudness {
+                        ui.separator();
+                        ui.small(trf("Loudness: {} LUFS", &[&format!("{:.1}", lufs)]));
+                    }
+                    if let Some(pitch) = analysis.pitch {
+                        ui.separator();
+                        ui.small(trf("Pitch: {} Hz", &[&format!("{:.1}", pitch.hz)]));
+                    }
+                    if let Some(ratio) = analysis.ratio {
+                        ui.separator();
+                        self.ratio_readout_ui(ui, ratio);
+                    }
                     ui.separator();
-                    let mode_str = match self.input_mode {
-                        InputMode::Live => "Live Input",
-                        InputMode::File => "File Playback",
+                    let mode_str = if self.demo_engine.is_running() {
+                        tr("Demo")
+                    } else {
+                        match self.input_mode {
+                            InputMode::Live => tr("Live Input"),
+                            InputMode::File => tr("File Playback"),
+                            InputMode::Generator => tr("Generator"),
+                        }
                     };
-                    ui.small(format!(
+                    ui.small(trf(
                         "Mode: {} | Display: {}",
-                        mode_str,
-                        self.oscilloscope.settings.display_mode.name()
+                        &[
+                            &mode_str,
+                            &tr(self.oscilloscope.settings.display_mode.name()),
+                        ],
                     ));
+                    if self.frozen.is_some() {
+                        ui.separator();
+                        ui.small(tr("Frozen"));
+                    }
+                    if !self.screenshot_status.is_empty() {
+                        ui.separator();
+                        ui.small(&self.screenshot_status);
+                    }
                 });
             });
+            self.scratch = samples;
         });
     }
 }
@@ -583,3 +5125,21 @@ fn format_duration(d: Duration) -> String {
     let secs = secs % 60;
     format!("{:02}:{:02}", mins, secs)
 }
+
+/// Format a level as dBFS, relative to a full-scale value of 1.0
+fn format_dbfs(value: f32) -> String {
+    if value == 0.0 {
+        "-∞ dBFS".to_string()
+    } else {
+        format!("{:.1} dBFS", 20.0 * value.abs().log10())
+    }
+}
+
+/// Sample count in binary thousands ("16k"), as buffer sizes are shown
+fn format_sample_count(count: usize) -> String {
+    if count >= 1024 && count.is_multiple_of(1024) {
+        format!("{}k", count / 1024)
+    } else {
+        count.to_string()
+    }
+}
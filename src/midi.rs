@@ -3,6 +3,10 @@
 //! Receives MIDI CC messages and maps them to oscilloscope parameters.
 //! Uses a lock-free approach: the MIDI callback writes to shared atomics
 //! that the UI thread reads each frame.
+//!
+//! A note can also fire the display trigger. That happens right in the
+//! callback, so the trigger point is stamped when the note arrives rather
+//! than at the next frame.
 
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU8, Ordering};
@@ -11,6 +15,11 @@ use std::sync::Arc;
 use midir::{MidiInput, MidiInputConnection};
 use serde::{Deserialize, Serialize};
 
+use crate::audio::ExternalTrigger;
+
+/// `trigger_note` value for no trigger note
+const NO_NOTE: u8 = u8::MAX;
+
 /// A parameter that can be controlled via MIDI CC
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MidiParam {
@@ -23,6 +32,10 @@ pub enum MidiParam {
     Zoom,
     DcOffsetX,
     DcOffsetY,
+    ModelSpinX,
+    ModelSpinY,
+    ModelSpinZ,
+    PresetMorph,
 }
 
 impl MidiParam {
@@ -36,6 +49,10 @@ impl MidiParam {
         Self::Zoom,
         Self::DcOffsetX,
         Self::DcOffsetY,
+        Self::ModelSpinX,
+        Self::ModelSpinY,
+        Self::ModelSpinZ,
+        Self::PresetMorph,
     ];
 
     pub fn name(&self) -> &'static str {
@@ -49,6 +66,10 @@ impl MidiParam {
             Self::Zoom => "Zoom",
             Self::DcOffsetX => "DC Offset X",
             Self::DcOffsetY => "DC Offset Y",
+            Self::ModelSpinX => "Model Spin X",
+            Self::ModelSpinY => "Model Spin Y",
+            Self::ModelSpinZ => "Model Spin Z",
+            Self::PresetMorph => "Preset Morph",
         }
     }
 
@@ -60,7 +81,7 @@ impl MidiParam {
     }
 
     /// The (min, max) range for this parameter
-    fn range(&self) -> (f32, f32) {
+    pub fn range(&self) -> (f32, f32) {
         match self {
             Self::Gain => (0.1, 10.0),
             Self::Volume => (0.0, 2.0),
@@ -71,6 +92,8 @@ impl MidiParam {
             Self::Zoom => (0.1, 2.0),
             Self::DcOffsetX => (-1.0, 1.0),
             Self::DcOffsetY => (-1.0, 1.0),
+            Self::ModelSpinX | Self::ModelSpinY | Self::ModelSpinZ => (-180.0, 180.0),
+            Self::PresetMorph => (0.0, 1.0),
         }
     }
 }
@@ -141,6 +164,13 @@ pub struct MidiController {
 
     /// CC number being learned (for MIDI learn mode)
     pub learning: Option<usize>,
+
+    /// Note that fires the display trigger (`NO_NOTE` for none), shared
+    /// with the MIDI callback
+    trigger_note: Arc<AtomicU8>,
+
+    /// Fired by the MIDI callback on the trigger note
+    trigger: Option<ExternalTrigger>,
 }
 
 impl MidiController {
@@ -154,6 +184,8 @@ impl MidiController {
             status: "Disconnected".to_string(),
             is_connected: false,
             learning: None,
+            trigger_note: Arc::new(AtomicU8::new(NO_NOTE)),
+            trigger: None,
         };
         controller.scan_ports();
         controller
@@ -208,6 +240,8 @@ impl MidiController {
             .unwrap_or_else(|_| "Unknown".to_string());
 
         let cc_values = self.cc_values.clone();
+        let trigger_note = Arc::clone(&self.trigger_note);
+        let trigger = self.trigger.clone();
 
         match midi_in.connect(
             port,
@@ -219,6 +253,16 @@ impl MidiController {
                     let value = message[2] & 0x7F;
                     cc_values.set(cc, value);
                 }
+                // Note on: [0x90 | channel, note, velocity]; velocity 0
+                // is a note off
+                if message.len() == 3 && (message[0] & 0xF0) == 0x90 && message[2] > 0 {
+                    let note = message[1] & 0x7F;
+                    if let Some(trigger) = trigger.as_ref() {
+                        if note == trigger_note.load(Ordering::Relaxed) {
+                            trigger.fire();
+                        }
+                    }
+                }
             },
             (),
         ) {
@@ -288,6 +332,22 @@ impl MidiController {
         updates
     }
 
+    /// Set the handle notes fire the display trigger through; takes effect
+    /// at the next connect
+    pub fn set_trigger(&mut self, trigger: ExternalTrigger) {
+        self.trigger = Some(trigger);
+    }
+
+    /// Note (on any channel) that fires the display trigger
+    pub fn trigger_note(&self) -> Option<u8> {
+        Some(self.trigger_note.load(Ordering::Relaxed)).filter(|&note| note != NO_NOTE)
+    }
+
+    pub fn set_trigger_note(&self, note: Option<u8>) {
+        let note = note.map_or(NO_NOTE, |note| note & 0x7F);
+        self.trigger_note.store(note, Ordering::Relaxed);
+    }
+
     /// Add a new mapping
     pub fn add_mapping(&mut self, cc: u8, param: MidiParam) {
         self.mappings.push(MidiMapping { cc, param });
@@ -328,6 +388,31 @@ impl MidiController {
     }
 }
 
+/// Read the current value of a parameter from the app state
+pub fn current_value(
+    param: MidiParam,
+    oscilloscope: &crate::render::Oscilloscope,
+    audio: &crate::audio::AudioInput,
+    file_player: &crate::audio::AudioFilePlayer,
+    model: &crate::generator::ModelParams,
+) -> f32 {
+    match param {
+        MidiParam::Gain => audio.gain,
+        MidiParam::Volume => file_player.volume,
+        MidiParam::Speed => file_player.speed,
+        MidiParam::LineWidth => oscilloscope.settings.line_width,
+        MidiParam::Intensity => oscilloscope.settings.intensity,
+        MidiParam::Persistence => oscilloscope.settings.persistence,
+        MidiParam::Zoom => oscilloscope.settings.zoom,
+        MidiParam::DcOffsetX => oscilloscope.settings.dc_offset_x,
+        MidiParam::DcOffsetY => oscilloscope.settings.dc_offset_y,
+        MidiParam::ModelSpinX => model.spin[0],
+        MidiParam::ModelSpinY => model.spin[1],
+        MidiParam::ModelSpinZ => model.spin[2],
+        MidiParam::PresetMorph => 0.0,
+    }
+}
+
 /// Apply MIDI parameter updates to the app state.
 /// Returns a HashMap of which parameters were updated (for syncing atomics).
 pub fn apply_updates(
@@ -335,6 +420,7 @@ pub fn apply_updates(
     oscilloscope: &mut crate::render::Oscilloscope,
     audio: &mut crate::audio::AudioInput,
     file_player: &mut crate::audio::AudioFilePlayer,
+    model: &mut crate::generator::ModelParams,
 ) -> HashMap<MidiParam, ()> {
     let mut applied = HashMap::new();
 
@@ -369,6 +455,17 @@ pub fn apply_updates(
             MidiParam::DcOffsetY => {
                 oscilloscope.settings.dc_offset_y = value;
             }
+            MidiParam::ModelSpinX | MidiParam::ModelSpinY | MidiParam::ModelSpinZ => {
+                let axis = match param {
+                    MidiParam::ModelSpinX => 0,
+                    MidiParam::ModelSpinY => 1,
+                    _ => 2,
+                };
+                model.spin[axis] = value;
+                model.sync_spin();
+            }
+            // A trigger rather than a value; the app starts the morph itself
+            MidiParam::PresetMorph => {}
         }
         applied.insert(param, ());
     }
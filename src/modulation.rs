@@ -0,0 +1,221 @@
+//! Audio-reactive modulation
+//!
+//! Each route follows the level of one band with an envelope follower and
+//! pushes a display parameter by up to its depth, so the picture pumps with
+//! the music. Modulation never touches the settings themselves: every frame
+//! a modulated copy is drawn and the sliders keep their own values.
+
+use eframe::egui::ecolor::Hsva;
+use eframe::egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Band;
+use crate::render::OscilloscopeSettings;
+
+/// Band level, in dBFS RMS, that reads as no envelope at all
+const FLOOR_DB: f32 = -48.0;
+
+/// A display parameter a route can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModTarget {
+    Intensity,
+    Zoom,
+    LineWidth,
+    Hue,
+}
+
+impl ModTarget {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Intensity => "Intensity",
+            Self::Zoom => "Zoom",
+            Self::LineWidth => "Line Width",
+            Self::Hue => "Hue",
+        }
+    }
+
+    pub fn all() -> &'static [ModTarget] {
+        &[Self::Intensity, Self::Zoom, Self::LineWidth, Self::Hue]
+    }
+
+    /// How far full depth moves the parameter
+    fn span(&self) -> f32 {
+        match self {
+            Self::Intensity => 0.9,
+            Self::Zoom => 1.0,
+            Self::LineWidth => 4.5,
+            // Degrees around the color wheel
+            Self::Hue => 360.0,
+        }
+    }
+}
+
+/// One envelope follower and where it goes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModRoute {
+    pub band: Band,
+    pub target: ModTarget,
+    /// -1 to 1; negative pushes the parameter the other way
+    pub depth: f32,
+    /// Rise and fall times of the envelope, in seconds
+    pub attack: f32,
+    pub release: f32,
+    /// Current envelope, 0 to 1
+    #[serde(skip)]
+    envelope: f32,
+}
+
+impl Default for ModRoute {
+    fn default() -> Self {
+        Self {
+            band: Band::Low,
+            target: ModTarget::Zoom,
+            depth: -0.3,
+            attack: 0.01,
+            release: 0.25,
+            envelope: 0.0,
+        }
+    }
+}
+
+impl ModRoute {
+    pub fn envelope(&self) -> f32 {
+        self.envelope
+    }
+
+    /// Follow a band level (linear RMS) for `dt` seconds
+    fn follow(&mut self, rms: f32, dt: f32) {
+        let db = 20.0 * rms.max(1e-6).log10();
+        let target = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+        let time = if target > self.envelope {
+            self.attack
+        } else {
+            self.release
+        };
+        let step = 1.0 - (-dt.max(0.0) / time.max(1e-3)).exp();
+        self.envelope += (target - self.envelope) * step;
+    }
+}
+
+/// The routes and their envelopes
+#[derive(Debug, Clone, Default)]
+pub struct Modulation {
+    pub enabled: bool,
+    pub routes: Vec<ModRoute>,
+}
+
+impl Modulation {
+    /// Move the envelopes towards the latest band levels, given in
+    /// `Band::all()` order
+    pub fn update(&mut self, bands: &[f32; 4], dt: f32) {
+        for route in &mut self.routes {
+            route.follow(bands[route.band.index()], dt);
+        }
+    }
+
+    /// A copy of `settings` with the modulation applied. Routes to the same
+    /// target add up.
+    pub fn apply(&self, settings: &OscilloscopeSettings) -> OscilloscopeSettings {
+        let mut modulated = settings.clone();
+        if !self.enabled {
+            return modulated;
+        }
+        let amount = |target: ModTarget| -> f32 {
+            self.routes
+                .iter()
+                .filter(|route| route.target == target)
+                .map(|route| route.depth * route.envelope * target.span())
+                .sum()
+        };
+        modulated.intensity = (settings.intensity + amount(ModTarget::Intensity)).clamp(0.1, 1.0);
+        modulated.zoom = (settings.zoom + amount(ModTarget::Zoom)).clamp(0.1, 2.0);
+        modulated.line_width = (settings.line_width + amount(ModTarget::LineWidth)).clamp(0.5, 5.0);
+        let hue = amount(ModTarget::Hue);
+        if hue != 0.0 {
+            modulated.color = rotate_hue(settings.color, hue);
+        }
+        modulated
+    }
+}
+
+/// Turn a color around the color wheel by `degrees`
+fn rotate_hue(color: Color32, degrees: f32) -> Color32 {
+    let mut hsva = Hsva::from(color);
+    hsva.h = (hsva.h + degrees / 360.0).rem_euclid(1.0);
+    hsva.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_attacks_and_releases() {
+        let mut route = ModRoute {
+            attack: 0.01,
+            release: 0.5,
+            ..ModRoute::default()
+        };
+        // Full scale for 50 ms: five attack time constants
+        for _ in 0..5 {
+            route.follow(1.0, 0.01);
+        }
+        assert!(route.envelope() > 0.99);
+        // Silence for one release time constant
+        for _ in 0..50 {
+            route.follow(0.0, 0.01);
+        }
+        assert!((route.envelope() - (-1.0f32).exp()).abs() < 0.01);
+
+        // Half way up the range: -24 dBFS
+        let mut route = ModRoute::default();
+        for _ in 0..100 {
+            route.follow(10f32.powf(-24.0 / 20.0), 0.01);
+        }
+        assert!((route.envelope() - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_apply_leaves_the_settings_alone() {
+        let settings = OscilloscopeSettings {
+            zoom: 1.0,
+            intensity: 0.5,
+            ..OscilloscopeSettings::default()
+        };
+        let mut modulation = Modulation {
+            enabled: true,
+            routes: vec![
+                ModRoute {
+                    target: ModTarget::Zoom,
+                    depth: -0.5,
+                    envelope: 1.0,
+                    ..ModRoute::default()
+                },
+                ModRoute {
+                    target: ModTarget::Intensity,
+                    depth: 1.0,
+                    envelope: 1.0,
+                    ..ModRoute::default()
+                },
+                ModRoute {
+                    target: ModTarget::Hue,
+                    depth: 0.5,
+                    envelope: 1.0,
+                    ..ModRoute::default()
+                },
+            ],
+        };
+        let modulated = modulation.apply(&settings);
+        assert!((modulated.zoom - 0.5).abs() < 1e-6);
+        // Clamped to the slider's range
+        assert_eq!(modulated.intensity, 1.0);
+        // Green half way round the wheel is magenta
+        let color = modulated.color;
+        assert!(color.r() > color.g() && color.b() > color.g(), "{color:?}");
+        assert_eq!(settings.zoom, 1.0);
+
+        modulation.enabled = false;
+        assert_eq!(modulation.apply(&settings).zoom, 1.0);
+    }
+}
@@ -0,0 +1,592 @@
+//! OBS remote control
+//!
+//! A client for obs-websocket, the remote control protocol built into OBS
+//! 28 and later. It can start OBS recording when playback starts and stop
+//! it when playback stops, so a performance and its recording begin
+//! together, and switch to the scene named after a preset whenever that
+//! preset is loaded.
+//!
+//! The socket lives on its own thread. The UI sends it requests over a
+//! channel and picks up what OBS says back with `poll`; reads time out now
+//! and then so the thread can see requests and a stop. Connecting times out
+//! too, and a stopped thread is left to finish on its own, so the UI never
+//! waits on the network. While the remote is on, a lost or refused
+//! connection is tried again, less often the longer OBS stays away.
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tungstenite::handshake::HandshakeError;
+use tungstenite::{Message, WebSocket};
+
+/// Longest wait for a message before checking for requests to send
+const READ_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Longest wait for OBS to accept the connection and answer the handshake
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Wait before the first reconnect, doubling up to the longest
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// obs-websocket protocol revision spoken
+const RPC_VERSION: u64 = 1;
+
+/// Message opcodes
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+
+/// Errors that end a connection
+#[derive(Debug, Error)]
+pub enum ObsError {
+    #[error("{0}")]
+    Socket(Box<tungstenite::Error>),
+
+    #[error("Bad message from OBS: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Unexpected message from OBS")]
+    Protocol,
+
+    #[error("OBS closed the connection; check the password")]
+    Closed,
+
+    #[error("OBS didn't answer")]
+    NoAnswer,
+}
+
+impl From<tungstenite::Error> for ObsError {
+    fn from(e: tungstenite::Error) -> Self {
+        Self::Socket(Box::new(e))
+    }
+}
+
+/// What the thread is asked to do
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    StartRecord,
+    StopRecord,
+    SetScene(String),
+}
+
+/// What the thread reports
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Connected,
+    Scenes(Vec<String>),
+    /// A request OBS turned down, which doesn't end the connection
+    Refused(String),
+    Ended(String),
+}
+
+/// The thread and its channels. Dropping it stops the thread without
+/// waiting for it.
+struct Connection {
+    stop: Arc<AtomicBool>,
+    commands: mpsc::Sender<Command>,
+    events: mpsc::Receiver<Event>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Connection to OBS and what to do with it
+pub struct ObsRemote {
+    pub host: String,
+    pub port: u16,
+    pub password: String,
+    /// Start and stop recording with playback
+    pub follow_playback: bool,
+    /// Switch to the scene named like a preset when it's loaded
+    pub scene_per_preset: bool,
+    /// Status message
+    pub status: String,
+    /// Scene names, once connected
+    pub scenes: Vec<String>,
+    /// Whether the user has the remote on, connected or not
+    enabled: bool,
+    connected: bool,
+    connection: Option<Connection>,
+    /// When to try again after the connection ended
+    retry_at: Option<Instant>,
+    retry_delay: Duration,
+}
+
+impl Default for ObsRemote {
+    fn default() -> Self {
+        Self {
+            host: Self::DEFAULT_HOST.to_string(),
+            port: Self::DEFAULT_PORT,
+            password: String::new(),
+            follow_playback: true,
+            scene_per_preset: false,
+            status: "Disconnected".to_string(),
+            scenes: Vec::new(),
+            enabled: false,
+            connected: false,
+            connection: None,
+            retry_at: None,
+            retry_delay: RETRY_DELAY,
+        }
+    }
+}
+
+impl ObsRemote {
+    pub const DEFAULT_HOST: &str = "localhost";
+    pub const DEFAULT_PORT: u16 = 4455;
+
+    /// Whether the remote is on, connected or not
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Connect or disconnect
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.start();
+        } else {
+            self.stop();
+        }
+    }
+
+    /// Connect on a new thread, and keep reconnecting until `stop`;
+    /// reconnects at once if already running
+    pub fn start(&mut self) {
+        self.stop();
+        self.enabled = true;
+        self.retry_delay = RETRY_DELAY;
+        self.connect();
+    }
+
+    fn connect(&mut self) {
+        self.retry_at = None;
+        let stop = Arc::new(AtomicBool::new(false));
+        let (commands, command_receiver) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let (host, port) = (self.host.clone(), self.port);
+        let password = self.password.clone();
+        {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let result = run(
+                    &host,
+                    port,
+                    &password,
+                    &stop,
+                    &command_receiver,
+                    &event_sender,
+                );
+                let message = match result {
+                    Ok(()) => "Disconnected".to_string(),
+                    Err(e) => {
+                        log::error!("OBS connection to {}:{} ended: {}", host, port, e);
+                        format!("Error: {}", e)
+                    }
+                };
+                let _ = event_sender.send(Event::Ended(message));
+            });
+        }
+        self.connection = Some(Connection {
+            stop,
+            commands,
+            events,
+        });
+        self.status = format!("Connecting to {}:{}", self.host, self.port);
+    }
+
+    /// Disconnect and stop reconnecting. The thread closes the socket on
+    /// its own time.
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        self.retry_at = None;
+        if self.connection.take().is_some() {
+            self.status = "Disconnected".to_string();
+        }
+        self.connected = false;
+        self.scenes.clear();
+    }
+
+    /// Pick up what the thread has reported, and reconnect when it's time.
+    /// Call once per frame.
+    pub fn poll(&mut self) {
+        if self
+            .retry_at
+            .is_some_and(|retry_at| Instant::now() >= retry_at)
+        {
+            self.connect();
+        }
+        let Some(connection) = &self.connection else {
+            return;
+        };
+        let mut ended = false;
+        while let Ok(event) = connection.events.try_recv() {
+            match event {
+                Event::Connected => {
+                    self.connected = true;
+                    self.retry_delay = RETRY_DELAY;
+                    self.status = format!("Connected to {}:{}", self.host, self.port);
+                    log::info!("Connected to OBS at {}:{}", self.host, self.port);
+                }
+                Event::Scenes(scenes) => self.scenes = scenes,
+                Event::Refused(message) => {
+                    log::warn!("OBS: {}", message);
+                    self.status = message;
+                }
+                Event::Ended(message) => {
+                    self.status = message;
+                    ended = true;
+                }
+            }
+        }
+        if ended {
+            self.connected = false;
+            self.scenes.clear();
+            self.connection = None;
+            if self.enabled {
+                self.retry_at = Some(Instant::now() + self.retry_delay);
+                self.status = format!(
+                    "{}; trying again in {} s",
+                    self.status,
+                    self.retry_delay.as_secs()
+                );
+                self.retry_delay = (self.retry_delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+
+    fn send(&self, command: Command) {
+        if let (true, Some(connection)) = (self.connected, &self.connection) {
+            let _ = connection.commands.send(command);
+        }
+    }
+
+    /// Start or stop OBS recording
+    pub fn set_recording(&self, recording: bool) {
+        self.send(if recording {
+            Command::StartRecord
+        } else {
+            Command::StopRecord
+        });
+    }
+
+    /// Switch OBS to `scene`, if it has one by that name
+    pub fn set_scene(&self, scene: &str) {
+        if self.scenes.iter().any(|s| s == scene) {
+            self.send(Command::SetScene(scene.to_string()));
+        }
+    }
+}
+
+impl Drop for ObsRemote {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+type Socket = WebSocket<TcpStream>;
+
+/// Open the socket to `host:port` and shake hands, giving up on each step
+/// after `CONNECT_TIMEOUT`
+fn open(host: &str, port: u16) -> Result<Socket, ObsError> {
+    let mut last_error = None;
+    let addresses = (host, port)
+        .to_socket_addrs()
+        .map_err(tungstenite::Error::Io)?;
+    let stream = addresses
+        .into_iter()
+        .find_map(
+            |address| match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(stream) => Some(stream),
+                Err(e) => {
+                    last_error = Some(e);
+                    None
+                }
+            },
+        )
+        .ok_or_else(|| match last_error {
+            Some(e) => ObsError::from(tungstenite::Error::Io(e)),
+            None => ObsError::NoAnswer,
+        })?;
+    stream
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(tungstenite::Error::Io)?;
+    let url = format!("ws://{host}:{port}");
+    let (socket, _) = tungstenite::client(url, stream).map_err(|e| match e {
+        HandshakeError::Failure(e) => ObsError::from(e),
+        HandshakeError::Interrupted(_) => ObsError::NoAnswer,
+    })?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(tungstenite::Error::Io)?;
+    Ok(socket)
+}
+
+/// Connect, identify, then pass requests on until told to stop
+fn run(
+    host: &str,
+    port: u16,
+    password: &str,
+    stop: &AtomicBool,
+    commands: &mpsc::Receiver<Command>,
+    events: &mpsc::Sender<Event>,
+) -> Result<(), ObsError> {
+    let mut socket = open(host, port)?;
+    if stop.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let Some(hello) = receive(&mut socket, stop)? else {
+        return Ok(());
+    };
+    if hello["op"] != OP_HELLO {
+        return Err(ObsError::Protocol);
+    }
+    let mut identify = json!({ "rpcVersion": RPC_VERSION, "eventSubscriptions": 0 });
+    if let Some(auth) = hello["d"].get("authentication") {
+        let (Some(challenge), Some(salt)) = (auth["challenge"].as_str(), auth["salt"].as_str())
+        else {
+            return Err(ObsError::Protocol);
+        };
+        identify["authentication"] = authentication(password, salt, challenge).into();
+    }
+    send(&mut socket, OP_IDENTIFY, identify)?;
+    let Some(identified) = receive(&mut socket, stop)? else {
+        return Ok(());
+    };
+    if identified["op"] != OP_IDENTIFIED {
+        return Err(ObsError::Protocol);
+    }
+    let _ = events.send(Event::Connected);
+    request(&mut socket, "GetSceneList", Value::Null)?;
+
+    while !stop.load(Ordering::Relaxed) {
+        while let Ok(command) = commands.try_recv() {
+            match command {
+                Command::StartRecord => request(&mut socket, "StartRecord", Value::Null)?,
+                Command::StopRecord => request(&mut socket, "StopRecord", Value::Null)?,
+                Command::SetScene(scene) => request(
+                    &mut socket,
+                    "SetCurrentProgramScene",
+                    json!({ "sceneName": scene }),
+                )?,
+            }
+        }
+        let Some(message) = read(&mut socket)? else {
+            continue;
+        };
+        if message["op"] != OP_REQUEST_RESPONSE {
+            continue;
+        }
+        let response = &message["d"];
+        let kind = response["requestType"].as_str().unwrap_or_default();
+        if response["requestStatus"]["result"] != true {
+            let comment = response["requestStatus"]["comment"]
+                .as_str()
+                .unwrap_or("refused");
+            let _ = events.send(Event::Refused(format!("{kind}: {comment}")));
+        } else if kind == "GetSceneList" {
+            let scenes = response["responseData"]["scenes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|scene| scene["sceneName"].as_str().map(str::to_string))
+                .collect();
+            let _ = events.send(Event::Scenes(scenes));
+        }
+    }
+    let _ = socket.close(None);
+    let _ = socket.flush();
+    Ok(())
+}
+
+/// The next message, or None if there isn't one yet
+fn read(socket: &mut Socket) -> Result<Option<Value>, ObsError> {
+    match socket.read() {
+        Ok(Message::Text(text)) => Ok(Some(serde_json::from_str(&text)?)),
+        Ok(Message::Close(_)) => Err(ObsError::Closed),
+        Ok(_) => Ok(None),
+        Err(tungstenite::Error::Io(e))
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+            Err(ObsError::Closed)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Wait for the next message, or None if told to stop first
+fn receive(socket: &mut Socket, stop: &AtomicBool) -> Result<Option<Value>, ObsError> {
+    while !stop.load(Ordering::Relaxed) {
+        if let Some(message) = read(socket)? {
+            return Ok(Some(message));
+        }
+    }
+    Ok(None)
+}
+
+fn send(socket: &mut Socket, op: u64, data: Value) -> Result<(), ObsError> {
+    let message = json!({ "op": op, "d": data });
+    socket.send(Message::Text(message.to_string()))?;
+    Ok(())
+}
+
+/// Send a request; the answer comes back through `read`
+fn request(socket: &mut Socket, kind: &str, data: Value) -> Result<(), ObsError> {
+    let mut request = json!({ "requestType": kind, "requestId": kind });
+    if !data.is_null() {
+        request["requestData"] = data;
+    }
+    send(socket, OP_REQUEST, request)
+}
+
+/// The answer to OBS's challenge: base64(sha256(secret + challenge)), where
+/// the secret is base64(sha256(password + salt))
+fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let base64 = base64::engine::general_purpose::STANDARD;
+    let secret = base64.encode(Sha256::digest(format!("{password}{salt}")));
+    base64.encode(Sha256::digest(format!("{secret}{challenge}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn test_authentication() {
+        // Example from the obs-websocket protocol documentation
+        assert_eq!(
+            authentication(
+                "supersecretpassword",
+                "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=",
+                "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY=",
+            ),
+            "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+        );
+    }
+
+    /// Poll until `done` or give up after a few seconds
+    fn wait_for(obs: &mut ObsRemote, done: impl Fn(&ObsRemote) -> bool) {
+        let start = Instant::now();
+        while !done(obs) {
+            assert!(start.elapsed() < Duration::from_secs(5), "{}", obs.status);
+            obs.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_identifies_and_sends_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Plays OBS: asks for a password, then answers the scene list and
+        // hands back every other request it gets
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            let hello = json!({ "op": 0, "d": {
+                "rpcVersion": 1,
+                "authentication": { "challenge": "abc", "salt": "xyz" },
+            }});
+            socket.send(Message::Text(hello.to_string())).unwrap();
+            let mut requests = Vec::new();
+            loop {
+                let message = match socket.read() {
+                    Ok(Message::Text(text)) => serde_json::from_str::<Value>(&text).unwrap(),
+                    Ok(_) => continue,
+                    Err(_) => break,
+                };
+                match message["op"].as_u64() {
+                    Some(OP_IDENTIFY) => {
+                        let auth = authentication("secret", "xyz", "abc");
+                        assert_eq!(message["d"]["authentication"], auth);
+                        let identified = json!({ "op": 2, "d": { "negotiatedRpcVersion": 1 } });
+                        socket.send(Message::Text(identified.to_string())).unwrap();
+                    }
+                    Some(OP_REQUEST) if message["d"]["requestType"] == "GetSceneList" => {
+                        let response = json!({ "op": 7, "d": {
+                            "requestType": "GetSceneList",
+                            "requestId": message["d"]["requestId"],
+                            "requestStatus": { "result": true, "code": 100 },
+                            "responseData": { "scenes": [
+                                { "sceneName": "Green" }, { "sceneName": "Live" },
+                            ]},
+                        }});
+                        socket.send(Message::Text(response.to_string())).unwrap();
+                    }
+                    Some(OP_REQUEST) => requests.push(message["d"].clone()),
+                    _ => {}
+                }
+            }
+            requests
+        });
+
+        let mut obs = ObsRemote::default();
+        obs.host = "127.0.0.1".to_string();
+        obs.port = port;
+        obs.password = "secret".to_string();
+        obs.start();
+        wait_for(&mut obs, |obs| obs.scenes.len() == 2);
+        assert!(obs.is_connected());
+        obs.set_recording(true);
+        // Scenes OBS doesn't have aren't asked for
+        obs.set_scene("Nope");
+        obs.set_scene("Live");
+        obs.set_recording(false);
+        // Give the thread a moment to pass them on
+        thread::sleep(Duration::from_millis(200));
+        obs.stop();
+
+        let requests = server.join().unwrap();
+        let kinds: Vec<_> = requests.iter().map(|r| r["requestType"].clone()).collect();
+        assert_eq!(
+            kinds,
+            ["StartRecord", "SetCurrentProgramScene", "StopRecord"]
+        );
+        assert_eq!(requests[1]["requestData"]["sceneName"], "Live");
+    }
+
+    #[test]
+    fn test_keeps_trying_while_enabled() {
+        // A port nobody listens on any more
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let mut obs = ObsRemote::default();
+        obs.host = "127.0.0.1".to_string();
+        obs.port = port;
+        obs.set_enabled(true);
+        wait_for(&mut obs, |obs| obs.retry_at.is_some());
+        assert!(obs.is_enabled() && !obs.is_connected());
+        assert_eq!(obs.retry_delay, RETRY_DELAY * 2);
+
+        obs.set_enabled(false);
+        assert!(!obs.is_enabled() && obs.retry_at.is_none());
+    }
+}
@@ -0,0 +1,173 @@
+//! OSC trigger input
+//!
+//! Listens on a UDP port for OSC messages and fires the display trigger
+//! whenever one arrives at the chosen address, so a sequencer or lighting
+//! desk can lock the display to its downbeat. Only the address pattern is
+//! read; arguments are ignored and bundles are unpacked. The socket lives on
+//! its own thread, which checks for a stop request between reads.
+
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::audio::ExternalTrigger;
+
+/// Longest wait for a packet before checking whether to stop
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Largest packet read; anything longer is truncated
+const MAX_PACKET: usize = 1536;
+
+/// UDP listener that fires the trigger on one OSC address
+pub struct OscListener {
+    pub port: u16,
+    /// OSC address to trigger on, e.g. "/scope/trigger"
+    pub address: String,
+    /// Status message
+    pub status: String,
+    trigger: ExternalTrigger,
+    running: Option<(Arc<AtomicBool>, thread::JoinHandle<()>)>,
+}
+
+impl OscListener {
+    pub const DEFAULT_PORT: u16 = 9000;
+    pub const DEFAULT_ADDRESS: &str = "/scope/trigger";
+
+    pub fn new(trigger: ExternalTrigger) -> Self {
+        Self {
+            port: Self::DEFAULT_PORT,
+            address: Self::DEFAULT_ADDRESS.to_string(),
+            status: "Stopped".to_string(),
+            trigger,
+            running: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Start or stop listening
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.start();
+        } else {
+            self.stop();
+        }
+    }
+
+    /// Bind the port and start listening; restarts if already running
+    pub fn start(&mut self) {
+        self.stop();
+        let socket = match UdpSocket::bind(("0.0.0.0", self.port)) {
+            Ok(socket) => socket,
+            Err(e) => {
+                self.status = format!("Error: {}", e);
+                log::error!("OSC bind error on port {}: {}", self.port, e);
+                return;
+            }
+        };
+        if let Err(e) = socket.set_read_timeout(Some(READ_TIMEOUT)) {
+            self.status = format!("Error: {}", e);
+            return;
+        }
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = Arc::clone(&running);
+        let address = self.address.clone();
+        let trigger = self.trigger.clone();
+        let handle = thread::spawn(move || {
+            let mut packet = [0u8; MAX_PACKET];
+            while thread_running.load(Ordering::Relaxed) {
+                // Timeouts and stray errors just mean another round
+                if let Ok(len) = socket.recv(&mut packet) {
+                    if addressed_to(&packet[..len], &address) {
+                        trigger.fire();
+                    }
+                }
+            }
+        });
+
+        self.running = Some((running, handle));
+        self.status = format!("Listening on port {} for {}", self.port, self.address);
+        log::info!("OSC listening on port {}", self.port);
+    }
+
+    pub fn stop(&mut self) {
+        if let Some((running, handle)) = self.running.take() {
+            running.store(false, Ordering::Relaxed);
+            let _ = handle.join();
+            self.status = "Stopped".to_string();
+        }
+    }
+}
+
+impl Drop for OscListener {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Whether an OSC packet is, or is a bundle containing, a message to
+/// `address`
+fn addressed_to(packet: &[u8], address: &str) -> bool {
+    // Bundle: "#bundle\0", an 8-byte time tag, then elements each prefixed
+    // with their big-endian length
+    if let Some(mut rest) = packet
+        .strip_prefix(b"#bundle\0")
+        .and_then(|rest| rest.get(8..))
+    {
+        while let Some((size, tail)) = rest.split_first_chunk::<4>() {
+            let size = u32::from_be_bytes(*size) as usize;
+            let Some(element) = tail.get(..size) else {
+                return false;
+            };
+            if addressed_to(element, address) {
+                return true;
+            }
+            rest = &tail[size..];
+        }
+        return false;
+    }
+    // Message: the address comes first, NUL-terminated
+    packet.split(|&b| b == 0).next() == Some(address.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An OSC message to `address` with one int argument
+    fn message(address: &str) -> Vec<u8> {
+        let mut packet = address.as_bytes().to_vec();
+        packet.resize((packet.len() / 4 + 1) * 4, 0);
+        packet.extend_from_slice(b",i\0\0");
+        packet.extend_from_slice(&1i32.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn test_matches_address_in_messages_and_bundles() {
+        assert!(addressed_to(&message("/scope/trigger"), "/scope/trigger"));
+        assert!(!addressed_to(&message("/scope/trig"), "/scope/trigger"));
+        assert!(!addressed_to(
+            &message("/scope/trigger/x"),
+            "/scope/trigger"
+        ));
+
+        let mut bundle = b"#bundle\0".to_vec();
+        bundle.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        for address in ["/beat/count", "/scope/trigger"] {
+            let element = message(address);
+            bundle.extend_from_slice(&(element.len() as u32).to_be_bytes());
+            bundle.extend_from_slice(&element);
+        }
+        assert!(addressed_to(&bundle, "/scope/trigger"));
+        assert!(!addressed_to(&bundle, "/other"));
+
+        // Cut off inside the first element
+        assert!(!addressed_to(&bundle[..24], "/scope/trigger"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Long exposures
+//!
+//! A stretch of a file, up to a whole track, drawn into one image the way a
+//! camera with its shutter held open would see a real scope: the beam
+//! energy of every sample is added up in floating point, with no decay.
+//! EXR keeps the sums as linear HDR values for grading elsewhere; a 16-bit
+//! PNG tone-maps them into something ready to print.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use eframe::egui::ecolor::{gamma_from_linear, Rgba};
+use serde::{Deserialize, Serialize};
+
+use super::job::{skip_samples, RenderError, RenderJob, RenderKind};
+use super::screenshot::ScreenshotError;
+use super::sequence::sample_range;
+use crate::audio::{FileReader, XYSample};
+use crate::render::{OscilloscopeSettings, Rasterizer};
+
+/// Share of the lit pixels at or under full brightness. Spots where the
+/// beam sat still get far more energy than the rest of the figure, so the
+/// very brightest don't set the scale.
+const WHITE_PERCENTILE: f32 = 0.995;
+
+/// How hard the PNG's tone curve bends; full brightness comes out at
+/// 1 - e^-TONE of the beam color
+const TONE: f32 = 2.0;
+
+/// File format of an exposure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExposureFormat {
+    /// Linear, unclamped 32-bit float
+    Exr,
+    /// Tone-mapped sRGB, 16 bits a channel
+    Png16,
+}
+
+impl ExposureFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Exr => "EXR (HDR)",
+            Self::Png16 => "16-bit PNG",
+        }
+    }
+
+    pub fn all() -> &'static [ExposureFormat] {
+        &[Self::Exr, Self::Png16]
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Exr => "exr",
+            Self::Png16 => "png",
+        }
+    }
+}
+
+/// What to expose
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExposureParams {
+    pub start: Duration,
+    pub end: Duration,
+    pub width: usize,
+    pub height: usize,
+    pub format: ExposureFormat,
+}
+
+/// Expose `params` from the file at `source` into `path` on a background
+/// thread
+pub fn start_exposure(
+    source: PathBuf,
+    path: PathBuf,
+    params: ExposureParams,
+    settings: OscilloscopeSettings,
+) -> RenderJob {
+    RenderJob::spawn(
+        RenderKind::Exposure,
+        path.clone(),
+        move |cancel, progress| {
+            render_exposure(&source, &path, &params, &settings, cancel, progress)
+        },
+    )
+}
+
+/// Expose `params` from the file at `source` and write the image to
+/// `path`, on the calling thread. Returns the number of windows drawn.
+pub fn render_exposure(
+    source: &Path,
+    path: &Path,
+    params: &ExposureParams,
+    settings: &OscilloscopeSettings,
+    cancel: &AtomicBool,
+    progress: &AtomicU32,
+) -> Result<u64, RenderError> {
+    let mut reader = FileReader::open(source)?;
+    let (start, end) = sample_range(params.start, params.end, reader.sample_rate());
+    skip_samples(&mut reader, start, cancel)?;
+
+    // Every sample is drawn once, so thinning the points would only lose
+    // detail the exposure has time for
+    let settings = OscilloscopeSettings {
+        decimate: false,
+        ..settings.clone()
+    };
+    let window = settings.sample_count.max(2);
+    let mut rasterizer = Rasterizer::new(params.width, params.height);
+    let mut block: VecDeque<XYSample> = VecDeque::new();
+    let mut last = None;
+    let mut done = 0;
+    let mut windows = 0;
+    while done < end - start {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(RenderError::Cancelled);
+        }
+        // Each window starts with the last sample of the one before, so
+        // the lines join up
+        block.extend(last);
+        let want = (window - block.len()).min((end - start - done) as usize);
+        let read = reader.read(want, &mut block);
+        if read == 0 {
+            break;
+        }
+        rasterizer.accumulate(block.make_contiguous(), &settings);
+        last = block.back().copied();
+        block.clear();
+        done += read as u64;
+        windows += 1;
+        let fraction = done as f32 / (end - start) as f32;
+        progress.store(fraction.to_bits(), Ordering::Relaxed);
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let energy = rasterizer.energy();
+    let white = white_point(energy);
+    let (width, height) = (rasterizer.width(), rasterizer.height());
+    match params.format {
+        ExposureFormat::Exr => write_exr(path, width, height, energy, white, &settings)?,
+        ExposureFormat::Png16 => write_png16(path, width, height, energy, white, &settings)?,
+    }
+    log::info!("Saved long exposure to {}", path.display());
+    Ok(windows)
+}
+
+/// Energy that comes out at full brightness
+fn white_point(energy: &[f32]) -> f32 {
+    let mut lit: Vec<f32> = energy.iter().copied().filter(|&e| e > 0.0).collect();
+    if lit.is_empty() {
+        return 1.0;
+    }
+    let index = ((lit.len() - 1) as f32 * WHITE_PERCENTILE) as usize;
+    let (_, white, _) = lit.select_nth_unstable_by(index, f32::total_cmp);
+    *white
+}
+
+/// Linear RGB of the beam color and the background
+fn linear_colors(settings: &OscilloscopeSettings) -> ([f32; 3], [f32; 3]) {
+    let rgb = |color| {
+        let linear = Rgba::from(color);
+        [linear.r(), linear.g(), linear.b()]
+    };
+    (rgb(settings.color), rgb(settings.background))
+}
+
+/// The background plus the beam color scaled by energy, left unclamped
+fn write_exr(
+    path: &Path,
+    width: usize,
+    height: usize,
+    energy: &[f32],
+    white: f32,
+    settings: &OscilloscopeSettings,
+) -> Result<(), exr::error::Error> {
+    let (fg, bg) = linear_colors(settings);
+    exr::prelude::write_rgb_file(path, width, height, |x, y| {
+        let level = energy[y * width + x] / white;
+        (
+            bg[0] + fg[0] * level,
+            bg[1] + fg[1] * level,
+            bg[2] + fg[2] * level,
+        )
+    })
+}
+
+/// The beam color blended over the background along a soft-shouldered
+/// curve, in linear light, then encoded as 16-bit sRGB
+fn write_png16(
+    path: &Path,
+    width: usize,
+    height: usize,
+    energy: &[f32],
+    white: f32,
+    settings: &OscilloscopeSettings,
+) -> Result<(), ScreenshotError> {
+    let (fg, bg) = linear_colors(settings);
+    let mut data = Vec::with_capacity(energy.len() * 6);
+    for &e in energy {
+        let a = 1.0 - (-TONE * e / white).exp();
+        for (fg, bg) in fg.iter().zip(bg) {
+            let value = gamma_from_linear(bg + (fg - bg) * a).clamp(0.0, 1.0);
+            data.extend_from_slice(&((value * 65535.0).round() as u16).to_be_bytes());
+        }
+    }
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u32,
+        height as u32,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    encoder.write_header()?.write_image_data(&data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::job::write_test_wav;
+    use eframe::egui::Color32;
+
+    #[test]
+    fn test_exposes_a_whole_file() {
+        let root = std::env::temp_dir().join(format!("scope-rs-exposure-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let source = root.join("ramp.wav");
+        write_test_wav(&source);
+
+        let params = ExposureParams {
+            start: Duration::ZERO,
+            end: Duration::from_secs(2),
+            width: 64,
+            height: 32,
+            format: ExposureFormat::Png16,
+        };
+        let settings = OscilloscopeSettings {
+            sample_count: 512,
+            color: Color32::WHITE,
+            background: Color32::BLACK,
+            ..OscilloscopeSettings::default()
+        };
+        let path = root.join("out").join("exposure.png");
+        let progress = AtomicU32::new(0);
+        let cancel = AtomicBool::new(false);
+        let windows = render_exposure(&source, &path, &params, &settings, &cancel, &progress);
+        // 2000 samples, 511 new ones a window after the first
+        assert_eq!(windows.unwrap(), 4);
+        assert_eq!(f32::from_bits(progress.load(Ordering::Relaxed)), 1.0);
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut buf).unwrap();
+        assert_eq!(frame.bit_depth, png::BitDepth::Sixteen);
+        let pixel = |x: usize, y: usize| {
+            let i = (y * 64 + x) * 6;
+            u16::from_be_bytes([buf[i], buf[i + 1]])
+        };
+        // The ramp draws a line across the middle and nothing else
+        assert!(pixel(48, 16) > 40_000, "{}", pixel(48, 16));
+        assert_eq!(pixel(48, 2), 0);
+
+        let exr = root.join("exposure.exr");
+        let params = ExposureParams {
+            format: ExposureFormat::Exr,
+            ..params
+        };
+        render_exposure(&source, &exr, &params, &settings, &cancel, &progress).unwrap();
+        let magic = std::fs::read(&exr).unwrap();
+        assert_eq!(magic[..4], [0x76, 0x2f, 0x31, 0x01]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_white_point_ignores_hot_spots() {
+        let mut energy = vec![0.0; 1000];
+        energy[..500].fill(1.0);
+        energy[500] = 1000.0;
+        assert_eq!(white_point(&energy), 1.0);
+        assert_eq!(white_point(&[0.0; 4]), 1.0);
+    }
+}
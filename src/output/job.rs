@@ -0,0 +1,153 @@
+//! Offline renders on a background thread
+//!
+//! Image sequences and long exposures both decode a file as fast as it can
+//! be read and draw it off screen. They run on their own thread so the UI
+//! keeps going, and report progress and their outcome back through a job.
+//! A stopped recording finishes the same way while ffmpeg muxes its sound.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use thiserror::Error;
+
+use super::screenshot::ScreenshotError;
+use crate::audio::{FileError, FileReader};
+
+/// Samples decoded at a time while skipping to the start
+const SKIP_BLOCK: usize = 65_536;
+
+/// Errors that can stop a render
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error("Failed to read audio: {0}")]
+    FileError(#[from] FileError),
+
+    #[error("Failed to write frame: {0}")]
+    FrameError(#[from] ScreenshotError),
+
+    #[error("Failed to write EXR: {0}")]
+    ExrError(#[from] exr::error::Error),
+
+    #[error("Failed to create directory: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to write audio: {0}")]
+    WavError(#[from] hound::Error),
+
+    #[error("ffmpeg failed: {0}")]
+    EncoderError(String),
+
+    #[error("Nothing played while recording")]
+    NothingRecorded,
+
+    #[error("Cancelled")]
+    Cancelled,
+}
+
+/// What a job makes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderKind {
+    Sequence,
+    Exposure,
+    Recording,
+}
+
+/// A render running on a background thread
+pub struct RenderJob {
+    pub kind: RenderKind,
+    /// Directory or file being written
+    pub output: PathBuf,
+    cancel: Arc<AtomicBool>,
+    /// Fraction done as f32 bits
+    progress: Arc<AtomicU32>,
+    receiver: mpsc::Receiver<Result<u64, RenderError>>,
+}
+
+impl RenderJob {
+    /// Run `work` on a new thread. It's handed the cancel flag and the
+    /// progress to keep up to date.
+    pub(super) fn spawn(
+        kind: RenderKind,
+        output: PathBuf,
+        work: impl FnOnce(&AtomicBool, &AtomicU32) -> Result<u64, RenderError> + Send + 'static,
+    ) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new(AtomicU32::new(0.0_f32.to_bits()));
+        let (sender, receiver) = mpsc::channel();
+        {
+            let cancel = Arc::clone(&cancel);
+            let progress = Arc::clone(&progress);
+            thread::spawn(move || {
+                let _ = sender.send(work(&cancel, &progress));
+            });
+        }
+        Self {
+            kind,
+            output,
+            cancel,
+            progress,
+            receiver,
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        f32::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// The outcome, once the thread is done: frames rendered, or what
+    /// stopped it
+    pub fn finished(&self) -> Option<Result<u64, RenderError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(RenderError::Cancelled)),
+        }
+    }
+}
+
+/// Decode and drop the first `count` samples. Returns how many there were,
+/// fewer only if the file is shorter.
+pub(super) fn skip_samples(
+    reader: &mut FileReader,
+    count: u64,
+    cancel: &AtomicBool,
+) -> Result<u64, RenderError> {
+    let mut dropped = VecDeque::new();
+    let mut skipped = 0;
+    while skipped < count {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(RenderError::Cancelled);
+        }
+        let read = reader.read(SKIP_BLOCK.min((count - skipped) as usize), &mut dropped);
+        dropped.clear();
+        if read == 0 {
+            break;
+        }
+        skipped += read as u64;
+    }
+    Ok(skipped)
+}
+
+/// Two seconds of a rising ramp on X at 1 kHz, for the render tests
+#[cfg(test)]
+pub(super) fn write_test_wav(path: &std::path::Path) {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: 1000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+    for i in 0..2000 {
+        writer.write_sample((i * 16) as i16).unwrap();
+        writer.write_sample(0_i16).unwrap();
+    }
+    writer.finalize().unwrap();
+}
@@ -0,0 +1,23 @@
+//! Output module - sending rendered frames outside the app
+//!
+//! This module provides:
+//! - Shared-memory frame output for external compositors
+//! - PNG screenshots, image sequences and long exposures
+//! - Video recordings with the audio muxed in
+//! - Export file names from a template
+
+mod exposure;
+mod job;
+mod naming;
+mod recording;
+mod screenshot;
+mod sequence;
+mod shm;
+
+pub use exposure::{start_exposure, ExposureFormat, ExposureParams};
+pub use job::{RenderJob, RenderKind};
+pub use naming::{ExportNaming, NameFields, NamePreview, DEFAULT_TEMPLATE, TOKENS};
+pub use recording::{Recording, RecordingParams, DEFAULT_FFMPEG};
+pub use screenshot::save_screenshot;
+pub use sequence::{start_sequence, SequenceParams};
+pub use shm::SharedFrameOutput;
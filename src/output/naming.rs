@@ -0,0 +1,277 @@
+//! Export file names
+//!
+//! Exports are named from a template such as `{title}/{timestamp}`, where
+//! each token in braces is replaced with what was playing when the export
+//! was made. Slashes in the template make subdirectories of the output
+//! directory, so a long session can be filed by track or by date as it goes.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Template for new installs, giving the same names as before templates
+pub const DEFAULT_TEMPLATE: &str = "scope-rs-{timestamp}";
+
+/// Tokens a template can use, with what they stand for
+pub const TOKENS: &[(&str, &str)] = &[
+    ("{title}", "Track title, or the input's name"),
+    ("{preset}", "Preset last loaded or saved"),
+    ("{position}", "Playback position in the file"),
+    ("{timestamp}", "Date and time"),
+    ("{date}", "Date"),
+    ("{time}", "Time of day"),
+];
+
+/// Name used when a template expands to nothing
+const FALLBACK_NAME: &str = "scope-rs";
+
+/// Longest a directory or file name gets, in bytes. File systems allow
+/// 255, which leaves room for a `-2` and an extension.
+const MAX_NAME_BYTES: usize = 200;
+
+/// How long a preview of the next name stands; no token is finer than a
+/// second
+const PREVIEW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What the tokens are replaced with
+#[derive(Debug, Clone)]
+pub struct NameFields {
+    pub title: String,
+    pub preset: Option<String>,
+    pub position: Option<Duration>,
+    pub time: jiff::Zoned,
+}
+
+/// Where exports go and what they're called
+#[derive(Debug, Clone)]
+pub struct ExportNaming {
+    /// Output directory; the pictures directory if None
+    pub directory: Option<PathBuf>,
+    pub template: String,
+}
+
+impl Default for ExportNaming {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            template: DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl ExportNaming {
+    /// The user's pictures directory, or their home
+    pub fn default_directory() -> PathBuf {
+        dirs::picture_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    pub fn directory(&self) -> PathBuf {
+        self.directory
+            .clone()
+            .unwrap_or_else(Self::default_directory)
+    }
+
+    /// A path for a new export with the given extension, or a directory
+    /// for an empty one, that doesn't name anything existing yet.
+    /// Subdirectories aren't created.
+    pub fn path(&self, fields: &NameFields, extension: &str) -> PathBuf {
+        let base = self.directory().join(expand(&self.template, fields));
+        let stem = base.file_name().unwrap_or_default().to_os_string();
+        let named = |suffix: String| {
+            let mut name = stem.clone();
+            name.push(suffix);
+            if !extension.is_empty() {
+                name.push(format!(".{extension}"));
+            }
+            base.with_file_name(name)
+        };
+        let mut path = named(String::new());
+        let mut n = 2;
+        while path.exists() {
+            path = named(format!("-{n}"));
+            n += 1;
+        }
+        path
+    }
+}
+
+/// The next export's path, for showing while the naming is edited. Finding
+/// a free name touches the file system, so it is only worked out again when
+/// the directory or template changes, or once a second for the tokens.
+#[derive(Debug, Default)]
+pub struct NamePreview {
+    /// Directory and template the path is for
+    naming: Option<(Option<PathBuf>, String)>,
+    checked: Option<Instant>,
+    path: PathBuf,
+}
+
+impl NamePreview {
+    /// Whether `path` needs working out again for `naming` at `now`
+    pub fn is_stale(&self, naming: &ExportNaming, now: Instant) -> bool {
+        let same = self.naming.as_ref().is_some_and(|(directory, template)| {
+            *directory == naming.directory && *template == naming.template
+        });
+        !same
+            || self
+                .checked
+                .is_none_or(|checked| now.duration_since(checked) >= PREVIEW_INTERVAL)
+    }
+
+    pub fn refresh(&mut self, naming: &ExportNaming, fields: &NameFields, now: Instant) {
+        self.path = naming.path(fields, "png");
+        self.naming = Some((naming.directory.clone(), naming.template.clone()));
+        self.checked = Some(now);
+    }
+
+    /// The path from the last refresh
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Replace the tokens in `template`, keeping the slashes between
+/// directories but none from the values themselves
+pub fn expand(template: &str, fields: &NameFields) -> PathBuf {
+    let position = fields.position.map(|position| {
+        let secs = position.as_secs();
+        if secs >= 3600 {
+            format!("{}-{:02}-{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            format!("{:02}-{:02}", secs / 60, secs % 60)
+        }
+    });
+    let values = [
+        ("{title}", sanitize(&fields.title)),
+        ("{preset}", sanitize(fields.preset.as_deref().unwrap_or(""))),
+        ("{position}", position.unwrap_or_default()),
+        (
+            "{timestamp}",
+            fields.time.strftime("%Y%m%d-%H%M%S").to_string(),
+        ),
+        ("{date}", fields.time.strftime("%Y-%m-%d").to_string()),
+        ("{time}", fields.time.strftime("%H%M%S").to_string()),
+    ];
+
+    let mut path = PathBuf::new();
+    for part in template.split(['/', '\\']) {
+        let mut name = part.to_string();
+        for (token, value) in &values {
+            name = name.replace(token, value);
+        }
+        // A long title would make a name the file system refuses
+        if name.len() > MAX_NAME_BYTES {
+            let end = (0..=MAX_NAME_BYTES)
+                .rfind(|&i| name.is_char_boundary(i))
+                .unwrap_or(0);
+            name.truncate(end);
+        }
+        // Tokens without a value leave separators hanging
+        let name = name.trim_matches(|c: char| c == '-' || c == '_' || c.is_whitespace());
+        if !name.is_empty() && name != "." && name != ".." {
+            path.push(sanitize(name));
+        }
+    }
+    if path.as_os_str().is_empty() {
+        path.push(FALLBACK_NAME);
+    }
+    path
+}
+
+/// `value` with the characters file systems reject replaced
+fn sanitize(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> NameFields {
+        NameFields {
+            title: "AC/DC: Thunderstruck".to_string(),
+            preset: Some("Green".to_string()),
+            position: Some(Duration::from_secs(3725)),
+            time: "2024-03-09T21:05:07[UTC]".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_expand_tokens() {
+        let fields = fields();
+        assert_eq!(
+            expand(DEFAULT_TEMPLATE, &fields),
+            PathBuf::from("scope-rs-20240309-210507")
+        );
+        assert_eq!(
+            expand("{date}/{title} {position} {preset}", &fields),
+            PathBuf::from("2024-03-09").join("AC_DC_ Thunderstruck 1-02-05 Green")
+        );
+
+        // Missing values don't leave stray separators or empty names
+        let bare = NameFields {
+            preset: None,
+            position: None,
+            ..fields
+        };
+        assert_eq!(
+            expand("{preset}/{position}-{time}", &bare),
+            PathBuf::from("210507")
+        );
+        assert_eq!(expand("../{preset}", &bare), PathBuf::from(FALLBACK_NAME));
+
+        // Long titles are cut short, on a character boundary
+        let long = NameFields {
+            title: "Ä".repeat(300),
+            ..bare
+        };
+        let path = expand("{date}/{title} {preset}", &long);
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name, "Ä".repeat(MAX_NAME_BYTES / 2));
+        assert_eq!(path.parent(), Some(Path::new("2024-03-09")));
+    }
+
+    #[test]
+    fn test_path_avoids_existing_files() {
+        let dir = std::env::temp_dir().join(format!("scope-rs-naming-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let naming = ExportNaming {
+            directory: Some(dir.clone()),
+            template: "{preset}".to_string(),
+        };
+        let first = naming.path(&fields(), "png");
+        assert_eq!(first, dir.join("Green.png"));
+        std::fs::write(&first, b"").unwrap();
+        assert_eq!(naming.path(&fields(), "png"), dir.join("Green-2.png"));
+        // Dots in a name aren't taken for an extension
+        let naming = ExportNaming {
+            template: "{preset} v1.2".to_string(),
+            ..naming
+        };
+        assert_eq!(naming.path(&fields(), ""), dir.join("Green v1.2"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_preview_waits_for_a_change() {
+        let mut naming = ExportNaming::default();
+        let mut preview = NamePreview::default();
+        let start = Instant::now();
+        assert!(preview.is_stale(&naming, start));
+        preview.refresh(&naming, &fields(), start);
+        assert!(!preview.is_stale(&naming, start + PREVIEW_INTERVAL / 2));
+        assert!(preview.is_stale(&naming, start + PREVIEW_INTERVAL));
+
+        naming.template.push_str("-{preset}");
+        assert!(preview.is_stale(&naming, start));
+    }
+}
@@ -0,0 +1,399 @@
+//! Screen recordings with sound
+//!
+//! While recording, every frame the display draws is rasterized off screen
+//! and piped to ffmpeg as raw RGBA, and the audio the display took for it
+//! is written to a WAV file alongside. When recording stops the two are
+//! muxed into one MP4, ready to share without lining the sound up by hand.
+//!
+//! The audio is the clock: a frame is due every `sample_rate / fps` samples
+//! the display has taken, repeated when the UI falls behind and skipped
+//! when it runs ahead, so picture and sound can't drift apart however
+//! unevenly frames are drawn.
+//!
+//! Drawing never waits for the encoder. Each picture goes to it once with
+//! the number of frames it stands for; while the encoder is too far behind
+//! to take one, the frames due are counted as dropped and the next picture
+//! that gets through stands for them as well, keeping the length right.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use super::job::{RenderError, RenderJob, RenderKind};
+use crate::audio::{SampleConsumer, XYSample};
+use crate::render::{FrameLayer, OscilloscopeSettings, Rasterizer};
+
+/// Encoder run when the user hasn't pointed at one
+pub const DEFAULT_FFMPEG: &str = "ffmpeg";
+
+/// Pictures waiting for the encoder; past this new ones are dropped
+const FRAME_QUEUE: usize = 4;
+
+/// How to record
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingParams {
+    pub fps: u32,
+    pub width: usize,
+    pub height: usize,
+    /// Mux in the audio, or write the picture alone
+    pub audio: bool,
+    /// Record the scope alone, without the layers drawn over it
+    pub clean_feed: bool,
+    /// ffmpeg executable, looked up on the PATH if it's a bare name
+    pub ffmpeg: String,
+}
+
+impl Default for RecordingParams {
+    fn default() -> Self {
+        Self {
+            fps: 60,
+            width: 1920,
+            height: 1080,
+            audio: true,
+            clean_feed: false,
+            ffmpeg: DEFAULT_FFMPEG.to_string(),
+        }
+    }
+}
+
+/// Video frames owed for the audio taken so far
+#[derive(Debug, Clone, Copy)]
+struct FrameClock {
+    sample_rate: u32,
+    fps: u32,
+    samples: u64,
+    frames: u64,
+}
+
+impl FrameClock {
+    fn new(sample_rate: u32, fps: u32) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            fps: fps.max(1),
+            samples: 0,
+            frames: 0,
+        }
+    }
+
+    /// Take `samples` more audio and return how many frames are now due
+    fn advance(&mut self, samples: u64) -> u64 {
+        self.samples += samples;
+        let due = self.samples * self.fps as u64 / self.sample_rate as u64;
+        let owed = due - self.frames;
+        self.frames = due;
+        owed
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.samples as f64 / self.sample_rate as f64)
+    }
+}
+
+/// A recording in progress
+pub struct Recording {
+    /// The finished MP4
+    pub path: PathBuf,
+    params: RecordingParams,
+    /// What ffmpeg writes: `path` itself without audio, or a file next to
+    /// it that's muxed into `path` at the end
+    video_path: PathBuf,
+    audio_path: PathBuf,
+    /// Pictures for the encoder, each with the number of frames it fills
+    frames: mpsc::SyncSender<(Vec<u8>, u64)>,
+    /// Frames due that no picture has been sent for yet
+    owed: u64,
+    /// Frames filled with a later picture because the encoder was behind
+    dropped: u64,
+    encoder: Option<thread::JoinHandle<Result<(), RenderError>>>,
+    wav: Option<hound::WavWriter<BufWriter<File>>>,
+    rasterizer: Rasterizer,
+    clock: FrameClock,
+    /// Stream position of the display's consumer taken up to
+    taken: Option<u64>,
+    chunk: Vec<XYSample>,
+}
+
+impl Recording {
+    /// Start ffmpeg and, with audio, the WAV file, for audio at
+    /// `sample_rate`
+    pub fn start(
+        path: PathBuf,
+        params: RecordingParams,
+        sample_rate: u32,
+    ) -> Result<Self, RenderError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // yuv420p needs even dimensions
+        let width = (params.width.max(16)) & !1;
+        let height = (params.height.max(16)) & !1;
+        let (video_path, audio_path) = if params.audio {
+            (
+                path.with_extension("video.mp4"),
+                path.with_extension("audio.wav"),
+            )
+        } else {
+            (path.clone(), PathBuf::new())
+        };
+
+        let child = Command::new(&params.ffmpeg)
+            .args(["-y", "-nostdin", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{width}x{height}")])
+            .args(["-r", &params.fps.max(1).to_string()])
+            .args(["-i", "-"])
+            .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "18"])
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(&video_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| RenderError::EncoderError(format!("{}: {e}", params.ffmpeg)))?;
+
+        let wav = if params.audio {
+            let spec = hound::WavSpec {
+                channels: 2,
+                sample_rate,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            Some(hound::WavWriter::create(&audio_path, spec)?)
+        } else {
+            None
+        };
+
+        let (frames, receiver) = mpsc::sync_channel(FRAME_QUEUE);
+        let encoder = thread::spawn(move || encode(child, receiver));
+        log::info!("Recording to {}", path.display());
+        Ok(Self {
+            path,
+            clock: FrameClock::new(sample_rate, params.fps),
+            params,
+            video_path,
+            audio_path,
+            frames,
+            owed: 0,
+            dropped: 0,
+            encoder: Some(encoder),
+            wav,
+            rasterizer: Rasterizer::new(width, height),
+            taken: None,
+            chunk: Vec::new(),
+        })
+    }
+
+    /// Sample rate the audio is recorded at
+    pub fn sample_rate(&self) -> u32 {
+        self.clock.sample_rate
+    }
+
+    /// Length recorded so far
+    pub fn duration(&self) -> Duration {
+        self.clock.duration()
+    }
+
+    /// Frames the encoder was too far behind to get a picture of their own
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Take whatever `consumer` has read since the last call, and send the
+    /// frames it makes due showing `samples` drawn with `settings`, under
+    /// `layers` unless it's a clean feed. Never waits for the encoder.
+    pub fn record(
+        &mut self,
+        consumer: &SampleConsumer,
+        samples: &[XYSample],
+        settings: &OscilloscopeSettings,
+        layers: &[&dyn FrameLayer],
+    ) -> Result<(), RenderError> {
+        let end = consumer.drained();
+        // A new consumer counts from zero again
+        let new = end.saturating_sub(*self.taken.get_or_insert(end));
+        self.taken = Some(end);
+        if new == 0 {
+            return Ok(());
+        }
+
+        if let Some(wav) = &mut self.wav {
+            let len = new as usize;
+            let kept = len.min(consumer.capacity());
+            // More arrived than the consumer keeps: what fell out is lost,
+            // but silence in its place keeps the sound in time
+            let lost = if consumer.copy_range_into(end, kept, &mut self.chunk) {
+                len - kept
+            } else {
+                self.chunk.clear();
+                len
+            };
+            for _ in 0..lost * 2 {
+                wav.write_sample(0.0_f32)?;
+            }
+            for sample in &self.chunk {
+                wav.write_sample(sample.x)?;
+                wav.write_sample(sample.y)?;
+            }
+        }
+
+        let due = self.clock.advance(new);
+        if due == 0 {
+            return Ok(());
+        }
+        self.rasterizer.render(samples, settings);
+        let layers = if self.params.clean_feed { &[] } else { layers };
+        let pixels = self.rasterizer.draw_layers(layers, settings.color).to_vec();
+        match self.frames.try_send((pixels, self.owed + due)) {
+            Ok(()) => self.owed = 0,
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.owed += due;
+                self.dropped += due;
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => return Err(self.encoder_error()),
+        }
+        Ok(())
+    }
+
+    /// Why the encoder stopped taking frames
+    fn encoder_error(&mut self) -> RenderError {
+        match self.encoder.take().map(thread::JoinHandle::join) {
+            Some(Ok(Err(e))) => e,
+            _ => RenderError::EncoderError("ffmpeg stopped".to_string()),
+        }
+    }
+
+    /// Stop recording, and let ffmpeg finish and the audio be muxed in on
+    /// a background thread. The job's result is the number of frames.
+    pub fn finish(self) -> RenderJob {
+        let Recording {
+            path,
+            params,
+            video_path,
+            audio_path,
+            frames,
+            owed,
+            encoder,
+            wav,
+            clock,
+            ..
+        } = self;
+        let audio = wav.map(hound::WavWriter::finalize).transpose();
+        RenderJob::spawn(RenderKind::Recording, path.clone(), move |_, progress| {
+            // Frames still owed repeat the last picture; off the UI thread
+            // this can wait for room
+            if owed > 0 {
+                let _ = frames.send((Vec::new(), owed));
+            }
+            // Closing the pipe tells ffmpeg it has every frame
+            drop(frames);
+            let encoded = match encoder.map(thread::JoinHandle::join) {
+                Some(Ok(result)) => result,
+                Some(Err(_)) => Err(RenderError::EncoderError("encoder panicked".to_string())),
+                None => Ok(()),
+            };
+            let result = audio
+                .map_err(RenderError::from)
+                .and(encoded)
+                .and_then(|()| {
+                    if clock.frames == 0 {
+                        Err(RenderError::NothingRecorded)
+                    } else if params.audio {
+                        mux(&params.ffmpeg, &video_path, &audio_path, &path)
+                    } else {
+                        Ok(())
+                    }
+                });
+            // Left behind on failure, so the recording can still be saved
+            if params.audio && result.is_ok() {
+                let _ = std::fs::remove_file(&video_path);
+                let _ = std::fs::remove_file(&audio_path);
+            }
+            result?;
+            progress.store(1.0_f32.to_bits(), Ordering::Relaxed);
+            log::info!("Saved recording to {}", path.display());
+            Ok(clock.frames)
+        })
+    }
+}
+
+/// Write frames to ffmpeg until the sender hangs up, then wait for it.
+/// Each picture is written as many times as it has frames; an empty one
+/// repeats the picture before.
+fn encode(mut child: Child, frames: mpsc::Receiver<(Vec<u8>, u64)>) -> Result<(), RenderError> {
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut last = Vec::new();
+        'frames: for (frame, repeats) in frames {
+            if !frame.is_empty() {
+                last = frame;
+            }
+            for _ in 0..repeats {
+                // ffmpeg quit early; what it printed says why
+                if stdin.write_all(&last).is_err() {
+                    break 'frames;
+                }
+            }
+        }
+    }
+    check(child)
+}
+
+/// Copy the video from `video` and encode `audio` as AAC into `path`
+fn mux(ffmpeg: &str, video: &Path, audio: &Path, path: &Path) -> Result<(), RenderError> {
+    let child = Command::new(ffmpeg)
+        .args(["-y", "-nostdin", "-loglevel", "error", "-i"])
+        .arg(video)
+        .arg("-i")
+        .arg(audio)
+        .args(["-map", "0:v", "-map", "1:a", "-c:v", "copy"])
+        .args(["-c:a", "aac", "-b:a", "256k", "-shortest"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| RenderError::EncoderError(format!("{ffmpeg}: {e}")))?;
+    check(child)
+}
+
+/// Wait for ffmpeg, turning a failure into its last words
+fn check(child: Child) -> Result<(), RenderError> {
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr.lines().last().unwrap_or_default().trim();
+    Err(RenderError::EncoderError(if message.is_empty() {
+        output.status.to_string()
+    } else {
+        message.to_string()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_follow_the_audio() {
+        let mut clock = FrameClock::new(48_000, 60);
+        // 800 samples a frame; a short UI frame makes none due yet
+        assert_eq!(clock.advance(500), 0);
+        assert_eq!(clock.advance(300), 1);
+        // A stall is made up with repeats
+        assert_eq!(clock.advance(4000), 5);
+        assert_eq!(clock.frames, 6);
+        assert_eq!(clock.duration(), Duration::from_millis(100));
+
+        // Rates that don't divide evenly don't drift over a minute
+        let mut clock = FrameClock::new(44_100, 30);
+        let frames: u64 = (0..44_100 * 60 / 512).map(|_| clock.advance(512)).sum();
+        assert_eq!(frames, clock.samples * 30 / 44_100);
+        assert_eq!(clock.advance(44_100 * 60 - clock.samples), 1800 - frames);
+    }
+}
@@ -0,0 +1,65 @@
+//! PNG screenshots
+//!
+//! Screenshots are rendered with the offscreen rasterizer rather than read
+//! back from the window, so they are always square, full resolution, and free
+//! of UI panels.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::audio::XYSample;
+use crate::render::{FrameLayer, OscilloscopeSettings, Rasterizer};
+
+/// Width and height of saved screenshots in pixels
+const SCREENSHOT_SIZE: usize = 1024;
+
+/// Errors that can occur while saving a screenshot
+#[derive(Debug, Error)]
+pub enum ScreenshotError {
+    #[error("Failed to write file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to encode PNG: {0}")]
+    EncodingError(#[from] png::EncodingError),
+}
+
+/// Render `samples` under `layers` and write them to `path` as a PNG,
+/// creating its directory if need be
+pub fn save_screenshot(
+    samples: &[XYSample],
+    settings: &OscilloscopeSettings,
+    layers: &[&dyn FrameLayer],
+    path: &Path,
+) -> Result<(), ScreenshotError> {
+    let mut rasterizer = Rasterizer::new(SCREENSHOT_SIZE, SCREENSHOT_SIZE);
+    rasterizer.render(samples, settings);
+    let pixels = rasterizer.draw_layers(layers, settings.color);
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    write_png(path, SCREENSHOT_SIZE, SCREENSHOT_SIZE, pixels)?;
+    log::info!("Saved screenshot to {}", path.display());
+    Ok(())
+}
+
+/// Write tightly packed RGBA8 `pixels` to `path` as a PNG
+pub(super) fn write_png(
+    path: &Path,
+    width: usize,
+    height: usize,
+    pixels: &[u8],
+) -> Result<(), ScreenshotError> {
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u32,
+        height as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(pixels)?;
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! PNG image sequences
+//!
+//! A stretch of a file is rendered offline into numbered PNGs for
+//! compositing. Frame `n` shows the window of samples that ends exactly
+//! `n / fps` seconds into the stretch, which is what the live display would
+//! have drawn at that moment, and persistence carries from frame to frame
+//! as it does on screen. Rendering doesn't wait for playback, so it runs as
+//! fast as the files can be written.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use super::job::{skip_samples, RenderError, RenderJob, RenderKind};
+use super::screenshot::write_png;
+use crate::audio::{FileReader, XYSample};
+use crate::render::{OscilloscopeSettings, Rasterizer};
+
+/// What to render
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SequenceParams {
+    pub start: Duration,
+    pub end: Duration,
+    pub fps: u32,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl SequenceParams {
+    /// Frames in the sequence at `sample_rate`
+    pub fn frame_count(&self, sample_rate: u32) -> u64 {
+        let (start, end) = sample_range(self.start, self.end, sample_rate);
+        ((end - start) * self.fps as u64).div_ceil(sample_rate as u64)
+    }
+
+    /// Sample index the window of frame `n` ends at
+    fn frame_end(&self, n: u64, sample_rate: u32) -> u64 {
+        let start = sample_range(self.start, self.end, sample_rate).0;
+        start + n * sample_rate as u64 / self.fps.max(1) as u64
+    }
+}
+
+/// First and last sample index of a stretch of a file
+pub(super) fn sample_range(start: Duration, end: Duration, sample_rate: u32) -> (u64, u64) {
+    let index = |time: Duration| (time.as_secs_f64() * sample_rate as f64).round() as u64;
+    let start = index(start);
+    (start, index(end).max(start))
+}
+
+/// File name of frame `n` in `dir`, numbered so they sort in order
+pub fn frame_path(dir: &Path, n: u64) -> PathBuf {
+    let stem = dir.file_name().unwrap_or_default().to_string_lossy();
+    dir.join(format!("{stem}_{n:05}.png"))
+}
+
+/// Render `params` from the file at `source` into `dir` on a background
+/// thread
+pub fn start_sequence(
+    source: PathBuf,
+    dir: PathBuf,
+    params: SequenceParams,
+    settings: OscilloscopeSettings,
+) -> RenderJob {
+    RenderJob::spawn(
+        RenderKind::Sequence,
+        dir.clone(),
+        move |cancel, progress| {
+            render_sequence(&source, &dir, &params, &settings, cancel, progress)
+        },
+    )
+}
+
+/// Render `params` from the file at `source` into `dir`, on the calling
+/// thread, and return the number of frames written. `progress` is set to
+/// the fraction done as f32 bits.
+pub fn render_sequence(
+    source: &Path,
+    dir: &Path,
+    params: &SequenceParams,
+    settings: &OscilloscopeSettings,
+    cancel: &AtomicBool,
+    progress: &AtomicU32,
+) -> Result<u64, RenderError> {
+    let mut reader = FileReader::open(source)?;
+    let rate = reader.sample_rate();
+    let frames = params.frame_count(rate);
+    std::fs::create_dir_all(dir)?;
+
+    // Nothing before the first frame's window is drawn
+    let window = settings.sample_count as u64;
+    let skip = params.frame_end(0, rate).saturating_sub(window);
+    let mut read = skip_samples(&mut reader, skip, cancel)?;
+
+    let mut history: VecDeque<XYSample> = VecDeque::new();
+    let mut rasterizer = Rasterizer::new(params.width, params.height);
+    for n in 0..frames {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(RenderError::Cancelled);
+        }
+        let end = params.frame_end(n, rate);
+        read += reader.read(end.saturating_sub(read) as usize, &mut history) as u64;
+        let excess = history.len().saturating_sub(window as usize);
+        history.drain(..excess);
+
+        let pixels = rasterizer.render(history.make_contiguous(), settings);
+        write_png(&frame_path(dir, n), params.width, params.height, pixels)?;
+        progress.store(
+            ((n + 1) as f32 / frames as f32).to_bits(),
+            Ordering::Relaxed,
+        );
+    }
+    log::info!("Wrote {} frames to {}", frames, dir.display());
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::job::write_test_wav;
+
+    #[test]
+    fn test_renders_numbered_frames() {
+        let root = std::env::temp_dir().join(format!("scope-rs-sequence-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let source = root.join("ramp.wav");
+        write_test_wav(&source);
+
+        // 0.5 s to 1.25 s at 24 fps: frames end at samples 500, 541, 583...
+        let params = SequenceParams {
+            start: Duration::from_millis(500),
+            end: Duration::from_millis(1250),
+            fps: 24,
+            width: 32,
+            height: 16,
+        };
+        assert_eq!(params.frame_count(1000), 18);
+        assert_eq!(params.frame_end(1, 1000), 541);
+        assert_eq!(params.frame_end(17, 1000), 1208);
+
+        let dir = root.join("frames");
+        let progress = AtomicU32::new(0);
+        let settings = OscilloscopeSettings::default();
+        let written = render_sequence(
+            &source,
+            &dir,
+            &params,
+            &settings,
+            &AtomicBool::new(false),
+            &progress,
+        )
+        .unwrap();
+        assert_eq!(written, 18);
+        assert!(frame_path(&dir, 17).ends_with("frames/frames_00017.png"));
+        let decoder = png::Decoder::new(std::fs::File::open(frame_path(&dir, 17)).unwrap());
+        let info = decoder.read_info().unwrap().info().clone();
+        assert_eq!((info.width, info.height), (32, 16));
+        assert!(!frame_path(&dir, 18).exists());
+        assert_eq!(f32::from_bits(progress.load(Ordering::Relaxed)), 1.0);
+
+        let cancelled = render_sequence(
+            &source,
+            &dir,
+            &params,
+            &settings,
+            &AtomicBool::new(true),
+            &progress,
+        );
+        assert!(matches!(cancelled, Err(RenderError::Cancelled)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,316 @@
+//! Shared-memory frame output
+//!
+//! Writes every rendered frame into a memory-mapped file so external tools
+//! (compositors, custom capture pipelines) can grab frames with no encoding
+//! step. On Linux the file lives in `/dev/shm`, which is RAM-backed; on other
+//! platforms it goes to the temp directory and the OS page cache does the
+//! same job.
+//!
+//! ## Region Layout
+//!
+//! All fields are little-endian:
+//!
+//! | Offset | Type      | Field                          |
+//! |--------|-----------|--------------------------------|
+//! | 0      | `[u8; 4]` | Magic `SCRS`                   |
+//! | 4      | `u32`     | Layout version (1)             |
+//! | 8      | `u32`     | Width in pixels                |
+//! | 12     | `u32`     | Height in pixels               |
+//! | 16     | `u32`     | Stride in bytes                |
+//! | 20     | `u32`     | Pixel format (1 = RGBA8)       |
+//! | 24     | `u64`     | Frame counter                  |
+//! | 32     | `[u8]`    | Pixel data, `height * stride`  |
+//!
+//! The frame counter works like a seqlock: it is odd while a frame is being
+//! written and even once it is complete. Readers should read the counter,
+//! copy the pixels if it is even, then re-read it and retry if it changed.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+use memmap2::MmapMut;
+
+use crate::audio::XYSample;
+use crate::render::{FrameLayer, OscilloscopeSettings, Rasterizer};
+
+const MAGIC: &[u8; 4] = b"SCRS";
+const LAYOUT_VERSION: u32 = 1;
+const FORMAT_RGBA8: u32 = 1;
+const HEADER_SIZE: usize = 32;
+const COUNTER_OFFSET: usize = 24;
+
+/// Directory used for shared-memory files on this platform
+fn shm_dir() -> PathBuf {
+    let dev_shm = PathBuf::from("/dev/shm");
+    if cfg!(target_os = "linux") && dev_shm.is_dir() {
+        dev_shm
+    } else {
+        std::env::temp_dir()
+    }
+}
+
+/// An open, mapped region
+struct SharedRegion {
+    path: PathBuf,
+    mmap: MmapMut,
+    width: u32,
+    height: u32,
+}
+
+/// Publishes rendered frames to a named shared-memory region
+pub struct SharedFrameOutput {
+    /// Whether frames are being published
+    pub enabled: bool,
+
+    /// Region name (file name inside the shared-memory directory)
+    pub name: String,
+
+    /// Output width in pixels
+    pub width: u32,
+
+    /// Output height in pixels
+    pub height: u32,
+
+    /// Publish the scope alone, without the layers drawn over it
+    pub clean_feed: bool,
+
+    /// Status message
+    pub status: String,
+
+    rasterizer: Rasterizer,
+    region: Option<SharedRegion>,
+    frame_counter: u64,
+}
+
+impl Default for SharedFrameOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedFrameOutput {
+    /// Smallest side, in pixels
+    pub const MIN_SIZE: u32 = 64;
+
+    /// Largest side, in pixels
+    pub const MAX_SIZE: u32 = 4096;
+
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            name: "scope-rs-frames".to_string(),
+            width: 512,
+            height: 512,
+            clean_feed: false,
+            status: "Disabled".to_string(),
+            rasterizer: Rasterizer::new(512, 512),
+            region: None,
+            frame_counter: 0,
+        }
+    }
+
+    /// Full path of the shared-memory file for the current name. The name
+    /// is a plain file name; anything that could reach outside the
+    /// shared-memory directory is refused.
+    pub fn path(&self) -> io::Result<PathBuf> {
+        let name = self.name.trim();
+        if name.is_empty() || name.contains("..") || name.chars().any(|c| c == '/' || c == '\\') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{}' isn't a plain file name", self.name),
+            ));
+        }
+        Ok(shm_dir().join(name))
+    }
+
+    /// Render and publish one frame, under `layers` unless it's a clean
+    /// feed. Call once per UI frame.
+    ///
+    /// Opens, resizes, or closes the region as the public fields change.
+    pub fn publish(
+        &mut self,
+        samples: &[XYSample],
+        settings: &OscilloscopeSettings,
+        layers: &[&dyn FrameLayer],
+    ) {
+        if !self.enabled {
+            if self.region.is_some() {
+                self.close();
+                self.status = "Disabled".to_string();
+            }
+            return;
+        }
+
+        let path = match self.path() {
+            Ok(path) => path,
+            Err(e) => {
+                self.close();
+                self.status = format!("Error: {}", e);
+                self.enabled = false;
+                return;
+            }
+        };
+        // Sizes from imported settings haven't been through the UI's limits
+        self.width = self.width.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        self.height = self.height.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        let stale = self
+            .region
+            .as_ref()
+            .map(|r| r.path != path || r.width != self.width || r.height != self.height)
+            .unwrap_or(true);
+        if stale {
+            self.close();
+            if let Err(e) = self.open(path) {
+                log::error!("Failed to open shared-memory output: {}", e);
+                self.status = format!("Error: {}", e);
+                self.enabled = false;
+                return;
+            }
+        }
+
+        self.rasterizer
+            .resize(self.width as usize, self.height as usize);
+        self.rasterizer.render(samples, settings);
+        let layers = if self.clean_feed { &[] } else { layers };
+        let pixels = self.rasterizer.draw_layers(layers, settings.color);
+
+        let Some(region) = self.region.as_mut() else {
+            return;
+        };
+
+        // Odd counter = frame in progress
+        self.frame_counter += 1;
+        store_counter(&mut region.mmap, self.frame_counter, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        region.mmap[HEADER_SIZE..HEADER_SIZE + pixels.len()].copy_from_slice(pixels);
+
+        // Even counter = frame complete
+        self.frame_counter += 1;
+        store_counter(&mut region.mmap, self.frame_counter, Ordering::Release);
+    }
+
+    /// Create the backing file, map it, and write the header. A file that's
+    /// already there is left alone, since it may belong to something else.
+    fn open(&mut self, path: PathBuf) -> io::Result<()> {
+        let (width, height) = (self.width, self.height);
+        let stride = width * 4;
+        let len = HEADER_SIZE + stride as usize * height as usize;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => {
+                    io::Error::new(e.kind(), format!("{} already exists", path.display()))
+                }
+                _ => e,
+            })?;
+        file.set_len(len as u64)?;
+
+        // SAFETY: the file was just created by us and stays open for
+        // the mapping's lifetime. Other processes may read it concurrently,
+        // which is what the seqlock counter is for.
+        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        mmap[0..4].copy_from_slice(MAGIC);
+        mmap[4..8].copy_from_slice(&LAYOUT_VERSION.to_le_bytes());
+        mmap[8..12].copy_from_slice(&width.to_le_bytes());
+        mmap[12..16].copy_from_slice(&height.to_le_bytes());
+        mmap[16..20].copy_from_slice(&stride.to_le_bytes());
+        mmap[20..24].copy_from_slice(&FORMAT_RGBA8.to_le_bytes());
+        store_counter(&mut mmap, 0, Ordering::Release);
+
+        log::info!(
+            "Shared-memory output: {} ({}x{})",
+            path.display(),
+            width,
+            height
+        );
+        self.status = format!("Publishing to {}", path.display());
+        self.frame_counter = 0;
+        self.region = Some(SharedRegion {
+            path,
+            mmap,
+            width,
+            height,
+        });
+        Ok(())
+    }
+
+    /// Unmap and remove the region; only ever a file `open` created
+    pub fn close(&mut self) {
+        if let Some(region) = self.region.take() {
+            drop(region.mmap);
+            if let Err(e) = std::fs::remove_file(&region.path) {
+                log::warn!("Failed to remove shared-memory file: {}", e);
+            }
+        }
+    }
+
+    /// Frames published since the region was opened
+    pub fn frames_published(&self) -> u64 {
+        self.frame_counter / 2
+    }
+}
+
+/// Write the frame counter as an atomic, so readers in other processes
+/// that load it with acquire ordering see the pixels written before it
+fn store_counter(mmap: &mut MmapMut, value: u64, order: Ordering) {
+    // SAFETY: mappings are page-aligned, so the counter at offset 24 is
+    // aligned for a u64, and the region is at least HEADER_SIZE long. The
+    // reference ends with this call, so it never overlaps a borrow of the
+    // pixel data.
+    let counter = unsafe { AtomicU64::from_ptr(mmap.as_mut_ptr().add(COUNTER_OFFSET).cast()) };
+    counter.store(value.to_le(), order);
+}
+
+impl Drop for SharedFrameOutput {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_stay_in_the_shm_dir() {
+        let mut output = SharedFrameOutput::new();
+        for name in ["../../home/u/.bashrc", "a/b", "a\\b", "..", ""] {
+            output.name = name.to_string();
+            assert!(output.path().is_err(), "{:?}", name);
+        }
+        output.name = "scope-rs-frames.v2".to_string();
+        assert_eq!(output.path().unwrap(), shm_dir().join("scope-rs-frames.v2"));
+    }
+
+    #[test]
+    fn test_leaves_other_files_alone() {
+        let mut output = SharedFrameOutput::new();
+        output.name = format!("scope-rs-test-{}", std::process::id());
+        let path = output.path().unwrap();
+        std::fs::write(&path, b"not ours").unwrap();
+
+        output.enabled = true;
+        output.width = 0;
+        output.publish(&[], &OscilloscopeSettings::default(), &[]);
+        assert!(!output.enabled);
+        output.close();
+        assert_eq!(std::fs::read(&path).unwrap(), b"not ours");
+        std::fs::remove_file(&path).unwrap();
+
+        // Its own region opens at a size it can hold, and goes when closed
+        output.enabled = true;
+        output.publish(&[], &OscilloscopeSettings::default(), &[]);
+        assert_eq!(output.width, SharedFrameOutput::MIN_SIZE);
+        assert_eq!(output.frames_published(), 1);
+        output.close();
+        assert!(!path.exists());
+    }
+}
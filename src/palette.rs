@@ -0,0 +1,112 @@
+//! Command palette
+//!
+//! A searchable list of every keyboard action and every continuous parameter.
+//! Typing filters the list with a fuzzy subsequence match, so "clp" finds
+//! "Clear Persistence". Matching uses the translated names, so it works in
+//! the selected language. Parameters are the same set MIDI can control.
+
+use crate::i18n::tr;
+use crate::keybindings::Action;
+use crate::midi::MidiParam;
+
+/// One entry in the palette
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaletteItem {
+    Action(Action),
+    Param(MidiParam),
+}
+
+impl PaletteItem {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Action(action) => action.name(),
+            Self::Param(param) => param.name(),
+        }
+    }
+
+    /// Every item, actions first
+    fn all() -> impl Iterator<Item = PaletteItem> {
+        let actions = Action::all()
+            .iter()
+            .filter(|&&a| a != Action::CommandPalette)
+            .map(|&a| Self::Action(a));
+        // Preset morph is a trigger, already listed as an action
+        let params = MidiParam::ALL
+            .iter()
+            .filter(|&&p| p != MidiParam::PresetMorph)
+            .map(|&p| Self::Param(p));
+        actions.chain(params)
+    }
+}
+
+/// Palette state
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    pub query: String,
+    /// Highlighted row in the filtered list
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Items matching the query, best match first
+    pub fn matches(&self) -> Vec<PaletteItem> {
+        let mut scored: Vec<(i32, usize, PaletteItem)> = PaletteItem::all()
+            .enumerate()
+            .filter_map(|(i, item)| fuzzy_score(&self.query, tr(item.name())).map(|s| (s, i, item)))
+            .collect();
+        // Stable on the original order for equal scores
+        scored.sort_by_key(|&(score, i, _)| (std::cmp::Reverse(score), i));
+        scored.into_iter().map(|(_, _, item)| item).collect()
+    }
+}
+
+/// Score how well `query` matches `text` as a case-insensitive subsequence.
+/// Returns None if it doesn't match; higher is better. Consecutive letters and
+/// letters at the start of a word score extra.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.chars().flat_map(char::to_lowercase) {
+        if q.is_whitespace() {
+            continue;
+        }
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous == Some(found.wrapping_sub(1)) {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("clp", "Clear Persistence").is_some());
+        assert!(fuzzy_score("xyz", "Clear Persistence").is_none());
+        assert_eq!(fuzzy_score("", "Zoom"), Some(0));
+
+        // Word starts and runs beat scattered letters
+        let word = fuzzy_score("zoom", "Zoom").unwrap();
+        let scattered = fuzzy_score("zoom", "Z offset or mode").unwrap();
+        assert!(word > scattered);
+    }
+}
@@ -0,0 +1,105 @@
+//! Performance statistics
+//!
+//! Smoothed frame timings and sample throughput for the stats overlay. Each
+//! timing is an exponential moving average, so the numbers stay readable at
+//! high frame rates but still follow a change within a second or so.
+
+use std::time::{Duration, Instant};
+
+use crate::render::FrameTimings;
+
+/// Weight of the newest frame in the moving averages
+const SMOOTHING: f32 = 0.05;
+
+/// How often the throughput figure is recomputed
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct PerfStats {
+    last_frame: Option<Instant>,
+    /// Average time between frames, in seconds
+    pub frame_time: f32,
+    /// Average CPU time per frame, without waiting for vsync, in seconds
+    pub cpu_time: f32,
+    /// Average time spent fetching samples, in seconds
+    pub fetch: f32,
+    /// Average persistence update time, in seconds
+    pub persistence: f32,
+    /// Average time building the scope shapes, in seconds
+    pub painting: f32,
+    /// Samples per second arriving in the buffer
+    pub throughput: f32,
+    window: Option<(Instant, u64)>,
+}
+
+impl PerfStats {
+    pub fn fps(&self) -> f32 {
+        if self.frame_time > 0.0 {
+            1.0 / self.frame_time
+        } else {
+            0.0
+        }
+    }
+
+    /// Record the CPU time eframe measured for the previous frame
+    pub fn record_cpu(&mut self, seconds: Option<f32>) {
+        if let Some(seconds) = seconds {
+            smooth(&mut self.cpu_time, seconds);
+        }
+    }
+
+    /// Record one frame's timings; `written` is the buffer's running sample count
+    pub fn record(&mut self, now: Instant, fetch: Duration, timings: FrameTimings, written: u64) {
+        if let Some(last) = self.last_frame {
+            smooth(&mut self.frame_time, (now - last).as_secs_f32());
+        }
+        self.last_frame = Some(now);
+        smooth(&mut self.fetch, fetch.as_secs_f32());
+        smooth(&mut self.persistence, timings.persistence.as_secs_f32());
+        smooth(&mut self.painting, timings.painting.as_secs_f32());
+
+        match self.window {
+            Some((start, start_written)) => {
+                let elapsed = now - start;
+                if elapsed >= THROUGHPUT_WINDOW {
+                    let samples = written.saturating_sub(start_written);
+                    self.throughput = samples as f32 / elapsed.as_secs_f32();
+                    self.window = Some((now, written));
+                }
+            }
+            None => self.window = Some((now, written)),
+        }
+    }
+}
+
+fn smooth(average: &mut f32, value: f32) {
+    if *average == 0.0 {
+        *average = value;
+    } else {
+        *average += (value - *average) * SMOOTHING;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fps_and_throughput() {
+        let mut stats = PerfStats::default();
+        let start = Instant::now();
+        let frame = Duration::from_millis(20);
+        for i in 0..=60u32 {
+            // 48000 samples per second, delivered every frame
+            let written = i as u64 * 960;
+            stats.record(
+                start + frame * i,
+                Duration::ZERO,
+                FrameTimings::default(),
+                written,
+            );
+        }
+        assert!((stats.fps() - 50.0).abs() < 0.1);
+        assert!((stats.throughput - 48000.0).abs() < 1.0);
+    }
+}
@@ -0,0 +1,485 @@
+//! Named presets
+//!
+//! A preset is a snapshot of the look of the scope (all display and channel
+//! settings) plus the gain and volume controls. Each preset is stored as its
+//! own JSON file in `~/.config/scope-rs/presets/`, named after the preset, so
+//! presets can be copied between machines or shared individually.
+//!
+//! Presets can also be morphed: numeric settings are interpolated over a
+//! few seconds, discrete ones (display mode, theme, switches) flip at the
+//! halfway point, and the trace and background colors crossfade.
+//!
+//! For quick comparisons there is also an A/B switch that holds two unsaved
+//! looks and flips between them.
+//!
+//! Source profiles use the same snapshot to remember a separate look for each
+//! source (every input device, file playback, the generator) and swap it in
+//! when the source changes.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use eframe::egui::Color32;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::render::{ColorTheme, DisplayMode, OscilloscopeSettings};
+use crate::ScopeApp;
+
+/// Errors that can occur while saving or loading presets
+#[derive(Debug, Error)]
+pub enum PresetError {
+    #[error("Preset file error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid preset: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Invalid preset name")]
+    InvalidName,
+}
+
+/// Returns the preset directory: `~/.config/scope-rs/presets/`
+fn presets_dir() -> PathBuf {
+    crate::settings::config_dir().join("presets")
+}
+
+/// A saved look
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    // Display
+    pub display_mode: DisplayMode,
+    pub color_theme: ColorTheme,
+    pub line_width: f32,
+    pub intensity: f32,
+    pub persistence: f32,
+    pub zoom: f32,
+    pub sample_count: usize,
+    pub show_graticule: bool,
+
+    // Channel controls
+    pub swap_xy: bool,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    pub dc_offset_x: f32,
+    pub dc_offset_y: f32,
+
+    // Levels
+    pub gain: f32,
+    pub volume: f32,
+    pub generator_volume: f32,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        let display = OscilloscopeSettings::default();
+        Self {
+            display_mode: display.display_mode,
+            color_theme: display.theme,
+            line_width: display.line_width,
+            intensity: display.intensity,
+            persistence: display.persistence,
+            zoom: display.zoom,
+            sample_count: display.sample_count,
+            show_graticule: display.show_graticule,
+            swap_xy: display.swap_xy,
+            invert_x: display.invert_x,
+            invert_y: display.invert_y,
+            dc_offset_x: display.dc_offset_x,
+            dc_offset_y: display.dc_offset_y,
+            gain: 1.0,
+            volume: 1.0,
+            generator_volume: 0.5,
+        }
+    }
+}
+
+impl Preset {
+    /// Capture the current look of the application
+    pub fn from_app(app: &ScopeApp) -> Self {
+        let display = &app.oscilloscope.settings;
+        Self {
+            display_mode: display.display_mode,
+            color_theme: display.theme,
+            line_width: display.line_width,
+            intensity: display.intensity,
+            persistence: display.persistence,
+            zoom: display.zoom,
+            sample_count: display.sample_count,
+            show_graticule: display.show_graticule,
+            swap_xy: display.swap_xy,
+            invert_x: display.invert_x,
+            invert_y: display.invert_y,
+            dc_offset_x: display.dc_offset_x,
+            dc_offset_y: display.dc_offset_y,
+            gain: app.audio.gain,
+            volume: app.file_player.volume,
+            generator_volume: app.generator.volume,
+        }
+    }
+
+    /// Apply this preset to the running application
+    pub fn apply(&self, app: &mut ScopeApp) {
+        let display = &mut app.oscilloscope.settings;
+        display.display_mode = self.display_mode;
+        display.apply_theme(self.color_theme);
+        display.line_width = self.line_width;
+        display.intensity = self.intensity;
+        display.persistence = self.persistence;
+        display.zoom = self.zoom;
+        display.sample_count = self.sample_count;
+        display.show_graticule = self.show_graticule;
+        display.swap_xy = self.swap_xy;
+        display.invert_x = self.invert_x;
+        display.invert_y = self.invert_y;
+        display.dc_offset_x = self.dc_offset_x;
+        display.dc_offset_y = self.dc_offset_y;
+
+        app.audio.gain = self.gain;
+        app.audio.sync_gain();
+        app.file_player.volume = self.volume;
+        app.file_player.sync_volume();
+        app.generator.volume = self.generator_volume;
+        app.generator.sync_volume();
+    }
+
+    /// Blend towards `other` by `t` (0.0 = self, 1.0 = other)
+    pub fn lerp(&self, other: &Preset, t: f32) -> Preset {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        // Discrete settings switch at the midpoint
+        let pick = if t < 0.5 { self } else { other };
+        Preset {
+            display_mode: pick.display_mode,
+            color_theme: pick.color_theme,
+            line_width: mix(self.line_width, other.line_width),
+            intensity: mix(self.intensity, other.intensity),
+            persistence: mix(self.persistence, other.persistence),
+            zoom: mix(self.zoom, other.zoom),
+            sample_count: mix(self.sample_count as f32, other.sample_count as f32).round() as usize,
+            show_graticule: pick.show_graticule,
+            swap_xy: pick.swap_xy,
+            invert_x: pick.invert_x,
+            invert_y: pick.invert_y,
+            dc_offset_x: mix(self.dc_offset_x, other.dc_offset_x),
+            dc_offset_y: mix(self.dc_offset_y, other.dc_offset_y),
+            gain: mix(self.gain, other.gain),
+            volume: mix(self.volume, other.volume),
+            generator_volume: mix(self.generator_volume, other.generator_volume),
+        }
+    }
+}
+
+/// A running crossfade between two presets
+pub struct PresetMorph {
+    from: Preset,
+    to: Preset,
+    start: Instant,
+    duration: Duration,
+}
+
+impl PresetMorph {
+    pub fn new(from: Preset, to: Preset, seconds: f32) -> Self {
+        Self {
+            from,
+            to,
+            start: Instant::now(),
+            duration: Duration::from_secs_f32(seconds.max(0.0)),
+        }
+    }
+
+    /// Apply the blend for the current time. Returns true once finished.
+    pub fn step(&self, app: &mut ScopeApp) -> bool {
+        let t = if self.duration.is_zero() {
+            1.0
+        } else {
+            self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32()
+        };
+        let t = t.min(1.0);
+        self.from.lerp(&self.to, t).apply(app);
+
+        let (from_color, from_bg) = self.from.color_theme.colors();
+        let (to_color, to_bg) = self.to.color_theme.colors();
+        app.oscilloscope.settings.color = lerp_color(from_color, to_color, t);
+        app.oscilloscope.settings.background = lerp_color(from_bg, to_bg, t);
+
+        t >= 1.0
+    }
+}
+
+fn lerp_color(a: Color32, b: Color32, t: f32) -> Color32 {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgba_unmultiplied(
+        mix(a.r(), b.r()),
+        mix(a.g(), b.g()),
+        mix(a.b(), b.b()),
+        mix(a.a(), b.a()),
+    )
+}
+
+/// Two looks to flip between. Only the inactive one is stored; the active one
+/// is whatever the app currently shows.
+#[derive(Default)]
+pub struct AbCompare {
+    /// 0 = A, 1 = B
+    pub active: usize,
+    other: Option<Preset>,
+}
+
+impl AbCompare {
+    /// Store `current` in the active slot and return the other slot's look.
+    /// The first switch starts B as a copy of A.
+    pub fn switch(&mut self, current: Preset) -> Preset {
+        self.active ^= 1;
+        let next = self.other.take().unwrap_or_else(|| current.clone());
+        self.other = Some(current);
+        next
+    }
+
+    /// Overwrite the inactive slot with `current`
+    pub fn copy_to_other(&mut self, current: Preset) {
+        self.other = Some(current);
+    }
+
+    pub fn active_name(&self) -> &'static str {
+        if self.active == 0 {
+            "A"
+        } else {
+            "B"
+        }
+    }
+}
+
+/// A remembered look per source
+#[derive(Default)]
+pub struct SourceProfiles {
+    pub enabled: bool,
+    /// Looks keyed by source, e.g. "Live: USB Audio" or "File"
+    pub profiles: BTreeMap<String, Preset>,
+    /// Source the current look belongs to
+    pub active: Option<String>,
+}
+
+impl SourceProfiles {
+    /// Note that the source is now `key`. If it changed, `current` is stored
+    /// for the previous source and the new source's look (if any) is returned.
+    pub fn switch(&mut self, key: &str, current: Preset) -> Option<Preset> {
+        if !self.enabled || self.active.as_deref() == Some(key) {
+            return None;
+        }
+        if let Some(previous) = self.active.replace(key.to_string()) {
+            self.profiles.insert(previous, current);
+        }
+        self.profiles.get(key).cloned()
+    }
+
+    /// All profiles with the active one updated to `current`
+    pub fn snapshot(&self, current: Preset) -> BTreeMap<String, Preset> {
+        let mut profiles = self.profiles.clone();
+        if let (true, Some(active)) = (self.enabled, &self.active) {
+            profiles.insert(active.clone(), current);
+        }
+        profiles
+    }
+}
+
+/// Lists, saves, loads and deletes presets on disk
+pub struct PresetManager {
+    /// Names of the presets on disk, sorted
+    pub names: Vec<String>,
+
+    /// Selected preset index in `names`
+    pub selected: usize,
+
+    /// Name typed for the next save
+    pub new_name: String,
+
+    /// Existing preset a save would replace, waiting for the user to agree
+    pub pending_overwrite: Option<String>,
+
+    /// Status message
+    pub status: String,
+
+    /// Preset the look was last loaded from or saved as
+    pub current: Option<String>,
+
+    /// Preset index the next morph goes to
+    pub morph_target: usize,
+
+    /// Morph length in seconds
+    pub morph_seconds: f32,
+
+    /// Morph in progress
+    pub morph: Option<PresetMorph>,
+}
+
+impl PresetManager {
+    pub fn new() -> Self {
+        let mut manager = Self {
+            names: Vec::new(),
+            selected: 0,
+            new_name: String::new(),
+            pending_overwrite: None,
+            status: String::new(),
+            current: None,
+            morph_target: 0,
+            morph_seconds: 4.0,
+            morph: None,
+        };
+        manager.scan();
+        manager
+    }
+
+    /// Refresh the list of presets from disk
+    pub fn scan(&mut self) {
+        self.names = std::fs::read_dir(presets_dir())
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.names.sort_by_key(|n| n.to_lowercase());
+        self.selected = self.selected.min(self.names.len().saturating_sub(1));
+        self.morph_target = self.morph_target.min(self.names.len().saturating_sub(1));
+    }
+
+    /// Start morphing from `current` to the morph target. The selected
+    /// preset and the target then swap, so triggering again morphs back.
+    pub fn start_morph(&mut self, current: Preset) -> Result<(), PresetError> {
+        let Some(name) = self.names.get(self.morph_target).cloned() else {
+            return Err(PresetError::InvalidName);
+        };
+        let target = self.load(&name)?;
+        self.morph = Some(PresetMorph::new(current, target, self.morph_seconds));
+        self.current = Some(name.clone());
+        std::mem::swap(&mut self.selected, &mut self.morph_target);
+        self.status = format!("Morphing to '{}'", name);
+        Ok(())
+    }
+
+    /// Name of the selected preset
+    pub fn selected_name(&self) -> Option<&str> {
+        self.names.get(self.selected).map(String::as_str)
+    }
+
+    /// Whether a preset called `name` is on disk. Case is ignored, as it is
+    /// by the file systems of some platforms.
+    pub fn exists(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        self.names.iter().any(|n| n.to_lowercase() == name)
+    }
+
+    pub fn save(&mut self, name: &str, preset: &Preset) -> Result<(), PresetError> {
+        let path = preset_path(name)?;
+        std::fs::create_dir_all(presets_dir())?;
+        crate::settings::write_atomic(&path, &serde_json::to_string_pretty(preset)?)?;
+        log::info!("Saved preset to {}", path.display());
+
+        self.scan();
+        if let Some(i) = self.names.iter().position(|n| n == name.trim()) {
+            self.selected = i;
+        }
+        Ok(())
+    }
+
+    pub fn load(&self, name: &str) -> Result<Preset, PresetError> {
+        let contents = std::fs::read_to_string(preset_path(name)?)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), PresetError> {
+        std::fs::remove_file(preset_path(name)?)?;
+        self.scan();
+        Ok(())
+    }
+}
+
+/// File path for a preset name. Names must be usable as file names.
+fn preset_path(name: &str) -> Result<PathBuf, PresetError> {
+    let name = name.trim();
+    let invalid = name.is_empty()
+        || name.starts_with('.')
+        || name.chars().any(|c| {
+            matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+        });
+    if invalid {
+        return Err(PresetError::InvalidName);
+    }
+    Ok(presets_dir().join(format!("{}.json", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lerp_numeric_and_discrete() {
+        let a = Preset {
+            zoom: 1.0,
+            display_mode: DisplayMode::Dots,
+            ..Preset::default()
+        };
+        let b = Preset {
+            zoom: 2.0,
+            display_mode: DisplayMode::Gradient,
+            ..Preset::default()
+        };
+
+        let early = a.lerp(&b, 0.25);
+        assert!((early.zoom - 1.25).abs() < 1e-6);
+        assert_eq!(early.display_mode, DisplayMode::Dots);
+
+        let late = a.lerp(&b, 0.75);
+        assert!((late.zoom - 1.75).abs() < 1e-6);
+        assert_eq!(late.display_mode, DisplayMode::Gradient);
+
+        assert_eq!(a.lerp(&b, 1.0), b);
+    }
+
+    #[test]
+    fn test_source_profiles() {
+        let mic = Preset {
+            gain: 10.0,
+            ..Preset::default()
+        };
+        let file = Preset::default();
+
+        let mut profiles = SourceProfiles {
+            enabled: true,
+            ..SourceProfiles::default()
+        };
+        assert_eq!(profiles.switch("Live: Mic", mic.clone()), None);
+        // Nothing remembered for File yet, so the mic look carries over
+        assert_eq!(profiles.switch("File", mic.clone()), None);
+        assert_eq!(profiles.switch("Live: Mic", file.clone()), Some(mic));
+        assert_eq!(profiles.switch("File", Preset::default()), Some(file));
+    }
+
+    #[test]
+    fn test_ab_switch() {
+        let a = Preset {
+            zoom: 1.0,
+            ..Preset::default()
+        };
+        let b = Preset {
+            zoom: 2.0,
+            ..Preset::default()
+        };
+
+        let mut ab = AbCompare::default();
+        // B starts as a copy of A
+        assert_eq!(ab.switch(a.clone()), a);
+        assert_eq!(ab.active_name(), "B");
+
+        // Tweaked B is kept when switching back to A
+        assert_eq!(ab.switch(b.clone()), a);
+        assert_eq!(ab.switch(a), b);
+    }
+}
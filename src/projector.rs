@@ -0,0 +1,354 @@
+//! Projector output
+//!
+//! Sends the scope to a display of its own: a borderless fullscreen window on
+//! a chosen monitor, typically the projector at a gig, while the main window
+//! keeps the controls on the laptop screen. The projector view is drawn by
+//! the software rasterizer at its own resolution and can be turned for a
+//! projector mounted sideways or upside down.
+//!
+//! The window is placed on the chosen display and then made fullscreen
+//! there. Displays are listed with XRandR on Linux. Elsewhere, and on Wayland,
+//! where windows can't place themselves, it goes wherever the desktop puts
+//! new windows.
+
+use std::sync::Arc;
+
+use eframe::egui::{self, Color32, ColorImage, TextureOptions};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::XYSample;
+use crate::i18n::tr;
+use crate::render::{FrameLayer, OscilloscopeSettings, Rasterizer};
+
+/// Smallest rendered side, in pixels
+pub const MIN_SIZE: u32 = 64;
+
+/// Largest rendered side, in pixels; the rasterizer draws every frame on
+/// the CPU, and 4K is as much as it keeps up with
+pub const MAX_SIZE: u32 = 3840;
+
+/// How the projected picture is turned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise,
+    UpsideDown,
+    CounterClockwise,
+}
+
+impl Rotation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Clockwise => "90° clockwise",
+            Self::UpsideDown => "180°",
+            Self::CounterClockwise => "90° counter-clockwise",
+        }
+    }
+
+    pub fn all() -> &'static [Rotation] {
+        &[
+            Self::None,
+            Self::Clockwise,
+            Self::UpsideDown,
+            Self::CounterClockwise,
+        ]
+    }
+
+    /// Whether width and height swap
+    pub fn is_sideways(&self) -> bool {
+        matches!(self, Self::Clockwise | Self::CounterClockwise)
+    }
+}
+
+/// A monitor, in desktop pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Display {
+    pub name: String,
+    pub position: [i32; 2],
+    pub size: [u32; 2],
+}
+
+/// The scope on a display of its own
+pub struct Projector {
+    /// Name of the display to show on; empty leaves it to the desktop
+    pub display: String,
+    /// Rendered width in pixels, before rotation
+    pub width: u32,
+    /// Rendered height in pixels, before rotation
+    pub height: u32,
+    pub rotation: Rotation,
+    /// Project the scope alone, without the layers drawn over it
+    pub clean_feed: bool,
+    /// Status message
+    pub status: String,
+    /// Displays found by the last `refresh_displays`
+    pub displays: Vec<Display>,
+    open: bool,
+    rasterizer: Rasterizer,
+    /// The turned picture, kept to be filled again next frame
+    image: Arc<ColorImage>,
+    texture: Option<egui::TextureHandle>,
+    /// Pixels per point the projector window reported on the display it
+    /// was opened on
+    window_scale: Option<(String, f32)>,
+}
+
+impl Default for Projector {
+    fn default() -> Self {
+        Self {
+            display: String::new(),
+            width: 1920,
+            height: 1080,
+            rotation: Rotation::None,
+            clean_feed: false,
+            status: "Closed".to_string(),
+            displays: Vec::new(),
+            open: false,
+            rasterizer: Rasterizer::new(1920, 1080),
+            image: Arc::default(),
+            texture: None,
+            window_scale: None,
+        }
+    }
+}
+
+impl Projector {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Open or close the projector window
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+        if open {
+            self.refresh_displays();
+        } else {
+            self.texture = None;
+            self.window_scale = None;
+            self.rasterizer.clear();
+            self.status = "Closed".to_string();
+        }
+    }
+
+    /// List the connected displays again
+    pub fn refresh_displays(&mut self) {
+        match platform::displays() {
+            Ok(displays) => {
+                self.displays = displays;
+                self.status = match (self.open, self.target()) {
+                    (false, _) => "Closed".to_string(),
+                    (true, Some(display)) => format!("On {}", display.name),
+                    (true, None) if self.display.is_empty() => "Open".to_string(),
+                    (true, None) => format!("{} isn't connected", self.display),
+                };
+            }
+            Err(e) => {
+                log::warn!("Couldn't list displays: {}", e);
+                self.displays.clear();
+                self.status = format!("Error: {}", e);
+            }
+        }
+    }
+
+    /// The chosen display, if it's connected
+    pub fn target(&self) -> Option<&Display> {
+        self.displays.iter().find(|d| d.name == self.display)
+    }
+
+    /// Render a frame of `samples` and show it on the projector, under
+    /// `layers` unless it's a clean feed. Call once per UI frame.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        samples: &[XYSample],
+        settings: &OscilloscopeSettings,
+        layers: &[&dyn FrameLayer],
+    ) {
+        if !self.open {
+            return;
+        }
+
+        // Sizes from imported settings haven't been through the UI's limits
+        let width = self.width.clamp(MIN_SIZE, MAX_SIZE) as usize;
+        let height = self.height.clamp(MIN_SIZE, MAX_SIZE) as usize;
+        self.rasterizer.resize(width, height);
+        self.rasterizer.render(samples, settings);
+        let layers = if self.clean_feed { &[] } else { layers };
+        let pixels = self.rasterizer.draw_layers(layers, settings.color);
+        // egui lets go of last frame's picture once it's uploaded, so this
+        // only allocates when the size changes
+        rotate(
+            pixels,
+            width,
+            height,
+            self.rotation,
+            Arc::make_mut(&mut self.image),
+        );
+        let image = self.image.clone();
+        let texture = match &mut self.texture {
+            Some(texture) => {
+                texture.set(image, TextureOptions::LINEAR);
+                texture.clone()
+            }
+            None => self
+                .texture
+                .insert(ctx.load_texture("projector", image, TextureOptions::LINEAR))
+                .clone(),
+        };
+
+        let mut viewport = egui::ViewportBuilder::default()
+            .with_title("scope-rs projector")
+            .with_decorations(false)
+            .with_fullscreen(true);
+        if let Some(display) = self.target() {
+            // Open a small window in the middle of the display, then go
+            // fullscreen on it. The position is in points, which the window
+            // turns into pixels at the scale of the display it ends up on.
+            // That's only known once it's there, so the first guess uses
+            // the main window's scale; the small window still lands on the
+            // display unless the two scales are far apart, and is moved to
+            // the right spot once it reports its own.
+            let scale = match &self.window_scale {
+                Some((name, scale)) if *name == display.name => *scale,
+                _ => ctx.pixels_per_point(),
+            };
+            let size = egui::vec2(640.0, 360.0);
+            let center = egui::pos2(
+                display.position[0] as f32 + display.size[0] as f32 / 2.0,
+                display.position[1] as f32 + display.size[1] as f32 / 2.0,
+            );
+            viewport = viewport
+                .with_position(center / scale - size / 2.0)
+                .with_inner_size(size);
+        }
+
+        // A new window for a new display; the old one closes when it's no
+        // longer shown
+        let id = egui::ViewportId::from_hash_of(("projector", &self.display));
+        let (close, scale) = ctx.show_viewport_immediate(id, viewport, |ctx, class| {
+            if class == egui::ViewportClass::Embedded {
+                // No native windows, so show a preview inside the main one
+                let mut open = true;
+                egui::Window::new(tr("Projector"))
+                    .open(&mut open)
+                    .show(ctx, |ui| {
+                        ui.add(egui::Image::new(&texture).max_width(480.0));
+                    });
+                return (!open, None);
+            }
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(Color32::BLACK))
+                .show(ctx, |ui| {
+                    // Fit the picture and keep its shape; the rest stays dark
+                    let rect = ui.max_rect();
+                    let size = texture.size_vec2();
+                    let scale = (rect.width() / size.x).min(rect.height() / size.y);
+                    let picture = egui::Rect::from_center_size(rect.center(), size * scale);
+                    ui.painter().image(
+                        texture.id(),
+                        picture,
+                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        Color32::WHITE,
+                    );
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::None);
+                });
+            let close = ctx.input(|input| {
+                input.viewport().close_requested() || input.key_pressed(egui::Key::Escape)
+            });
+            (close, Some(ctx.pixels_per_point()))
+        });
+        if let Some(scale) = scale {
+            self.window_scale = Some((self.display.clone(), scale));
+        }
+        if close {
+            self.set_open(false);
+        }
+    }
+}
+
+/// RGBA8 `pixels`, `width` by `height`, turned by `rotation` into `out`
+fn rotate(pixels: &[u8], width: usize, height: usize, rotation: Rotation, out: &mut ColorImage) {
+    let (out_width, out_height) = if rotation.is_sideways() {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    out.size = [out_width, out_height];
+    out.pixels.clear();
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let (sx, sy) = match rotation {
+                Rotation::None => (x, y),
+                Rotation::Clockwise => (y, height - 1 - x),
+                Rotation::UpsideDown => (width - 1 - x, height - 1 - y),
+                Rotation::CounterClockwise => (width - 1 - y, x),
+            };
+            let p = &pixels[(sy * width + sx) * 4..][..4];
+            out.pixels
+                .push(Color32::from_rgba_premultiplied(p[0], p[1], p[2], p[3]));
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::randr::ConnectionExt as _;
+    use x11rb::protocol::xproto::ConnectionExt as _;
+
+    use super::Display;
+
+    /// Active monitors from XRandR, primary first
+    pub fn displays() -> Result<Vec<Display>, Box<dyn std::error::Error>> {
+        let (conn, screen) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen].root;
+        let mut monitors = conn.randr_get_monitors(root, true)?.reply()?.monitors;
+        monitors.sort_by_key(|monitor| !monitor.primary);
+        monitors
+            .iter()
+            .map(|monitor| {
+                let name = conn.get_atom_name(monitor.name)?.reply()?.name;
+                Ok(Display {
+                    name: String::from_utf8_lossy(&name).into_owned(),
+                    position: [monitor.x.into(), monitor.y.into()],
+                    size: [monitor.width.into(), monitor.height.into()],
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::Display;
+
+    pub fn displays() -> Result<Vec<Display>, Box<dyn std::error::Error>> {
+        Err("Listing displays isn't supported on this platform yet".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_turns_corners() {
+        // 2x1: red then green
+        let pixels = [255, 0, 0, 255, 0, 255, 0, 255];
+        let (red, green) = (Color32::RED, Color32::GREEN);
+        let cases = [
+            (Rotation::None, [2, 1], vec![red, green]),
+            (Rotation::UpsideDown, [2, 1], vec![green, red]),
+            (Rotation::Clockwise, [1, 2], vec![red, green]),
+            (Rotation::CounterClockwise, [1, 2], vec![green, red]),
+        ];
+        let mut image = ColorImage::default();
+        for (rotation, size, expected) in cases {
+            rotate(&pixels, 2, 1, rotation, &mut image);
+            assert_eq!(image.size, size, "{:?}", rotation);
+            assert_eq!(image.pixels, expected, "{:?}", rotation);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Adaptive render quality
+//!
+//! On a slow GPU a long sample window and a dense persistence trail can push
+//! frames past their budget, and the trace stutters. `QualityGovernor`
+//! watches the smoothed CPU time per frame and halves the render quality while
+//! frames run over, then doubles it again once they have stayed within
+//! budget for a while. Quality that is restored too eagerly and immediately
+//! has to be dropped again makes the next restore wait twice as long, so a
+//! marginal machine settles instead of flickering between levels.
+
+use std::time::{Duration, Instant};
+
+/// Lowest quality scale
+pub const MIN_SCALE: f32 = 0.125;
+
+/// Frames this much slower than the budget count as over
+const OVER_BUDGET: f32 = 1.2;
+
+/// Frames within this much of the budget leave room to restore
+const HEADROOM: f32 = 1.05;
+
+/// Time for the smoothed frame time to catch up after a drop
+const DROP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long frames must stay within budget before restoring, at first
+const RESTORE_HOLD: Duration = Duration::from_secs(3);
+const MAX_RESTORE_HOLD: Duration = Duration::from_secs(60);
+
+pub struct QualityGovernor {
+    /// Fraction of full quality, from `MIN_SCALE` to 1
+    scale: f32,
+    last_change: Option<Instant>,
+    /// Start of the current run of frames within budget
+    within_since: Option<Instant>,
+    /// When the last restore happened, while it is still on probation
+    restored_at: Option<Instant>,
+    restore_hold: Duration,
+}
+
+impl Default for QualityGovernor {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            last_change: None,
+            within_since: None,
+            restored_at: None,
+            restore_hold: RESTORE_HOLD,
+        }
+    }
+}
+
+impl QualityGovernor {
+    /// Current quality, 1.0 being full
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Back to full quality and a fresh restore delay
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Keep the level while frames aren't being timed (nothing playing), and
+    /// give the frame time a moment to settle once they are again
+    pub fn hold(&mut self, now: Instant) {
+        self.last_change = Some(now);
+        self.within_since = None;
+    }
+
+    /// Adjust the quality for a smoothed CPU `frame_time` against `budget`,
+    /// both in seconds
+    pub fn update(&mut self, now: Instant, frame_time: f32, budget: f32) {
+        let since_change = self
+            .last_change
+            .map_or(Duration::MAX, |last| now.duration_since(last));
+
+        if frame_time > budget * OVER_BUDGET {
+            self.within_since = None;
+            if self.scale > MIN_SCALE && since_change >= DROP_INTERVAL {
+                if let Some(restored) = self.restored_at.take() {
+                    if now.duration_since(restored) < self.restore_hold {
+                        self.restore_hold = (self.restore_hold * 2).min(MAX_RESTORE_HOLD);
+                    }
+                }
+                self.scale = (self.scale * 0.5).max(MIN_SCALE);
+                self.last_change = Some(now);
+            }
+        } else if frame_time <= budget * HEADROOM {
+            let since = *self.within_since.get_or_insert(now);
+            if self.scale < 1.0 && now.duration_since(since) >= self.restore_hold {
+                self.scale = (self.scale * 2.0).min(1.0);
+                self.last_change = Some(now);
+                self.restored_at = Some(now);
+                // The next step up needs a hold of its own
+                self.within_since = Some(now);
+            }
+        } else {
+            // Neither over nor comfortably under: hold the level
+            self.within_since = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drops_under_load_and_restores_with_backoff() {
+        let budget = 1.0 / 60.0;
+        let mut governor = QualityGovernor::default();
+        let start = Instant::now();
+        let at = |seconds: f32| start + Duration::from_secs_f32(seconds);
+
+        // Over budget: one halving per second, down to the floor
+        for i in 0..10 {
+            governor.update(at(i as f32), budget * 2.0, budget);
+        }
+        assert_eq!(governor.scale(), MIN_SCALE);
+
+        // Within budget: one doubling per hold
+        governor.update(at(10.0), budget, budget);
+        governor.update(at(12.0), budget, budget);
+        assert_eq!(governor.scale(), MIN_SCALE);
+        governor.update(at(13.0), budget, budget);
+        assert_eq!(governor.scale(), MIN_SCALE * 2.0);
+
+        // Dropping straight after a restore doubles the next hold
+        governor.update(at(14.0), budget * 2.0, budget);
+        assert_eq!(governor.scale(), MIN_SCALE);
+        governor.update(at(15.0), budget, budget);
+        governor.update(at(20.0), budget, budget);
+        assert_eq!(governor.scale(), MIN_SCALE);
+        governor.update(at(21.0), budget, budget);
+        assert_eq!(governor.scale(), MIN_SCALE * 2.0);
+
+        governor.reset();
+        assert_eq!(governor.scale(), 1.0);
+    }
+}
@@ -0,0 +1,256 @@
+//! Beat-synced actions
+//!
+//! Rules fire an action every so many kicks, beats or bars as the beat
+//! detector counts them: clearing the persistence trail on each bar,
+//! stepping through the color themes every few bars, or pulsing the
+//! intensity on each kick. Bars are counted from the beat marked as the
+//! downbeat, or from wherever the detector started.
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::Beats;
+use crate::render::OscilloscopeSettings;
+
+/// How long an intensity pulse takes to die away, in seconds
+const PULSE_DECAY: f32 = 0.15;
+
+/// What a rule counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BeatEvent {
+    Kick,
+    Beat,
+    Bar,
+}
+
+impl BeatEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Kick => "Kicks",
+            Self::Beat => "Beats",
+            Self::Bar => "Bars",
+        }
+    }
+
+    pub fn all() -> &'static [BeatEvent] {
+        &[Self::Kick, Self::Beat, Self::Bar]
+    }
+}
+
+/// What a rule does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BeatAction {
+    ClearPersistence,
+    NextTheme,
+    PulseIntensity,
+}
+
+impl BeatAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ClearPersistence => "Clear persistence",
+            Self::NextTheme => "Next theme",
+            Self::PulseIntensity => "Pulse intensity",
+        }
+    }
+
+    pub fn all() -> &'static [BeatAction] {
+        &[
+            Self::ClearPersistence,
+            Self::NextTheme,
+            Self::PulseIntensity,
+        ]
+    }
+}
+
+/// Do `action` every `every` `event`s
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BeatRule {
+    pub every: u32,
+    pub event: BeatEvent,
+    pub action: BeatAction,
+}
+
+/// The rules and the counts they fire on
+#[derive(Debug, Clone)]
+pub struct Reactivity {
+    pub enabled: bool,
+    pub beats_per_bar: u32,
+    /// How far a pulse lifts the intensity
+    pub pulse_depth: f32,
+    pub rules: Vec<BeatRule>,
+    /// Detector counts at the last update
+    last: Beats,
+    /// Beat count at the last marked downbeat
+    downbeat: u64,
+    /// Current pulse, 0 to 1
+    pulse: f32,
+}
+
+impl Default for Reactivity {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            beats_per_bar: 4,
+            pulse_depth: 0.5,
+            rules: vec![
+                BeatRule {
+                    every: 1,
+                    event: BeatEvent::Bar,
+                    action: BeatAction::ClearPersistence,
+                },
+                BeatRule {
+                    every: 16,
+                    event: BeatEvent::Bar,
+                    action: BeatAction::NextTheme,
+                },
+                BeatRule {
+                    every: 1,
+                    event: BeatEvent::Kick,
+                    action: BeatAction::PulseIntensity,
+                },
+            ],
+            last: Beats::default(),
+            downbeat: 0,
+            pulse: 0.0,
+        }
+    }
+}
+
+impl Reactivity {
+    /// Take the detector's latest counts, `dt` seconds after the last ones,
+    /// and return the actions due. Pulses are kept here for `apply`.
+    pub fn update(&mut self, beats: &Beats, dt: f32) -> Vec<BeatAction> {
+        self.pulse *= (-dt.max(0.0) / PULSE_DECAY).exp();
+        if beats.count < self.last.count || beats.kicks < self.last.kicks {
+            // The detector started over
+            self.last = Beats::default();
+            self.downbeat = 0;
+        }
+        let last = std::mem::replace(&mut self.last, *beats);
+        if !self.enabled {
+            return Vec::new();
+        }
+
+        let bar = self.beats_per_bar.max(1) as u64;
+        let mut actions = Vec::new();
+        for rule in &self.rules {
+            let every = rule.every.max(1) as u64;
+            let (from, to, step) = match rule.event {
+                BeatEvent::Kick => (last.kicks, beats.kicks, every),
+                BeatEvent::Beat => (last.count, beats.count, every),
+                BeatEvent::Bar => (last.count, beats.count, every * bar),
+            };
+            let offset = if rule.event == BeatEvent::Kick {
+                0
+            } else {
+                self.downbeat
+            };
+            // One action however many were due since the last frame
+            let due = (from + 1..=to).any(|n| n.wrapping_sub(offset) % step == 0);
+            if due {
+                if rule.action == BeatAction::PulseIntensity {
+                    self.pulse = 1.0;
+                }
+                if !actions.contains(&rule.action) {
+                    actions.push(rule.action);
+                }
+            }
+        }
+        actions
+    }
+
+    /// Make the latest beat the first of a bar
+    pub fn mark_downbeat(&mut self) {
+        self.downbeat = self.last.count;
+    }
+
+    /// Which beat of the bar the latest one was, from 0
+    pub fn beat_in_bar(&self) -> u64 {
+        self.last.count.wrapping_sub(self.downbeat) % self.beats_per_bar.max(1) as u64
+    }
+
+    /// A copy of `settings` with the current pulse applied
+    pub fn apply(&self, settings: &OscilloscopeSettings) -> OscilloscopeSettings {
+        let mut pulsed = settings.clone();
+        if self.enabled {
+            pulsed.intensity = (settings.intensity + self.pulse * self.pulse_depth).clamp(0.1, 1.0);
+        }
+        pulsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beats(count: u64, kicks: u64) -> Beats {
+        Beats {
+            count,
+            kicks,
+            ..Beats::default()
+        }
+    }
+
+    #[test]
+    fn test_rules_fire_on_their_counts() {
+        let mut reactivity = Reactivity {
+            enabled: true,
+            ..Reactivity::default()
+        };
+        let mut fired = Vec::new();
+        for beat in 1..=64 {
+            for action in reactivity.update(&beats(beat, beat), 0.5) {
+                fired.push((beat, action));
+            }
+        }
+        let on = |wanted: BeatAction| -> Vec<u64> {
+            fired
+                .iter()
+                .filter(|(_, action)| *action == wanted)
+                .map(|(beat, _)| *beat)
+                .collect()
+        };
+        assert_eq!(
+            on(BeatAction::ClearPersistence),
+            (1..=16).map(|bar| bar * 4).collect::<Vec<_>>()
+        );
+        assert_eq!(on(BeatAction::NextTheme), vec![64]);
+        assert_eq!(on(BeatAction::PulseIntensity).len(), 64);
+
+        // Bars count from the marked downbeat
+        reactivity.update(&beats(65, 65), 0.5);
+        reactivity.mark_downbeat();
+        assert_eq!(reactivity.beat_in_bar(), 0);
+        let cleared: Vec<bool> = (66..=69)
+            .map(|beat| {
+                reactivity
+                    .update(&beats(beat, beat), 0.5)
+                    .contains(&BeatAction::ClearPersistence)
+            })
+            .collect();
+        assert_eq!(cleared, vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn test_pulse_decays() {
+        let mut reactivity = Reactivity {
+            enabled: true,
+            rules: vec![BeatRule {
+                every: 1,
+                event: BeatEvent::Kick,
+                action: BeatAction::PulseIntensity,
+            }],
+            ..Reactivity::default()
+        };
+        let settings = OscilloscopeSettings {
+            intensity: 0.4,
+            ..OscilloscopeSettings::default()
+        };
+        reactivity.update(&beats(0, 1), 0.0);
+        assert!((reactivity.apply(&settings).intensity - 0.9).abs() < 1e-6);
+        reactivity.update(&beats(0, 1), PULSE_DECAY);
+        let lifted = reactivity.apply(&settings).intensity - 0.4;
+        assert!((lifted - 0.5 * (-1.0f32).exp()).abs() < 1e-4);
+        assert_eq!(settings.intensity, 0.4);
+    }
+}
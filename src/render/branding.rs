@@ -0,0 +1,361 @@
+//! Branding overlay
+//!
+//! A streamer's own line of text and small logo, sitting in a corner of
+//! the scope. The logo is a PNG or JPEG, drawn as an egui texture on screen
+//! and blended by hand into rasterized frames, so screenshots, recordings
+//! and the shared memory output carry it too. The text uses the overlay's
+//! stroke font and follows the logo on the same row.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use eframe::egui::{self, Color32, ColorImage, Pos2, Rect, Shape, Stroke, TextureHandle};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::overlay::{draw_strokes, line_strokes, line_width, FrameLayer, OverlayCorner};
+
+/// Height of a capital in font units
+const CAP_HEIGHT: f32 = 6.0;
+
+/// Capital height as a share of the row
+const TEXT_SIZE: f32 = 0.5;
+
+/// Stroke width as a share of the capital height
+const TEXT_WEIGHT: f32 = 0.12;
+
+/// Gap between the logo and the text, and from the edges, as a share of
+/// the row
+const SPACING: f32 = 0.3;
+
+/// Errors loading a logo
+#[derive(Debug, Error)]
+pub enum BrandingError {
+    #[error("Failed to read logo: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to decode PNG: {0}")]
+    PngError(#[from] png::DecodingError),
+
+    #[error("Failed to decode JPEG: {0}")]
+    JpegError(#[from] jpeg_decoder::Error),
+
+    #[error("Not a PNG or JPEG")]
+    Unsupported,
+}
+
+/// What the branding shows and where
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrandingSettings {
+    pub enabled: bool,
+    pub text: String,
+    /// PNG or JPEG shown before the text
+    pub logo: Option<PathBuf>,
+    pub corner: OverlayCorner,
+    /// Height of the row as a fraction of the frame height
+    pub scale: f32,
+    /// 0.0 - 1.0
+    pub opacity: f32,
+}
+
+impl Default for BrandingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            text: String::new(),
+            logo: None,
+            corner: OverlayCorner::TopRight,
+            scale: 0.08,
+            opacity: 0.8,
+        }
+    }
+}
+
+/// A decoded logo, RGBA8 with straight alpha
+#[derive(Debug, Clone, PartialEq)]
+pub struct Logo {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl Logo {
+    pub fn load(path: &Path) -> Result<Self, BrandingError> {
+        Self::decode(&std::fs::read(path)?)
+    }
+
+    /// Decode an encoded PNG or JPEG
+    pub fn decode(data: &[u8]) -> Result<Self, BrandingError> {
+        if data.starts_with(b"\x89PNG") {
+            let mut decoder = png::Decoder::new(Cursor::new(data));
+            decoder.set_transformations(png::Transformations::normalize_to_color8());
+            let mut reader = decoder.read_info()?;
+            let mut buf = vec![0; reader.output_buffer_size()];
+            let frame = reader.next_frame(&mut buf)?;
+            let pixels = &buf[..frame.buffer_size()];
+            let rgba = match frame.color_type {
+                png::ColorType::Rgba => pixels.to_vec(),
+                png::ColorType::Rgb => pixels
+                    .chunks_exact(3)
+                    .flat_map(|p| [p[0], p[1], p[2], 255])
+                    .collect(),
+                png::ColorType::GrayscaleAlpha => pixels
+                    .chunks_exact(2)
+                    .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                    .collect(),
+                // Indexed images are expanded by the transformations
+                png::ColorType::Grayscale | png::ColorType::Indexed => {
+                    pixels.iter().flat_map(|&v| [v, v, v, 255]).collect()
+                }
+            };
+            Ok(Self {
+                width: frame.width as usize,
+                height: frame.height as usize,
+                rgba,
+            })
+        } else if data.starts_with(&[0xFF, 0xD8]) {
+            let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(data));
+            let pixels = decoder.decode()?;
+            let info = decoder.info().ok_or(BrandingError::Unsupported)?;
+            let rgba = match info.pixel_format {
+                jpeg_decoder::PixelFormat::RGB24 => pixels
+                    .chunks_exact(3)
+                    .flat_map(|p| [p[0], p[1], p[2], 255])
+                    .collect(),
+                jpeg_decoder::PixelFormat::L8 => {
+                    pixels.iter().flat_map(|&v| [v, v, v, 255]).collect()
+                }
+                _ => return Err(BrandingError::Unsupported),
+            };
+            Ok(Self {
+                width: info.width as usize,
+                height: info.height as usize,
+                rgba,
+            })
+        } else {
+            Err(BrandingError::Unsupported)
+        }
+    }
+
+    /// Color at `(u, v)` in 0.0 - 1.0, blended between the nearest pixels
+    fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        let x = (u * self.width as f32 - 0.5).clamp(0.0, (self.width - 1) as f32);
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (fx, fy) = (x.fract(), y.fract());
+        let pixel = |x: usize, y: usize| &self.rgba[(y * self.width + x) * 4..][..4];
+        let mut out = [0.0; 4];
+        for (i, out) in out.iter_mut().enumerate() {
+            let top = pixel(x0, y0)[i] as f32 * (1.0 - fx) + pixel(x1, y0)[i] as f32 * fx;
+            let bottom = pixel(x0, y1)[i] as f32 * (1.0 - fx) + pixel(x1, y1)[i] as f32 * fx;
+            *out = top * (1.0 - fy) + bottom * fy;
+        }
+        out
+    }
+}
+
+/// Where the parts of the branding go in a frame
+struct Layout {
+    logo: Option<Rect>,
+    strokes: Vec<Vec<Pos2>>,
+    stroke_width: f32,
+}
+
+/// The branding's settings and its decoded logo
+#[derive(Default)]
+pub struct Branding {
+    pub settings: BrandingSettings,
+    logo: Option<Logo>,
+    /// The logo uploaded for the screen, made on first paint
+    texture: Option<TextureHandle>,
+}
+
+impl Branding {
+    /// Show the logo at `path`, or none. On failure the logo shown before
+    /// stays.
+    pub fn set_logo(&mut self, path: Option<PathBuf>) -> Result<(), BrandingError> {
+        self.logo = path.as_deref().map(Logo::load).transpose()?;
+        self.settings.logo = path;
+        self.texture = None;
+        Ok(())
+    }
+
+    fn visible(&self) -> bool {
+        self.settings.enabled
+            && self.settings.opacity > 0.0
+            && (self.logo.is_some() || !self.settings.text.trim().is_empty())
+    }
+
+    fn layout(&self, rect: Rect) -> Layout {
+        let settings = &self.settings;
+        let row = settings.scale * rect.height();
+        let spacing = row * SPACING;
+        let logo_width = self
+            .logo
+            .as_ref()
+            .map(|logo| row * logo.width as f32 / logo.height.max(1) as f32);
+        let cap = row * TEXT_SIZE;
+        let scale = cap / CAP_HEIGHT;
+        let text_width = line_width(&settings.text).map(|width| width * scale);
+
+        let width = match (logo_width, text_width) {
+            (Some(logo), Some(text)) => logo + spacing + text,
+            (logo, text) => logo.or(text).unwrap_or(0.0),
+        };
+        let left = if settings.corner.right() {
+            rect.right() - spacing - width
+        } else {
+            rect.left() + spacing
+        };
+        let top = if settings.corner.bottom() {
+            rect.bottom() - spacing - row
+        } else {
+            rect.top() + spacing
+        };
+
+        let logo = logo_width
+            .map(|width| Rect::from_min_size(Pos2::new(left, top), egui::vec2(width, row)));
+        let text_left = logo.map_or(left, |logo| logo.right() + spacing);
+        let baseline = top + (row + cap) / 2.0;
+        Layout {
+            logo,
+            strokes: line_strokes(&settings.text, Pos2::new(text_left, baseline), scale),
+            stroke_width: (cap * TEXT_WEIGHT).max(1.0),
+        }
+    }
+
+    /// Paint the branding over the scope in `rect`, text in `color`
+    pub fn paint(&mut self, painter: &egui::Painter, rect: Rect, color: Color32) {
+        if !self.visible() {
+            return;
+        }
+        let layout = self.layout(rect);
+        let opacity = self.settings.opacity;
+        if let (Some(logo_rect), Some(logo)) = (layout.logo, &self.logo) {
+            let texture = self.texture.get_or_insert_with(|| {
+                let image =
+                    ColorImage::from_rgba_unmultiplied([logo.width, logo.height], &logo.rgba);
+                painter
+                    .ctx()
+                    .load_texture("branding_logo", image, egui::TextureOptions::LINEAR)
+            });
+            painter.image(
+                texture.id(),
+                logo_rect,
+                Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                Color32::WHITE.gamma_multiply(opacity),
+            );
+        }
+        let stroke = Stroke::new(layout.stroke_width, color.gamma_multiply(opacity));
+        for points in layout.strokes {
+            painter.add(Shape::line(points, stroke));
+        }
+    }
+}
+
+impl FrameLayer for Branding {
+    fn draw(&self, pixels: &mut [u8], width: usize, height: usize, color: Color32) {
+        if !self.visible() {
+            return;
+        }
+        let frame = Rect::from_min_size(Pos2::ZERO, egui::vec2(width as f32, height as f32));
+        let layout = self.layout(frame);
+        let opacity = self.settings.opacity;
+
+        if let (Some(rect), Some(logo)) = (layout.logo, &self.logo) {
+            let area = rect.intersect(frame);
+            for y in area.top() as usize..(area.bottom().ceil() as usize).min(height) {
+                let v = (y as f32 + 0.5 - rect.top()) / rect.height();
+                for x in area.left() as usize..(area.right().ceil() as usize).min(width) {
+                    let u = (x as f32 + 0.5 - rect.left()) / rect.width();
+                    if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+                        continue;
+                    }
+                    let [r, g, b, a] = logo.sample(u, v);
+                    let alpha = a / 255.0 * opacity;
+                    let px = &mut pixels[(y * width + x) * 4..][..3];
+                    for (channel, fg) in px.iter_mut().zip([r, g, b]) {
+                        *channel = (*channel as f32 + (fg - *channel as f32) * alpha).round() as u8;
+                    }
+                }
+            }
+        }
+        draw_strokes(
+            pixels,
+            width,
+            height,
+            &layout.strokes,
+            layout.stroke_width / 2.0,
+            color,
+            opacity,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x2 PNG: opaque red on top, clear below
+    fn logo_png() -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 2, 2);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels = [
+            255, 0, 0, 255, 255, 0, 0, 255, //
+            0, 0, 255, 0, 0, 0, 255, 0,
+        ];
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&pixels)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_logo_blends_into_the_corner() {
+        let logo = Logo::decode(&logo_png()).unwrap();
+        assert_eq!((logo.width, logo.height), (2, 2));
+        assert!(matches!(
+            Logo::decode(b"GIF89a"),
+            Err(BrandingError::Unsupported)
+        ));
+
+        let mut branding = Branding {
+            settings: BrandingSettings {
+                enabled: true,
+                corner: OverlayCorner::TopLeft,
+                scale: 0.2,
+                opacity: 1.0,
+                ..BrandingSettings::default()
+            },
+            logo: Some(logo),
+            texture: None,
+        };
+        // A 20 pixel row, 6 in from the edges
+        let (width, height) = (100, 100);
+        let mut pixels = vec![0; width * height * 4];
+        branding.draw(&mut pixels, width, height, Color32::WHITE);
+        let pixel = |x: usize, y: usize| &pixels[(y * width + x) * 4..][..3];
+        assert_eq!(pixel(10, 7), [255, 0, 0]);
+        // The clear half lets the frame through
+        assert_eq!(pixel(10, 24), [0, 0, 0]);
+        assert_eq!(pixel(50, 50), [0, 0, 0]);
+
+        // Text after the logo
+        branding.settings.text = "HI".to_string();
+        let mut pixels = vec![0; width * height * 4];
+        branding.draw(&mut pixels, width, height, Color32::WHITE);
+        let lit = (6..26).any(|y| (32..48).any(|x| pixels[(y * width + x) * 4 + 1] > 0));
+        assert!(lit);
+
+        branding.settings.enabled = false;
+        let mut pixels = vec![0; width * height * 4];
+        branding.draw(&mut pixels, width, height, Color32::WHITE);
+        assert!(pixels.iter().all(|&b| b == 0));
+    }
+}
@@ -0,0 +1,151 @@
+//! Log-frequency plot
+//!
+//! Draws curves and bars on a log-frequency (X) / dB (Y) grid. Used for
+//! measured frequency responses, the spectrum and the third-octave bands.
+
+use eframe::egui::{self, Align2, Color32, FontId, Pos2, Rect, Stroke, Vec2};
+
+/// A log-frequency / dB plot
+pub struct FrequencyPlot {
+    pub min_freq: f32,
+    pub max_freq: f32,
+    pub min_db: f32,
+    pub max_db: f32,
+}
+
+impl Default for FrequencyPlot {
+    fn default() -> Self {
+        Self {
+            min_freq: 20.0,
+            max_freq: 20000.0,
+            min_db: -60.0,
+            max_db: 12.0,
+        }
+    }
+}
+
+impl FrequencyPlot {
+    /// Allocate space and draw the grid. Curves are added afterwards with
+    /// `curve`, using the returned painter and `response.rect`.
+    pub fn begin(&self, ui: &mut egui::Ui, size: Vec2) -> (egui::Response, egui::Painter) {
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 4.0, Color32::from_gray(20));
+
+        let grid = Stroke::new(0.5, Color32::from_gray(60));
+        let font = FontId::proportional(10.0);
+        let label_color = Color32::from_gray(140);
+
+        // Decade and 2x/5x frequency lines
+        let mut decade = 10f32.powf(self.min_freq.log10().floor());
+        while decade <= self.max_freq {
+            for mult in [1.0, 2.0, 5.0] {
+                let f = decade * mult;
+                if f < self.min_freq || f > self.max_freq {
+                    continue;
+                }
+                let x = self.freq_to_x(f, rect);
+                painter.line_segment(
+                    [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+                    grid,
+                );
+                painter.text(
+                    Pos2::new(x + 2.0, rect.bottom() - 2.0),
+                    Align2::LEFT_BOTTOM,
+                    format_freq(f),
+                    font.clone(),
+                    label_color,
+                );
+            }
+            decade *= 10.0;
+        }
+
+        // dB lines every 12 dB
+        let mut db = (self.min_db / 12.0).ceil() * 12.0;
+        while db <= self.max_db {
+            let y = self.db_to_y(db, rect);
+            painter.line_segment(
+                [Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)],
+                grid,
+            );
+            painter.text(
+                Pos2::new(rect.left() + 2.0, y - 1.0),
+                Align2::LEFT_BOTTOM,
+                format!("{:+.0} dB", db),
+                font.clone(),
+                label_color,
+            );
+            db += 12.0;
+        }
+
+        (response, painter)
+    }
+
+    /// Draw a curve of (frequency, dB) points
+    pub fn curve(
+        &self,
+        painter: &egui::Painter,
+        rect: Rect,
+        points: impl Iterator<Item = (f32, f32)>,
+        color: Color32,
+    ) {
+        let pts: Vec<Pos2> = points
+            .filter(|(f, _)| *f >= self.min_freq && *f <= self.max_freq)
+            .map(|(f, db)| {
+                Pos2::new(
+                    self.freq_to_x(f, rect),
+                    self.db_to_y(db.clamp(self.min_db, self.max_db), rect),
+                )
+            })
+            .collect();
+        if pts.len() >= 2 {
+            painter.add(egui::Shape::line(pts, Stroke::new(1.5, color)));
+        }
+    }
+
+    /// Draw a bar from the bottom up to `db`, spanning `low` to `high` Hz
+    pub fn bar(
+        &self,
+        painter: &egui::Painter,
+        rect: Rect,
+        (low, high): (f32, f32),
+        db: f32,
+        color: Color32,
+    ) {
+        let left = self.freq_to_x(low.max(self.min_freq), rect);
+        let right = self.freq_to_x(high.min(self.max_freq), rect);
+        if right <= left {
+            return;
+        }
+        let top = self.db_to_y(db.clamp(self.min_db, self.max_db), rect);
+        painter.rect_filled(
+            Rect::from_min_max(Pos2::new(left, top), Pos2::new(right, rect.bottom())),
+            0.0,
+            color,
+        );
+    }
+
+    pub fn freq_to_x(&self, freq: f32, rect: Rect) -> f32 {
+        let span = self.max_freq.ln() - self.min_freq.ln();
+        // A single frequency goes in the middle
+        if span.abs() < f32::EPSILON {
+            return rect.center().x;
+        }
+        let t = (freq.ln() - self.min_freq.ln()) / span;
+        rect.left() + t * rect.width()
+    }
+
+    pub fn db_to_y(&self, db: f32, rect: Rect) -> f32 {
+        let t = (db - self.min_db) / (self.max_db - self.min_db);
+        rect.bottom() - t * rect.height()
+    }
+}
+
+/// Format a frequency compactly: 50, 200, 1k, 20k
+fn format_freq(f: f32) -> String {
+    if f >= 1000.0 {
+        format!("{}k", f / 1000.0)
+    } else {
+        format!("{}", f)
+    }
+}
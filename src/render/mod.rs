@@ -1,6 +1,19 @@
 //! Render module - UI components for visualization
 
+mod branding;
+mod freq_plot;
 mod oscilloscope;
+mod overlay;
+mod persistence;
+mod raster;
+mod transform;
+mod waterfall;
 
+pub use branding::{Branding, BrandingError, BrandingSettings, Logo};
+pub use freq_plot::FrequencyPlot;
 #[allow(unused_imports)]
-pub use oscilloscope::{ColorTheme, DisplayMode, Oscilloscope, OscilloscopeSettings};
+pub use oscilloscope::{ColorTheme, DisplayMode, FrameTimings, Oscilloscope, OscilloscopeSettings};
+pub use overlay::{format_timecode, FrameLayer, Overlay, OverlayCorner, OverlaySettings};
+pub use raster::Rasterizer;
+pub use transform::ScreenTransform;
+pub use waterfall::Waterfall;
@@ -2,9 +2,14 @@
 //!
 //! Enhanced version with multiple display modes and channel controls.
 
+use std::time::{Duration, Instant};
+
+use eframe::egui::ecolor::HsvaGamma;
 use eframe::egui::{self, Color32, Pos2, Rect, Stroke, Vec2};
 use serde::{Deserialize, Serialize};
 
+use super::persistence::PersistenceRing;
+use super::transform::{decimate, point_budget, ScreenTransform};
 use crate::audio::XYSample;
 
 /// Display mode for the oscilloscope
@@ -31,6 +36,16 @@ impl DisplayMode {
         }
     }
 
+    /// One-line explanation for the help overlay
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Dots => "A dot at each sample. Fast movements look sparse.",
+            Self::Lines => "Samples joined by lines, the usual XY look.",
+            Self::Gradient => "Lines that are brighter where the beam moves slowly, like a CRT.",
+            Self::Points => "Larger points, without the persistence trail.",
+        }
+    }
+
     pub fn all() -> &'static [DisplayMode] {
         &[Self::Dots, Self::Lines, Self::Gradient, Self::Points]
     }
@@ -47,8 +62,16 @@ pub enum ColorTheme {
     Purple,
     Cyan,
     Red,
+    /// Generated from a palette, such as a cover's, by `from_palette`
+    Artwork {
+        color: [u8; 3],
+        background: [u8; 3],
+    },
 }
 
+/// Saturation a palette color needs to be picked as the trace color
+const MIN_TRACE_SATURATION: f32 = 0.25;
+
 impl ColorTheme {
     pub fn name(&self) -> &'static str {
         match self {
@@ -59,6 +82,7 @@ impl ColorTheme {
             Self::Purple => "Purple",
             Self::Cyan => "Cyan",
             Self::Red => "Red",
+            Self::Artwork { .. } => "Artwork",
         }
     }
 
@@ -89,9 +113,46 @@ impl ColorTheme {
                 Color32::from_rgb(255, 100, 100),
                 Color32::from_rgb(20, 10, 10),
             ),
+            Self::Artwork { color, background } => {
+                let [r, g, b] = *color;
+                let [br, bg, bb] = *background;
+                (Color32::from_rgb(r, g, b), Color32::from_rgb(br, bg, bb))
+            }
         }
     }
 
+    /// A theme in the spirit of the built-in ones from a palette, most
+    /// prominent color first: the trace takes the most prominent colorful
+    /// one at full brightness, the background the next one, nearly black
+    pub fn from_palette(palette: &[[u8; 3]]) -> Option<ColorTheme> {
+        let hsv: Vec<HsvaGamma> = palette
+            .iter()
+            .map(|&[r, g, b]| Color32::from_rgb(r, g, b).into())
+            .collect();
+        let trace = hsv
+            .iter()
+            .position(|c| c.s >= MIN_TRACE_SATURATION && c.v >= 0.2)
+            .unwrap_or(0);
+        let mut color = *hsv.get(trace)?;
+        let mut background = hsv
+            .iter()
+            .enumerate()
+            .find(|(i, c)| *i != trace && c.s >= MIN_TRACE_SATURATION)
+            .map_or(color, |(_, c)| *c);
+        color.s = color.s.min(0.85);
+        color.v = 1.0;
+        background.s = background.s.min(0.5);
+        background.v = 0.08;
+        let rgb = |c: HsvaGamma| {
+            let c = Color32::from(c);
+            [c.r(), c.g(), c.b()]
+        };
+        Some(Self::Artwork {
+            color: rgb(color),
+            background: rgb(background),
+        })
+    }
+
     pub fn all() -> &'static [ColorTheme] {
         &[
             Self::Green,
@@ -105,6 +166,10 @@ impl ColorTheme {
     }
 }
 
+/// Time constant of the auto-centering servo, in seconds: slow enough not to
+/// follow the figure's own motion, fast enough to chase drifting DC
+const CENTER_TIME_CONSTANT: f32 = 0.5;
+
 /// Display settings for the oscilloscope
 #[derive(Clone)]
 pub struct OscilloscopeSettings {
@@ -114,6 +179,11 @@ pub struct OscilloscopeSettings {
     pub display_mode: DisplayMode,
     pub intensity: f32,
     pub sample_count: usize,
+    /// Thin out windows with more samples than the display can resolve
+    pub decimate: bool,
+    /// Step through file playback at the audio clock rate instead of
+    /// showing whatever was decoded last
+    pub steady_pacing: bool,
     pub zoom: f32,
     pub show_graticule: bool,
     pub persistence: f32,
@@ -124,6 +194,12 @@ pub struct OscilloscopeSettings {
     pub invert_y: bool,
     pub dc_offset_x: f32,
     pub dc_offset_y: f32,
+    /// Keep the figure's centroid in the middle with an offset of its own
+    pub auto_center: bool,
+    /// Offsets the auto-centering servo steers, added to the DC offsets
+    /// while it is on. Never saved: they only follow the current input.
+    pub center_x: f32,
+    pub center_y: f32,
 }
 
 impl Default for OscilloscopeSettings {
@@ -137,6 +213,8 @@ impl Default for OscilloscopeSettings {
             display_mode: DisplayMode::default(),
             intensity: 1.0,
             sample_count: 2048,
+            decimate: true,
+            steady_pacing: true,
             zoom: 1.0,
             show_graticule: true,
             persistence: 0.85,
@@ -146,6 +224,9 @@ impl Default for OscilloscopeSettings {
             invert_y: false,
             dc_offset_x: 0.0,
             dc_offset_y: 0.0,
+            auto_center: false,
+            center_x: 0.0,
+            center_y: 0.0,
         }
     }
 }
@@ -158,12 +239,139 @@ impl OscilloscopeSettings {
         self.color = color;
         self.background = background;
     }
+
+    /// Total offset added to each sample: the DC offsets, plus the
+    /// servo's while auto-centering
+    pub fn offset(&self) -> (f32, f32) {
+        if self.auto_center {
+            (
+                self.dc_offset_x + self.center_x,
+                self.dc_offset_y + self.center_y,
+            )
+        } else {
+            (self.dc_offset_x, self.dc_offset_y)
+        }
+    }
+
+    /// Apply channel controls (DC offset, invert, swap) to a sample
+    pub fn process_sample(&self, sample: XYSample) -> XYSample {
+        let (offset_x, offset_y) = self.offset();
+        let mut x = sample.x + offset_x;
+        let mut y = sample.y + offset_y;
+
+        // Apply invert
+        if self.invert_x {
+            x = -x;
+        }
+        if self.invert_y {
+            y = -y;
+        }
+
+        // Apply swap
+        if self.swap_xy {
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        XYSample::new(x, y)
+    }
+
+    /// Convert a shift in view units (-1 to 1 across the display, Y up) into
+    /// the DC offset change that moves the trace by that much, undoing swap
+    /// and invert
+    pub fn view_to_offset(&self, delta: Vec2) -> Vec2 {
+        let (mut x, mut y) = (delta.x, delta.y);
+        if self.swap_xy {
+            std::mem::swap(&mut x, &mut y);
+        }
+        if self.invert_x {
+            x = -x;
+        }
+        if self.invert_y {
+            y = -y;
+        }
+        Vec2::new(x, y)
+    }
+
+    /// Move the trace by `delta` view units
+    pub fn pan(&mut self, delta: Vec2) {
+        let shift = self.view_to_offset(delta * self.zoom);
+        self.dc_offset_x = (self.dc_offset_x + shift.x).clamp(-1.0, 1.0);
+        self.dc_offset_y = (self.dc_offset_y + shift.y).clamp(-1.0, 1.0);
+    }
+
+    /// Set the zoom and shift the DC offsets so whatever is at `anchor` (view
+    /// units) stays there. Offsets are kept within their slider range, so
+    /// the anchor can slip when zooming far off-center.
+    pub fn zoom_around(&mut self, zoom: f32, anchor: Vec2) {
+        let zoom = zoom.clamp(0.1, 2.0);
+        let shift = self.view_to_offset(anchor * (zoom - self.zoom));
+        self.dc_offset_x = (self.dc_offset_x + shift.x).clamp(-1.0, 1.0);
+        self.dc_offset_y = (self.dc_offset_y + shift.y).clamp(-1.0, 1.0);
+        self.zoom = zoom;
+    }
+
+    /// Move the servo offsets toward the ones that, with the DC offsets,
+    /// put the centroid of `samples` in the middle, as far as the servo gets
+    /// in `dt` seconds. The DC offsets are left as the user set them. The
+    /// offsets are added before invert and swap, so centering the raw
+    /// samples centers the figure whatever those are set to.
+    pub fn track_center(&mut self, samples: &[XYSample], dt: f32) {
+        if samples.is_empty() {
+            return;
+        }
+        let n = samples.len() as f32;
+        let (sum_x, sum_y) = samples
+            .iter()
+            .fold((0.0, 0.0), |(x, y), s| (x + s.x, y + s.y));
+        let step = 1.0 - (-dt.max(0.0) / CENTER_TIME_CONSTANT).exp();
+        let target_x = (-sum_x / n).clamp(-1.0, 1.0) - self.dc_offset_x;
+        let target_y = (-sum_y / n).clamp(-1.0, 1.0) - self.dc_offset_y;
+        self.center_x += (target_x - self.center_x) * step;
+        self.center_y += (target_y - self.center_y) * step;
+    }
+
+    /// The newest `sample_count` of `samples`, which run oldest first
+    pub fn window<'a>(&self, samples: &'a [XYSample]) -> &'a [XYSample] {
+        &samples[samples.len().saturating_sub(self.sample_count)..]
+    }
+
+    /// Map a sample to normalized view coordinates (0.0 - 1.0, Y pointing up)
+    ///
+    /// Channel controls and zoom are applied, so this is shared by the egui
+    /// widget and the offscreen rasterizer.
+    pub fn sample_to_unit(&self, sample: XYSample) -> (f32, f32) {
+        let processed = self.process_sample(sample);
+        let zoom = self.zoom;
+        (
+            (processed.x / zoom + 1.0) / 2.0,
+            (processed.y / zoom + 1.0) / 2.0,
+        )
+    }
+}
+
+/// Time spent in the last `Oscilloscope::show`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameTimings {
+    pub persistence: Duration,
+    /// Building the shapes; egui tessellates and uploads them later
+    pub painting: Duration,
 }
 
+/// Most points kept for the persistence trail
+const MAX_PERSISTED_POINTS: usize = 50_000;
+
 /// XY Oscilloscope widget
 pub struct Oscilloscope {
     pub settings: OscilloscopeSettings,
-    persistence_buffer: Vec<(Pos2, f32)>,
+    pub timings: FrameTimings,
+    /// Fraction of the full window, trail and point budget to render, for
+    /// adaptive quality; 1.0 draws everything
+    pub quality: f32,
+    /// X level to mark with a dashed line while the trigger is on
+    pub trigger_level: Option<f32>,
+    persistence: PersistenceRing,
+    /// Screen positions of the current samples; the allocation is reused
+    points: Vec<Pos2>,
 }
 
 impl Default for Oscilloscope {
@@ -176,45 +384,57 @@ impl Oscilloscope {
     pub fn new() -> Self {
         Self {
             settings: OscilloscopeSettings::default(),
-            persistence_buffer: Vec::with_capacity(8192),
+            timings: FrameTimings::default(),
+            quality: 1.0,
+            trigger_level: None,
+            persistence: PersistenceRing::new(MAX_PERSISTED_POINTS),
+            points: Vec::new(),
         }
     }
 
-    /// Apply channel controls to a sample
-    fn process_sample(&self, sample: XYSample) -> XYSample {
-        let mut x = sample.x;
-        let mut y = sample.y;
-
-        // Apply DC offset
-        x += self.settings.dc_offset_x;
-        y += self.settings.dc_offset_y;
-
-        // Apply invert
-        if self.settings.invert_x {
-            x = -x;
-        }
-        if self.settings.invert_y {
-            y = -y;
-        }
-
-        // Apply swap
-        if self.settings.swap_xy {
-            std::mem::swap(&mut x, &mut y);
-        }
+    /// Signal value at a screen position, undoing zoom and channel controls
+    pub fn screen_to_sample(&self, pos: Pos2, rect: Rect) -> XYSample {
+        let view = Vec2::new(
+            (pos.x - rect.center().x) / (rect.width() / 2.0),
+            (rect.center().y - pos.y) / (rect.height() / 2.0),
+        );
+        let raw = self.settings.view_to_offset(view * self.settings.zoom);
+        let (offset_x, offset_y) = self.settings.offset();
+        XYSample::new(raw.x - offset_x, raw.y - offset_y)
+    }
 
-        XYSample::new(x, y)
+    /// Index into `samples` of the drawn sample closest to `pos`, if one is
+    /// within `radius` screen points
+    pub fn nearest_sample(
+        &self,
+        samples: &[XYSample],
+        rect: Rect,
+        pos: Pos2,
+        radius: f32,
+    ) -> Option<usize> {
+        let transform = ScreenTransform::new(&self.settings, rect);
+        let window = self.shown(samples);
+        window
+            .iter()
+            .map(|s| transform.apply(*s).distance_sq(pos))
+            .enumerate()
+            .filter(|&(_, d)| d <= radius * radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| samples.len() - window.len() + i)
     }
 
-    fn sample_to_screen(&self, sample: XYSample, rect: Rect) -> Pos2 {
-        let processed = self.process_sample(sample);
-        let zoom = self.settings.zoom;
-        let norm_x = (processed.x / zoom + 1.0) / 2.0;
-        let norm_y = (processed.y / zoom + 1.0) / 2.0;
+    /// The part of `samples` that is drawn: the settings' window, cut down
+    /// to the current quality
+    fn shown<'a>(&self, samples: &'a [XYSample]) -> &'a [XYSample] {
+        /// Fewest samples drawn however low the quality
+        const MIN_SHOWN: usize = 64;
 
-        Pos2::new(
-            rect.left() + norm_x * rect.width(),
-            rect.bottom() - norm_y * rect.height(),
-        )
+        let window = self.settings.window(samples);
+        if self.quality >= 1.0 {
+            return window;
+        }
+        let shown = ((window.len() as f32 * self.quality) as usize).max(MIN_SHOWN);
+        &window[window.len().saturating_sub(shown)..]
     }
 
     pub fn show(
@@ -229,18 +449,37 @@ impl Oscilloscope {
             Vec2::new(side, side)
         });
 
-        let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::click_and_drag());
         let rect = response.rect;
 
+        let started = Instant::now();
+        // Map the window once; persistence and drawing share the points
+        let samples = self.shown(samples);
+        self.points.clear();
+        ScreenTransform::new(&self.settings, rect).extend(samples, &mut self.points);
+        if self.settings.decimate || self.quality < 1.0 {
+            let budget = point_budget(rect) as f32 * self.quality.min(1.0);
+            decimate(&mut self.points, budget as usize);
+        }
+        self.update_persistence(rect);
+        let persisted = Instant::now();
+
         painter.rect_filled(rect, 4.0, self.settings.background);
 
         if self.settings.show_graticule {
             self.draw_graticule(&painter, rect);
         }
 
-        self.update_persistence(samples, rect);
         self.draw_persistence(&painter, rect);
-        self.draw_samples(&painter, rect, samples);
+        self.draw_samples(&painter, rect);
+        if let Some(level) = self.trigger_level {
+            self.draw_trigger_level(&painter, rect, level);
+        }
+
+        self.timings = FrameTimings {
+            persistence: persisted - started,
+            painting: persisted.elapsed(),
+        };
 
         response
     }
@@ -269,49 +508,56 @@ impl Oscilloscope {
         }
     }
 
-    fn update_persistence(&mut self, samples: &[XYSample], rect: Rect) {
-        let decay = self.settings.persistence;
-
-        self.persistence_buffer.retain_mut(|(_, alpha)| {
-            *alpha *= decay;
-            *alpha > 0.01
-        });
-
-        for sample in samples.iter().take(self.settings.sample_count) {
-            let pos = self.sample_to_screen(*sample, rect);
-            if rect.contains(pos) {
-                self.persistence_buffer.push((pos, self.settings.intensity));
-            }
-        }
-
-        const MAX_POINTS: usize = 50000;
-        if self.persistence_buffer.len() > MAX_POINTS {
-            let excess = self.persistence_buffer.len() - MAX_POINTS;
-            self.persistence_buffer.drain(0..excess);
-        }
+    fn update_persistence(&mut self, rect: Rect) {
+        self.persistence.push_frame(
+            self.points
+                .iter()
+                .copied()
+                .filter(|pos| rect.contains(*pos)),
+            self.settings.intensity,
+            self.settings.persistence,
+        );
     }
 
     fn draw_persistence(&self, painter: &egui::Painter, rect: Rect) {
         let base_color = self.settings.color;
+        let radius = self.settings.line_width * 0.5;
+
+        self.persistence
+            .for_each_frame(self.settings.persistence, |points, alpha| {
+                let color = Color32::from_rgba_unmultiplied(
+                    base_color.r(),
+                    base_color.g(),
+                    base_color.b(),
+                    (alpha * 255.0 * 0.3) as u8,
+                );
+                for pos in points.iter().filter(|pos| rect.contains(**pos)) {
+                    painter.circle_filled(*pos, radius, color);
+                }
+            });
+    }
 
-        for (pos, alpha) in &self.persistence_buffer {
-            if !rect.contains(*pos) {
-                continue;
-            }
-
-            let color = Color32::from_rgba_unmultiplied(
-                base_color.r(),
-                base_color.g(),
-                base_color.b(),
-                (alpha * 255.0 * 0.3) as u8,
-            );
-
-            painter.circle_filled(*pos, self.settings.line_width * 0.5, color);
-        }
+    /// The line where X equals the trigger level; it follows swap, invert,
+    /// offset and zoom like the trace does
+    fn draw_trigger_level(&self, painter: &egui::Painter, rect: Rect, level: f32) {
+        let transform = ScreenTransform::new(&self.settings, rect);
+        // Past the edges at any zoom and offset
+        let reach = self.settings.zoom + 2.0;
+        let ends = [-reach, reach].map(|y| transform.apply(XYSample::new(level, y)));
+        let color = Color32::from_rgba_unmultiplied(255, 200, 80, 160);
+        painter
+            .with_clip_rect(rect)
+            .extend(egui::Shape::dashed_line(
+                &ends,
+                Stroke::new(1.0, color),
+                6.0,
+                4.0,
+            ));
     }
 
-    fn draw_samples(&self, painter: &egui::Painter, rect: Rect, samples: &[XYSample]) {
-        if samples.is_empty() {
+    fn draw_samples(&self, painter: &egui::Painter, rect: Rect) {
+        let points = &self.points;
+        if points.is_empty() {
             return;
         }
 
@@ -325,16 +571,10 @@ impl Oscilloscope {
             (intensity * 255.0) as u8,
         );
 
-        let points: Vec<Pos2> = samples
-            .iter()
-            .take(self.settings.sample_count)
-            .map(|s| self.sample_to_screen(*s, rect))
-            .collect();
-
         match self.settings.display_mode {
             DisplayMode::Dots => {
                 // Draw small dots at each sample point
-                for pos in &points {
+                for pos in points {
                     if rect.contains(*pos) {
                         painter.circle_filled(*pos, self.settings.line_width * 0.5, color);
                     }
@@ -390,7 +630,7 @@ impl Oscilloscope {
             }
             DisplayMode::Points => {
                 // Just points, no lines, no persistence effect
-                for pos in &points {
+                for pos in points {
                     if rect.contains(*pos) {
                         painter.circle_filled(*pos, self.settings.line_width, color);
                     }
@@ -400,6 +640,110 @@ impl Oscilloscope {
     }
 
     pub fn clear_persistence(&mut self) {
-        self.persistence_buffer.clear();
+        self.persistence.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_theme_takes_the_colorful_colors() {
+        // A grey cover with a red title and a blue band
+        let theme = ColorTheme::from_palette(&[[128, 128, 128], [200, 30, 30], [20, 40, 160]]);
+        let (color, background) = theme.unwrap().colors();
+        assert!(
+            color.r() == 255 && color.g() < 130 && color.g() == color.b(),
+            "{color:?}"
+        );
+        assert!(
+            background.b() > background.r() && background.b() < 30,
+            "{background:?}"
+        );
+
+        // Greys alone give a grey theme
+        let (color, _) = ColorTheme::from_palette(&[[90, 90, 90]]).unwrap().colors();
+        assert_eq!(color, Color32::WHITE);
+        assert_eq!(ColorTheme::from_palette(&[]), None);
+    }
+
+    #[test]
+    fn test_zoom_around_keeps_anchor() {
+        let mut settings = OscilloscopeSettings {
+            swap_xy: true,
+            invert_x: true,
+            ..OscilloscopeSettings::default()
+        };
+        let sample = XYSample::new(0.2, -0.3);
+        let (x, y) = settings.sample_to_unit(sample);
+        let anchor = Vec2::new(x * 2.0 - 1.0, y * 2.0 - 1.0);
+
+        settings.zoom_around(0.5, anchor);
+        let (x2, y2) = settings.sample_to_unit(sample);
+        assert!((x - x2).abs() < 1e-5 && (y - y2).abs() < 1e-5);
+        assert_eq!(settings.zoom, 0.5);
+    }
+
+    #[test]
+    fn test_screen_to_sample_inverts_mapping() {
+        let mut scope = Oscilloscope::new();
+        scope.settings.zoom = 0.7;
+        scope.settings.swap_xy = true;
+        scope.settings.invert_y = true;
+        scope.settings.dc_offset_x = 0.1;
+        let rect = Rect::from_min_size(Pos2::new(10.0, 20.0), Vec2::new(300.0, 200.0));
+        let sample = XYSample::new(0.25, -0.4);
+        let screen = ScreenTransform::new(&scope.settings, rect).apply(sample);
+        let back = scope.screen_to_sample(screen, rect);
+        assert!((back.x - sample.x).abs() < 1e-5 && (back.y - sample.y).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_pan_moves_trace() {
+        let mut settings = OscilloscopeSettings {
+            zoom: 0.5,
+            invert_y: true,
+            ..OscilloscopeSettings::default()
+        };
+        let sample = XYSample::new(0.1, 0.1);
+        let (x, y) = settings.sample_to_unit(sample);
+        settings.pan(Vec2::new(0.2, -0.4));
+        let (x2, y2) = settings.sample_to_unit(sample);
+        // View units span 2 across, unit coordinates span 1
+        assert!((x2 - x - 0.1).abs() < 1e-5 && (y2 - y + 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_track_center_settles_on_centroid() {
+        let mut settings = OscilloscopeSettings {
+            swap_xy: true,
+            invert_x: true,
+            dc_offset_y: 0.1,
+            auto_center: true,
+            ..OscilloscopeSettings::default()
+        };
+        // A circle around (0.3, -0.2)
+        let samples: Vec<XYSample> = (0..480)
+            .map(|i| {
+                let t = i as f32 / 480.0 * std::f32::consts::TAU;
+                XYSample::new(0.3 + 0.4 * t.cos(), -0.2 + 0.4 * t.sin())
+            })
+            .collect();
+
+        // One time constant covers about 63% of the way
+        settings.track_center(&samples, CENTER_TIME_CONSTANT);
+        assert!((settings.center_x + 0.19).abs() < 0.01);
+
+        for _ in 0..100 {
+            settings.track_center(&samples, 0.05);
+        }
+        let (x, y) = settings.sample_to_unit(XYSample::new(0.3, -0.2));
+        assert!((x - 0.5).abs() < 1e-3 && (y - 0.5).abs() < 1e-3);
+
+        // The user's offsets are kept, and come back once it's off
+        assert_eq!((settings.dc_offset_x, settings.dc_offset_y), (0.0, 0.1));
+        settings.auto_center = false;
+        assert_eq!(settings.offset(), (0.0, 0.1));
     }
 }
@@ -0,0 +1,377 @@
+//! Track info overlay
+//!
+//! A few lines of text in a corner of the scope: the playback timecode,
+//! the track title and the tempo. They're set in the stroke font the text
+//! generator uses, so the same strokes can be painted on screen and drawn
+//! into rasterized frames, and recordings and stream output show exactly
+//! what the window does. Sizes are fractions of the frame height, so the
+//! overlay looks the same at any output resolution.
+
+use std::time::Duration;
+
+use eframe::egui::{self, Color32, Pos2, Rect, Shape, Stroke};
+use serde::{Deserialize, Serialize};
+
+use crate::generator::text_path;
+
+/// Height of a capital in font units
+const CAP_HEIGHT: f32 = 6.0;
+
+/// Distance between baselines in font units
+const LINE_HEIGHT: f32 = 9.0;
+
+/// Something drawn over rasterized frames, after the beam
+pub trait FrameLayer {
+    /// Blend over a frame of tightly packed RGBA8 `pixels`, in `color`
+    /// wherever it takes the beam's color
+    fn draw(&self, pixels: &mut [u8], width: usize, height: usize, color: Color32);
+}
+
+/// Corner of the scope the overlay sits in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OverlayCorner {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TopLeft => "Top left",
+            Self::TopRight => "Top right",
+            Self::BottomLeft => "Bottom left",
+            Self::BottomRight => "Bottom right",
+        }
+    }
+
+    pub fn all() -> &'static [OverlayCorner] {
+        &[
+            Self::TopLeft,
+            Self::TopRight,
+            Self::BottomLeft,
+            Self::BottomRight,
+        ]
+    }
+
+    pub(super) fn right(&self) -> bool {
+        matches!(self, Self::TopRight | Self::BottomRight)
+    }
+
+    pub(super) fn bottom(&self) -> bool {
+        matches!(self, Self::BottomLeft | Self::BottomRight)
+    }
+}
+
+/// What the overlay shows and how
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    pub timecode: bool,
+    pub title: bool,
+    pub bpm: bool,
+    pub corner: OverlayCorner,
+    /// Capital height as a fraction of the frame height
+    pub size: f32,
+    /// Stroke width as a fraction of the capital height
+    pub weight: f32,
+    /// 0.0 - 1.0
+    pub opacity: f32,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timecode: true,
+            title: true,
+            bpm: true,
+            corner: OverlayCorner::BottomLeft,
+            size: 0.03,
+            weight: 0.12,
+            opacity: 0.8,
+        }
+    }
+}
+
+/// The overlay's settings and the text it's showing this frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overlay {
+    pub settings: OverlaySettings,
+    pub lines: Vec<String>,
+}
+
+impl Overlay {
+    /// Replace the text with whichever of the parts are switched on
+    pub fn set_text(&mut self, title: Option<&str>, timecode: Option<Duration>, bpm: Option<f32>) {
+        self.lines.clear();
+        let settings = self.settings;
+        self.lines
+            .extend(title.filter(|_| settings.title).map(str::to_string));
+        self.lines
+            .extend(timecode.filter(|_| settings.timecode).map(format_timecode));
+        self.lines.extend(
+            bpm.filter(|_| settings.bpm)
+                .map(|bpm| format!("{bpm:.0} BPM")),
+        );
+    }
+
+    fn visible(&self) -> bool {
+        self.settings.enabled && self.settings.opacity > 0.0 && !self.lines.is_empty()
+    }
+
+    /// Stroke width for a frame `height` high
+    fn stroke_width(&self, height: f32) -> f32 {
+        (self.settings.size * height * self.settings.weight).max(1.0)
+    }
+
+    /// The text's strokes laid out in the corner of `rect`, y down
+    pub fn strokes(&self, rect: Rect) -> Vec<Vec<Pos2>> {
+        let settings = &self.settings;
+        let cap = settings.size * rect.height();
+        let scale = cap / CAP_HEIGHT;
+        let margin = cap;
+        let count = self.lines.len();
+        let mut strokes = Vec::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            let Some(right) = line_width(line) else {
+                continue;
+            };
+            let baseline = if settings.corner.bottom() {
+                rect.bottom() - margin - (count - 1 - i) as f32 * LINE_HEIGHT * scale
+            } else {
+                rect.top() + margin + cap + i as f32 * LINE_HEIGHT * scale
+            };
+            let left = if settings.corner.right() {
+                rect.right() - margin - right * scale
+            } else {
+                rect.left() + margin
+            };
+            strokes.extend(line_strokes(line, Pos2::new(left, baseline), scale));
+        }
+        strokes
+    }
+
+    /// Paint the overlay over the scope in `rect`
+    pub fn paint(&self, painter: &egui::Painter, rect: Rect, color: Color32) {
+        if !self.visible() {
+            return;
+        }
+        let stroke = Stroke::new(
+            self.stroke_width(rect.height()),
+            color.gamma_multiply(self.settings.opacity),
+        );
+        for points in self.strokes(rect) {
+            painter.add(Shape::line(points, stroke));
+        }
+    }
+}
+
+impl FrameLayer for Overlay {
+    fn draw(&self, pixels: &mut [u8], width: usize, height: usize, color: Color32) {
+        if !self.visible() {
+            return;
+        }
+        let rect = Rect::from_min_size(Pos2::ZERO, egui::vec2(width as f32, height as f32));
+        let strokes = self.strokes(rect);
+        let radius = self.stroke_width(height as f32) / 2.0;
+        draw_strokes(
+            pixels,
+            width,
+            height,
+            &strokes,
+            radius,
+            color,
+            self.settings.opacity,
+        );
+    }
+}
+
+/// Strokes of a line of text in `scale` pixels to a font unit, starting at
+/// `origin` on its baseline, y down
+pub(super) fn line_strokes(line: &str, origin: Pos2, scale: f32) -> Vec<Vec<Pos2>> {
+    text_path(line)
+        .strokes
+        .iter()
+        .map(|stroke| {
+            stroke
+                .iter()
+                .map(|&(x, y)| Pos2::new(origin.x + x * scale, origin.y - y * scale))
+                .collect()
+        })
+        .collect()
+}
+
+/// Width of a line of text in font units, or None if none of it is drawn
+pub(super) fn line_width(line: &str) -> Option<f32> {
+    text_path(line).bounds().map(|(_, _, right, _)| right)
+}
+
+/// Blend `strokes`, `radius` pixels either side of the line, over a frame of
+/// tightly packed RGBA8 `pixels`
+pub(super) fn draw_strokes(
+    pixels: &mut [u8],
+    width: usize,
+    height: usize,
+    strokes: &[Vec<Pos2>],
+    radius: f32,
+    color: Color32,
+    opacity: f32,
+) {
+    let rect = Rect::from_min_size(Pos2::ZERO, egui::vec2(width as f32, height as f32));
+    // Coverage of the pixels the text can touch, so strokes crossing
+    // each other don't get brighter where they overlap
+    let Some(bounds) = strokes
+        .iter()
+        .flatten()
+        .map(|&p| Rect::from_min_max(p, p))
+        .reduce(|a, b| a.union(b))
+    else {
+        return;
+    };
+    let bounds = bounds.expand(radius + 1.0).intersect(rect);
+    let (x0, y0) = (bounds.left() as usize, bounds.top() as usize);
+    let x1 = (bounds.right().ceil() as usize).min(width);
+    let y1 = (bounds.bottom().ceil() as usize).min(height);
+    if x1 <= x0 || y1 <= y0 {
+        return;
+    }
+    let span = x1 - x0;
+    let mut coverage = vec![0.0_f32; span * (y1 - y0)];
+    for stroke in strokes {
+        let segments = stroke.windows(2).map(|pair| (pair[0], pair[1]));
+        // A single point still leaves a dot
+        let dot = (stroke.len() == 1).then(|| (stroke[0], stroke[0]));
+        for (a, b) in segments.chain(dot) {
+            let area = Rect::from_two_pos(a, b).expand(radius + 1.0);
+            let xs = (area.left().max(x0 as f32) as usize)..(area.right().ceil() as usize).min(x1);
+            let ys = (area.top().max(y0 as f32) as usize)..(area.bottom().ceil() as usize).min(y1);
+            for y in ys {
+                for x in xs.clone() {
+                    let center = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let cover = (radius + 0.5 - distance_to_segment(center, a, b)).clamp(0.0, 1.0);
+                    let c = &mut coverage[(y - y0) * span + (x - x0)];
+                    *c = c.max(cover);
+                }
+            }
+        }
+    }
+
+    let fg = [color.r() as f32, color.g() as f32, color.b() as f32];
+    for (row, y) in (y0..y1).enumerate() {
+        for (col, x) in (x0..x1).enumerate() {
+            let alpha = coverage[row * span + col] * opacity;
+            if alpha <= 0.0 {
+                continue;
+            }
+            let px = &mut pixels[(y * width + x) * 4..][..3];
+            for (channel, fg) in px.iter_mut().zip(fg) {
+                *channel = (*channel as f32 + (fg - *channel as f32) * alpha).round() as u8;
+            }
+        }
+    }
+}
+
+/// Distance from `p` to the segment from `a` to `b`
+fn distance_to_segment(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let length = ab.length_sq();
+    let t = if length > 0.0 {
+        ((p - a).dot(ab) / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    p.distance(a + ab * t)
+}
+
+/// `H:MM:SS.cc`, or `MM:SS.cc` under an hour
+pub fn format_timecode(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    let (secs, centis) = (centis / 100, centis % 100);
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}.{centis:02}")
+    } else {
+        format!("{mins:02}:{secs:02}.{centis:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlay(corner: OverlayCorner) -> Overlay {
+        let mut overlay = Overlay {
+            settings: OverlaySettings {
+                enabled: true,
+                corner,
+                size: 0.1,
+                opacity: 1.0,
+                ..OverlaySettings::default()
+            },
+            lines: Vec::new(),
+        };
+        overlay.set_text(Some("Title"), Some(Duration::from_millis(3_723_450)), None);
+        overlay
+    }
+
+    #[test]
+    fn test_text_parts() {
+        let mut overlay = overlay(OverlayCorner::TopLeft);
+        assert_eq!(overlay.lines, ["Title", "1:02:03.45"]);
+        overlay.settings.title = false;
+        overlay.set_text(
+            Some("Title"),
+            Some(Duration::from_millis(61_500)),
+            Some(127.6),
+        );
+        assert_eq!(overlay.lines, ["01:01.50", "128 BPM"]);
+    }
+
+    #[test]
+    fn test_strokes_sit_in_the_corner() {
+        let rect = Rect::from_min_size(Pos2::new(100.0, 50.0), egui::vec2(400.0, 200.0));
+        let bounds = |corner| {
+            let strokes = overlay(corner).strokes(rect);
+            strokes
+                .iter()
+                .flatten()
+                .map(|&p| Rect::from_min_max(p, p))
+                .reduce(|a, b| a.union(b))
+                .unwrap()
+        };
+        // Caps 20 high, with a 20 margin
+        let top_left = bounds(OverlayCorner::TopLeft);
+        assert_eq!(top_left.min, Pos2::new(120.0, 70.0));
+        let bottom_right = bounds(OverlayCorner::BottomRight);
+        assert_eq!(bottom_right.max, Pos2::new(480.0, 230.0));
+        assert_eq!(bottom_right.height(), top_left.height());
+    }
+
+    #[test]
+    fn test_draw_blends_over_pixels() {
+        let (width, height) = (200, 100);
+        let mut pixels = vec![0; width * height * 4];
+        let overlay = overlay(OverlayCorner::TopLeft);
+        overlay.draw(&mut pixels, width, height, Color32::WHITE);
+        let lit = |x0: usize, x1: usize, y0: usize, y1: usize| {
+            (y0..y1).any(|y| (x0..x1).any(|x| pixels[(y * width + x) * 4] > 0))
+        };
+        // The T's bar, ten pixels down a frame 100 high
+        assert!(lit(10, 20, 9, 12));
+        assert!(!lit(100, 200, 50, 100));
+
+        let hidden = Overlay {
+            settings: OverlaySettings {
+                enabled: false,
+                ..overlay.settings
+            },
+            ..overlay
+        };
+        let mut blank = vec![0; width * height * 4];
+        hidden.draw(&mut blank, width, height, Color32::WHITE);
+        assert!(blank.iter().all(|&b| b == 0));
+    }
+}
@@ -0,0 +1,185 @@
+//! Persistence trail storage
+//!
+//! The afterglow is the last few frames' points drawn at decreasing alpha.
+//! Rather than storing an alpha per point and fading (and compacting) every
+//! point every frame, `PersistenceRing` writes points into a fixed ring that
+//! overwrites the oldest, and records only how many points each frame
+//! added. A frame's alpha follows from its age, so aging the trail costs
+//! nothing and nothing is allocated after construction.
+
+use std::collections::VecDeque;
+
+use eframe::egui::Pos2;
+
+/// Faded frames below this alpha are dropped
+const MIN_ALPHA: f32 = 0.01;
+
+/// One frame's run of points, newest frames at the back of the ring
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    len: usize,
+    /// Alpha when the frame was new
+    intensity: f32,
+}
+
+/// Fixed-capacity store of recent frames' points
+pub struct PersistenceRing {
+    points: Vec<Pos2>,
+    /// Where the next point is written
+    head: usize,
+    /// Points currently stored, at most `points.len()`
+    len: usize,
+    frames: VecDeque<Frame>,
+}
+
+impl PersistenceRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            points: vec![Pos2::ZERO; capacity.max(1)],
+            head: 0,
+            len: 0,
+            // Enough for a 0.99 decay to fade out without reallocating
+            frames: VecDeque::with_capacity(512),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+        self.frames.clear();
+    }
+
+    /// Age the stored frames by one and add a new frame. Frames faded below
+    /// the cutoff are dropped; when the ring is full the oldest points are
+    /// overwritten.
+    pub fn push_frame(
+        &mut self,
+        points: impl IntoIterator<Item = Pos2>,
+        intensity: f32,
+        decay: f32,
+    ) {
+        // After this frame is added the oldest is `frames.len()` frames old
+        while let Some(oldest) = self.frames.front() {
+            let age = self.frames.len() as i32;
+            if oldest.intensity * decay.powi(age) > MIN_ALPHA {
+                break;
+            }
+            self.len -= oldest.len;
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(Frame { len: 0, intensity });
+        let capacity = self.points.len();
+        for point in points {
+            if self.len == capacity {
+                // Frames that added nothing hold no points to give up
+                while self.frames.front().is_some_and(|f| f.len == 0) {
+                    self.frames.pop_front();
+                }
+                // At worst the new frame, which then fills the whole ring
+                if let Some(oldest) = self.frames.front_mut() {
+                    oldest.len -= 1;
+                }
+            } else {
+                self.len += 1;
+            }
+            self.points[self.head] = point;
+            self.head = (self.head + 1) % capacity;
+            if let Some(newest) = self.frames.back_mut() {
+                newest.len += 1;
+            }
+        }
+    }
+
+    /// Call `f` with each stored frame's points and alpha, newest frame
+    /// first. A frame that wraps around the end of the ring comes in two
+    /// calls, in order.
+    pub fn for_each_frame(&self, decay: f32, mut f: impl FnMut(&[Pos2], f32)) {
+        let capacity = self.points.len();
+        let mut end = self.head;
+        let mut alpha = 1.0;
+        for frame in self.frames.iter().rev() {
+            let frame_alpha = frame.intensity * alpha;
+            let start = (end + capacity - frame.len) % capacity;
+            if frame.len > 0 && frame_alpha > MIN_ALPHA {
+                if start < end {
+                    f(&self.points[start..end], frame_alpha);
+                } else {
+                    f(&self.points[start..], frame_alpha);
+                    f(&self.points[..end], frame_alpha);
+                }
+            }
+            end = start;
+            alpha *= decay;
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(value: f32, n: usize) -> impl Iterator<Item = Pos2> {
+        std::iter::repeat_n(Pos2::new(value, value), n)
+    }
+
+    fn collect(ring: &PersistenceRing, decay: f32) -> Vec<(Vec<Pos2>, f32)> {
+        let mut out: Vec<(Vec<Pos2>, f32)> = Vec::new();
+        ring.for_each_frame(decay, |points, alpha| match out.last_mut() {
+            Some((last, a)) if *a == alpha => last.extend_from_slice(points),
+            _ => out.push((points.to_vec(), alpha)),
+        });
+        out
+    }
+
+    #[test]
+    fn test_frames_fade_by_age_and_expire() {
+        let mut ring = PersistenceRing::new(100);
+        for i in 0..3 {
+            ring.push_frame(frame(i as f32, 10), 1.0, 0.5);
+        }
+        let frames = collect(&ring, 0.5);
+        assert_eq!(frames.len(), 3);
+        for (age, (points, alpha)) in frames.iter().enumerate() {
+            assert_eq!(*alpha, 0.5f32.powi(age as i32));
+            assert_eq!(points.len(), 10);
+            assert!(points.iter().all(|p| p.x == (2 - age) as f32));
+        }
+
+        // 0.5^7 is below the cutoff, so nothing older than 6 frames is kept
+        for i in 3..20 {
+            ring.push_frame(frame(i as f32, 10), 1.0, 0.5);
+        }
+        assert_eq!(collect(&ring, 0.5).len(), 7);
+        assert_eq!(ring.len(), 70);
+    }
+
+    #[test]
+    fn test_overwrites_oldest_when_full() {
+        let mut ring = PersistenceRing::new(25);
+        for i in 0..3 {
+            ring.push_frame(frame(i as f32, 10), 1.0, 0.9);
+        }
+        // 30 points pushed: the oldest frame lost five, and the newest wraps
+        let frames = collect(&ring, 0.9);
+        let lens: Vec<usize> = frames.iter().map(|(p, _)| p.len()).collect();
+        assert_eq!(lens, [10, 10, 5]);
+        assert!(frames[0].0.iter().all(|p| p.x == 2.0));
+        assert!(frames[2].0.iter().all(|p| p.x == 0.0));
+
+        // A frame larger than the ring keeps its newest points
+        ring.push_frame((0..40).map(|i| Pos2::new(i as f32, 0.0)), 1.0, 0.9);
+        let frames = collect(&ring, 0.9);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].0.len(), 25);
+        assert!(frames[0].0.iter().all(|p| p.x >= 15.0));
+
+        ring.clear();
+        assert!(collect(&ring, 0.9).is_empty());
+    }
+}
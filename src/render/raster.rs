@@ -0,0 +1,206 @@
+//! Offscreen software rasterizer
+//!
+//! The on-screen scope is drawn with egui shapes, which never become pixels
+//! we can get at. Frame outputs (shared memory, exports) need real pixels, so
+//! this module renders the same samples and settings into an RGBA8 buffer on
+//! the CPU.
+//!
+//! Beam energy is accumulated per pixel in an `f32` buffer and decays each
+//! frame by the persistence factor, which gives the same afterglow look as the
+//! widget without keeping a list of old points around.
+
+use eframe::egui::{Color32, Pos2, Rect, Vec2};
+
+use super::oscilloscope::{DisplayMode, OscilloscopeSettings};
+use super::overlay::FrameLayer;
+use super::transform::{decimate, point_budget, ScreenTransform};
+use crate::audio::XYSample;
+
+/// CPU rasterizer producing RGBA8 frames
+pub struct Rasterizer {
+    width: usize,
+    height: usize,
+    /// Accumulated beam energy per pixel (row-major)
+    energy: Vec<f32>,
+    /// Composited RGBA8 pixels (row-major, no padding)
+    pixels: Vec<u8>,
+    /// Pixel positions of the current samples; the allocation is reused
+    points: Vec<Pos2>,
+}
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let height = height.max(1);
+        Self {
+            width,
+            height,
+            energy: vec![0.0; width * height],
+            pixels: vec![0; width * height * 4],
+            points: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Change the output size (clears any accumulated persistence)
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width.max(1) != self.width || height.max(1) != self.height {
+            *self = Self::new(width, height);
+        }
+    }
+
+    /// Clear accumulated persistence
+    pub fn clear(&mut self) {
+        self.energy.fill(0.0);
+    }
+
+    /// Last rendered frame as tightly packed RGBA8
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Accumulated beam energy per pixel (row-major)
+    pub fn energy(&self) -> &[f32] {
+        &self.energy
+    }
+
+    /// Render one frame and return the RGBA8 pixels
+    pub fn render(&mut self, samples: &[XYSample], settings: &OscilloscopeSettings) -> &[u8] {
+        // Points mode has no persistence, matching the widget
+        let decay = match settings.display_mode {
+            DisplayMode::Points => 0.0,
+            _ => settings.persistence,
+        };
+        for e in &mut self.energy {
+            *e *= decay;
+        }
+        self.accumulate(samples, settings);
+        self.composite(settings.color, settings.background);
+        &self.pixels
+    }
+
+    /// Draw `layers` over the last rendered frame, in order, and return
+    /// the pixels
+    pub fn draw_layers(&mut self, layers: &[&dyn FrameLayer], color: Color32) -> &[u8] {
+        for layer in layers {
+            layer.draw(&mut self.pixels, self.width, self.height, color);
+        }
+        &self.pixels
+    }
+
+    /// Add the beam energy of one frame without any decay or compositing,
+    /// for exposures longer than a frame
+    pub fn accumulate(&mut self, samples: &[XYSample], settings: &OscilloscopeSettings) {
+        let rect =
+            Rect::from_min_size(Pos2::ZERO, Vec2::new(self.width as f32, self.height as f32));
+        let samples = settings.window(samples);
+        let mut points = std::mem::take(&mut self.points);
+        points.clear();
+        ScreenTransform::new(settings, rect).extend(samples, &mut points);
+        if settings.decimate {
+            decimate(&mut points, point_budget(rect));
+        }
+
+        let intensity = settings.intensity;
+        match settings.display_mode {
+            DisplayMode::Dots => {
+                for p in &points {
+                    self.deposit(p.x, p.y, intensity);
+                }
+            }
+            DisplayMode::Points => {
+                // The widget's points are discs of the line width's radius
+                let radius = settings.line_width.max(1.0);
+                for p in &points {
+                    self.deposit_disc(p.x, p.y, radius, intensity);
+                }
+            }
+            DisplayMode::Lines | DisplayMode::Gradient => {
+                // Same discontinuity rule as the widget: skip jumps longer
+                // than half the view width
+                let max_dist = self.width as f32 * 0.5;
+                for pair in points.windows(2) {
+                    let (x0, y0) = (pair[0].x, pair[0].y);
+                    let (x1, y1) = (pair[1].x, pair[1].y);
+                    let dist = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+                    if dist >= max_dist {
+                        continue;
+                    }
+                    let energy = if settings.display_mode == DisplayMode::Gradient {
+                        // Slower movement = brighter
+                        intensity * (1.0 - dist / max_dist).max(0.12)
+                    } else {
+                        intensity
+                    };
+                    self.draw_line(x0, y0, x1, y1, energy);
+                }
+            }
+        }
+
+        self.points = points;
+    }
+
+    /// Add energy to the pixel containing (x, y), ignoring off-screen points
+    #[inline]
+    fn deposit(&mut self, x: f32, y: f32, amount: f32) {
+        if x < 0.0 || y < 0.0 {
+            return;
+        }
+        let (px, py) = (x as usize, y as usize);
+        if px < self.width && py < self.height {
+            self.energy[py * self.width + px] += amount;
+        }
+    }
+
+    /// Add energy to every pixel whose center lies within `radius` of (x, y)
+    fn deposit_disc(&mut self, x: f32, y: f32, radius: f32, amount: f32) {
+        let reach = radius.ceil() as i32;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let (px, py) = (x.floor() + dx as f32, y.floor() + dy as f32);
+                let (cx, cy) = (px + 0.5 - x, py + 0.5 - y);
+                if cx * cx + cy * cy <= radius * radius {
+                    self.deposit(px, py, amount);
+                }
+            }
+        }
+    }
+
+    /// Draw a line by stepping one pixel at a time along its major axis
+    fn draw_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, energy: f32) {
+        let dx = x1 - x0;
+        let dy = y1 - y0;
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+        // Spread the energy along the segment so fast strokes look dimmer,
+        // like a real beam, but keep a floor so lines stay visible
+        let per_step = energy / (steps as f32).sqrt().max(1.0);
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            self.deposit(x0 + dx * t, y0 + dy * t, per_step);
+        }
+    }
+
+    /// Blend beam color over the background according to accumulated energy
+    fn composite(&mut self, color: Color32, background: Color32) {
+        let fg = [color.r() as f32, color.g() as f32, color.b() as f32];
+        let bg = [
+            background.r() as f32,
+            background.g() as f32,
+            background.b() as f32,
+        ];
+        for (px, &e) in self.pixels.chunks_exact_mut(4).zip(&self.energy) {
+            let a = e.min(1.0);
+            px[0] = (bg[0] + (fg[0] - bg[0]) * a) as u8;
+            px[1] = (bg[1] + (fg[1] - bg[1]) * a) as u8;
+            px[2] = (bg[2] + (fg[2] - bg[2]) * a) as u8;
+            px[3] = 255;
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Sample-to-screen transform
+//!
+//! DC offset, invert, zoom and the mapping into a screen rectangle are all a
+//! scale and an offset per channel, followed by an optional swap of the two
+//! axes. `ScreenTransform` folds the settings into those coefficients once
+//! per frame and then maps the whole sample window four samples at a time
+//! with 8-lane SIMD, since it runs on every drawn and persisted point.
+
+use eframe::egui::{Pos2, Rect};
+use wide::f32x8;
+
+use super::oscilloscope::OscilloscopeSettings;
+use crate::audio::XYSample;
+
+/// Samples per SIMD batch (two lanes each)
+const BATCH: usize = 4;
+
+/// Maps samples to screen points for one frame's settings and rectangle
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenTransform {
+    /// Per-lane scale for interleaved `x, y, x, y, ...` input
+    scale: f32x8,
+    /// Per-lane offset, same layout
+    offset: f32x8,
+    swap: bool,
+}
+
+impl ScreenTransform {
+    pub fn new(settings: &OscilloscopeSettings, rect: Rect) -> Self {
+        let sign = |invert: bool| if invert { -1.0 } else { 1.0 };
+        let (sx, sy) = (sign(settings.invert_x), sign(settings.invert_y));
+
+        // Screen pixels per signal unit; Y points down on screen
+        let kx = rect.width() / (2.0 * settings.zoom);
+        let ky = -rect.height() / (2.0 * settings.zoom);
+        let center = rect.center();
+
+        // Which screen axis each input channel ends up on
+        let ((kx_in, cx_in), (ky_in, cy_in)) = if settings.swap_xy {
+            ((ky, center.y), (kx, center.x))
+        } else {
+            ((kx, center.x), (ky, center.y))
+        };
+        let (mx, my) = (kx_in * sx, ky_in * sy);
+        let (offset_x, offset_y) = settings.offset();
+        let (bx, by) = (cx_in + mx * offset_x, cy_in + my * offset_y);
+
+        Self {
+            scale: f32x8::from([mx, my, mx, my, mx, my, mx, my]),
+            offset: f32x8::from([bx, by, bx, by, bx, by, bx, by]),
+            swap: settings.swap_xy,
+        }
+    }
+
+    /// Map one sample
+    pub fn apply(&self, sample: XYSample) -> Pos2 {
+        let scale = self.scale.as_array_ref();
+        let offset = self.offset.as_array_ref();
+        let a = sample.x * scale[0] + offset[0];
+        let b = sample.y * scale[1] + offset[1];
+        self.point(a, b)
+    }
+
+    /// Map `samples` and append the points to `out`
+    pub fn extend(&self, samples: &[XYSample], out: &mut Vec<Pos2>) {
+        out.reserve(samples.len());
+
+        let mut batches = samples.chunks_exact(BATCH);
+        // The swap is decided once, outside the loop, so both loops stay
+        // branch-free
+        if self.swap {
+            for batch in &mut batches {
+                let m = self.map_batch(batch);
+                out.extend_from_slice(&[
+                    Pos2::new(m[1], m[0]),
+                    Pos2::new(m[3], m[2]),
+                    Pos2::new(m[5], m[4]),
+                    Pos2::new(m[7], m[6]),
+                ]);
+            }
+        } else {
+            for batch in &mut batches {
+                let m = self.map_batch(batch);
+                out.extend_from_slice(&[
+                    Pos2::new(m[0], m[1]),
+                    Pos2::new(m[2], m[3]),
+                    Pos2::new(m[4], m[5]),
+                    Pos2::new(m[6], m[7]),
+                ]);
+            }
+        }
+        out.extend(batches.remainder().iter().map(|&s| self.apply(s)));
+    }
+
+    /// Scale and offset four interleaved samples at once
+    #[inline]
+    fn map_batch(&self, batch: &[XYSample]) -> [f32; 8] {
+        let input = f32x8::from([
+            batch[0].x, batch[0].y, batch[1].x, batch[1].y, batch[2].x, batch[2].y, batch[3].x,
+            batch[3].y,
+        ]);
+        input.mul_add(self.scale, self.offset).to_array()
+    }
+
+    #[inline]
+    fn point(&self, a: f32, b: f32) -> Pos2 {
+        if self.swap {
+            Pos2::new(b, a)
+        } else {
+            Pos2::new(a, b)
+        }
+    }
+}
+
+/// Most points worth drawing in `rect`. A trace crossing the display a few
+/// times per window never needs more; beyond that extra points only add
+/// draw calls and noise.
+pub fn point_budget(rect: Rect) -> usize {
+    ((rect.width() + rect.height()) * 4.0).max(256.0) as usize
+}
+
+/// Reduce `points` to at most `budget` by keeping only the extremes of each
+/// run of consecutive points: its leftmost, rightmost, highest and lowest
+/// point, in their original order. Peaks on either axis survive, so the
+/// figure keeps its outline.
+pub fn decimate(points: &mut Vec<Pos2>, budget: usize) {
+    const KEPT_PER_RUN: usize = 4;
+    if points.len() <= budget || budget < KEPT_PER_RUN {
+        return;
+    }
+    let run = points.len().div_ceil(budget / KEPT_PER_RUN);
+    let mut kept = 0;
+    for start in (0..points.len()).step_by(run) {
+        let end = (start + run).min(points.len());
+        let (mut left, mut right, mut top, mut bottom) = (start, start, start, start);
+        for (i, p) in points.iter().enumerate().take(end).skip(start) {
+            if p.x < points[left].x {
+                left = i;
+            }
+            if p.x > points[right].x {
+                right = i;
+            }
+            if p.y < points[top].y {
+                top = i;
+            }
+            if p.y > points[bottom].y {
+                bottom = i;
+            }
+        }
+
+        let mut extremes = [left, right, top, bottom];
+        extremes.sort_unstable();
+        let mut previous = None;
+        for i in extremes {
+            if previous != Some(i) {
+                // Never overtakes the reads: a run keeps at most as many
+                // points as it has
+                points[kept] = points[i];
+                kept += 1;
+                previous = Some(i);
+            }
+        }
+    }
+    points.truncate(kept);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::Vec2;
+
+    /// The straightforward per-sample path the transform replaces
+    fn scalar(settings: &OscilloscopeSettings, rect: Rect, sample: XYSample) -> Pos2 {
+        let (ux, uy) = settings.sample_to_unit(sample);
+        Pos2::new(
+            rect.left() + ux * rect.width(),
+            rect.bottom() - uy * rect.height(),
+        )
+    }
+
+    fn test_samples(n: usize) -> Vec<XYSample> {
+        (0..n)
+            .map(|i| {
+                let t = i as f32 * 0.01;
+                XYSample::new(t.sin() * 0.8, (t * 1.5).cos() * 0.6)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_scalar_mapping() {
+        let rect = Rect::from_min_size(Pos2::new(12.0, 30.0), Vec2::new(400.0, 250.0));
+        let samples = test_samples(23);
+        for &(swap_xy, invert_x, invert_y) in &[
+            (false, false, false),
+            (true, false, false),
+            (false, true, true),
+            (true, true, false),
+        ] {
+            let settings = OscilloscopeSettings {
+                swap_xy,
+                invert_x,
+                invert_y,
+                zoom: 0.6,
+                dc_offset_x: 0.2,
+                dc_offset_y: -0.1,
+                ..OscilloscopeSettings::default()
+            };
+            let transform = ScreenTransform::new(&settings, rect);
+            let mut points = Vec::new();
+            transform.extend(&samples, &mut points);
+            assert_eq!(points.len(), samples.len());
+            for (point, &sample) in points.iter().zip(&samples) {
+                let expected = scalar(&settings, rect, sample);
+                assert!(point.distance(expected) < 1e-3, "{point:?} vs {expected:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_decimate_keeps_extremes_in_order() {
+        let mut points: Vec<Pos2> = (0..1000)
+            .map(|i| {
+                let t = i as f32 / 1000.0 * std::f32::consts::TAU;
+                Pos2::new(t.cos() * 100.0, t.sin() * 100.0)
+            })
+            .collect();
+        let extremes = Rect::from_points(&points);
+
+        decimate(&mut points, 100);
+        assert!(points.len() <= 100);
+        assert_eq!(Rect::from_points(&points), extremes);
+
+        // Still walks around the circle the same way
+        let angle = |p: &Pos2| p.y.atan2(p.x).rem_euclid(std::f32::consts::TAU);
+        assert!(points.windows(2).all(|w| angle(&w[0]) <= angle(&w[1])));
+
+        let mut short = points[..10].to_vec();
+        decimate(&mut short, 100);
+        assert_eq!(short.len(), 10);
+    }
+}
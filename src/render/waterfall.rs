@@ -0,0 +1,262 @@
+//! 3D waterfall spectrogram
+//!
+//! Keeps a history of spectrum frames, each resampled onto log-spaced
+//! columns, and draws them as a perspective landscape: frequency runs left
+//! to right, level rises up, and time recedes into the distance. Rows are
+//! filled underneath and painted back to front, so nearer ridges hide the
+//! ones behind them. Dragging the view or the sliders turns it; a spin rate
+//! keeps it turning by itself.
+
+use std::collections::VecDeque;
+
+use eframe::egui::{self, Color32, Mesh, Pos2, Rect, Shape, Stroke, Vec2};
+
+use crate::analysis::Spectrum;
+
+/// Log-spaced columns per row
+const COLUMNS: usize = 160;
+
+/// Frequency range across the columns, in Hz
+const MIN_FREQ: f32 = 20.0;
+const MAX_FREQ: f32 = 20_000.0;
+
+/// Camera distance from the landscape's center, in half-widths
+const CAMERA_DISTANCE: f32 = 4.0;
+
+/// Height of a full-scale ridge, in half-widths
+const HEIGHT: f32 = 0.7;
+
+/// Drag sensitivity, degrees per point
+const DRAG_DEGREES: f32 = 0.4;
+
+/// Perspective waterfall of the spectrum's history
+pub struct Waterfall {
+    /// Rotation about the vertical axis, in degrees
+    pub yaw: f32,
+    /// Tilt towards the viewer, in degrees
+    pub pitch: f32,
+    /// Turning speed, degrees per second
+    pub spin: f32,
+    /// Rows kept
+    pub depth: usize,
+    pub min_db: f32,
+    pub max_db: f32,
+    /// Levels per column, 0 at `min_db` to 1 at `max_db`, newest first
+    rows: VecDeque<Vec<f32>>,
+    /// Spectrum frame count when the newest row was taken
+    frames: u64,
+}
+
+impl Default for Waterfall {
+    fn default() -> Self {
+        Self {
+            yaw: -25.0,
+            pitch: 30.0,
+            spin: 0.0,
+            depth: 64,
+            min_db: -100.0,
+            max_db: 0.0,
+            rows: VecDeque::new(),
+            frames: 0,
+        }
+    }
+}
+
+impl Waterfall {
+    /// Add the spectrum's newest frame as a row, if there is one since the
+    /// last call
+    pub fn push(&mut self, spectrum: &Spectrum, sample_rate: u32) {
+        if spectrum.frames < self.frames {
+            // The analyzer started over
+            self.rows.clear();
+        }
+        if spectrum.size == 0 || spectrum.frames == self.frames {
+            return;
+        }
+        self.frames = spectrum.frames;
+
+        let bin_hz = sample_rate as f32 / spectrum.size as f32;
+        let (x, y) = (&spectrum.x.average, &spectrum.y.average);
+        let level = |bin: usize| {
+            // Both channels' mean power
+            let power = (10f32.powf(x[bin] / 10.0) + 10f32.powf(y[bin] / 10.0)) / 2.0;
+            10.0 * power.max(1e-20).log10()
+        };
+        let row = (0..COLUMNS)
+            .map(|column| {
+                // Loudest bin in the column's stretch, so narrow peaks at the
+                // top, where a column spans many bins, don't vanish
+                let (low, high) = (column_freq(column), column_freq(column + 1));
+                let first = (low / bin_hz).round() as usize;
+                let last = ((high / bin_hz).round() as usize).max(first + 1);
+                let db = (first..last.min(x.len()))
+                    .map(level)
+                    .fold(f32::NEG_INFINITY, f32::max);
+                ((db - self.min_db) / (self.max_db - self.min_db)).clamp(0.0, 1.0)
+            })
+            .collect();
+        self.rows.push_front(row);
+        self.rows.truncate(self.depth.max(2));
+    }
+
+    /// Turn by `spin` over `dt` seconds
+    pub fn advance(&mut self, dt: f32) {
+        self.yaw = (self.yaw + self.spin * dt + 180.0).rem_euclid(360.0) - 180.0;
+    }
+
+    /// Allocate space and draw the landscape in `color`; dragging rotates it
+    pub fn show(&mut self, ui: &mut egui::Ui, size: Vec2, color: Color32) -> egui::Response {
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::drag());
+        let rect = response.rect;
+        painter.rect_filled(rect, 4.0, Color32::from_gray(20));
+
+        let drag = response.drag_delta();
+        self.yaw = (self.yaw + drag.x * DRAG_DEGREES + 180.0).rem_euclid(360.0) - 180.0;
+        self.pitch = (self.pitch + drag.y * DRAG_DEGREES).clamp(-10.0, 89.0);
+
+        let camera = Camera::new(self.yaw, self.pitch, rect);
+        let count = self.rows.len();
+        if count < 2 {
+            return response;
+        }
+        // Row `i` sits at depth z, the newest at the front
+        let row_z = |i: usize| 2.0 * i as f32 / (count - 1) as f32 - 1.0;
+
+        let mut order: Vec<usize> = (0..count).collect();
+        order.sort_by(|&a, &b| {
+            let (da, db) = (camera.depth(row_z(a)), camera.depth(row_z(b)));
+            db.total_cmp(&da)
+        });
+
+        let fill = Color32::from_gray(20);
+        for i in order {
+            let z = row_z(i);
+            let x = |column: usize| 2.0 * column as f32 / (COLUMNS - 1) as f32 - 1.0;
+            let top: Vec<Pos2> = self.rows[i]
+                .iter()
+                .enumerate()
+                .map(|(column, &level)| camera.project([x(column), level * HEIGHT, z]))
+                .collect();
+            let base: Vec<Pos2> = (0..COLUMNS)
+                .map(|column| camera.project([x(column), 0.0, z]))
+                .collect();
+
+            // Fill under the ridge to hide what's behind it
+            let mut mesh = Mesh::default();
+            for (t, b) in top.iter().zip(&base) {
+                mesh.colored_vertex(*b, fill);
+                mesh.colored_vertex(*t, fill);
+            }
+            for column in 0..COLUMNS as u32 - 1 {
+                let v = column * 2;
+                mesh.add_triangle(v, v + 1, v + 3);
+                mesh.add_triangle(v, v + 3, v + 2);
+            }
+            painter.add(Shape::mesh(mesh));
+
+            // Older rows fade into the distance
+            let age = i as f32 / (count - 1) as f32;
+            let stroke = Stroke::new(1.2, color.gamma_multiply(1.0 - 0.8 * age));
+            painter.add(Shape::line(top, stroke));
+        }
+        response
+    }
+}
+
+/// Frequency at the left edge of `column`
+fn column_freq(column: usize) -> f32 {
+    MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(column as f32 / COLUMNS as f32)
+}
+
+/// Rotation and perspective onto the view
+struct Camera {
+    /// Sines and cosines of yaw and pitch
+    yaw: (f32, f32),
+    pitch: (f32, f32),
+    center: Pos2,
+    scale: f32,
+}
+
+impl Camera {
+    fn new(yaw: f32, pitch: f32, rect: Rect) -> Self {
+        Self {
+            yaw: yaw.to_radians().sin_cos(),
+            pitch: pitch.to_radians().sin_cos(),
+            center: rect.center(),
+            scale: rect.width().min(rect.height() * 1.6) * 0.42,
+        }
+    }
+
+    /// Turn a point about the vertical axis, then tilt the far side up;
+    /// Z grows away from the viewer
+    fn rotate(&self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        let ((sy, cy), (sp, cp)) = (self.yaw, self.pitch);
+        let (x, z) = (x * cy - z * sy, x * sy + z * cy);
+        let (y, z) = (y * cp + z * sp, z * cp - y * sp);
+        [x, y, z]
+    }
+
+    /// Distance from the viewer of the row at depth `z`
+    fn depth(&self, z: f32) -> f32 {
+        self.rotate([0.0, 0.0, z])[2]
+    }
+
+    fn project(&self, p: [f32; 3]) -> Pos2 {
+        let [x, y, z] = self.rotate(p);
+        // Centered on the landscape's middle height
+        let y = y - HEIGHT * 0.5 * self.pitch.1;
+        let perspective = CAMERA_DISTANCE / (CAMERA_DISTANCE + z).max(0.1);
+        self.center + egui::vec2(x, -y) * perspective * self.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::SpectrumTrace;
+
+    fn spectrum(frames: u64, peak_bin: usize) -> Spectrum {
+        let mut levels = vec![-120.0; 2049];
+        levels[peak_bin] = 0.0;
+        let trace = SpectrumTrace {
+            average: levels,
+            ..SpectrumTrace::default()
+        };
+        Spectrum {
+            size: 4096,
+            hop: 2048,
+            frames,
+            x: trace.clone(),
+            y: trace,
+            ..Spectrum::default()
+        }
+    }
+
+    #[test]
+    fn test_rows_take_new_frames_only() {
+        let mut waterfall = Waterfall {
+            depth: 3,
+            ..Waterfall::default()
+        };
+        // 1 kHz at 48 kHz / 4096
+        let bin = (1000.0f32 / (48_000.0 / 4096.0)).round() as usize;
+        waterfall.push(&spectrum(1, bin), 48_000);
+        waterfall.push(&spectrum(1, bin), 48_000);
+        assert_eq!(waterfall.rows.len(), 1);
+
+        // The peak lands in the column holding 1 kHz, at full height
+        let row = &waterfall.rows[0];
+        let column = (0..COLUMNS).find(|&c| column_freq(c + 1) > 1000.0).unwrap();
+        assert!((row[column] - 1.0).abs() < 0.01, "{row:?}");
+        assert_eq!(row.iter().filter(|&&level| level > 0.5).count(), 1);
+
+        for frames in 2..6 {
+            waterfall.push(&spectrum(frames, bin), 48_000);
+        }
+        assert_eq!(waterfall.rows.len(), 3);
+
+        // A restarted analyzer clears the history
+        waterfall.push(&spectrum(1, bin), 48_000);
+        assert_eq!(waterfall.rows.len(), 1);
+    }
+}
@@ -1,19 +1,221 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+use crate::analysis::{Ballistics, SilenceAction, SpectrumSettings};
+use crate::audio::{PlaybackState, SampleBuffer, TriggerSource};
+use crate::generator::{
+    CalibrationParams, DemoParams, FunctionParams, GeneratorKind, ModelParams, NoiseParams,
+    ShapeParams, SvgParams, SweepParams, TextParams,
+};
+use crate::grid::Pairing;
+use crate::i18n::{self, Language};
+use crate::keybindings::{KeyBinding, Keymap};
+use crate::layout::Workspaces;
 use crate::midi::MidiMapping;
-use crate::render::{ColorTheme, DisplayMode};
-use crate::ScopeApp;
+use crate::modulation::ModRoute;
+use crate::obs::ObsRemote;
+use crate::osc::OscListener;
+use crate::output;
+use crate::presets::{Preset, PresetError};
+use crate::projector::Rotation;
+use crate::reactivity::{BeatRule, Reactivity};
+use crate::render::{BrandingSettings, ColorTheme, DisplayMode, OverlaySettings};
+use crate::{InputMode, ScopeApp, SpectrumView};
 
-/// Returns the path to the settings file: `~/.config/scope-rs/settings.json`
-fn settings_path() -> PathBuf {
+/// Errors that can occur while importing or exporting a settings bundle
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Settings file error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid settings file: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    PresetFailed(#[from] PresetError),
+}
+
+/// Returns the app's config directory: `~/.config/scope-rs/`
+pub fn config_dir() -> PathBuf {
     let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
     path.push("scope-rs");
-    path.push("settings.json");
     path
 }
 
+/// Returns the path to the settings file: `~/.config/scope-rs/settings.json`
+fn settings_path() -> PathBuf {
+    config_dir().join("settings.json")
+}
+
+/// Number of settings backups kept, one per session
+const BACKUP_COUNT: usize = 5;
+
+/// Path of the `n`th newest backup: `~/.config/scope-rs/backups/settings.1.json`
+fn backup_path(n: usize) -> PathBuf {
+    config_dir()
+        .join("backups")
+        .join(format!("settings.{}.json", n))
+}
+
+/// Set once this session's backup has been taken
+static BACKED_UP: AtomicBool = AtomicBool::new(false);
+
+/// Write a file so that a crash or power loss leaves either the old or the
+/// new contents, never a half-written file: write a temporary file next to
+/// it, flush it to disk, then rename it over the original.
+pub fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    let mut file = std::fs::File::create(&temp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)
+}
+
+/// Shift the backups along and copy the current settings file in as the
+/// newest. A file that doesn't parse isn't worth keeping, so it is skipped.
+fn rotate_backups() -> std::io::Result<()> {
+    let current = settings_path();
+    let Ok(contents) = std::fs::read_to_string(&current) else {
+        return Ok(());
+    };
+    if serde_json::from_str::<AppSettings>(&contents).is_err() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(config_dir().join("backups"))?;
+    for n in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(n);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(n + 1))?;
+        }
+    }
+    std::fs::copy(&current, backup_path(1))?;
+    Ok(())
+}
+
+/// A settings backup that can be restored
+#[derive(Clone, Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// When the backup was written, for display
+    pub saved: String,
+}
+
+/// Existing backups, newest first
+pub fn backups() -> Vec<Backup> {
+    (1..=BACKUP_COUNT)
+        .map(backup_path)
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            let saved = jiff::Timestamp::try_from(modified)
+                .map(|t| {
+                    t.to_zoned(jiff::tz::TimeZone::system())
+                        .strftime("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            Some(Backup { path, saved })
+        })
+        .collect()
+}
+
+/// The settings file existed but couldn't be read; the app started with
+/// defaults and can offer these backups instead
+#[derive(Clone, Debug)]
+pub struct SettingsRecovery {
+    pub error: String,
+    /// Where the unreadable file was moved
+    pub moved_to: Option<PathBuf>,
+    pub backups: Vec<Backup>,
+}
+
+/// Window state to start in
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub enum StartupWindow {
+    #[default]
+    Normal,
+    Minimized,
+    Fullscreen,
+}
+
+impl StartupWindow {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Minimized => "Minimized",
+            Self::Fullscreen => "Fullscreen",
+        }
+    }
+
+    pub fn all() -> &'static [StartupWindow] {
+        &[Self::Normal, Self::Minimized, Self::Fullscreen]
+    }
+}
+
+/// Light or dark look for the panels and controls, independent of the
+/// scope's own `ColorTheme`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UiTheme {
+    /// Follow the operating system
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl UiTheme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Dark => "Dark",
+            Self::Light => "Light",
+        }
+    }
+
+    pub fn all() -> &'static [UiTheme] {
+        &[Self::System, Self::Dark, Self::Light]
+    }
+
+    pub fn preference(&self) -> eframe::egui::ThemePreference {
+        match self {
+            Self::System => eframe::egui::ThemePreference::System,
+            Self::Dark => eframe::egui::ThemePreference::Dark,
+            Self::Light => eframe::egui::ThemePreference::Light,
+        }
+    }
+}
+
+/// What to do when the app starts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartupOptions {
+    /// Start capturing from the last used input device
+    pub auto_capture: bool,
+    /// Load the last played audio file
+    pub reopen_file: bool,
+    /// Restore window size/position, the settings panel and the input mode
+    pub restore_window: bool,
+    pub window: StartupWindow,
+}
+
+impl Default for StartupOptions {
+    fn default() -> Self {
+        Self {
+            auto_capture: false,
+            reopen_file: false,
+            restore_window: true,
+            window: StartupWindow::Normal,
+        }
+    }
+}
+
 /// Persisted application settings.
 ///
 /// Serialized as JSON to the platform config directory.
@@ -29,6 +231,9 @@ pub struct AppSettings {
     pub intensity: f32,
     pub persistence: f32,
     pub zoom: f32,
+    pub sample_count: usize,
+    pub decimate: bool,
+    pub steady_pacing: bool,
 
     // Channel controls
     pub swap_xy: bool,
@@ -36,20 +241,158 @@ pub struct AppSettings {
     pub invert_y: bool,
     pub dc_offset_x: f32,
     pub dc_offset_y: f32,
+    pub auto_center: bool,
+
+    // Trigger
+    pub trigger_enabled: bool,
+    pub trigger_level: f32,
+    pub trigger_hysteresis: f32,
+    /// Seconds
+    pub trigger_holdoff: f32,
+    pub trigger_source: TriggerSource,
+    pub midi_trigger_note: Option<u8>,
+    pub osc_enabled: bool,
+    pub osc_port: u16,
+    pub osc_address: String,
+
+    // Spectrum
+    pub spectrum: SpectrumSettings,
+    pub spectrum_view: SpectrumView,
+    pub rta_ballistics: Ballistics,
+    /// Degrees
+    pub waterfall_yaw: f32,
+    pub waterfall_pitch: f32,
+    /// Degrees per second
+    pub waterfall_spin: f32,
 
     // Audio input
     pub gain: f32,
+    pub input_device: Option<String>,
+    /// Samples kept for display, one of `SampleBuffer::CAPACITIES`
+    pub buffer_size: usize,
 
     // File playback
+    pub last_file: Option<PathBuf>,
+    /// Playback position in the last file (0.0 - 1.0)
+    pub last_file_position: f32,
     pub volume: f32,
     pub speed: f32,
     pub loop_enabled: bool,
+    pub av_sync: bool,
+    pub display_offset_ms: f32,
+
+    // Generator
+    pub generator_kind: GeneratorKind,
+    pub generator_shape: ShapeParams,
+    pub generator_function: FunctionParams,
+    pub generator_sweep: SweepParams,
+    pub generator_noise: NoiseParams,
+    pub generator_text: TextParams,
+    pub generator_svg: SvgParams,
+    pub generator_model: ModelParams,
+    pub demo: DemoParams,
+    pub silence_action: SilenceAction,
+    pub silence_threshold_db: f32,
+    pub silence_delay: f32,
+    pub calibration: CalibrationParams,
+    pub generator_volume: f32,
+    pub generator_audio_output: bool,
+    pub generator_output_device: Option<String>,
+
+    // Presets
+    /// Preset selected in the presets list
+    pub active_preset: Option<String>,
+    pub preset_morph_seconds: f32,
+    pub source_profiles_enabled: bool,
+    pub source_profiles: BTreeMap<String, Preset>,
+    pub source_profile_active: Option<String>,
 
     // MIDI
     pub midi_mappings: Vec<MidiMapping>,
 
+    // Modulation
+    pub modulation_enabled: bool,
+    pub modulation_routes: Vec<ModRoute>,
+
+    // Beat-synced actions
+    pub reactivity_enabled: bool,
+    pub beats_per_bar: u32,
+    pub pulse_depth: f32,
+    pub beat_rules: Vec<BeatRule>,
+
+    // Multichannel grid
+    pub grid_enabled: bool,
+    pub grid_pairing: Pairing,
+    pub grid_reference: usize,
+
+    // Keyboard
+    pub keybindings: Keymap,
+    pub global_hotkeys_enabled: bool,
+    #[serde(deserialize_with = "crate::keybindings::deserialize_bindings")]
+    pub global_hotkeys: Vec<KeyBinding>,
+
+    // Frame output
+    pub shm_output_enabled: bool,
+    pub shm_output_name: String,
+    pub shm_output_width: u32,
+    pub shm_output_height: u32,
+    pub shm_output_clean_feed: bool,
+
+    // Projector
+    pub projector_open: bool,
+    pub projector_display: String,
+    pub projector_width: u32,
+    pub projector_height: u32,
+    pub projector_rotation: Rotation,
+    pub projector_clean_feed: bool,
+
+    // Exports
+    pub export_directory: Option<PathBuf>,
+    pub export_template: String,
+    pub sequence_fps: u32,
+    pub sequence_width: usize,
+    pub sequence_height: usize,
+    pub exposure_format: output::ExposureFormat,
+    pub exposure_width: usize,
+    pub exposure_height: usize,
+    pub record_fps: u32,
+    pub record_width: usize,
+    pub record_height: usize,
+    pub record_audio: bool,
+    pub record_clean_feed: bool,
+    pub ffmpeg_path: String,
+    pub obs_enabled: bool,
+    pub obs_host: String,
+    pub obs_port: u16,
+    pub obs_password: String,
+    pub obs_follow_playback: bool,
+    pub obs_scene_per_preset: bool,
+    pub tray_enabled: bool,
+    pub close_to_tray: bool,
+    pub overlay: OverlaySettings,
+    pub branding: BrandingSettings,
+
     // Window
     pub show_settings: bool,
+    pub show_perf_overlay: bool,
+    /// Phase and amplitude ratio of a two-sine ellipse over the scope
+    pub show_phase_readout: bool,
+    pub workspaces: Workspaces,
+    pub ui_scale: f32,
+    pub font_size: f32,
+    pub ui_theme: UiTheme,
+    /// Frame rate cap while something is playing; 0 follows the display
+    pub target_fps: u32,
+    pub vsync: bool,
+    /// Drop to a few frames per second when no source is running
+    pub idle_throttle: bool,
+    /// Lower render quality while frames run over budget
+    pub auto_quality: bool,
+    pub language: Language,
+    pub input_mode: InputMode,
+    pub window_size: Option<[f32; 2]>,
+    pub window_pos: Option<[f32; 2]>,
+    pub startup: StartupOptions,
 }
 
 impl Default for AppSettings {
@@ -61,63 +404,190 @@ impl Default for AppSettings {
             intensity: 1.0,
             persistence: 0.85,
             zoom: 1.0,
+            sample_count: SampleBuffer::DEFAULT_CAPACITY,
+            decimate: true,
+            steady_pacing: true,
 
             swap_xy: false,
             invert_x: false,
             invert_y: false,
             dc_offset_x: 0.0,
             dc_offset_y: 0.0,
+            auto_center: false,
+
+            trigger_enabled: false,
+            trigger_level: 0.0,
+            trigger_hysteresis: 0.05,
+            trigger_holdoff: 0.0,
+            trigger_source: TriggerSource::Signal,
+            midi_trigger_note: None,
+            osc_enabled: false,
+            osc_port: OscListener::DEFAULT_PORT,
+            osc_address: OscListener::DEFAULT_ADDRESS.to_string(),
+            spectrum: SpectrumSettings::default(),
+            spectrum_view: SpectrumView::Lines,
+            rta_ballistics: Ballistics::Fast,
+            waterfall_yaw: -25.0,
+            waterfall_pitch: 30.0,
+            waterfall_spin: 0.0,
 
             gain: 1.0,
+            input_device: None,
+            buffer_size: SampleBuffer::DEFAULT_CAPACITY,
 
+            last_file: None,
+            last_file_position: 0.0,
             volume: 1.0,
             speed: 1.0,
             loop_enabled: false,
+            av_sync: true,
+            display_offset_ms: 0.0,
+
+            generator_kind: GeneratorKind::default(),
+            generator_shape: ShapeParams::default(),
+            generator_function: FunctionParams::default(),
+            generator_sweep: SweepParams::default(),
+            generator_noise: NoiseParams::default(),
+            generator_text: TextParams::default(),
+            generator_svg: SvgParams::default(),
+            generator_model: ModelParams::default(),
+            demo: DemoParams::default(),
+            silence_action: SilenceAction::Off,
+            silence_threshold_db: -50.0,
+            silence_delay: 3.0,
+            calibration: CalibrationParams::default(),
+            generator_volume: 0.5,
+            generator_audio_output: false,
+            generator_output_device: None,
+
+            active_preset: None,
+            preset_morph_seconds: 4.0,
+            source_profiles_enabled: false,
+            source_profiles: BTreeMap::new(),
+            source_profile_active: None,
 
             midi_mappings: Vec::new(),
+            modulation_enabled: false,
+            modulation_routes: Vec::new(),
+            reactivity_enabled: false,
+            beats_per_bar: 4,
+            pulse_depth: 0.5,
+            beat_rules: Reactivity::default().rules,
+            grid_enabled: true,
+            grid_pairing: Pairing::Pairs,
+            grid_reference: 0,
+
+            keybindings: Keymap::default(),
+            global_hotkeys_enabled: false,
+            global_hotkeys: Vec::new(),
+
+            shm_output_enabled: false,
+            shm_output_name: "scope-rs-frames".to_string(),
+            shm_output_width: 512,
+            shm_output_height: 512,
+            shm_output_clean_feed: false,
+
+            projector_open: false,
+            projector_display: String::new(),
+            projector_width: 1920,
+            projector_height: 1080,
+            projector_rotation: Rotation::None,
+            projector_clean_feed: false,
+
+            export_directory: None,
+            export_template: output::DEFAULT_TEMPLATE.to_string(),
+            sequence_fps: 30,
+            sequence_width: 1920,
+            sequence_height: 1080,
+            exposure_format: output::ExposureFormat::Png16,
+            exposure_width: 4096,
+            exposure_height: 4096,
+            record_fps: 60,
+            record_width: 1920,
+            record_height: 1080,
+            record_audio: true,
+            record_clean_feed: false,
+            ffmpeg_path: output::DEFAULT_FFMPEG.to_string(),
+            obs_enabled: false,
+            obs_host: ObsRemote::DEFAULT_HOST.to_string(),
+            obs_port: ObsRemote::DEFAULT_PORT,
+            obs_password: String::new(),
+            obs_follow_playback: true,
+            obs_scene_per_preset: false,
+            tray_enabled: false,
+            close_to_tray: false,
+            overlay: OverlaySettings::default(),
+            branding: BrandingSettings::default(),
 
             show_settings: false,
+            show_perf_overlay: false,
+            show_phase_readout: false,
+            workspaces: Workspaces::default(),
+            ui_scale: 1.0,
+            font_size: 12.5,
+            ui_theme: UiTheme::default(),
+            target_fps: 0,
+            vsync: true,
+            idle_throttle: true,
+            auto_quality: false,
+            language: Language::default(),
+            input_mode: InputMode::default(),
+            window_size: None,
+            window_pos: None,
+            startup: StartupOptions::default(),
         }
     }
 }
 
 impl AppSettings {
     /// Load settings from disk, falling back to defaults on any error.
-    pub fn load() -> Self {
+    ///
+    /// A file that exists but can't be parsed is moved aside rather than
+    /// overwritten, and the returned recovery lists backups to offer.
+    pub fn load() -> (Self, Option<SettingsRecovery>) {
         let path = settings_path();
         match std::fs::read_to_string(&path) {
             Ok(contents) => match serde_json::from_str(&contents) {
                 Ok(settings) => {
                     log::info!("Loaded settings from {}", path.display());
-                    settings
+                    (settings, None)
                 }
                 Err(e) => {
-                    log::warn!("Failed to parse settings ({}), using defaults", e);
-                    Self::default()
+                    log::error!("Failed to parse settings ({}), using defaults", e);
+                    let corrupt = path.with_extension("corrupt.json");
+                    let moved_to = match std::fs::rename(&path, &corrupt) {
+                        Ok(()) => Some(corrupt),
+                        Err(e) => {
+                            log::warn!("Failed to move unreadable settings aside: {}", e);
+                            None
+                        }
+                    };
+                    let recovery = SettingsRecovery {
+                        error: e.to_string(),
+                        moved_to,
+                        backups: backups(),
+                    };
+                    (Self::default(), Some(recovery))
                 }
             },
             Err(e) => {
                 log::info!("No settings file found ({}), using defaults", e);
-                Self::default()
+                (Self::default(), None)
             }
         }
     }
 
+    /// Read settings from a backup
+    pub fn load_backup(backup: &Backup) -> Result<Self, SettingsError> {
+        let settings = serde_json::from_str(&std::fs::read_to_string(&backup.path)?)?;
+        log::info!("Restored settings from {}", backup.path.display());
+        Ok(settings)
+    }
+
     /// Save settings to disk as pretty JSON.
     pub fn save(&self) {
-        let path = settings_path();
-        if let Some(parent) = path.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                log::warn!("Failed to create config directory: {}", e);
-                return;
-            }
-        }
         match serde_json::to_string_pretty(self) {
-            Ok(json) => {
-                if let Err(e) = std::fs::write(&path, json) {
-                    log::warn!("Failed to write settings: {}", e);
-                }
-            }
+            Ok(json) => write_settings(&json),
             Err(e) => {
                 log::warn!("Failed to serialize settings: {}", e);
             }
@@ -133,25 +603,155 @@ impl AppSettings {
             intensity: app.oscilloscope.settings.intensity,
             persistence: app.oscilloscope.settings.persistence,
             zoom: app.oscilloscope.settings.zoom,
+            sample_count: app.oscilloscope.settings.sample_count,
+            decimate: app.oscilloscope.settings.decimate,
+            steady_pacing: app.oscilloscope.settings.steady_pacing,
 
             swap_xy: app.oscilloscope.settings.swap_xy,
             invert_x: app.oscilloscope.settings.invert_x,
             invert_y: app.oscilloscope.settings.invert_y,
             dc_offset_x: app.oscilloscope.settings.dc_offset_x,
             dc_offset_y: app.oscilloscope.settings.dc_offset_y,
+            auto_center: app.oscilloscope.settings.auto_center,
+
+            trigger_enabled: app.trigger.enabled,
+            trigger_level: app.trigger.level,
+            trigger_hysteresis: app.trigger.hysteresis,
+            trigger_holdoff: app.trigger.holdoff,
+            trigger_source: app.trigger.source,
+            midi_trigger_note: app.midi.trigger_note(),
+            osc_enabled: app.osc.is_running(),
+            osc_port: app.osc.port,
+            osc_address: app.osc.address.clone(),
+            spectrum: app.spectrum,
+            spectrum_view: app.spectrum_view,
+            rta_ballistics: app.rta_ballistics,
+            waterfall_yaw: app.waterfall.yaw,
+            waterfall_pitch: app.waterfall.pitch,
+            waterfall_spin: app.waterfall.spin,
 
             gain: app.audio.gain,
+            input_device: app.audio.devices.get(app.audio.selected_device).cloned(),
+            buffer_size: app.consumer.capacity(),
 
+            last_file: app.file_player.info.as_ref().map(|i| i.path.clone()),
+            last_file_position: app.file_player.position_fraction(),
             volume: app.file_player.volume,
             speed: app.file_player.speed,
             loop_enabled: app.file_player.loop_playback,
+            av_sync: app.file_player.av_sync,
+            display_offset_ms: app.file_player.display_offset_ms,
+
+            generator_kind: app.generator_kind,
+            generator_shape: app.shape.clone(),
+            generator_function: app.function.clone(),
+            generator_sweep: app.sweep.clone(),
+            generator_noise: app.noise.clone(),
+            generator_text: app.text.clone(),
+            generator_svg: app.svg.clone(),
+            generator_model: app.model.clone(),
+            demo: app.demo.clone(),
+            silence_action: app.silence.action,
+            silence_threshold_db: app.silence.threshold_db,
+            silence_delay: app.silence.delay,
+            calibration: app.calibration.clone(),
+            generator_volume: app.generator.volume,
+            generator_audio_output: app.generator.audio_output,
+            generator_output_device: app.generator.output_device().map(str::to_string),
+
+            active_preset: app.presets.selected_name().map(str::to_string),
+            preset_morph_seconds: app.presets.morph_seconds,
+            source_profiles_enabled: app.profiles.enabled,
+            source_profiles: app.profiles.snapshot(Preset::from_app(app)),
+            source_profile_active: app.profiles.active.clone(),
 
             midi_mappings: app.midi.mappings.clone(),
+            modulation_enabled: app.modulation.enabled,
+            modulation_routes: app.modulation.routes.clone(),
+            reactivity_enabled: app.reactivity.enabled,
+            beats_per_bar: app.reactivity.beats_per_bar,
+            pulse_depth: app.reactivity.pulse_depth,
+            beat_rules: app.reactivity.rules.clone(),
+            grid_enabled: app.scope_grid.enabled,
+            grid_pairing: app.scope_grid.pairing,
+            grid_reference: app.scope_grid.reference,
+
+            keybindings: app.keymap.clone(),
+            global_hotkeys_enabled: app.hotkeys.is_enabled(),
+            global_hotkeys: app.hotkeys.bindings().to_vec(),
+
+            shm_output_enabled: app.frame_output.enabled,
+            shm_output_name: app.frame_output.name.clone(),
+            shm_output_width: app.frame_output.width,
+            shm_output_height: app.frame_output.height,
+            shm_output_clean_feed: app.frame_output.clean_feed,
+
+            projector_open: app.projector.is_open(),
+            projector_display: app.projector.display.clone(),
+            projector_width: app.projector.width,
+            projector_height: app.projector.height,
+            projector_rotation: app.projector.rotation,
+            projector_clean_feed: app.projector.clean_feed,
+
+            export_directory: app.export_naming.directory.clone(),
+            export_template: app.export_naming.template.clone(),
+            sequence_fps: app.sequence.fps,
+            sequence_width: app.sequence.width,
+            sequence_height: app.sequence.height,
+            exposure_format: app.exposure.format,
+            exposure_width: app.exposure.width,
+            exposure_height: app.exposure.height,
+            record_fps: app.record_params.fps,
+            record_width: app.record_params.width,
+            record_height: app.record_params.height,
+            record_audio: app.record_params.audio,
+            record_clean_feed: app.record_params.clean_feed,
+            ffmpeg_path: app.record_params.ffmpeg.clone(),
+            obs_enabled: app.obs.is_enabled(),
+            obs_host: app.obs.host.clone(),
+            obs_port: app.obs.port,
+            obs_password: app.obs.password.clone(),
+            obs_follow_playback: app.obs.follow_playback,
+            obs_scene_per_preset: app.obs.scene_per_preset,
+            tray_enabled: app.tray.is_running(),
+            close_to_tray: app.tray.close_to_tray,
+            overlay: app.overlay.settings,
+            branding: app.branding.settings.clone(),
 
             show_settings: app.show_settings,
+            show_perf_overlay: app.show_perf,
+            show_phase_readout: app.show_phase,
+            workspaces: app.workspaces.clone(),
+            ui_scale: app.ui_scale,
+            font_size: app.font_size,
+            ui_theme: app.ui_theme,
+            target_fps: app.target_fps,
+            vsync: app.vsync,
+            idle_throttle: app.idle_throttle,
+            auto_quality: app.auto_quality,
+            language: i18n::language(),
+            input_mode: app.input_mode,
+            window_size: app.window_size,
+            window_pos: app.window_pos,
+            startup: app.startup.clone(),
         }
     }
 
+    /// Drop the saved session (open file, device, window, mode, selected
+    /// preset) but keep every preference
+    pub fn forget_session(&mut self) {
+        let defaults = Self::default();
+        self.input_device = defaults.input_device;
+        self.generator_output_device = defaults.generator_output_device;
+        self.last_file = defaults.last_file;
+        self.last_file_position = defaults.last_file_position;
+        self.active_preset = defaults.active_preset;
+        self.input_mode = defaults.input_mode;
+        self.show_settings = defaults.show_settings;
+        self.window_size = defaults.window_size;
+        self.window_pos = defaults.window_pos;
+    }
+
     /// Apply loaded settings to the running application.
     pub fn apply(&self, app: &mut ScopeApp) {
         app.oscilloscope.settings.display_mode = self.display_mode;
@@ -160,22 +760,371 @@ impl AppSettings {
         app.oscilloscope.settings.intensity = self.intensity;
         app.oscilloscope.settings.persistence = self.persistence;
         app.oscilloscope.settings.zoom = self.zoom;
+        app.oscilloscope.settings.sample_count = self.sample_count;
+        app.oscilloscope.settings.decimate = self.decimate;
+        app.oscilloscope.settings.steady_pacing = self.steady_pacing;
 
         app.oscilloscope.settings.swap_xy = self.swap_xy;
         app.oscilloscope.settings.invert_x = self.invert_x;
         app.oscilloscope.settings.invert_y = self.invert_y;
         app.oscilloscope.settings.dc_offset_x = self.dc_offset_x;
         app.oscilloscope.settings.dc_offset_y = self.dc_offset_y;
+        app.oscilloscope.settings.auto_center = self.auto_center;
+
+        app.trigger.enabled = self.trigger_enabled;
+        app.trigger.level = self.trigger_level;
+        app.trigger.hysteresis = self.trigger_hysteresis;
+        app.trigger.holdoff = self.trigger_holdoff;
+        app.trigger.source = self.trigger_source;
+        app.midi.set_trigger_note(self.midi_trigger_note);
+        app.osc.port = self.osc_port;
+        app.osc.address = self.osc_address.clone();
+        app.osc.set_enabled(self.osc_enabled);
+        app.spectrum = self.spectrum;
+        app.spectrum_view = self.spectrum_view;
+        app.rta_ballistics = self.rta_ballistics;
+        app.waterfall.yaw = self.waterfall_yaw;
+        app.waterfall.pitch = self.waterfall_pitch;
+        app.waterfall.spin = self.waterfall_spin;
 
         app.audio.gain = self.gain;
         app.audio.sync_gain();
+        if let Some(index) = self
+            .input_device
+            .as_ref()
+            .and_then(|name| app.audio.devices.iter().position(|d| d == name))
+        {
+            app.audio.selected_device = index;
+        }
+        app.set_buffer_size(self.buffer_size);
 
         app.file_player.volume = self.volume;
         app.file_player.speed = self.speed;
         app.file_player.loop_playback = self.loop_enabled;
+        app.file_player.av_sync = self.av_sync;
+        app.file_player.display_offset_ms = self.display_offset_ms;
+        app.file_player.sync_display_delay();
+
+        app.generator_kind = self.generator_kind;
+        app.shape = self.generator_shape.clone();
+        app.function = self.generator_function.clone();
+        app.sweep = self.generator_sweep.clone();
+        app.noise = self.generator_noise.clone();
+        app.text = self.generator_text.clone();
+        app.svg = self.generator_svg.clone();
+        app.model = self.generator_model.clone();
+        app.demo = self.demo.clone();
+        app.silence.action = self.silence_action;
+        app.silence.threshold_db = self.silence_threshold_db;
+        app.silence.delay = clamp_seconds(self.silence_delay, 0.5, 30.0);
+        app.calibration = self.calibration.clone();
+        app.generator.volume = self.generator_volume;
+        app.generator.sync_volume();
+        app.generator.audio_output = self.generator_audio_output;
+        if let Some(name) = &self.generator_output_device {
+            app.generator.select_output(name);
+        }
+
+        app.presets.morph_seconds = clamp_seconds(self.preset_morph_seconds, 0.0, 30.0);
+        app.profiles.enabled = self.source_profiles_enabled;
+        app.profiles.profiles = self.source_profiles.clone();
+        app.profiles.active = self.source_profile_active.clone();
 
         app.midi.mappings = self.midi_mappings.clone();
+        app.modulation.enabled = self.modulation_enabled;
+        app.modulation.routes = self.modulation_routes.clone();
+        app.reactivity.enabled = self.reactivity_enabled;
+        app.reactivity.beats_per_bar = self.beats_per_bar;
+        app.reactivity.pulse_depth = self.pulse_depth;
+        app.reactivity.rules = self.beat_rules.clone();
+        app.scope_grid.enabled = self.grid_enabled;
+        app.scope_grid.pairing = self.grid_pairing;
+        app.scope_grid.reference = self.grid_reference;
+
+        app.keymap = self.keybindings.clone();
+        app.hotkeys.set_bindings(&self.global_hotkeys);
+        app.hotkeys.set_enabled(self.global_hotkeys_enabled);
+
+        app.frame_output.enabled = self.shm_output_enabled;
+        app.frame_output.name = self.shm_output_name.clone();
+        app.frame_output.width = self.shm_output_width.clamp(
+            output::SharedFrameOutput::MIN_SIZE,
+            output::SharedFrameOutput::MAX_SIZE,
+        );
+        app.frame_output.height = self.shm_output_height.clamp(
+            output::SharedFrameOutput::MIN_SIZE,
+            output::SharedFrameOutput::MAX_SIZE,
+        );
+        app.frame_output.clean_feed = self.shm_output_clean_feed;
+
+        app.projector.display = self.projector_display.clone();
+        app.projector.width = self.projector_width;
+        app.projector.height = self.projector_height;
+        app.projector.rotation = self.projector_rotation;
+        app.projector.clean_feed = self.projector_clean_feed;
+        app.projector.set_open(self.projector_open);
+
+        app.export_naming.directory = self.export_directory.clone();
+        app.export_naming.template = self.export_template.clone();
+        app.sequence.fps = self.sequence_fps.max(1);
+        app.sequence.width = self.sequence_width.clamp(16, 8192);
+        app.sequence.height = self.sequence_height.clamp(16, 8192);
+        app.exposure.format = self.exposure_format;
+        app.exposure.width = self.exposure_width.clamp(16, 16384);
+        app.exposure.height = self.exposure_height.clamp(16, 16384);
+        app.overlay.settings = OverlaySettings {
+            size: self.overlay.size.clamp(0.01, 0.1),
+            weight: self.overlay.weight.clamp(0.05, 0.3),
+            opacity: self.overlay.opacity.clamp(0.0, 1.0),
+            ..self.overlay
+        };
+        app.branding.settings = BrandingSettings {
+            scale: self.branding.scale.clamp(0.02, 0.3),
+            opacity: self.branding.opacity.clamp(0.0, 1.0),
+            ..self.branding.clone()
+        };
+        if let Err(e) = app.branding.set_logo(self.branding.logo.clone()) {
+            log::warn!("Couldn't load logo: {}", e);
+        }
+        // Bundles leave the program to run out; keep the one set here
+        let ffmpeg = if !self.ffmpeg_path.trim().is_empty() {
+            self.ffmpeg_path.clone()
+        } else if !app.record_params.ffmpeg.trim().is_empty() {
+            app.record_params.ffmpeg.clone()
+        } else {
+            output::DEFAULT_FFMPEG.to_string()
+        };
+        app.record_params = output::RecordingParams {
+            fps: self.record_fps.max(1),
+            width: self.record_width.clamp(16, 8192),
+            height: self.record_height.clamp(16, 8192),
+            audio: self.record_audio,
+            clean_feed: self.record_clean_feed,
+            ffmpeg,
+        };
+        app.obs.host = self.obs_host.clone();
+        app.obs.port = self.obs_port;
+        // Bundles leave the password out; keep the one set here
+        if !self.obs_password.is_empty() {
+            app.obs.password = self.obs_password.clone();
+        }
+        app.obs.follow_playback = self.obs_follow_playback;
+        app.obs.scene_per_preset = self.obs_scene_per_preset;
+        app.obs.set_enabled(self.obs_enabled);
+        app.tray.close_to_tray = self.close_to_tray;
+        app.tray.set_enabled(self.tray_enabled);
+
+        app.workspaces = self.workspaces.clone();
+        app.show_perf = self.show_perf_overlay;
+        app.show_phase = self.show_phase_readout;
+        app.ui_scale = self.ui_scale.clamp(0.5, 3.0);
+        app.font_size = self.font_size.clamp(8.0, 24.0);
+        app.ui_theme = self.ui_theme;
+        app.target_fps = self.target_fps.min(240);
+        app.vsync = self.vsync;
+        app.idle_throttle = self.idle_throttle;
+        app.auto_quality = self.auto_quality;
+        i18n::set_language(self.language);
+        app.window_size = self.window_size;
+        app.window_pos = self.window_pos;
+        app.startup = self.startup.clone();
+        if self.startup.restore_window {
+            app.show_settings = self.show_settings;
+            app.input_mode = self.input_mode;
+        }
+    }
+
+    /// Window options for eframe, from the startup settings
+    pub fn viewport(&self) -> eframe::egui::ViewportBuilder {
+        let mut viewport = eframe::egui::ViewportBuilder::default()
+            .with_inner_size([800.0, 600.0])
+            .with_title("scope-rs");
+        if self.startup.restore_window {
+            if let Some(size) = self.window_size {
+                viewport = viewport.with_inner_size(size);
+            }
+            if let Some(pos) = self.window_pos {
+                viewport = viewport.with_position(pos);
+            }
+        }
+        if self.startup.window == StartupWindow::Fullscreen {
+            viewport = viewport.with_fullscreen(true);
+        }
+        viewport
+    }
+}
+
+/// A time from the settings file, kept to the UI's range; a NaN becomes
+/// `min`
+fn clamp_seconds(seconds: f32, min: f32, max: f32) -> f32 {
+    if seconds.is_nan() {
+        min
+    } else {
+        seconds.clamp(min, max)
+    }
+}
+
+/// Write already-serialized settings to the settings file
+fn write_settings(json: &str) {
+    let path = settings_path();
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            log::warn!("Failed to create config directory: {}", e);
+            return;
+        }
+    }
+    if !BACKED_UP.swap(true, Ordering::Relaxed) {
+        if let Err(e) = rotate_backups() {
+            log::warn!("Failed to back up settings: {}", e);
+        }
+    }
+    if let Err(e) = write_atomic(&path, json) {
+        log::warn!("Failed to write settings: {}", e);
+    }
+}
+
+/// How often the running settings are compared against the last save
+const AUTOSAVE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long settings must stay unchanged before they are written
+const AUTOSAVE_DEBOUNCE: Duration = Duration::from_secs(2);
 
-        app.show_settings = self.show_settings;
+/// Saves settings shortly after they change.
+///
+/// Dragging a slider changes settings every frame, so a save only happens
+/// once values have been still for `AUTOSAVE_DEBOUNCE`. Changes are detected
+/// by comparing the serialized settings, which keeps this independent of
+/// which UI code touched what.
+///
+/// The position in a playing file moves on by itself, so it's left out of
+/// the comparison while the file plays; it's saved with the next change,
+/// once playback pauses or stops, and on exit.
+pub struct AutoSave {
+    /// Comparison key of the settings last written to disk
+    saved: String,
+    /// Latest key seen, and when it first appeared
+    pending: String,
+    changed_at: Instant,
+    checked_at: Instant,
+}
+
+impl AutoSave {
+    pub fn new(app: &ScopeApp) -> Self {
+        let key = Self::key(app).unwrap_or_default();
+        let now = Instant::now();
+        Self {
+            saved: key.clone(),
+            pending: key,
+            changed_at: now,
+            checked_at: now,
+        }
+    }
+
+    /// The settings as JSON, without the ones that change by themselves
+    fn key(app: &ScopeApp) -> Result<String, serde_json::Error> {
+        let mut settings = AppSettings::from_app(app);
+        if app.file_player.state() == PlaybackState::Playing {
+            settings.last_file_position = 0.0;
+        }
+        if app.waterfall.spin != 0.0 {
+            settings.waterfall_yaw = 0.0;
+        }
+        serde_json::to_string(&settings)
+    }
+
+    /// Call once per frame
+    pub fn update(&mut self, app: &ScopeApp) {
+        if self.checked_at.elapsed() < AUTOSAVE_CHECK_INTERVAL {
+            return;
+        }
+        self.checked_at = Instant::now();
+
+        let key = match Self::key(app) {
+            Ok(key) => key,
+            Err(e) => {
+                log::warn!("Failed to serialize settings: {}", e);
+                return;
+            }
+        };
+        if key != self.pending {
+            self.pending = key;
+            self.changed_at = Instant::now();
+        } else if self.pending != self.saved && self.changed_at.elapsed() >= AUTOSAVE_DEBOUNCE {
+            match serde_json::to_string_pretty(&AppSettings::from_app(app)) {
+                Ok(json) => write_settings(&json),
+                Err(e) => log::warn!("Failed to serialize settings: {}", e),
+            }
+            self.saved.clone_from(&self.pending);
+            log::debug!("Auto-saved settings");
+        }
+    }
+}
+
+/// Everything needed to reproduce a setup on another machine: all settings
+/// (including MIDI mappings and key bindings) plus every saved preset.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsBundle {
+    pub settings: AppSettings,
+    pub presets: BTreeMap<String, Preset>,
+}
+
+impl SettingsBundle {
+    /// Collect the running settings and all presets on disk. Presets that
+    /// can't be read are left out; their names are returned with the bundle.
+    /// The OBS password and the ffmpeg program are left out too, since
+    /// bundles get passed around.
+    pub fn from_app(app: &ScopeApp) -> (Self, Vec<String>) {
+        let mut presets = BTreeMap::new();
+        let mut unreadable = Vec::new();
+        for name in &app.presets.names {
+            match app.presets.load(name) {
+                Ok(preset) => {
+                    presets.insert(name.clone(), preset);
+                }
+                Err(e) => {
+                    log::warn!("Leaving preset '{}' out of the export: {}", name, e);
+                    unreadable.push(name.clone());
+                }
+            }
+        }
+        let mut settings = AppSettings::from_app(app);
+        settings.obs_password.clear();
+        settings.ffmpeg_path.clear();
+        let bundle = Self { settings, presets };
+        (bundle, unreadable)
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), SettingsError> {
+        write_atomic(path, &serde_json::to_string_pretty(self)?)?;
+        log::info!("Exported settings to {}", path.display());
+        Ok(())
+    }
+
+    pub fn import(path: &Path) -> Result<Self, SettingsError> {
+        let bundle = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        log::info!("Imported settings from {}", path.display());
+        Ok(bundle)
+    }
+
+    /// Presets in the bundle that would replace ones on disk
+    pub fn conflicts(&self, app: &ScopeApp) -> Vec<String> {
+        self.presets
+            .keys()
+            .filter(|name| app.presets.exists(name))
+            .cloned()
+            .collect()
+    }
+
+    /// Apply the settings and write the presets. Presets with the name of
+    /// one on disk replace it only if `replace` is set.
+    pub fn apply(&self, app: &mut ScopeApp, replace: bool) -> Result<(), SettingsError> {
+        self.settings.apply(app);
+        for (name, preset) in &self.presets {
+            if replace || !app.presets.exists(name) {
+                app.presets.save(name, preset)?;
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,342 @@
+//! System tray icon
+//!
+//! Keeps scope-rs within reach while its window is hidden, with a menu of
+//! quick actions: show or hide the window, start or stop capture, and
+//! recall a preset. Hiding the window pauses nothing; the app keeps drawing
+//! frames it doesn't show, so capture, recordings and the shared memory
+//! output carry on.
+//!
+//! On Linux the icon is a StatusNotifierItem on the D-Bus session bus, which
+//! KDE and most other desktops show, and GNOME does with the AppIndicator
+//! extension. The menu runs on the service's own thread and hands picks to
+//! the UI over a channel, waking it to handle them. Other platforms don't
+//! have a tray icon yet.
+
+// Elsewhere the menu that reads the state and sends actions isn't built
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::sync::mpsc;
+
+use eframe::egui;
+
+/// Whether this platform has a tray icon
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// Something picked from the tray
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrayAction {
+    ToggleWindow,
+    ToggleCapture,
+    LoadPreset(String),
+    Quit,
+}
+
+/// What the tray menu shows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrayState {
+    pub window_visible: bool,
+    pub capturing: bool,
+    /// Whether there's a device to capture from
+    pub can_capture: bool,
+    pub presets: Vec<String>,
+    pub current_preset: Option<String>,
+}
+
+/// The tray icon, while it's shown
+struct Running {
+    service: platform::Service,
+    actions: mpsc::Receiver<TrayAction>,
+}
+
+/// Tray icon and its menu
+pub struct TrayIcon {
+    /// Hide the window to the tray when it's closed, instead of quitting
+    pub close_to_tray: bool,
+    /// Status message
+    pub status: String,
+    state: TrayState,
+    ctx: egui::Context,
+    running: Option<Running>,
+}
+
+impl TrayIcon {
+    /// A tray icon that wakes the UI in `ctx` when something is picked
+    pub fn new(ctx: egui::Context) -> Self {
+        Self {
+            close_to_tray: false,
+            status: "Off".to_string(),
+            state: TrayState::default(),
+            ctx,
+            running: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Show or remove the icon
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.start();
+        } else {
+            self.stop();
+        }
+    }
+
+    /// Show the icon; restarts it if already shown
+    pub fn start(&mut self) {
+        self.stop();
+        let (sender, actions) = mpsc::channel();
+        match platform::spawn(self.state.clone(), sender, self.ctx.clone()) {
+            Ok(service) => {
+                self.running = Some(Running { service, actions });
+                self.status = "In the tray".to_string();
+            }
+            Err(e) => {
+                log::error!("Tray icon failed: {}", e);
+                self.status = format!("Error: {}", e);
+            }
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if self.running.take().is_some() {
+            self.status = "Off".to_string();
+        }
+    }
+
+    /// Show the app's state in the menu, if it changed
+    pub fn update(
+        &mut self,
+        window_visible: bool,
+        capturing: bool,
+        can_capture: bool,
+        presets: &[String],
+        current_preset: Option<&str>,
+    ) {
+        let state = &self.state;
+        if state.window_visible == window_visible
+            && state.capturing == capturing
+            && state.can_capture == can_capture
+            && state.presets == presets
+            && state.current_preset.as_deref() == current_preset
+        {
+            return;
+        }
+        self.state = TrayState {
+            window_visible,
+            capturing,
+            can_capture,
+            presets: presets.to_vec(),
+            current_preset: current_preset.map(str::to_string),
+        };
+        if let Some(running) = &self.running {
+            running.service.update(self.state.clone());
+        }
+    }
+
+    /// The next thing picked from the tray, if any
+    pub fn poll(&self) -> Option<TrayAction> {
+        self.running.as_ref()?.actions.try_recv().ok()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::f32::consts::{FRAC_PI_4, TAU};
+    use std::sync::mpsc;
+
+    use eframe::egui;
+    use ksni::blocking::TrayMethods;
+    use ksni::menu::{RadioGroup, RadioItem, StandardItem, SubMenu};
+    use ksni::MenuItem;
+
+    use super::{TrayAction, TrayState};
+    use crate::i18n::tr;
+
+    /// Icon size in pixels
+    const ICON_SIZE: usize = 32;
+
+    /// The StatusNotifierItem service; removed from the tray when dropped
+    pub struct Service(ksni::blocking::Handle<Menu>);
+
+    pub fn spawn(
+        state: TrayState,
+        actions: mpsc::Sender<TrayAction>,
+        ctx: egui::Context,
+    ) -> Result<Service, ksni::Error> {
+        let menu = Menu {
+            state,
+            actions,
+            ctx,
+        };
+        Ok(Service(menu.spawn()?))
+    }
+
+    impl Service {
+        pub fn update(&self, state: TrayState) {
+            self.0.update(|menu| menu.state = state);
+        }
+    }
+
+    impl Drop for Service {
+        fn drop(&mut self) {
+            self.0.shutdown().wait();
+        }
+    }
+
+    pub struct Menu {
+        state: TrayState,
+        actions: mpsc::Sender<TrayAction>,
+        ctx: egui::Context,
+    }
+
+    impl Menu {
+        fn send(&self, action: TrayAction) {
+            let _ = self.actions.send(action);
+            self.ctx.request_repaint();
+        }
+    }
+
+    impl ksni::Tray for Menu {
+        fn id(&self) -> String {
+            "scope-rs".to_string()
+        }
+
+        fn title(&self) -> String {
+            "scope-rs".to_string()
+        }
+
+        fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+            vec![icon()]
+        }
+
+        fn tool_tip(&self) -> ksni::ToolTip {
+            ksni::ToolTip {
+                title: "scope-rs".to_string(),
+                description: self.state.current_preset.clone().unwrap_or_default(),
+                ..Default::default()
+            }
+        }
+
+        /// A click on the icon shows or hides the window
+        fn activate(&mut self, _x: i32, _y: i32) {
+            self.send(TrayAction::ToggleWindow);
+        }
+
+        fn menu(&self) -> Vec<MenuItem<Self>> {
+            let state = &self.state;
+            let window = if state.window_visible {
+                tr("Hide window")
+            } else {
+                tr("Show window")
+            };
+            let capture = if state.capturing {
+                tr("Stop capture")
+            } else {
+                tr("Start capture")
+            };
+            let presets = RadioGroup {
+                selected: state
+                    .current_preset
+                    .as_ref()
+                    .and_then(|current| state.presets.iter().position(|name| name == current))
+                    .unwrap_or(usize::MAX),
+                select: Box::new(|menu: &mut Self, index| {
+                    if let Some(name) = menu.state.presets.get(index) {
+                        menu.send(TrayAction::LoadPreset(name.clone()));
+                    }
+                }),
+                options: state
+                    .presets
+                    .iter()
+                    .map(|name| RadioItem {
+                        // Underscores mark access keys in menu labels
+                        label: name.replace('_', "__"),
+                        ..Default::default()
+                    })
+                    .collect(),
+            };
+            vec![
+                StandardItem {
+                    label: window.to_string(),
+                    activate: Box::new(|menu: &mut Self| menu.send(TrayAction::ToggleWindow)),
+                    ..Default::default()
+                }
+                .into(),
+                StandardItem {
+                    label: capture.to_string(),
+                    enabled: state.capturing || state.can_capture,
+                    activate: Box::new(|menu: &mut Self| menu.send(TrayAction::ToggleCapture)),
+                    ..Default::default()
+                }
+                .into(),
+                SubMenu {
+                    label: tr("Presets").to_string(),
+                    enabled: !state.presets.is_empty(),
+                    submenu: vec![presets.into()],
+                    ..Default::default()
+                }
+                .into(),
+                MenuItem::Separator,
+                StandardItem {
+                    label: tr("Quit").to_string(),
+                    icon_name: "application-exit".to_string(),
+                    activate: Box::new(|menu: &mut Self| menu.send(TrayAction::Quit)),
+                    ..Default::default()
+                }
+                .into(),
+            ]
+        }
+    }
+
+    /// A green 1:2 Lissajous figure on a dark disc, in ARGB
+    fn icon() -> ksni::Icon {
+        let mut data = vec![0; ICON_SIZE * ICON_SIZE * 4];
+        let center = ICON_SIZE as f32 / 2.0;
+        for y in 0..ICON_SIZE {
+            for x in 0..ICON_SIZE {
+                let (dx, dy) = (x as f32 + 0.5 - center, y as f32 + 0.5 - center);
+                if dx.hypot(dy) <= center {
+                    data[(y * ICON_SIZE + x) * 4..][..4].copy_from_slice(&[255, 16, 24, 16]);
+                }
+            }
+        }
+        for k in 0..400 {
+            let t = k as f32 / 400.0 * TAU;
+            let x = center + (t + FRAC_PI_4).sin() * center * 0.65;
+            let y = center - (t * 2.0).sin() * center * 0.65;
+            let i = (y as usize * ICON_SIZE + x as usize) * 4;
+            data[i..i + 4].copy_from_slice(&[255, 64, 255, 96]);
+        }
+        ksni::Icon {
+            width: ICON_SIZE as i32,
+            height: ICON_SIZE as i32,
+            data,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use std::sync::mpsc;
+
+    use eframe::egui;
+
+    use super::{TrayAction, TrayState};
+
+    pub struct Service;
+
+    pub fn spawn(
+        _state: TrayState,
+        _actions: mpsc::Sender<TrayAction>,
+        _ctx: egui::Context,
+    ) -> Result<Service, String> {
+        Err("There's no tray icon on this platform yet".to_string())
+    }
+
+    impl Service {
+        pub fn update(&self, _state: TrayState) {}
+    }
+}
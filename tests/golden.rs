@@ -0,0 +1,104 @@
+//! Golden-image tests for the rendering path
+//!
+//! Each test signal is rendered offscreen for a fixed number of frames and
+//! compared with a reference PNG in `tests/golden/`. After an intended
+//! change to the rendering, regenerate the references with
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test --test golden
+//! ```
+//!
+//! and look over the new images before committing them.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use scope_rs::harness::{ImageDiff, RenderHarness, TestSignal};
+use scope_rs::render::DisplayMode;
+
+const SIZE: usize = 256;
+
+/// Frames rendered before the comparison, enough for persistence to settle
+const FRAMES: usize = 30;
+
+/// Per-channel difference allowed for floating-point noise
+const TOLERANCE: u8 = 2;
+
+/// Share of pixels allowed beyond the tolerance
+const MAX_MISMATCHED: f32 = 0.002;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+fn read_png(path: &PathBuf) -> (usize, usize, Vec<u8>) {
+    let file = File::open(path).unwrap_or_else(|e| {
+        panic!(
+            "missing reference {} ({e}); create it with UPDATE_GOLDEN=1",
+            path.display()
+        )
+    });
+    let mut reader = png::Decoder::new(file).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    pixels.truncate(info.buffer_size());
+    (info.width as usize, info.height as usize, pixels)
+}
+
+fn write_png(path: &PathBuf, width: usize, height: usize, pixels: &[u8]) {
+    let file = File::create(path).unwrap();
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(pixels)
+        .unwrap();
+}
+
+fn check(name: &str, harness: &mut RenderHarness) {
+    let pixels = harness.run(FRAMES).to_vec();
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        write_png(&path, harness.width(), harness.height(), &pixels);
+        return;
+    }
+
+    let (width, height, expected) = read_png(&path);
+    assert_eq!((width, height), (harness.width(), harness.height()));
+    let diff = ImageDiff::compare(&pixels, &expected, TOLERANCE);
+    if diff.mismatched > MAX_MISMATCHED {
+        let actual = path.with_extension("actual.png");
+        write_png(&actual, width, height, &pixels);
+        panic!(
+            "{name} differs from its reference: {diff:?}; this run's frame is in {}",
+            actual.display()
+        );
+    }
+}
+
+#[test]
+fn golden_signals() {
+    for &signal in TestSignal::all() {
+        check(signal.name(), &mut RenderHarness::new(signal, SIZE, SIZE));
+    }
+}
+
+#[test]
+fn golden_display_modes() {
+    // The default mode is covered by `golden_signals`
+    for &mode in DisplayMode::all()
+        .iter()
+        .filter(|&&mode| mode != DisplayMode::default())
+    {
+        let mut harness = RenderHarness::new(TestSignal::Lissajous, SIZE, SIZE);
+        harness.settings.display_mode = mode;
+        let name = format!("lissajous-{}", mode.name().to_lowercase());
+        check(&name, &mut harness);
+    }
+}